@@ -0,0 +1,32 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rust_8::{blit_scaled, letterbox, Filter, HEIGHT, WIDTH};
+
+// the default 16x window
+const WINDOW_WIDTH: usize = WIDTH * 16;
+const WINDOW_HEIGHT: usize = HEIGHT * 16;
+
+// a checkerboard, so every other CHIP-8 pixel is lit
+fn frame() -> Vec<u32> {
+    (0..WIDTH * HEIGHT)
+        .map(|n| if (n % WIDTH + n / WIDTH) & 1 == 0 { 0xFFFFFF } else { 0x000000 })
+        .collect()
+}
+
+// a frame has about 16 ms; the filter should take a small slice of that
+fn filters(c: &mut Criterion) {
+    let frame = frame();
+    let viewport = letterbox(WINDOW_WIDTH, WINDOW_HEIGHT, WIDTH, HEIGHT);
+    let mut window = vec![0; WINDOW_WIDTH * WINDOW_HEIGHT];
+
+    for filter in Filter::ALL.iter() {
+        c.bench_function(&format!("scale and {} filter at 16x", filter), |b| {
+            b.iter(|| {
+                blit_scaled(&frame, WIDTH, &mut window, WINDOW_WIDTH, viewport, 0);
+                filter.apply(black_box(&mut window), WINDOW_WIDTH, viewport, WIDTH, HEIGHT);
+            })
+        });
+    }
+}
+
+criterion_group!(benches, filters);
+criterion_main!(benches);
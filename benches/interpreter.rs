@@ -0,0 +1,111 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rand::{rngs::StdRng, SeedableRng};
+use rust_8::Chip8;
+
+const INSTRUCTIONS_PER_SECOND: usize = 700;
+const FRAMES_PER_SECOND: usize = 60;
+
+// a loop of ALU, skip, BCD, load and call/return instructions
+const INSTRUCTION_MIX: [u8; 30] = [
+    0x60, 0x05, // LD V0, 0x05
+    0x61, 0x0A, // LD V1, 0x0A
+    0x80, 0x14, // ADD V0, V1
+    0x81, 0x05, // SUB V1, V0
+    0x82, 0x03, // XOR V2, V0
+    0x72, 0x01, // ADD V2, 0x01
+    0x30, 0x00, // SE V0, 0x00
+    0xA3, 0x00, // LD I, 0x300
+    0xF2, 0x33, // LD B, V2
+    0xF3, 0x65, // LD V3, [I]
+    0x22, 0x1A, // CALL 0x21A
+    0x12, 0x00, // JP 0x200
+    0x00, 0x00,
+    0x83, 0x0E, // SHL V3
+    0x00, 0xEE, // RET
+];
+
+fn machine(rom: &[u8], predecode: bool) -> Chip8 {
+    let mut chip8 = Chip8::new();
+    chip8.set_rng(Box::new(StdRng::seed_from_u64(0x5EED)));
+    chip8.set_predecode(predecode);
+    chip8.load_sprites();
+    chip8.load_rom(rom.to_vec()).unwrap();
+    chip8
+}
+
+// draws a solid 8x8 sprite at every 8 pixel step until the whole screen is covered
+fn full_screen_rom() -> Vec<u8> {
+    let mut rom = Vec::new();
+    let mut draws = Vec::new();
+    for y in (0..32).step_by(8) {
+        for x in (0..64).step_by(8) {
+            draws.extend_from_slice(&[0x60, x, 0x61, y, 0xD0, 0x18]);
+        }
+    }
+    let sprite = 0x200 + 2 + draws.len() as u16 + 2;
+    rom.extend_from_slice(&[0xA0 | (sprite >> 8) as u8, sprite as u8]);
+    rom.extend_from_slice(&draws);
+    rom.extend_from_slice(&[0x12, 0x00]);
+    rom.extend_from_slice(&[0xFF; 8]);
+    rom
+}
+
+fn instruction_mix(c: &mut Criterion) {
+    for (name, predecode) in [("instruction mix x10k", false), ("instruction mix x10k predecoded", true)].iter() {
+        let mut chip8 = machine(&INSTRUCTION_MIX, *predecode);
+        for _ in 0..INSTRUCTION_MIX.len() {
+            chip8.step().expect("instruction mix should run cleanly");
+        }
+
+        c.bench_function(name, |b| {
+            b.iter(|| {
+                for _ in 0..10_000 {
+                    black_box(chip8.step()).unwrap();
+                }
+            })
+        });
+    }
+}
+
+fn full_screen_draw(c: &mut Criterion) {
+    let rom = full_screen_rom();
+    let instructions = 1 + 32 * 3 + 1;
+    let mut chip8 = machine(&rom, false);
+    for _ in 0..instructions {
+        chip8.step().unwrap();
+    }
+    assert!((0..64).all(|x| (0..32).all(|y| chip8.display().pixel(x, y))));
+
+    c.bench_function("full screen DXYN", |b| {
+        b.iter(|| {
+            for _ in 0..instructions {
+                black_box(chip8.step()).unwrap();
+            }
+        })
+    });
+}
+
+fn simulated_second(chip8: &mut Chip8) {
+    for frame in 0..FRAMES_PER_SECOND {
+        let start = frame * INSTRUCTIONS_PER_SECOND / FRAMES_PER_SECOND;
+        let end = (frame + 1) * INSTRUCTIONS_PER_SECOND / FRAMES_PER_SECOND;
+        for _ in start..end {
+            chip8.step().unwrap();
+        }
+        chip8.tick_timers();
+    }
+}
+
+fn full_frames(c: &mut Criterion) {
+    for (name, predecode) in [("60 frames at 700 IPS", false), ("60 frames at 700 IPS predecoded", true)].iter() {
+        let mut chip8 = machine(include_bytes!("../roms/Tetris.ch8"), *predecode);
+        simulated_second(&mut chip8);
+
+        c.bench_function(name, |b| {
+            b.iter(|| simulated_second(black_box(&mut chip8)))
+        });
+    }
+}
+
+criterion_group!(benches, instruction_mix, full_screen_draw, full_frames);
+criterion_main!(benches);
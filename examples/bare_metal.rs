@@ -0,0 +1,107 @@
+// Chip8Core on a microcontroller with no std or allocator. Build it with
+//
+//     cargo build --example bare_metal --no-default-features --features no_std --target thumbv7em-none-eabihf
+//
+// The hal module stands in for a board support crate: swap its functions for the real
+// SysTick, GPIO and display driver calls (cortex-m-rt's #[entry] instead of _start, a
+// keypad matrix scan, an SSD1306 or similar that takes a line of pixels at a time).
+
+#![no_std]
+#![no_main]
+
+use core::panic::PanicInfo;
+
+use rust_8::{Chip8Core, CoreError, CoreStep, CORE_HEIGHT};
+
+// the ROM goes into flash alongside the program
+static ROM: &[u8] = &[
+    0x00, 0xE0, // CLS
+    0x60, 0x0A, // LD V0, 0A
+    0xF0, 0x29, // LD F, V0
+    0x61, 0x1C, // LD V1, 1C
+    0x62, 0x0D, // LD V2, 0D
+    0xD1, 0x25, // DRW V1, V2, 5
+    0xF3, 0x0A, // LD V3, K
+    0x00, 0xE0, // CLS
+    0x12, 0x00, // JP 200
+];
+
+// instructions run between two 60 Hz timer ticks
+const INSTRUCTIONS_PER_TICK: u32 = 10;
+
+mod hal {
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    static TICKS: AtomicU32 = AtomicU32::new(0);
+
+    // the SysTick interrupt would bump this 60 times a second
+    pub fn ticks() -> u32 {
+        TICKS.load(Ordering::Relaxed)
+    }
+
+    // a bit per key of the 4x4 matrix, key 0 in the lowest bit
+    pub fn scan_keys() -> u16 {
+        0
+    }
+
+    pub fn write_line(_y: usize, _pixels: u64) {}
+
+    pub fn set_buzzer(_on: bool) {}
+
+    // a fault LED, a blink code, or a message over a UART
+    pub fn report_error(_err: &super::CoreError) {}
+}
+
+#[no_mangle]
+pub extern "C" fn _start() -> ! {
+    let mut chip8 = Chip8Core::new(hal::ticks());
+    if let Err(err) = chip8.load_rom(ROM) {
+        halt(&err);
+    }
+
+    let mut last_tick = hal::ticks();
+    let mut shown = [0u64; CORE_HEIGHT];
+    loop {
+        let keys = hal::scan_keys();
+        for key in 0..16 {
+            chip8.keypad_mut().set(key, keys >> key & 1 == 1);
+        }
+
+        for _ in 0..INSTRUCTIONS_PER_TICK {
+            match chip8.step() {
+                Ok(CoreStep::Executed { .. }) => {}
+                Ok(CoreStep::WaitingForKey) => break,
+                Err(err) => halt(&err),
+            }
+        }
+
+        // only the rows that changed go out to the display
+        for (y, (row, old)) in chip8.rows().iter().zip(shown.iter_mut()).enumerate() {
+            if row != old {
+                hal::write_line(y, *row);
+                *old = *row;
+            }
+        }
+
+        while hal::ticks() == last_tick {
+            core::hint::spin_loop();
+        }
+        last_tick = hal::ticks();
+        chip8.tick_timers();
+        hal::set_buzzer(chip8.sound_active());
+    }
+}
+
+fn halt(err: &CoreError) -> ! {
+    hal::report_error(err);
+    loop {
+        core::hint::spin_loop();
+    }
+}
+
+#[panic_handler]
+fn panic(_info: &PanicInfo) -> ! {
+    loop {
+        core::hint::spin_loop();
+    }
+}
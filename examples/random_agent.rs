@@ -0,0 +1,63 @@
+// An agent pressing random keys through Chip8Env, for 1000 steps without a window:
+//
+//     cargo run --example random_agent -- BRIX.ch8 0x3F0
+//
+// The second argument is where the game keeps its score, for games like Brix that write
+// its decimal digits to memory with FX33 before drawing it; find it by watching the
+// game in the debugger. Each step's reward is how much the number there went up.
+// Without an address every reward is 0, and without a ROM the built-in opcode test runs.
+
+use std::{env, error::Error, fs};
+
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use rust_8::{builtin_rom, Chip8Env, EnvConfig, Keypad};
+
+const STEPS: u32 = 1000;
+
+// the three decimal digits FX33 wrote at `address`, as a number
+fn score(env: &Chip8Env, address: Option<u16>) -> u32 {
+    match address.map(|address| env.chip8().memory(address, 3)) {
+        Some(Ok(digits)) => digits.iter().fold(0, |score, &digit| score * 10 + digit as u32),
+        _ => 0,
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let rom = match args.first() {
+        Some(path) => fs::read(path)?,
+        None => builtin_rom("test-opcode").ok_or("no built-in test-opcode")?.data.to_vec(),
+    };
+    let address = match args.get(1) {
+        Some(address) => Some(u16::from_str_radix(address.trim_start_matches("0x"), 16)?),
+        None => None,
+    };
+
+    let mut env = Chip8Env::new(rom, EnvConfig { seed: 1, ..EnvConfig::default() })?;
+    let mut rng = SmallRng::seed_from_u64(2);
+    env.reset();
+    let (mut total, mut episodes) = (0, 1);
+    let mut last = score(&env, address);
+    for _ in 0..STEPS {
+        // one key at a time, or none, as a player would
+        let mut action = Keypad::new();
+        let key = rng.gen_range(0..17);
+        if key < 16 {
+            action.press(key);
+        }
+        let (_, info) = env.step(action);
+        let now = score(&env, address);
+        total += now.saturating_sub(last);
+        last = now;
+        if info.done {
+            if let Some(err) = info.error {
+                println!("episode {} crashed: {}", episodes, err);
+            }
+            env.reset();
+            last = score(&env, address);
+            episodes += 1;
+        }
+    }
+    println!("{} steps, {} episodes, {} reward", STEPS, episodes, total);
+    Ok(())
+}
@@ -0,0 +1,87 @@
+// What the fuzz target checks on every input, shared with tests/fuzz_regressions.rs so
+// the saved crashers run under cargo test too. The first byte picks the variant and the
+// rest is the ROM. There is no real time involved: the timers tick every TICK_EVERY
+// steps and the keys go down one after another, so a run only depends on its input.
+
+use std::{cell::Cell, rc::Rc};
+
+use rust_8::{Chip8, Chip8Builder, Chip8Error, Chip8Inspector, ChipVariant, InstructionResult, MAX_HEIGHT, MAX_WIDTH};
+
+const STEPS: usize = 1000;
+const TICK_EVERY: usize = 10;
+const MEMORY: usize = 4096;
+
+// remembers the opcode of the last instruction run
+struct LastOpcode(Rc<Cell<u16>>);
+
+impl Chip8Inspector for LastOpcode {
+    fn before_instruction(&mut self, _pc: u16, opcode: u16, _state: &Chip8) {
+        self.0.set(opcode);
+    }
+
+    fn after_instruction(&mut self, _result: &InstructionResult, _state: &Chip8) {}
+}
+
+pub fn run(data: &[u8]) {
+    let (variant, rom) = match data.split_first() {
+        Some((first, rom)) => (ChipVariant::ALL[*first as usize % ChipVariant::ALL.len()], rom),
+        None => return,
+    };
+    let opcode = Rc::new(Cell::new(0));
+    let mut chip8 = Chip8Builder::new()
+        .with_variant(variant)
+        .with_rng_seed(0)
+        .with_inspector(Box::new(LastOpcode(Rc::clone(&opcode))))
+        .build();
+    if chip8.load_rom(rom.to_vec()).is_err() {
+        return;
+    }
+
+    for step in 0..STEPS {
+        let pc = chip8.pc();
+        let i = chip8.i_register();
+        let result = chip8.step();
+        // a jump or skip can leave PC past the end, but then fetching from there has to fail
+        if pc as usize + 1 >= MEMORY {
+            assert_eq!(result, Err(Chip8Error::PcOutOfRange { pc }));
+        }
+        match result {
+            Ok(InstructionResult::Halted { .. }) => break,
+            Ok(_) => check(&chip8, i, opcode.get()),
+            // errors are how the machine is meant to refuse bad programs
+            Err(_) => break,
+        }
+        if step % TICK_EVERY == TICK_EVERY - 1 {
+            chip8.tick_timers();
+            let key = (step / TICK_EVERY % 16) as u8;
+            chip8.keypad_mut().release(key.wrapping_sub(1) & 0xF);
+            chip8.keypad_mut().press(key);
+        }
+    }
+    check_display(&chip8);
+}
+
+fn check(chip8: &Chip8, i_before: u16, opcode: u16) {
+    assert!(chip8.stack_pointer() <= 16, "stack pointer {}", chip8.stack_pointer());
+    // only FX1E can carry I past the end of memory, and reads through it are checked
+    let i = chip8.i_register() as usize;
+    assert!(
+        i < MEMORY || opcode & 0xF0FF == 0xF01E || i_before as usize >= MEMORY,
+        "I={:#X} after {:04X}",
+        i,
+        opcode
+    );
+}
+
+// every pixel can be read back and comes out the same through render_into
+fn check_display(chip8: &Chip8) {
+    let display = chip8.display();
+    assert!(display.width() <= MAX_WIDTH && display.height() <= MAX_HEIGHT);
+    let mut buffer = vec![0; display.width() * display.height()];
+    display.render_into(&mut buffer, 1, 0);
+    let lit = buffer.iter().filter(|&&pixel| pixel == 1).count();
+    let counted: usize = (0..display.height())
+        .map(|y| (0..display.width()).filter(|&x| display.pixel(x, y)).count())
+        .sum();
+    assert_eq!(lit, counted);
+}
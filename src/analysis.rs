@@ -0,0 +1,157 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    fmt,
+    rc::Rc,
+};
+
+use crate::chip8::{Chip8, InstructionResult, PROGRAM_START};
+use crate::inspector::Chip8Inspector;
+use crate::instruction::Instruction;
+
+// Addresses of every instruction reachable from PROGRAM_START, grouped by the top nibble
+// of the opcode, each list in address order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OpcodeCoverage {
+    pub by_nibble: HashMap<u8, Vec<u16>>,
+}
+
+// Follows every path through the ROM the way the CPU could: on after ordinary
+// instructions, both ways after a skip, to the target of JP and CALL (and on past the
+// CALL). RET, BNNN, 00FD and a jump to itself end a path. Anything only reached through
+// BNNN or self-modifying code is missed, and so are sprite bytes, which is the point.
+pub fn coverage_report(rom: &[u8]) -> OpcodeCoverage {
+    let start = PROGRAM_START as usize;
+    let end = start + rom.len();
+    let mut seen = vec![false; rom.len()];
+    let mut pending = vec![start];
+    let mut coverage = OpcodeCoverage::default();
+
+    while let Some(address) = pending.pop() {
+        if address < start || address + 1 >= end || seen[address - start] {
+            continue;
+        }
+        seen[address - start] = true;
+        let (high, low) = (rom[address - start], rom[address - start + 1]);
+        coverage.by_nibble.entry(high >> 4).or_default().push(address as u16);
+
+        let next = address + 2;
+        match Instruction::decode(high, low) {
+            Instruction::Ret | Instruction::JpV0(_) | Instruction::Exit => {}
+            Instruction::Jp(target) => pending.push(target as usize),
+            Instruction::Call(target) => pending.extend([next, target as usize]),
+            Instruction::SeByte(..)
+            | Instruction::SneByte(..)
+            | Instruction::SeReg(..)
+            | Instruction::SneReg(..)
+            | Instruction::Skp(_)
+            | Instruction::Sknp(_) => pending.extend([next, next + 2]),
+            _ => pending.push(next),
+        }
+    }
+
+    for addresses in coverage.by_nibble.values_mut() {
+        addresses.sort_unstable();
+    }
+    coverage
+}
+
+impl fmt::Display for OpcodeCoverage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for nibble in 0..16u8 {
+            let addresses = match self.by_nibble.get(&nibble) {
+                Some(addresses) => addresses,
+                None => continue,
+            };
+            let listed: Vec<String> = addresses.iter().map(|address| format!("{:03X}", address)).collect();
+            writeln!(f, "{:X}___ x{:<4} {}", nibble, addresses.len(), listed.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+// the opcode pattern an instruction was decoded from, like "8XY4"
+pub fn opcode_pattern(instruction: Instruction) -> &'static str {
+    match instruction {
+        Instruction::Cls => "00E0",
+        Instruction::Ret => "00EE",
+        Instruction::ScrollDown(_) => "00CN",
+        Instruction::ScrollUp(_) => "00DN",
+        Instruction::ScrollRight => "00FB",
+        Instruction::ScrollLeft => "00FC",
+        Instruction::Exit => "00FD",
+        Instruction::Sys(_) => "0NNN",
+        Instruction::Jp(_) => "1NNN",
+        Instruction::Call(_) => "2NNN",
+        Instruction::SeByte(..) => "3XNN",
+        Instruction::SneByte(..) => "4XNN",
+        Instruction::SeReg(..) => "5XY0",
+        Instruction::LdByte(..) => "6XNN",
+        Instruction::AddByte(..) => "7XNN",
+        Instruction::LdReg(..) => "8XY0",
+        Instruction::Or(..) => "8XY1",
+        Instruction::And(..) => "8XY2",
+        Instruction::Xor(..) => "8XY3",
+        Instruction::AddReg(..) => "8XY4",
+        Instruction::Sub(..) => "8XY5",
+        Instruction::Shr(..) => "8XY6",
+        Instruction::Subn(..) => "8XY7",
+        Instruction::Shl(..) => "8XYE",
+        Instruction::SneReg(..) => "9XY0",
+        Instruction::LdI(_) => "ANNN",
+        Instruction::JpV0(_) => "BNNN",
+        Instruction::Rnd(..) => "CXNN",
+        Instruction::Drw(..) => "DXYN",
+        Instruction::Skp(_) => "EX9E",
+        Instruction::Sknp(_) => "EXA1",
+        Instruction::LdVxDt(_) => "FX07",
+        Instruction::LdVxK(_) => "FX0A",
+        Instruction::LdDtVx(_) => "FX15",
+        Instruction::LdStVx(_) => "FX18",
+        Instruction::AddI(_) => "FX1E",
+        Instruction::LdF(_) => "FX29",
+        Instruction::LdB(_) => "FX33",
+        Instruction::LdIVx(_) => "FX55",
+        Instruction::LdVxI(_) => "FX65",
+        Instruction::Unknown(_) => "????",
+    }
+}
+
+// Counts how often each opcode pattern actually ran. Like LoggingInspector, keep the
+// handle from `counts()` before attaching it to read the counts afterwards.
+pub struct DynamicOpcodeCoverage {
+    counts: Rc<RefCell<BTreeMap<&'static str, u64>>>,
+    // the instruction before_instruction saw, counted once it has run
+    current: Option<&'static str>,
+}
+
+impl DynamicOpcodeCoverage {
+    pub fn new() -> Self {
+        DynamicOpcodeCoverage {
+            counts: Rc::new(RefCell::new(BTreeMap::new())),
+            current: None,
+        }
+    }
+
+    pub fn counts(&self) -> Rc<RefCell<BTreeMap<&'static str, u64>>> {
+        Rc::clone(&self.counts)
+    }
+}
+
+impl Default for DynamicOpcodeCoverage {
+    fn default() -> Self {
+        DynamicOpcodeCoverage::new()
+    }
+}
+
+impl Chip8Inspector for DynamicOpcodeCoverage {
+    fn before_instruction(&mut self, _pc: u16, opcode: u16, _state: &Chip8) {
+        self.current = Some(opcode_pattern(Instruction::decode((opcode >> 8) as u8, opcode as u8)));
+    }
+
+    fn after_instruction(&mut self, result: &InstructionResult, _state: &Chip8) {
+        if let (Some(pattern), InstructionResult::Executed { .. }) = (self.current.take(), result) {
+            *self.counts.borrow_mut().entry(pattern).or_insert(0) += 1;
+        }
+    }
+}
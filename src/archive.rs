@@ -0,0 +1,103 @@
+use std::{
+    fs,
+    io::{self, Cursor, Read},
+    path::Path,
+};
+
+use flate2::read::GzDecoder;
+use zip::ZipArchive;
+
+use crate::download::{download_rom, is_rom_url, rom_url_name};
+use crate::error::RomLoadError;
+
+// no CHIP-8 variant addresses more than 64K, so anything that unpacks to more is not a
+// ROM (or is a zip bomb); load_rom still checks the real limit for the variant
+pub(crate) const MAX_UNPACKED: u64 = 0x10000;
+
+// Splits "pack.zip:games/pong.ch8" into the archive and the entry inside it. Anything
+// else, "pack.zip" included, is a path on its own.
+pub fn split_rom_path(spec: &str) -> (&str, Option<&str>) {
+    let lower = spec.to_ascii_lowercase();
+    match lower.find(".zip:") {
+        Some(at) => (&spec[..at + 4], Some(&spec[at + 5..])),
+        None => (spec, None),
+    }
+}
+
+// Reads a ROM named the way the command line names one: a plain file, a .gz file, a
+// .zip holding a single .ch8 file, "archive.zip:entry", - for standard input, or an
+// http(s) URL of any of those.
+pub fn read_rom(spec: &str) -> Result<Vec<u8>, RomLoadError> {
+    if spec == "-" {
+        return read_rom_from(io::stdin().lock());
+    }
+    if is_rom_url(spec) {
+        let data = download_rom(spec)?;
+        return unpack(rom_url_name(spec).unwrap_or(""), data, None);
+    }
+    let (path, entry) = split_rom_path(spec);
+    let data = fs::read(path).map_err(|source| RomLoadError::Io { path: path.to_string(), source })?;
+    unpack(path, data, entry)
+}
+
+// what `rust-8 -` reads, piped in from an assembler say
+pub fn read_rom_from<R: Read>(reader: R) -> Result<Vec<u8>, RomLoadError> {
+    read_limited(reader, |source| RomLoadError::Io { path: String::from("standard input"), source })
+}
+
+// the file itself, or the ROM inside it for a .zip or .gz name
+fn unpack(name: &str, data: Vec<u8>, entry: Option<&str>) -> Result<Vec<u8>, RomLoadError> {
+    if entry.is_some() || has_extension(name, "zip") {
+        extract_zip_rom(&data, entry)
+    } else if has_extension(name, "gz") {
+        gunzip_rom(&data)
+    } else {
+        Ok(data)
+    }
+}
+
+// The ROM inside a zip archive: the named entry, or else the only .ch8 file in it.
+pub fn extract_zip_rom(archive: &[u8], entry: Option<&str>) -> Result<Vec<u8>, RomLoadError> {
+    let mut archive = ZipArchive::new(Cursor::new(archive)).map_err(|err| RomLoadError::Zip(err.to_string()))?;
+    let name = match entry {
+        Some(name) => name.to_string(),
+        None => {
+            let mut roms: Vec<String> = archive
+                .file_names()
+                .filter_map(Result::ok)
+                .map(|name| name.into_owned())
+                .filter(|name| !name.ends_with('/') && has_extension(name, "ch8"))
+                .collect();
+            roms.sort();
+            match roms.len() {
+                0 => return Err(RomLoadError::NoRomInArchive),
+                1 => roms.remove(0),
+                _ => return Err(RomLoadError::SeveralRoms(roms)),
+            }
+        }
+    };
+
+    let file = match archive.by_name(&name) {
+        Ok(file) => file,
+        Err(zip::result::ZipError::FileNotFound) => return Err(RomLoadError::NoSuchEntry(name)),
+        Err(err) => return Err(RomLoadError::Zip(err.to_string())),
+    };
+    read_limited(file, |err| RomLoadError::Zip(format!("{}: {}", name, err)))
+}
+
+pub fn gunzip_rom(data: &[u8]) -> Result<Vec<u8>, RomLoadError> {
+    read_limited(GzDecoder::new(data), |err| RomLoadError::Gzip(err.to_string()))
+}
+
+fn read_limited<R: Read>(reader: R, error: impl Fn(io::Error) -> RomLoadError) -> Result<Vec<u8>, RomLoadError> {
+    let mut data = Vec::new();
+    reader.take(MAX_UNPACKED + 1).read_to_end(&mut data).map_err(error)?;
+    if data.len() as u64 > MAX_UNPACKED {
+        return Err(RomLoadError::TooLarge { max: MAX_UNPACKED as usize });
+    }
+    Ok(data)
+}
+
+fn has_extension(path: &str, extension: &str) -> bool {
+    Path::new(path).extension().is_some_and(|found| found.eq_ignore_ascii_case(extension))
+}
@@ -0,0 +1,67 @@
+use crate::display::Framebuffer;
+
+// The screen as text to read over SSH or paste into a bug report: a character per pixel,
+// so 32 lines of 64 in low resolution and 64 of 128 in high, or with half_blocks two rows
+// of pixels per line. The ruler numbers the columns along the top, a line per digit, and
+// puts each line's first row number in front of it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AsciiDump {
+    pub on: char,
+    pub off: char,
+    pub ruler: bool,
+    // ▀, ▄ and █ for the lit pixels of each pair of rows, `off` for neither; `on` isn't used
+    pub half_blocks: bool,
+}
+
+impl Default for AsciiDump {
+    fn default() -> Self {
+        AsciiDump {
+            on: '█',
+            off: '·',
+            ruler: false,
+            half_blocks: false,
+        }
+    }
+}
+
+impl AsciiDump {
+    pub fn render(&self, display: &Framebuffer) -> String {
+        let (width, height) = (display.width(), display.height());
+        let label_width = height.saturating_sub(1).to_string().len();
+        let mut text = String::new();
+        if self.ruler {
+            // a line per digit, most significant first, each digit over the column it starts
+            let margin = " ".repeat(label_width + 1);
+            for place in (0..width.saturating_sub(1).to_string().len() as u32).rev() {
+                let step = 10usize.pow(place);
+                let line: String = (0..width)
+                    .map(|x| match x % step {
+                        0 => char::from_digit((x / step % 10) as u32, 10).unwrap(),
+                        _ => ' ',
+                    })
+                    .collect();
+                text.push_str(&format!("{}{}\n", margin, line.trim_end()));
+            }
+        }
+        let rows_per_line = if self.half_blocks { 2 } else { 1 };
+        for y in (0..height).step_by(rows_per_line) {
+            if self.ruler {
+                text.push_str(&format!("{:>w$} ", y, w = label_width));
+            }
+            text.extend((0..width).map(|x| {
+                if !self.half_blocks {
+                    return if display.pixel(x, y) { self.on } else { self.off };
+                }
+                let bottom = y + 1 < height && display.pixel(x, y + 1);
+                match (display.pixel(x, y), bottom) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => self.off,
+                }
+            }));
+            text.push('\n');
+        }
+        text
+    }
+}
@@ -0,0 +1,84 @@
+use std::{
+    convert::TryFrom,
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{Chip8, Chip8Error, EmulatorError, MachineState, StateDump};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// Savestates written when a session ends and picked up again by --resume, one file per
+// ROM named after its hash. Nothing here ever stops the emulator: a save that can't be
+// written or read is a warning, and the ROM boots fresh instead.
+#[derive(Serialize, Deserialize)]
+struct Autosave {
+    rom_hash: u64,
+    state: MachineState,
+}
+
+// $XDG_DATA_HOME/rust-8/saves, or ~/.local/share/rust-8/saves
+pub fn default_save_dir() -> Option<PathBuf> {
+    let data = match env::var_os("XDG_DATA_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".local").join("share"),
+    };
+    Some(data.join("rust-8").join("saves"))
+}
+
+pub fn autosave_path(dir: &Path, rom_hash: u64) -> PathBuf {
+    dir.join(format!("{:016x}.json", rom_hash))
+}
+
+pub fn save_autosave(dir: &Path, chip8: &Chip8) -> io::Result<()> {
+    let autosave = Autosave {
+        rom_hash: chip8.rom_hash(),
+        state: chip8.save_state(),
+    };
+    let text = serde_json::to_string(&autosave).map_err(io::Error::other)?;
+    fs::create_dir_all(dir)?;
+    fs::write(autosave_path(dir, autosave.rom_hash), text)
+}
+
+// true when the machine now holds the saved session; a missing save is not worth a warning
+pub fn restore_autosave(dir: &Path, chip8: &mut Chip8) -> bool {
+    let file = autosave_path(dir, chip8.rom_hash());
+    let text = match fs::read_to_string(&file) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return false,
+        Err(err) => return warn(&file, &err.to_string()),
+    };
+    let autosave: Autosave = match serde_json::from_str(&text) {
+        Ok(autosave) => autosave,
+        Err(err) => return warn(&file, &err.to_string()),
+    };
+    if autosave.rom_hash != chip8.rom_hash() {
+        return warn(&file, "it was saved from a different ROM");
+    }
+    match chip8.load_state(autosave.state) {
+        Ok(()) => true,
+        Err(err) => warn(&file, &err.to_string()),
+    }
+}
+
+fn warn(file: &Path, reason: &str) -> bool {
+    log::warn!("not resuming from {}: {}; starting fresh", file.display(), reason);
+    false
+}
+
+// Any of the state files rust-8 writes: an autosave, a --dump-state-on-exit StateDump, or
+// a bare MachineState.
+pub fn read_state(file: &Path) -> Result<MachineState, EmulatorError> {
+    let text = fs::read_to_string(file).map_err(|err| io::Error::new(err.kind(), format!("{}: {}", file.display(), err)))?;
+    let invalid = |err: String| Chip8Error::InvalidState(format!("{}: {}", file.display(), err));
+    let value: Value = serde_json::from_str(&text).map_err(|err| invalid(err.to_string()))?;
+    let state = if value.get("state").is_some() {
+        serde_json::from_value::<Autosave>(value).map(|autosave| autosave.state)
+    } else if value.get("registers").is_some() {
+        let dump = serde_json::from_value::<StateDump>(value).map_err(|err| invalid(err.to_string()))?;
+        return Ok(MachineState::try_from(dump).map_err(|err| invalid(err.to_string()))?);
+    } else {
+        serde_json::from_value::<MachineState>(value)
+    };
+    Ok(state.map_err(|err| invalid(err.to_string()))?)
+}
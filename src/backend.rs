@@ -0,0 +1,134 @@
+use crate::display::Framebuffer;
+use crate::error::EmulatorError;
+use crate::filter::Filter;
+use crate::keypad::Keypad;
+use crate::theme::ColorTheme;
+
+pub trait DisplayBackend {
+    fn is_open(&self) -> bool;
+    // intensity holds a brightness from 0 to 1 for every pixel, for backends that can show shades
+    fn present(&mut self, framebuffer: &Framebuffer, intensity: &[f32]) -> Result<(), EmulatorError>;
+    // something besides the screen changed the picture since the last present: the theme,
+    // the bell, the size of the window
+    fn needs_redraw(&self) -> bool;
+    // stands in for present on a frame where nothing changed, handling the window's events
+    // and keeping the pace without drawing
+    fn idle(&mut self) -> Result<(), EmulatorError>;
+    fn set_theme(&mut self, theme: ColorTheme);
+    fn set_filter(&mut self, filter: Filter);
+    // the buzzer is sounding; backends with a visual bell show it on the next present
+    fn set_bell(&mut self, on: bool);
+    fn set_title(&mut self, title: &str);
+    // moves one step up (positive) or down (negative) the list of window sizes
+    fn change_scale(&mut self, step: i32) -> Result<(), EmulatorError>;
+    // between a normal window and one covering the whole screen
+    fn toggle_fullscreen(&mut self) -> Result<(), EmulatorError>;
+    // shows or hides the on-screen keyboard, see VirtualKeyboard
+    fn toggle_keyboard(&mut self) -> Result<(), EmulatorError>;
+}
+
+pub trait InputBackend {
+    // refreshes the keypad with whatever the host reports as held down
+    fn poll(&mut self, keypad: &mut Keypad);
+    // whether the host holds CHIP-8 key `key`, 0 to F, right now
+    fn is_key_pressed(&self, key: u8) -> bool;
+    fn quit_requested(&self) -> bool;
+    // false while the player is busy with another window
+    fn has_focus(&self) -> bool;
+    // true once per press of the reset key
+    fn reset_requested(&mut self) -> bool;
+    // true once per press of the key that switches to the next color theme
+    fn theme_requested(&mut self) -> bool;
+    // true once per press of the key that switches to the next presentation filter
+    fn filter_requested(&mut self) -> bool;
+    // true once per press of the mute key
+    fn mute_requested(&mut self) -> bool;
+    // true once per press of the undo key
+    fn undo_requested(&mut self) -> bool;
+    // true once per press of the pause key
+    fn pause_requested(&mut self) -> bool;
+    // true once per press of the key that moves on to the next ROM of the library
+    fn next_rom_requested(&mut self) -> bool;
+    // +1 or -1 once per press of the keys that grow or shrink the window, 0 otherwise
+    fn scale_requested(&mut self) -> i32;
+    // true once per press of the fullscreen key
+    fn fullscreen_requested(&mut self) -> bool;
+    // true once per press of the key that shows or hides the on-screen keyboard
+    fn keyboard_requested(&mut self) -> bool;
+}
+
+pub trait AudioSink {
+    // the buzzer should sound while this is true; called once per emulated frame
+    fn set_tone(&mut self, on: bool);
+}
+
+// for machines without sound and for tests: remembers the tone state and plays nothing
+#[derive(Debug, Default)]
+pub struct NullSink {
+    pub on: bool,
+}
+
+impl AudioSink for NullSink {
+    fn set_tone(&mut self, on: bool) {
+        self.on = on;
+    }
+}
+
+// The buzzer as a machine that drives itself sees it: told when the sound timer starts
+// and stops, see Chip8::set_audio. The emulation thread reports the buzzer through
+// FrameSnapshot::sound to an AudioSink instead.
+pub trait AudioBackend {
+    fn start_beep(&mut self);
+    fn stop_beep(&mut self);
+    fn is_beeping(&self) -> bool;
+}
+
+// for tests: remembers whether a beep is in progress and plays nothing
+#[derive(Debug, Default)]
+pub struct NullAudioBackend {
+    beeping: bool,
+}
+
+impl AudioBackend for NullAudioBackend {
+    fn start_beep(&mut self) {
+        self.beeping = true;
+    }
+
+    fn stop_beep(&mut self) {
+        self.beeping = false;
+    }
+
+    fn is_beeping(&self) -> bool {
+        self.beeping
+    }
+}
+
+// Plays the beeps on any AudioSink, such as the frontend's cpal output. This stands in
+// for a rodio backend: rodio plays through cpal itself, so it would only add a second
+// dependency on the same device code.
+pub struct SinkAudioBackend<S: AudioSink> {
+    sink: S,
+    beeping: bool,
+}
+
+impl<S: AudioSink> SinkAudioBackend<S> {
+    pub fn new(sink: S) -> Self {
+        SinkAudioBackend { sink, beeping: false }
+    }
+}
+
+impl<S: AudioSink> AudioBackend for SinkAudioBackend<S> {
+    fn start_beep(&mut self) {
+        self.beeping = true;
+        self.sink.set_tone(true);
+    }
+
+    fn stop_beep(&mut self) {
+        self.beeping = false;
+        self.sink.set_tone(false);
+    }
+
+    fn is_beeping(&self) -> bool {
+        self.beeping
+    }
+}
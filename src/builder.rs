@@ -0,0 +1,176 @@
+use rand::RngCore;
+
+use crate::backend::AudioBackend;
+use crate::chip8::Chip8;
+use crate::inspector::Chip8Inspector;
+use crate::symbols::SymbolTable;
+use crate::syscall::SyscallHandler;
+use crate::theme::ColorTheme;
+use crate::quirks::{MemoryProtection, QuirkConfig, UnknownOpcodePolicy};
+use crate::variant::ChipVariant;
+
+pub struct Chip8Builder {
+    variant: ChipVariant,
+    theme: ColorTheme,
+    phosphor_decay: f32,
+    quirks: QuirkConfig,
+    predecode: bool,
+    warn_odd_rom: bool,
+    rng: Option<Box<dyn RngCore>>,
+    rng_seed: Option<u64>,
+    inspector: Option<Box<dyn Chip8Inspector>>,
+    syscall_handler: Option<SyscallHandler>,
+    audio: Option<Box<dyn AudioBackend>>,
+    font: Option<[[u8; 5]; 16]>,
+    precise_timing: bool,
+    symbols: Option<SymbolTable>,
+}
+
+impl Chip8Builder {
+    pub fn new() -> Self {
+        Chip8Builder {
+            variant: ChipVariant::default(),
+            theme: ColorTheme::default(),
+            phosphor_decay: 0.0,
+            quirks: QuirkConfig::default(),
+            predecode: false,
+            warn_odd_rom: false,
+            rng: None,
+            rng_seed: None,
+            inspector: None,
+            syscall_handler: None,
+            audio: None,
+            font: None,
+            precise_timing: false,
+            symbols: None,
+        }
+    }
+
+    // also resets the quirks to the variant's defaults, so call it before with_quirks
+    pub fn with_variant(mut self, variant: ChipVariant) -> Self {
+        self.variant = variant;
+        self.quirks = variant.default_quirks();
+        self
+    }
+
+    pub fn with_theme(mut self, theme: ColorTheme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    pub fn with_phosphor_decay(mut self, decay: f32) -> Self {
+        self.phosphor_decay = decay;
+        self
+    }
+
+    pub fn with_quirks(mut self, quirks: QuirkConfig) -> Self {
+        self.quirks = quirks;
+        self
+    }
+
+    pub fn with_unknown_opcode_policy(mut self, policy: UnknownOpcodePolicy) -> Self {
+        self.quirks.unknown_opcode_policy = policy;
+        self
+    }
+
+    pub fn with_memory_protection(mut self, protection: MemoryProtection) -> Self {
+        self.quirks.memory_protection = protection;
+        self
+    }
+
+    pub fn with_predecode(mut self, predecode: bool) -> Self {
+        self.predecode = predecode;
+        self
+    }
+
+    pub fn with_odd_rom_warning(mut self, enabled: bool) -> Self {
+        self.warn_odd_rom = enabled;
+        self
+    }
+
+    pub fn with_rng(mut self, rng: Box<dyn RngCore>) -> Self {
+        self.rng = Some(rng);
+        self.rng_seed = None;
+        self
+    }
+
+    // makes CXNN deterministic, for tests, benchmarks and recordings
+    pub fn with_rng_seed(mut self, seed: u64) -> Self {
+        self.rng = None;
+        self.rng_seed = Some(seed);
+        self
+    }
+
+    pub fn with_inspector(mut self, inspector: Box<dyn Chip8Inspector>) -> Self {
+        self.inspector = Some(inspector);
+        self
+    }
+
+    pub fn with_syscall_handler(mut self, handler: SyscallHandler) -> Self {
+        self.syscall_handler = Some(handler);
+        self
+    }
+
+    pub fn with_audio(mut self, audio: Box<dyn AudioBackend>) -> Self {
+        self.audio = Some(audio);
+        self
+    }
+
+    // sixteen 4x5 glyphs for 0 to F, one byte per row with the pixels in the top nibble
+    pub fn with_custom_font(mut self, sprites: [[u8; 5]; 16]) -> Self {
+        self.font = Some(sprites);
+        self
+    }
+
+    // paces EmulatorThread with a PreciseClock that spins out the end of every frame
+    pub fn with_precise_timing(mut self, precise: bool) -> Self {
+        self.precise_timing = precise;
+        self
+    }
+
+    // names the trace log gives addresses
+    pub fn with_symbols(mut self, symbols: SymbolTable) -> Self {
+        self.symbols = Some(symbols);
+        self
+    }
+
+    // the built machine has its font loaded and is ready for load_rom
+
+    pub fn build(self) -> Chip8 {
+        let mut chip8 = Chip8::new();
+        chip8.set_variant(self.variant);
+        chip8.set_theme(self.theme);
+        chip8.set_phosphor_decay(self.phosphor_decay);
+        chip8.set_quirks(self.quirks);
+        chip8.set_predecode(self.predecode);
+        chip8.set_warn_odd_rom(self.warn_odd_rom);
+        chip8.set_precise_timing(self.precise_timing);
+        chip8.set_symbols(self.symbols);
+        if let Some(rng) = self.rng {
+            chip8.set_rng(rng);
+        }
+        if let Some(seed) = self.rng_seed {
+            chip8.seed_rng(seed);
+        }
+        if let Some(inspector) = self.inspector {
+            chip8.set_inspector(inspector);
+        }
+        if let Some(handler) = self.syscall_handler {
+            chip8.set_syscall_handler(handler);
+        }
+        if let Some(audio) = self.audio {
+            chip8.set_audio(audio);
+        }
+        if let Some(font) = self.font {
+            chip8.set_font(font);
+        }
+        chip8.load_sprites();
+        chip8
+    }
+}
+
+impl Default for Chip8Builder {
+    fn default() -> Self {
+        Chip8Builder::new()
+    }
+}
@@ -0,0 +1,36 @@
+use crate::variant::ChipVariant;
+
+// A ROM compiled into the binary, so --builtin and selftest work without any files.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BuiltinRom {
+    // what --builtin takes
+    pub name: &'static str,
+    pub title: &'static str,
+    pub author: &'static str,
+    pub description: &'static str,
+    pub variant: ChipVariant,
+    pub data: &'static [u8],
+}
+
+pub const BUILTIN_ROMS: [BuiltinRom; 2] = [
+    BuiltinRom {
+        name: "ibm-logo",
+        title: "IBM Logo",
+        author: "unknown",
+        description: "draws the IBM logo with CLS, LD, ADD and DRW, then loops",
+        variant: ChipVariant::Chip8Original,
+        data: include_bytes!("builtin/ibm-logo.ch8"),
+    },
+    BuiltinRom {
+        name: "test-opcode",
+        title: "CHIP-8 opcode test",
+        author: "corax89",
+        description: "runs the arithmetic, skip and memory opcodes and shows OK or a failure code for each",
+        variant: ChipVariant::Chip8Original,
+        data: include_bytes!("builtin/test-opcode.ch8"),
+    },
+];
+
+pub fn builtin_rom(name: &str) -> Option<&'static BuiltinRom> {
+    BUILTIN_ROMS.iter().find(|rom| rom.name == name)
+}
@@ -0,0 +1,157 @@
+use std::fmt;
+
+use crate::instruction::Instruction;
+use crate::snapshot::StackSnapshot;
+use crate::symbols::SymbolTable;
+
+// A CALL that hasn't returned yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame {
+    // the routine called; None when the frame turned up on the stack and the instruction
+    // before its return address isn't a CALL
+    pub entry: Option<u16>,
+    pub call_site: u16,
+}
+
+impl Frame {
+    pub fn return_address(&self) -> u16 {
+        self.call_site.wrapping_add(2)
+    }
+}
+
+// Something a ROM did with the stack other than call a routine and return from it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StackAnomaly {
+    // the stack changed other than by the CALL or RET at `pc`: a machine code routine, a
+    // restored state or an undo
+    Rewritten { pc: u16 },
+    // a return address on the stack that doesn't come after a CALL
+    NotACall { pc: u16, return_address: u16 },
+    // a CALL from a call site that already has a frame: recursion, or a routine that was
+    // left with a jump instead of RET and leaves its frame behind every time
+    Reentered { pc: u16 },
+}
+
+impl fmt::Display for StackAnomaly {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StackAnomaly::Rewritten { pc } => write!(f, "the stack changed without a CALL or RET at {:#06X}", pc),
+            StackAnomaly::NotACall { pc, return_address } => {
+                write!(f, "return address {:#06X} on the stack at {:#06X} doesn't follow a CALL", return_address, pc)
+            }
+            StackAnomaly::Reentered { pc } => {
+                write!(f, "CALL at {:#06X} again before it returned: recursion, or a routine left by a jump", pc)
+            }
+        }
+    }
+}
+
+// The calls the ROM is in, kept from the CALLs and RETs it runs rather than read off the
+// raw stack, which only holds return addresses. Whenever the two disagree the raw stack
+// wins and the difference is recorded as an anomaly.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CallStack {
+    // outermost first
+    frames: Vec<Frame>,
+    anomalies: Vec<StackAnomaly>,
+}
+
+impl CallStack {
+    pub fn new() -> Self {
+        CallStack::default()
+    }
+
+    // the frames on a stack already in use, for a session that starts part way through
+    pub fn from_stack(pc: u16, stack: &StackSnapshot, ram: &[u8]) -> Self {
+        let mut call_stack = CallStack::new();
+        call_stack.resync(pc, stack, ram);
+        call_stack
+    }
+
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    pub fn depth(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn anomalies(&self) -> &[StackAnomaly] {
+        &self.anomalies
+    }
+
+    // Call after every instruction that ran, with its address, the stack after it and RAM
+    // to find the routines of frames that turn up on the stack.
+    pub fn update(&mut self, pc: u16, instruction: Instruction, stack: &StackSnapshot, ram: &[u8]) {
+        match instruction {
+            Instruction::Call(entry) => {
+                if self.frames.iter().any(|frame| frame.call_site == pc) {
+                    self.anomalies.push(StackAnomaly::Reentered { pc });
+                }
+                self.frames.push(Frame {
+                    entry: Some(entry),
+                    call_site: pc,
+                });
+            }
+            Instruction::Ret => {
+                self.frames.pop();
+            }
+            _ => {}
+        }
+        if !self.matches(stack) {
+            self.anomalies.push(StackAnomaly::Rewritten { pc });
+            self.resync(pc, stack, ram);
+        }
+    }
+
+    // Innermost first, like a debugger's backtrace:
+    //
+    //     #0 0x0456 draw_paddle (called from 0x0212 main+18)
+    //     #1 0x0200 main (called from 0x0ABC)
+    pub fn backtrace(&self, symbols: Option<&SymbolTable>) -> Vec<String> {
+        let label = |address: u16| match symbols.and_then(|symbols| symbols.label(address)) {
+            Some(label) => format!("{:#06X} {}", address, label),
+            None => format!("{:#06X}", address),
+        };
+        self.frames
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(n, frame)| {
+                let entry = frame.entry.map(label).unwrap_or_else(|| String::from("?"));
+                format!("#{} {} (called from {})", n, entry, label(frame.call_site))
+            })
+            .collect()
+    }
+
+    fn matches(&self, stack: &StackSnapshot) -> bool {
+        let raw = &stack.stack[..(stack.sp as usize).min(stack.stack.len())];
+        raw.len() == self.frames.len() && raw.iter().zip(self.frames.iter()).all(|(address, frame)| *address == frame.return_address())
+    }
+
+    // keeps the frames the raw stack still agrees with and works out the rest from the
+    // CALLs before their return addresses
+    fn resync(&mut self, pc: u16, stack: &StackSnapshot, ram: &[u8]) {
+        let raw = &stack.stack[..(stack.sp as usize).min(stack.stack.len())];
+        let kept = raw
+            .iter()
+            .zip(self.frames.iter())
+            .take_while(|(address, frame)| **address == frame.return_address())
+            .count();
+        self.frames.truncate(kept);
+        for &return_address in &raw[kept..] {
+            let call_site = return_address.wrapping_sub(2);
+            let entry = match (ram.get(call_site as usize), ram.get(call_site as usize + 1)) {
+                (Some(&hb), Some(&lb)) => match Instruction::decode(hb, lb) {
+                    Instruction::Call(nnn) => Some(nnn),
+                    _ => None,
+                },
+                _ => None,
+            };
+            if entry.is_none() {
+                self.anomalies.push(StackAnomaly::NotACall { pc, return_address });
+            }
+            self.frames.push(Frame { entry, call_site });
+        }
+    }
+}
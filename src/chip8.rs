@@ -0,0 +1,1071 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt, fs,
+    path::Path,
+};
+
+use rand::{rngs::SmallRng, Rng, RngCore, SeedableRng};
+
+use crate::backend::{AudioBackend, InputBackend, NullAudioBackend};
+use crate::archive::read_rom;
+use crate::clock::Instant;
+use crate::cpu::Cpu;
+use crate::display::{self, Framebuffer};
+use crate::error::{Chip8Error, EmulatorError};
+use crate::inspector::Chip8Inspector;
+use crate::instruction::Instruction;
+use crate::keypad::Keypad;
+use crate::quirks::{MemoryProtection, QuirkConfig, UnknownOpcodePolicy};
+use crate::snapshot::{Chip8Snapshot, CpuSnapshot, StackSnapshot, TimerSnapshot};
+use crate::syscall::{SyscallHandler, SyscallResult};
+use crate::stack::Stack;
+use crate::profiler::{CallTreeReport, Profiler};
+use crate::symbols::SymbolTable;
+use crate::theme::ColorTheme;
+use crate::timer::Timer;
+use crate::variant::ChipVariant;
+
+pub use crate::memory::{sprite_address, PROGRAM_START, SPRITE_DATA};
+
+// top left corner of the sprite atlas, centering the 19x23 grid
+const ATLAS_X: usize = 22;
+const ATLAS_Y: usize = 4;
+
+// how many executed instructions are kept for crash reports
+const HISTORY_LEN: usize = 32;
+
+const RAM_SIZE: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InstructionResult {
+    Executed { pc: u16 },
+    // FX0A found no key down and will run again on the next step
+    WaitingForKey,
+    // the program is stuck on a jump to itself and nothing can ever change that
+    Halted { pc: u16 },
+}
+
+impl fmt::Display for InstructionResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InstructionResult::Executed { pc } => write!(f, "Instruction executed at PC={:#05X}", pc),
+            InstructionResult::WaitingForKey => write!(f, "Waiting for key press"),
+            InstructionResult::Halted { pc } => write!(f, "Halted at PC={:#05X}", pc),
+        }
+    }
+}
+
+pub struct Chip8 {
+    pub(crate) cpu: Cpu,
+    pub(crate) ram: [u8; RAM_SIZE],
+    pub(crate) display: Framebuffer,
+    // brightness of each pixel from 0 to 1, lit pixels are 1 and unlit ones fade by phosphor_decay
+    display_intensity: Vec<f32>,
+    phosphor_decay: f32,
+    pub(crate) stack: Stack,
+    keypad: Keypad,
+    pub(crate) hour: Timer,
+    pub(crate) quirks: QuirkConfig,
+    variant: ChipVariant,
+    theme: ColorTheme,
+    rng: Box<dyn RngCore>,
+    // (seed, numbers drawn since) when rng is a seeded SmallRng, which is enough to
+    // bring it back to the same point
+    pub(crate) rng_seed: Option<(u64, u64)>,
+    rom_len: usize,
+    pub(crate) rom_name: Option<String>,
+    pub(crate) rom_hash: u64,
+    pub(crate) history: VecDeque<(u16, Instruction)>,
+    predecoded: Option<Vec<Option<Instruction>>>,
+    pub(crate) halted: bool,
+    // registers as they were before the last instruction, for undo_step
+    pre_step_snapshot: Option<Chip8Snapshot>,
+    warn_odd_rom: bool,
+    // for EmulatorThread, which spins out the end of each frame instead of only sleeping
+    precise_timing: bool,
+    // labels for the trace log, from --symbols
+    symbols: Option<SymbolTable>,
+    inspector: Option<Box<dyn Chip8Inspector>>,
+    // without one, 0NNN is an unknown opcode
+    syscall_handler: Option<SyscallHandler>,
+    // (title, bytes) of the ROMs switch_to_rom can pick from, and which one is running
+    rom_library: Vec<(String, Vec<u8>)>,
+    rom_index: Option<usize>,
+    // instructions run since construction or the last reset, and when that was
+    instructions_executed: u64,
+    started: Instant,
+    // DXYN instructions run since construction or the last reset
+    draws: u64,
+    // started and stopped by tick_timers
+    audio: Box<dyn AudioBackend>,
+    // what load_sprites puts in RAM, SPRITE_DATA unless set_font replaced it
+    font: [u8; 80],
+    // addresses of the unknown opcodes already warned about since the last reset
+    unknown_opcodes: HashSet<u16>,
+    // the call tree since enable_profiling, started over by a reset
+    profiler: Option<Profiler>,
+    // the screen changed since take_display_dirty last looked, so a frontend has to draw it
+    display_dirty: bool,
+}
+
+impl Chip8 {
+    pub fn new() -> Self {
+        let display = Framebuffer::new();
+        Chip8 {
+            cpu: Cpu::new(),
+            ram: [0; RAM_SIZE],
+            display_intensity: vec![0.0; display.width() * display.height()],
+            display,
+            phosphor_decay: 0.0,
+            stack: Stack::new(),
+            keypad: Keypad::new(),
+            hour: Timer::new(),
+            quirks: QuirkConfig::default(),
+            variant: ChipVariant::default(),
+            theme: ColorTheme::default(),
+            rng: Box::new(rand::thread_rng()),
+            rng_seed: None,
+            rom_len: 0,
+            rom_name: None,
+            rom_hash: 0,
+            history: VecDeque::with_capacity(HISTORY_LEN),
+            predecoded: None,
+            halted: false,
+            pre_step_snapshot: None,
+            warn_odd_rom: false,
+            precise_timing: false,
+            symbols: None,
+            inspector: None,
+            syscall_handler: None,
+            rom_library: Vec::new(),
+            rom_index: None,
+            instructions_executed: 0,
+            started: Instant::now(),
+            draws: 0,
+            audio: Box::new(NullAudioBackend::default()),
+            font: SPRITE_DATA,
+            unknown_opcodes: HashSet::new(),
+            profiler: None,
+            display_dirty: true,
+        }
+    }
+
+    pub fn set_rng(&mut self, rng: Box<dyn RngCore>) {
+        self.rng = rng;
+        self.rng_seed = None;
+    }
+
+    // a SmallRng from `seed`, which unlike other generators can be saved in a MachineState
+    pub fn seed_rng(&mut self, seed: u64) {
+        self.rng = Box::new(SmallRng::seed_from_u64(seed));
+        self.rng_seed = Some((seed, 0));
+    }
+
+    pub fn rng_seed(&self) -> Option<u64> {
+        self.rng_seed.map(|(seed, _)| seed)
+    }
+
+    // seeds like seed_rng and throws away `drawn` numbers, picking up where a saved
+    // generator left off
+    pub(crate) fn replay_rng(&mut self, seed: u64, drawn: u64) {
+        self.seed_rng(seed);
+        for _ in 0..drawn {
+            let _: u8 = self.rng.gen();
+        }
+        self.rng_seed = Some((seed, drawn));
+    }
+
+    // after RAM and the screen were replaced wholesale: the afterglow starts over from
+    // the lit pixels, the predecoded program is redone and undo has nothing to go back to
+    pub(crate) fn refresh(&mut self) {
+        self.display_dirty = true;
+        self.display_intensity.clear();
+        self.update_intensity();
+        if self.predecoded.is_some() {
+            self.predecoded = Some(self.predecode());
+        }
+        self.pre_step_snapshot = None;
+    }
+
+    pub fn set_audio(&mut self, audio: Box<dyn AudioBackend>) {
+        self.audio = audio;
+    }
+
+    // replaces the built-in font from the next load_sprites (or reset) on
+    pub fn set_font(&mut self, font: [[u8; 5]; 16]) {
+        for (bytes, sprite) in self.font.chunks_mut(5).zip(font.iter()) {
+            bytes.copy_from_slice(sprite);
+        }
+    }
+
+    pub fn set_quirks(&mut self, quirks: QuirkConfig) {
+        self.quirks = quirks;
+    }
+
+    // only selects which instructions are available; quirks are set separately
+    pub fn set_variant(&mut self, variant: ChipVariant) {
+        self.variant = variant;
+    }
+
+    pub fn variant(&self) -> ChipVariant {
+        self.variant
+    }
+
+    // the framebuffer itself is 1 bit per pixel, the theme only picks the colors it is rendered in
+    pub fn set_theme(&mut self, theme: ColorTheme) {
+        self.theme = theme;
+    }
+
+    pub fn theme(&self) -> ColorTheme {
+        self.theme
+    }
+
+    pub fn render_into(&self, buffer: &mut [u32]) {
+        let (fg, bg) = (self.theme.fg(), self.theme.bg());
+        for (n, pixel) in buffer.iter_mut().take(self.pixel_count()).enumerate() {
+            *pixel = display::intensity_color(self.pixel_intensity(n), fg, bg);
+        }
+    }
+
+    // the screen as render_into colors it, as [r, g, b, 0xFF] bytes row by row for
+    // renderers that upload textures, width * height * 4 bytes of it at the current resolution
+    pub fn render_to_rgba(&self, buf: &mut [u8]) -> Result<(), Chip8Error> {
+        self.render_bytes(buf, 4)
+    }
+
+    // like render_to_rgba without the alpha byte, width * height * 3 bytes
+    pub fn render_to_rgb(&self, buf: &mut [u8]) -> Result<(), Chip8Error> {
+        self.render_bytes(buf, 3)
+    }
+
+    fn render_bytes(&self, buf: &mut [u8], channels: usize) -> Result<(), Chip8Error> {
+        let needed = self.pixel_count() * channels;
+        if buf.len() < needed {
+            return Err(Chip8Error::BufferTooSmall { needed, len: buf.len() });
+        }
+        let (fg, bg) = (self.theme.fg(), self.theme.bg());
+        for (n, pixel) in buf.chunks_exact_mut(channels).take(self.pixel_count()).enumerate() {
+            let color = display::intensity_color(self.pixel_intensity(n), fg, bg);
+            let rgba = [(color >> 16) as u8, (color >> 8) as u8, color as u8, 0xFF];
+            pixel.copy_from_slice(&rgba[..channels]);
+        }
+        Ok(())
+    }
+
+    // how much of its brightness an unlit pixel keeps each frame: 0 switches pixels off
+    // at once, values closer to 1 fade them out like slow phosphor and hide flicker
+    pub fn set_phosphor_decay(&mut self, decay: f32) {
+        self.phosphor_decay = decay.clamp(0.0, 1.0);
+    }
+
+    pub fn intensity(&self) -> &[f32] {
+        &self.display_intensity
+    }
+
+    fn pixel_count(&self) -> usize {
+        self.display.width() * self.display.height()
+    }
+
+    // The afterglow is only brought up to date once a frame, but a pixel drawn since then
+    // is already lit and one cleared without any afterglow is already dark. Pixel n of the
+    // current resolution, row by row.
+    fn pixel_intensity(&self, n: usize) -> f32 {
+        let width = self.display.width();
+        if self.display.pixel(n % width, n / width) {
+            1.0
+        } else if self.phosphor_decay == 0.0 {
+            0.0
+        } else {
+            self.display_intensity.get(n).copied().unwrap_or(0.0)
+        }
+    }
+
+    fn update_intensity(&mut self) {
+        let width = self.display.width();
+        // a change of resolution starts the afterglow over
+        self.display_intensity.resize(width * self.display.height(), 0.0);
+        for (n, value) in self.display_intensity.iter_mut().enumerate() {
+            *value = if self.display.pixel(n % width, n / width) {
+                1.0
+            } else {
+                *value * self.phosphor_decay
+            };
+        }
+    }
+
+    // instructions are 2 bytes, so an odd length usually means a truncated or non-CHIP-8 file
+    pub fn set_warn_odd_rom(&mut self, enabled: bool) {
+        self.warn_odd_rom = enabled;
+    }
+
+    pub fn set_precise_timing(&mut self, precise: bool) {
+        self.precise_timing = precise;
+    }
+
+    pub fn precise_timing(&self) -> bool {
+        self.precise_timing
+    }
+
+    pub fn set_symbols(&mut self, symbols: Option<SymbolTable>) {
+        self.symbols = symbols;
+    }
+
+    pub fn symbols(&self) -> Option<&SymbolTable> {
+        self.symbols.as_ref()
+    }
+
+    // starts timing subroutines from here, if it wasn't already
+    pub fn enable_profiling(&mut self) -> &mut Profiler {
+        self.profiler.get_or_insert_with(Profiler::new)
+    }
+
+    // empty unless enable_profiling was called
+    pub fn profiling_report(&self) -> CallTreeReport {
+        self.profiler.as_ref().map(Profiler::report).unwrap_or_default()
+    }
+
+    // decodes the whole ROM up front so hot loops skip the decoder; writes into the
+    // program area drop the affected entries and those addresses decode on the fly again
+    pub fn set_predecode(&mut self, enabled: bool) {
+        self.predecoded = if enabled {
+            Some(self.predecode())
+        } else {
+            None
+        };
+    }
+
+    fn predecode(&self) -> Vec<Option<Instruction>> {
+        let mut table = vec![None; self.ram.len()];
+        let start = PROGRAM_START as usize;
+        let end = (start + self.rom_len).min(self.ram.len() - 1);
+        for (address, entry) in table.iter_mut().enumerate().take(end).skip(start) {
+            *entry = Some(Instruction::decode(self.ram[address], self.ram[address + 1]));
+        }
+        table
+    }
+
+    pub fn display(&self) -> &Framebuffer {
+        &self.display
+    }
+
+    // for a machine built to replace `previous`: its screen carries on from the old one's
+    // generations, so a renderer following deltas of the old screen starts over cleanly
+    pub fn take_over_display(&mut self, previous: &Chip8) {
+        let mut display = previous.display.clone();
+        display.replace_from(&self.display);
+        self.display = display;
+    }
+
+    // Lights or darkens one pixel as if something had drawn it, for tests that need the
+    // screen in some state before an instruction runs. Coordinates wrap like DRW's.
+    pub fn set_display_pixel(&mut self, x: usize, y: usize, on: bool) {
+        self.display.set_pixel(x, y, on);
+        self.display_dirty = true;
+    }
+
+    pub fn get_display_pixel(&self, x: usize, y: usize) -> bool {
+        self.display.pixel(x, y)
+    }
+
+    pub fn count_lit_pixels(&self) -> usize {
+        self.display.packed().iter().map(|byte| byte.count_ones() as usize).sum()
+    }
+
+    // whether a draw, clear or scroll touched the screen since the last call, which starts
+    // it over; true for a new machine, as nothing has shown its screen yet
+    pub fn take_display_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.display_dirty)
+    }
+
+    pub fn keypad_mut(&mut self) -> &mut Keypad {
+        &mut self.keypad
+    }
+
+    // the keys the host holds as `input` sees them, which the machine's own keypad only
+    // catches up with on the next poll; for overlays showing them, which a frontend may
+    // draw away from the machine
+    pub fn keys_pressed(input: &dyn InputBackend) -> [bool; 16] {
+        std::array::from_fn(|key| input.is_key_pressed(key as u8))
+    }
+
+    // whether the host holds any key at all, what an FX0A waits for
+    pub fn any_key_pressed(input: &dyn InputBackend) -> bool {
+        (0..16).any(|key| input.is_key_pressed(key))
+    }
+
+    // called once per 60 Hz frame, which is also when pixel intensities are brought up to date
+    pub fn tick_timers(&mut self) {
+        if self.hour.delay > 0 || self.hour.sound > 0 {
+            log::debug!("timers tick: delay {} sound {}", self.hour.delay, self.hour.sound);
+        }
+        self.hour.delay_countdown(&mut *self.audio);
+        self.update_intensity();
+    }
+
+    pub fn load_rom(&mut self, data: Vec<u8>) -> Result<(), Chip8Error> {
+        Chip8::check_rom(&data)?;
+        if self.warn_odd_rom && data.len() % 2 == 1 {
+            log::warn!("ROM length {} is odd, the last byte is not a full instruction", data.len());
+        }
+
+        let start = PROGRAM_START as usize;
+        self.ram[start..start + data.len()].copy_from_slice(&data);
+        log::info!("loaded a {}-byte ROM", data.len());
+        self.rom_len = data.len();
+        self.rom_hash = fnv1a(&data);
+        if self.predecoded.is_some() {
+            self.predecoded = Some(self.predecode());
+        }
+        Ok(())
+    }
+
+    // whether load_rom would take data, without needing a machine to try it on
+    pub fn check_rom(data: &[u8]) -> Result<(), Chip8Error> {
+        let max = RAM_SIZE - PROGRAM_START as usize;
+        if data.is_empty() {
+            return Err(Chip8Error::EmptyRom);
+        }
+        if data.len() > max {
+            return Err(Chip8Error::RomTooLarge { size: data.len(), max });
+        }
+        Ok(())
+    }
+
+    // Back to power-on: registers, memory, screen, timers and keys. The configuration, the
+    // ROM library and anything attached (inspector, syscall handler) stay.
+    pub fn reset(&mut self) {
+        self.cpu = Cpu::new();
+        self.ram = [0; RAM_SIZE];
+        self.clear_display();
+        self.display_intensity.fill(0.0);
+        self.stack = Stack::new();
+        self.keypad = Keypad::new();
+        self.hour = Timer::new();
+        self.rom_len = 0;
+        self.rom_hash = 0;
+        self.history.clear();
+        self.halted = false;
+        self.pre_step_snapshot = None;
+        self.instructions_executed = 0;
+        self.started = Instant::now();
+        self.draws = 0;
+        self.unknown_opcodes.clear();
+        if self.profiler.is_some() {
+            self.profiler = Some(Profiler::new());
+        }
+        self.load_sprites();
+        if self.predecoded.is_some() {
+            self.predecoded = Some(self.predecode());
+        }
+    }
+
+    // reset and then load_rom, for a ROM that changed on disk: the configuration stays
+    pub fn reload_rom(&mut self, data: Vec<u8>) -> Result<(), Chip8Error> {
+        self.reset();
+        self.load_rom(data)
+    }
+
+    // refuses a ROM load_rom wouldn't take, so switching to it later can't fail halfway
+    pub fn add_rom_to_library(&mut self, title: &str, data: &[u8]) -> Result<(), Chip8Error> {
+        Chip8::check_rom(data)?;
+        self.rom_library.push((title.to_string(), data.to_vec()));
+        Ok(())
+    }
+
+    pub fn rom_library(&self) -> &[(String, Vec<u8>)] {
+        &self.rom_library
+    }
+
+    // index into the library of the running ROM, None when it was loaded some other way
+    pub fn current_rom(&self) -> Option<usize> {
+        self.rom_index
+    }
+
+    // resets the machine and starts ROM `index` of the library; on an error the running
+    // ROM carries on untouched
+    pub fn switch_to_rom(&mut self, index: usize) -> Result<(), Chip8Error> {
+        let count = self.rom_library.len();
+        let (title, data) = self.rom_library.get(index).cloned().ok_or(Chip8Error::NoSuchRom { index, count })?;
+        Chip8::check_rom(&data)?;
+        self.reset();
+        self.load_rom(data)?;
+        self.set_rom_name(&title);
+        self.rom_index = Some(index);
+        Ok(())
+    }
+
+    // the library entry after the running one, wrapping around; nothing happens without a library
+    pub fn next_rom(&mut self) -> Result<(), Chip8Error> {
+        if self.rom_library.is_empty() {
+            return Ok(());
+        }
+        let index = self.rom_index.map_or(0, |index| (index + 1) % self.rom_library.len());
+        self.switch_to_rom(index)
+    }
+
+    // FNV-1a of the loaded ROM, 0 before one is loaded
+    pub fn rom_hash(&self) -> u64 {
+        self.rom_hash
+    }
+
+    // 32 bit FNV-1a of all of RAM, to compare machines in tests without a 4 KB diff
+    pub fn memory_checksum(&self) -> u32 {
+        fnv1a_32(&self.ram)
+    }
+
+    // the same of the ROM's bytes as they are in RAM now, so a ROM that modifies itself
+    // changes it where rom_hash stays as loaded
+    pub fn rom_checksum(&self) -> u32 {
+        let start = PROGRAM_START as usize;
+        let end = (start + self.rom_len).min(self.ram.len());
+        fnv1a_32(&self.ram[start..end])
+    }
+
+    // only used to label crash reports
+    pub fn set_rom_name(&mut self, name: &str) {
+        self.rom_name = Some(name.to_string());
+    }
+
+    pub fn load_rom_file<P: AsRef<Path>>(&mut self, path: P) -> Result<(), EmulatorError> {
+        // a path that isn't UTF-8 can't be an archive:entry name either
+        let data = match path.as_ref().to_str() {
+            Some(spec) => read_rom(spec)?,
+            None => fs::read(&path)?,
+        };
+        self.load_rom(data)?;
+        self.set_rom_name(&path.as_ref().display().to_string());
+        Ok(())
+    }
+
+    pub fn load_sprites(&mut self) {
+        self.ram[0..80].copy_from_slice(&self.font);
+    }
+
+    // draws the 16 font sprites as a 4x4 grid, straight from RAM so a bad font load shows up.
+    // each glyph is 4x5 and gets one pixel of space to its right and below
+    pub fn render_sprite_atlas(&mut self) {
+        self.clear_display();
+        for digit in 0..16 {
+            let x = ATLAS_X + (digit % 4) * 5;
+            let y = ATLAS_Y + (digit / 4) * 6;
+            for row in 0..5 {
+                self.display.draw_sprite_row(x, y + row, self.ram[sprite_address(digit as u8) as usize + row]);
+            }
+        }
+        self.update_intensity();
+    }
+
+    pub fn set_inspector(&mut self, inspector: Box<dyn Chip8Inspector>) {
+        self.inspector = Some(inspector);
+    }
+
+    pub fn take_inspector(&mut self) -> Option<Box<dyn Chip8Inspector>> {
+        self.inspector.take()
+    }
+
+    pub fn set_syscall_handler(&mut self, handler: SyscallHandler) {
+        self.syscall_handler = Some(handler);
+    }
+
+    pub fn take_syscall_handler(&mut self) -> Option<SyscallHandler> {
+        self.syscall_handler.take()
+    }
+
+    pub fn step(&mut self) -> Result<InstructionResult, Chip8Error> {
+        let mut inspector = match self.inspector.take() {
+            Some(inspector) => inspector,
+            None => return self.run_instruction(),
+        };
+
+        let pc = self.cpu.pc;
+        let opcode = if (pc as usize) + 1 < self.ram.len() { self.word_at(pc) } else { 0 };
+        inspector.before_instruction(pc, opcode, self);
+        let result = self.run_instruction();
+        if let Ok(result) = &result {
+            inspector.after_instruction(result, self);
+        }
+        self.inspector = Some(inspector);
+        result
+    }
+
+    fn run_instruction(&mut self) -> Result<InstructionResult, Chip8Error> {
+        let pc = self.cpu.pc;
+        if self.halted {
+            return Ok(InstructionResult::Halted { pc });
+        }
+
+        let mut instruction = self.fetch(pc)?;
+        if !self.variant.supports(instruction) {
+            // the SCHIP opcodes a plain CHIP-8 doesn't know are still machine calls to it
+            instruction = match self.word_at(pc) {
+                opcode if opcode & 0xF000 == 0 => Instruction::Sys(opcode),
+                opcode => Instruction::Unknown(opcode),
+            };
+        }
+        // a jump to itself can never be left, but while a timer is still running
+        // it is more likely a timing loop (or a final beep) than the end of the program
+        if instruction == Instruction::Jp(pc) && self.hour.delay == 0 && self.hour.sound == 0 {
+            log::info!("halted on a jump to itself at {:#05X}", pc);
+            self.halted = true;
+            return Ok(InstructionResult::Halted { pc });
+        }
+
+        if instruction == Instruction::Exit {
+            log::info!("halted by EXIT at {:#05X}", pc);
+            self.halted = true;
+            return Ok(InstructionResult::Halted { pc });
+        }
+
+        if self.history.len() == HISTORY_LEN {
+            self.history.pop_front();
+        }
+        self.history.push_back((pc, instruction));
+        match &self.symbols {
+            Some(symbols) if log::log_enabled!(log::Level::Trace) => log::trace!("{}", symbols.annotate(pc, instruction)),
+            _ => log::trace!("{:#05X}: {:?}", pc, instruction),
+        }
+
+        self.pre_step_snapshot = Some(self.snapshot());
+        self.cpu.pc += 2;
+        self.execute(instruction)?;
+        self.instructions_executed += 1;
+        if let Some(profiler) = self.profiler.as_mut() {
+            profiler.record(instruction);
+        }
+        if self.halted {
+            return Ok(InstructionResult::Halted { pc });
+        }
+
+        if let Instruction::LdVxK(_) = instruction {
+            if self.cpu.pc == pc {
+                return Ok(InstructionResult::WaitingForKey);
+            }
+        }
+        Ok(InstructionResult::Executed { pc })
+    }
+
+    // the buzzer sounds for as long as the sound timer is counting down
+    pub fn sound_active(&self) -> bool {
+        self.hour.sound > 0
+    }
+
+    pub fn snapshot(&self) -> Chip8Snapshot {
+        Chip8Snapshot {
+            vx: self.cpu.vx,
+            pc: self.cpu.pc,
+            i: self.cpu.i,
+            sp: self.stack.size,
+            stack: self.stack.mem,
+        }
+    }
+
+    pub fn restore(&mut self, snapshot: &Chip8Snapshot) {
+        self.cpu.vx = snapshot.vx;
+        self.cpu.pc = snapshot.pc;
+        self.cpu.i = snapshot.i;
+        self.stack.size = snapshot.sp;
+        self.stack.mem = snapshot.stack;
+        self.halted = false;
+    }
+
+    pub fn cpu_snapshot(&self) -> CpuSnapshot {
+        self.cpu.dump()
+    }
+
+    pub fn restore_cpu(&mut self, snapshot: CpuSnapshot) {
+        self.cpu.restore(snapshot);
+        self.halted = false;
+    }
+
+    pub fn stack_snapshot(&self) -> StackSnapshot {
+        self.stack.dump()
+    }
+
+    pub fn restore_stack(&mut self, snapshot: StackSnapshot) {
+        self.stack.restore(snapshot);
+    }
+
+    pub fn timer_snapshot(&self) -> TimerSnapshot {
+        self.hour.dump()
+    }
+
+    pub fn restore_timers(&mut self, snapshot: TimerSnapshot) {
+        self.hour.restore(snapshot);
+    }
+
+    pub fn pc(&self) -> u16 {
+        self.cpu.pc
+    }
+
+    // instructions are two bytes long and start on even addresses
+    pub fn set_pc(&mut self, pc: u16) -> Result<(), Chip8Error> {
+        if pc & 1 != 0 || pc as usize + 1 >= self.ram.len() {
+            return Err(Chip8Error::PcOutOfRange { pc });
+        }
+        self.cpu.pc = pc;
+        Ok(())
+    }
+
+    // panics for a register past VF, like indexing would
+    pub fn register(&self, x: u8) -> u8 {
+        self.cpu.vx[x as usize]
+    }
+
+    pub fn set_register(&mut self, x: u8, value: u8) -> Result<(), Chip8Error> {
+        let register = self.cpu.vx.get_mut(x as usize).ok_or(Chip8Error::NoSuchRegister { x })?;
+        *register = value;
+        Ok(())
+    }
+
+    pub fn i_register(&self) -> u16 {
+        self.cpu.i
+    }
+
+    // up to the i_max quirk, the most the variant can address
+    pub fn set_i_register(&mut self, i: u16) -> Result<(), Chip8Error> {
+        if i as usize > self.i_limit() {
+            return Err(Chip8Error::IRegisterOutOfBounds { i, size: 1 });
+        }
+        self.cpu.i = i;
+        Ok(())
+    }
+
+    pub fn stack_pointer(&self) -> u8 {
+        self.stack.size
+    }
+
+    pub fn delay_timer(&self) -> u8 {
+        self.hour.delay
+    }
+
+    pub fn sound_timer(&self) -> u8 {
+        self.hour.sound
+    }
+
+    // `len` bytes of RAM from `address`
+    pub fn memory(&self, address: u16, len: usize) -> Result<&[u8], Chip8Error> {
+        self.ram
+            .get(address as usize..address as usize + len)
+            .ok_or(Chip8Error::MemoryOutOfRange { address, len })
+    }
+
+    // Writes `bytes` from `address` the way a debugger would, past the memory protection
+    // quirk. Whatever it overwrites is decoded again when it runs.
+    pub fn write_memory(&mut self, address: u16, bytes: &[u8]) -> Result<(), Chip8Error> {
+        let range = address as usize..address as usize + bytes.len();
+        let len = bytes.len();
+        self.ram.get_mut(range.clone()).ok_or(Chip8Error::MemoryOutOfRange { address, len })?.copy_from_slice(bytes);
+        for address in range {
+            self.forget_predecoded(address);
+        }
+        Ok(())
+    }
+
+    // Puts the registers back the way they were before the last instruction. Only one
+    // level deep, so a second undo without a step in between does nothing.
+    pub fn undo_step(&mut self) -> bool {
+        match self.pre_step_snapshot.take() {
+            Some(snapshot) => {
+                self.restore(&snapshot);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn instructions_since_reset(&self) -> u64 {
+        self.instructions_executed
+    }
+
+    pub fn draws_since_reset(&self) -> u64 {
+        self.draws
+    }
+
+    // instructions per second of wall clock time since construction or the last reset,
+    // so time spent paused counts against it
+    pub fn ips(&self) -> f64 {
+        let seconds = self.started.elapsed().as_secs_f64();
+        if seconds > 0.0 {
+            self.instructions_executed as f64 / seconds
+        } else {
+            0.0
+        }
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    fn fetch(&self, address: u16) -> Result<Instruction, Chip8Error> {
+        if address as usize + 1 >= self.ram.len() {
+            return Err(Chip8Error::PcOutOfRange { pc: address });
+        }
+        if let Some(Some(instruction)) = self.predecoded.as_ref().map(|table| table[address as usize]) {
+            return Ok(instruction);
+        }
+        Ok(Instruction::decode(self.ram[address as usize], self.ram[(address + 1) as usize]))
+    }
+
+    pub(crate) fn word_at(&self, address: u16) -> u16 {
+        (self.ram[address as usize] as u16) << 8 | self.ram[(address + 1) as usize] as u16
+    }
+
+    fn execute(&mut self, instruction: Instruction) -> Result<(), Chip8Error> {
+        match instruction {
+            Instruction::Cls => self.clear_display(),
+            Instruction::Ret => self.cpu.pc = self.stack.pop(self.cpu.pc.wrapping_sub(2))?,
+            Instruction::ScrollDown(n) => self.scroll(|display| display.scroll_down(n as usize)),
+            Instruction::ScrollUp(n) => self.scroll(|display| display.scroll_up(n as usize)),
+            Instruction::ScrollRight => self.scroll(|display| display.scroll_right(4)),
+            Instruction::ScrollLeft => self.scroll(|display| display.scroll_left(4)),
+            // handled in step, which stops the machine instead
+            Instruction::Exit => {}
+            Instruction::Jp(nnn) => self.cpu.pc = nnn,
+            Instruction::Call(nnn) => self.call_subroutine(nnn)?,
+            Instruction::SeByte(x, kk) => {
+                if self.cpu.vx[x as usize] == kk {
+                    self.cpu.pc += 2
+                }
+            }
+            Instruction::SneByte(x, kk) => {
+                if self.cpu.vx[x as usize] != kk {
+                    self.cpu.pc += 2
+                }
+            }
+            Instruction::SeReg(x, y) => {
+                if self.cpu.vx[x as usize] == self.cpu.vx[y as usize] {
+                    self.cpu.pc += 2
+                }
+            }
+            Instruction::LdByte(x, kk) => self.cpu.vx[x as usize] = kk,
+            Instruction::AddByte(x, kk) => self.cpu.vx[x as usize] = self.cpu.vx[x as usize].wrapping_add(kk),
+            Instruction::LdReg(x, y) => self.cpu.vx[x as usize] = self.cpu.vx[y as usize],
+            Instruction::Or(x, y) => self.cpu.vx[x as usize] |= self.cpu.vx[y as usize],
+            Instruction::And(x, y) => self.cpu.vx[x as usize] &= self.cpu.vx[y as usize],
+            Instruction::Xor(x, y) => self.cpu.vx[x as usize] ^= self.cpu.vx[y as usize],
+            Instruction::AddReg(x, y) => self.cpu.add_registers(x, y),
+            Instruction::Sub(x, y) => self.cpu.substract_registers(x, y, x),
+            Instruction::Shr(x, y) => {
+                let y = if self.quirks.shift_uses_vy { y } else { x };
+                self.cpu.half_register(x, y);
+            }
+            Instruction::Subn(x, y) => self.cpu.substract_registers(y, x, x),
+            Instruction::Shl(x, y) => {
+                let y = if self.quirks.shift_uses_vy { y } else { x };
+                self.cpu.double_register(x, y);
+            }
+            Instruction::SneReg(x, y) => {
+                if self.cpu.vx[x as usize] != self.cpu.vx[y as usize] {
+                    self.cpu.pc += 2
+                }
+            }
+            Instruction::LdI(nnn) => self.cpu.i = nnn,
+            Instruction::JpV0(nnn) => {
+                let x = if self.quirks.bnnn_uses_vx { (nnn >> 8) as usize } else { 0 };
+                self.cpu.pc = nnn + self.cpu.vx[x] as u16;
+            }
+            Instruction::Rnd(x, kk) => self.random_number(x, kk),
+            Instruction::Drw(x, y, n) => self.draw_sprite(self.cpu.i, x, y, n as u16)?,
+            Instruction::Skp(x) => {
+                if self.keypad.is_pressed(self.cpu.vx[x as usize]) {
+                    self.cpu.pc += 2;
+                }
+            }
+            Instruction::Sknp(x) => {
+                if !self.keypad.is_pressed(self.cpu.vx[x as usize]) {
+                    self.cpu.pc += 2;
+                }
+            }
+            Instruction::LdVxDt(x) => self.cpu.vx[x as usize] = self.hour.delay,
+            Instruction::LdVxK(x) => self.wait_for_key(x),
+            Instruction::LdDtVx(x) => self.hour.delay = self.cpu.vx[x as usize],
+            Instruction::LdStVx(x) => self.hour.sound = self.cpu.vx[x as usize],
+            Instruction::AddI(x) => {
+                let sum = self.cpu.i.wrapping_add(self.cpu.vx[x as usize] as u16);
+                self.cpu.i = sum;
+                if self.quirks.fx1e_overflow_sets_vf {
+                    self.cpu.vx[0xF] = (sum >= 0x1000) as u8;
+                }
+            }
+            // only the low digit of Vx picks a glyph
+            Instruction::LdF(x) => self.cpu.i = sprite_address(self.cpu.vx[x as usize] & 0xF),
+            Instruction::LdB(x) => {
+                self.check_i_range(3)?;
+                let value = self.cpu.vx[x as usize];
+                self.write_ram(self.cpu.i, value / 100)?;
+                self.write_ram(self.cpu.i + 1, value % 100 / 10)?;
+                self.write_ram(self.cpu.i + 2, value % 10)?;
+            }
+            Instruction::LdIVx(x) => {
+                self.check_i_range(x as u16 + 1)?;
+                for i in 0..=x as u16 {
+                    self.write_ram(i + self.cpu.i, self.cpu.vx[i as usize])?;
+                }
+            }
+            Instruction::LdVxI(x) => {
+                self.check_i_range(x as u16 + 1)?;
+                for i in 0..=x as u16 {
+                    self.cpu.vx[i as usize] = self.ram[(i + self.cpu.i) as usize];
+                }
+            }
+            Instruction::Sys(address) => self.syscall(address)?,
+            Instruction::Unknown(opcode) => self.unknown_opcode(opcode)?,
+        }
+        Ok(())
+    }
+
+    fn syscall(&mut self, address: u16) -> Result<(), Chip8Error> {
+        let result = match self.syscall_handler.as_mut() {
+            Some(handler) => handler(address, &mut self.cpu),
+            None => SyscallResult::Unhandled,
+        };
+        match result {
+            SyscallResult::Done => {}
+            SyscallResult::Unhandled => self.unknown_opcode(address)?,
+            SyscallResult::Halt => self.halted = true,
+        }
+        Ok(())
+    }
+
+    fn unknown_opcode(&mut self, opcode: u16) -> Result<(), Chip8Error> {
+        // the PC has already moved past the offending instruction
+        let pc = self.cpu.pc.wrapping_sub(2);
+        match self.quirks.unknown_opcode_policy {
+            // once per address, or a loop through a bad word would say it every frame
+            UnknownOpcodePolicy::Log if self.unknown_opcodes.insert(pc) => {
+                log::warn!("unknown opcode {:04X} at {:#05X}", opcode, pc)
+            }
+            UnknownOpcodePolicy::Log => log::debug!("unknown opcode {:04X} at {:#05X}", opcode, pc),
+            UnknownOpcodePolicy::Halt => return Err(Chip8Error::InvalidOpcode { pc, opcode }),
+            UnknownOpcodePolicy::Skip => {}
+            UnknownOpcodePolicy::Trap(address) => self.cpu.pc = address,
+        }
+        Ok(())
+    }
+
+    // every store from a running program goes through here
+    fn write_ram(&mut self, address: u16, value: u8) -> Result<(), Chip8Error> {
+        if address < PROGRAM_START && self.quirks.memory_protection == MemoryProtection::Strict {
+            let pc = self.cpu.pc.wrapping_sub(2);
+            return Err(Chip8Error::ProtectedWrite { pc, opcode: self.word_at(pc), address });
+        }
+
+        self.ram[address as usize] = value;
+        self.forget_predecoded(address as usize);
+        Ok(())
+    }
+
+    fn forget_predecoded(&mut self, address: usize) {
+        if let Some(table) = self.predecoded.as_mut() {
+            // the instruction starting one byte earlier also covers this address
+            table[address] = None;
+            if address > 0 {
+                table[address - 1] = None;
+            }
+        }
+    }
+
+    // the last address I may reach: the i_max quirk, but never past the end of RAM, as
+    // the quirks are public and a state file can say anything
+    fn i_limit(&self) -> usize {
+        (self.quirks.i_max as usize).min(self.ram.len() - 1)
+    }
+
+    fn check_i_range(&self, size: u16) -> Result<(), Chip8Error> {
+        if self.cpu.i as usize + size as usize > self.i_limit() + 1 {
+            return Err(Chip8Error::IRegisterOutOfBounds { i: self.cpu.i, size });
+        }
+        Ok(())
+    }
+
+    fn clear_display(&mut self) {
+        self.display.clear();
+        self.display_dirty = true;
+    }
+
+    fn scroll(&mut self, scroll: impl FnOnce(&mut Framebuffer)) {
+        scroll(&mut self.display);
+        self.display_dirty = true;
+    }
+
+    fn call_subroutine(&mut self, address: u16) -> Result<(), Chip8Error> {
+        self.stack.add(self.cpu.pc)?;
+        self.cpu.pc = address;
+        Ok(())
+    }
+
+    fn random_number(&mut self, vx: u8, kk: u8) {
+        let number: u8 = self.rng.gen();
+        if let Some((_, drawn)) = self.rng_seed.as_mut() {
+            *drawn += 1;
+        }
+        self.cpu.vx[vx as usize] = number & kk;
+    }
+
+    fn draw_sprite(&mut self, i: u16, x: u8, y: u8, n: u16) -> Result<(), Chip8Error> {
+        self.check_i_range(n)?;
+        self.draws += 1;
+        let xcord = self.cpu.vx[x as usize] as usize;
+        let ycord = self.cpu.vx[y as usize] as usize;
+        self.cpu.vx[0xF] = 0;
+        self.display_dirty = true;
+
+        for j in 0..n {
+            let row = self.ram[(i + j) as usize];
+            if self.display.draw_sprite_row(xcord, ycord + j as usize, row) {
+                self.cpu.vx[0xF] = 1;
+            }
+        }
+        log::debug!("drew {} rows from {:#05X} at ({}, {}), VF={}", n, i, xcord, ycord, self.cpu.vx[0xF]);
+        Ok(())
+    }
+
+    fn wait_for_key(&mut self, register: u8) {
+        match self.keypad.first_pressed() {
+            Some(key) => self.cpu.vx[register as usize] = key,
+            None => self.cpu.pc -= 2,
+        }
+    }
+}
+
+impl Default for Chip8 {
+    fn default() -> Self {
+        Chip8::new()
+    }
+}
+
+// 64 bit FNV-1a, enough to tell ROM versions apart in a crash report
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn fnv1a_32(data: &[u8]) -> u32 {
+    data.iter().fold(0x811c_9dc5, |hash, byte| (hash ^ *byte as u32).wrapping_mul(0x0100_0193))
+}
+
+// the screen, see the Display of Framebuffer
+impl fmt::Display for Chip8 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.display, f)
+    }
+}
+
+impl fmt::Debug for Chip8 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:?}", self.cpu)?;
+        writeln!(f, "{:?}", self.stack)?;
+        writeln!(f, "{:?}", self.hour)?;
+
+        // the first row of each quarter of the screen, so the whole of it fits in 4 lines
+        let height = self.display.height();
+        for y in (0..height).step_by(height.div_ceil(4)) {
+            let line: String = (0..self.display.width())
+                .map(|x| if self.display.pixel(x, y) { '#' } else { '.' })
+                .collect();
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,417 @@
+use std::{env, path::Path};
+
+use rust_8::{AsciiDump, ChipVariant, ColorTheme, ConfigError, DisplayTransform, Filter, Waveform};
+
+use crate::{config, frontend};
+
+pub struct Options {
+    pub rom: String,
+    // run one of rust_8::BUILTIN_ROMS instead of `rom`, or list them for "list"
+    pub builtin: Option<String>,
+    // a directory of .ch8 files or a playlist to switch between with Tab, instead of `rom`
+    pub rom_dir: Option<String>,
+    pub terminal: bool,
+    pub headless: bool,
+    // reload the ROM whenever the file changes
+    pub watch: bool,
+    // stop a headless run after this many frames even if the ROM never halts
+    pub frames: Option<u64>,
+    // run this many instructions without a window and print the state as JSON
+    pub dump_state_json: Option<u64>,
+    // run this one opcode on a fresh machine and print what it changed, instead of a ROM
+    pub test_opcode: Option<u16>,
+    // registers to set before --test-opcode, as JSON like {"V0": 1, "V1": 2}
+    pub initial_state: Option<String>,
+    // check the state after every instruction against this trace, without a window
+    pub ref_trace: Option<String>,
+    // write the state after every instruction here, in the format ref_trace reads
+    pub emit_ref_trace: Option<String>,
+    // print the screen as text at the end of a headless run, in the style of ascii
+    pub dump_ascii: bool,
+    pub ascii: AsciiDump,
+    // key presses to play back at given frames of a headless run, see rust_8::InputScript
+    pub input_script: Option<String>,
+    // names for addresses in the trace log, see rust_8::SymbolTable
+    pub symbols: Option<String>,
+    // print the subroutine call tree when the run ends, see rust_8::Profiler
+    pub profile_calls: bool,
+    // serve the remote debugging protocol there instead of opening a window, see
+    // rust_8::RemoteServer; anything but loopback needs listen_remote too
+    pub listen: Option<String>,
+    pub listen_remote: bool,
+    // serve the screen and take key presses over HTTP there, see rust_8::HttpServer
+    pub http: Option<String>,
+    pub variant: ChipVariant,
+    // --variant was given, so the ROM database doesn't pick one
+    pub variant_given: bool,
+    // instructions per frame, instead of the variant's usual speed
+    pub tickrate: Option<usize>,
+    // vary the speed to keep the ROM drawing at a steady rate, between min_hz and max_hz
+    // instructions per second
+    pub adaptive_speed: bool,
+    // busy-wait the end of every frame for steadier 60 Hz, see rust_8::PreciseClock
+    pub precise_timing: bool,
+    pub min_hz: u32,
+    pub max_hz: u32,
+    // programs.json of the CHIP-8 database, to recognize the ROM by its hash
+    pub rom_database: Option<String>,
+    // the ROM's name from the database, shown instead of the file name
+    pub rom_title: Option<String>,
+    pub theme: ColorTheme,
+    // fraction of brightness an unlit pixel keeps per frame
+    pub phosphor: f32,
+    pub border: u32,
+    pub border_color: u32,
+    pub visual_bell: bool,
+    pub bell_color: u32,
+    pub transform: DisplayTransform,
+    pub filter: Filter,
+    // starting window size, in host pixels per CHIP-8 pixel
+    pub scale: usize,
+    // multiply the scale by the desktop's DPI scale factor
+    pub hidpi: bool,
+    pub fullscreen: bool,
+    // the screen the fullscreen window covers, which minifb has no way to ask for
+    pub screen_size: (usize, usize),
+    pub magnifier: bool,
+    // start with the on-screen keyboard under the screen; K shows and hides it
+    pub virtual_keyboard: bool,
+    // run the ROM on these two variants side by side
+    pub compare: Option<(ChipVariant, ChipVariant)>,
+    // write a StateDump here when the emulator is quit
+    pub dump_state_on_exit: Option<String>,
+    // how much to log: 0 for warnings, then info, debug and trace (every instruction).
+    // RUST_LOG overrides it
+    pub verbosity: u8,
+    // list the opcodes the ROM can reach, and with --headless the ones that ran
+    pub coverage: bool,
+    // carry on from the session saved when this ROM was last quit
+    pub resume: bool,
+    // where sessions are saved, autosave::default_dir when not given
+    pub save_dir: Option<String>,
+    pub mute: bool,
+    // stop emulation while the window is in the background
+    pub pause_on_focus_loss: bool,
+    pub waveform: Waveform,
+    // buzzer pitch in Hz and loudness from 0 to 1
+    pub frequency: f32,
+    pub volume: f32,
+    // write the buzzer of the whole session to this WAV file
+    pub wav_out: Option<String>,
+    pub warn_odd_rom: bool,
+    // show the built-in font instead of running a ROM
+    pub sprite_test: bool,
+    // print the TextMate grammar for CHIP-8 assembly and exit
+    pub generate_grammar: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            rom: String::from("roms/test_opcode.ch8"),
+            builtin: None,
+            rom_dir: None,
+            terminal: false,
+            headless: false,
+            watch: false,
+            frames: None,
+            dump_state_json: None,
+            test_opcode: None,
+            initial_state: None,
+            ref_trace: None,
+            emit_ref_trace: None,
+            dump_ascii: false,
+            ascii: AsciiDump::default(),
+            input_script: None,
+            symbols: None,
+            profile_calls: false,
+            listen: None,
+            listen_remote: false,
+            http: None,
+            variant: ChipVariant::default(),
+            variant_given: false,
+            tickrate: None,
+            adaptive_speed: false,
+            precise_timing: false,
+            min_hz: 60,
+            max_hz: 6000,
+            rom_database: None,
+            rom_title: None,
+            theme: ColorTheme::default(),
+            phosphor: 0.0,
+            border: 0,
+            border_color: 0x202020,
+            visual_bell: false,
+            bell_color: 0xFF0000,
+            transform: DisplayTransform::Normal,
+            filter: Filter::None,
+            scale: 16,
+            hidpi: false,
+            fullscreen: false,
+            screen_size: (1920, 1080),
+            magnifier: false,
+            virtual_keyboard: false,
+            compare: None,
+            dump_state_on_exit: None,
+            verbosity: 0,
+            coverage: false,
+            resume: false,
+            save_dir: None,
+            mute: false,
+            pause_on_focus_loss: true,
+            waveform: Waveform::default(),
+            frequency: 440.0,
+            volume: 0.25,
+            wav_out: None,
+            warn_odd_rom: false,
+            sprite_test: false,
+            generate_grammar: false,
+        }
+    }
+}
+
+pub fn parse_args() -> Result<Options, ConfigError> {
+    let mut options = Options::default();
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    // the config file goes first so the rest of the command line can override it
+    match args.iter().position(|arg| arg == "--config") {
+        Some(n) => {
+            let path = args.get(n + 1).ok_or_else(|| ConfigError::MissingValue(args[n].clone()))?;
+            config::load(Path::new(path), &mut options)?;
+        }
+        None if Path::new(config::DEFAULT_PATH).exists() => {
+            config::load(Path::new(config::DEFAULT_PATH), &mut options)?;
+        }
+        None => {}
+    }
+
+    let mut args = args.into_iter();
+    let (mut fg, mut bg) = (None, None);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--terminal" => options.terminal = true,
+            "--headless" => options.headless = true,
+            "--watch" => options.watch = true,
+            "--builtin" => options.builtin = Some(parse_builtin(&arg, args.next())?),
+            "--rom-dir" => options.rom_dir = Some(parse_value(&arg, args.next())?),
+            "--frames" => options.frames = Some(parse_value(&arg, args.next())?),
+            "--dump-state-json" => options.dump_state_json = Some(parse_value(&arg, args.next())?),
+            "--test-opcode" => options.test_opcode = Some(parse_opcode(&arg, args.next())?),
+            "--initial-state" => options.initial_state = Some(parse_value(&arg, args.next())?),
+            "--ref-trace" => options.ref_trace = Some(parse_value(&arg, args.next())?),
+            "--emit-ref-trace" => options.emit_ref_trace = Some(parse_value(&arg, args.next())?),
+            "--input-script" => options.input_script = Some(parse_value(&arg, args.next())?),
+            "--dump-ascii" => options.dump_ascii = true,
+            "--ascii-chars" => (options.ascii.on, options.ascii.off) = parse_chars(&arg, args.next())?,
+            "--ascii-ruler" => options.ascii.ruler = true,
+            "--ascii-half-blocks" => options.ascii.half_blocks = true,
+            "--symbols" => options.symbols = Some(parse_value(&arg, args.next())?),
+            "--profile-calls" => options.profile_calls = true,
+            "--listen" => options.listen = Some(parse_value(&arg, args.next())?),
+            "--listen-remote" => options.listen_remote = true,
+            "--http" => options.http = Some(parse_value(&arg, args.next())?),
+            "--sprite-test" => options.sprite_test = true,
+            "--generate-grammar" => options.generate_grammar = true,
+            "--warn-odd-rom" => options.warn_odd_rom = true,
+            "--phosphor" => options.phosphor = parse_value(&arg, args.next())?,
+            "--border" => options.border = parse_value(&arg, args.next())?,
+            "--border-color" => options.border_color = parse_color(&arg, args.next())?,
+            "--transform" => options.transform = parse_value(&arg, args.next())?,
+            "--filter" => options.filter = parse_value(&arg, args.next())?,
+            "--fullscreen" => options.fullscreen = true,
+            "--magnifier" => options.magnifier = true,
+            "--virtual-keyboard" => options.virtual_keyboard = true,
+            "--resume" => options.resume = true,
+            "--save-dir" => options.save_dir = Some(parse_value(&arg, args.next())?),
+            "--coverage" => options.coverage = true,
+            "--verbose" | "-v" => options.verbosity += 1,
+            "-vv" => options.verbosity += 2,
+            "-vvv" => options.verbosity += 3,
+            "--dump-state-on-exit" => options.dump_state_on_exit = Some(parse_value(&arg, args.next())?),
+            "--compare" => options.compare = Some(parse_pair(&arg, args.next())?),
+            "--screen-size" => options.screen_size = parse_size(&arg, args.next())?,
+            "--scale" => options.scale = parse_scale(&arg, args.next())?,
+            "--hidpi" => options.hidpi = true,
+            "--visual-bell" => options.visual_bell = true,
+            "--bell-color" => options.bell_color = parse_color(&arg, args.next())?,
+            "--config" => {
+                args.next();
+            }
+            "--mute" => options.mute = true,
+            "--no-pause-on-focus-loss" => options.pause_on_focus_loss = false,
+            "--wav-out" => options.wav_out = Some(parse_value(&arg, args.next())?),
+            "--waveform" => options.waveform = parse_value(&arg, args.next())?,
+            "--frequency" => options.frequency = parse_value(&arg, args.next())?,
+            "--volume" => options.volume = parse_value(&arg, args.next())?,
+            "--fg" => fg = Some(parse_color(&arg, args.next())?),
+            "--bg" => bg = Some(parse_color(&arg, args.next())?),
+            "--theme" => options.theme = parse_value(&arg, args.next())?,
+            "--variant" => {
+                options.variant = parse_value(&arg, args.next())?;
+                options.variant_given = true;
+            }
+            "--tickrate" => options.tickrate = Some(parse_value(&arg, args.next())?),
+            "--adaptive-speed" => options.adaptive_speed = true,
+            "--precise-timing" => options.precise_timing = true,
+            "--min-hz" => options.min_hz = parse_value(&arg, args.next())?,
+            "--max-hz" => options.max_hz = parse_value(&arg, args.next())?,
+            "--rom-database" => options.rom_database = Some(parse_value(&arg, args.next())?),
+            flag if flag.starts_with("--") => return Err(ConfigError::UnknownOption(flag.to_string())),
+            rom => options.rom = rom.to_string(),
+        }
+    }
+
+    // --fg and --bg start from the chosen theme, whatever order they come in
+    if fg.is_some() || bg.is_some() {
+        let theme = options.theme;
+        options.theme = ColorTheme::Custom(fg.unwrap_or(theme.fg()), bg.unwrap_or(theme.bg()));
+    }
+
+    if options.initial_state.is_some() && options.test_opcode.is_none() {
+        return Err(ConfigError::InvalidValue {
+            option: String::from("--initial-state"),
+            value: options.initial_state.clone().unwrap_or_default(),
+            reason: String::from("only used with --test-opcode"),
+        });
+    }
+    // a window has a keyboard of its own
+    let windowless = options.headless || options.ref_trace.is_some() || options.emit_ref_trace.is_some();
+    if options.input_script.is_some() && !windowless {
+        return Err(ConfigError::InvalidValue {
+            option: String::from("--input-script"),
+            value: options.input_script.clone().unwrap_or_default(),
+            reason: String::from("only used with --headless"),
+        });
+    }
+    if let (Some(addr), true) = (&options.http, windowless) {
+        return Err(ConfigError::InvalidValue {
+            option: String::from("--http"),
+            value: addr.clone(),
+            reason: String::from("serves the emulator a window or terminal runs, so not with --headless"),
+        });
+    }
+    if options.dump_ascii && !options.headless {
+        return Err(ConfigError::InvalidValue {
+            option: String::from("--dump-ascii"),
+            value: String::from("true"),
+            reason: String::from("only used with --headless"),
+        });
+    }
+    if options.min_hz > options.max_hz {
+        return Err(ConfigError::InvalidValue {
+            option: String::from("--min-hz"),
+            value: options.min_hz.to_string(),
+            reason: format!("above --max-hz {}", options.max_hz),
+        });
+    }
+
+    Ok(options)
+}
+
+fn parse_value<T>(flag: &str, value: Option<String>) -> Result<T, ConfigError>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    let value = value.ok_or_else(|| ConfigError::MissingValue(flag.to_string()))?;
+    value.parse().map_err(|err: T::Err| ConfigError::InvalidValue {
+        option: flag.to_string(),
+        value: value.clone(),
+        reason: err.to_string(),
+    })
+}
+
+fn parse_scale(flag: &str, value: Option<String>) -> Result<usize, ConfigError> {
+    let scale: usize = parse_value(flag, value)?;
+    if (frontend::MIN_SCALE..=frontend::MAX_SCALE).contains(&scale) {
+        return Ok(scale);
+    }
+    Err(ConfigError::InvalidValue {
+        option: flag.to_string(),
+        value: scale.to_string(),
+        reason: format!("expected a whole number from {} to {}", frontend::MIN_SCALE, frontend::MAX_SCALE),
+    })
+}
+
+// the name of a built-in ROM, or "list"
+fn parse_builtin(flag: &str, value: Option<String>) -> Result<String, ConfigError> {
+    let value = value.ok_or_else(|| ConfigError::MissingValue(flag.to_string()))?;
+    if value == "list" || rust_8::builtin_rom(&value).is_some() {
+        return Ok(value);
+    }
+    let names: Vec<&str> = rust_8::BUILTIN_ROMS.iter().map(|rom| rom.name).collect();
+    Err(ConfigError::InvalidValue {
+        option: flag.to_string(),
+        value,
+        reason: format!("expected list or one of {}", names.join(", ")),
+    })
+}
+
+// four hex digits, like 8014 or 0x8014
+fn parse_opcode(flag: &str, value: Option<String>) -> Result<u16, ConfigError> {
+    let value = value.ok_or_else(|| ConfigError::MissingValue(flag.to_string()))?;
+    let digits = value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")).unwrap_or(&value);
+    match u16::from_str_radix(digits, 16) {
+        Ok(opcode) if digits.len() == 4 => Ok(opcode),
+        _ => Err(ConfigError::InvalidValue {
+            option: flag.to_string(),
+            value,
+            reason: String::from("expected an opcode as four hex digits, like 8014"),
+        }),
+    }
+}
+
+// two variants, like chip8,schip11
+// two characters, for lit and dark pixels, like "#."
+fn parse_chars(flag: &str, value: Option<String>) -> Result<(char, char), ConfigError> {
+    let value = value.ok_or_else(|| ConfigError::MissingValue(flag.to_string()))?;
+    match value.chars().collect::<Vec<_>>()[..] {
+        [on, off] => Ok((on, off)),
+        _ => Err(ConfigError::InvalidValue {
+            option: flag.to_string(),
+            value,
+            reason: String::from("expected two characters, for lit and dark pixels"),
+        }),
+    }
+}
+
+fn parse_pair(flag: &str, value: Option<String>) -> Result<(ChipVariant, ChipVariant), ConfigError> {
+    let value = value.ok_or_else(|| ConfigError::MissingValue(flag.to_string()))?;
+    let (left, right) = value.split_once(',').ok_or_else(|| ConfigError::InvalidValue {
+        option: flag.to_string(),
+        value: value.clone(),
+        reason: String::from("expected two variants separated by a comma, like chip8,schip11"),
+    })?;
+    Ok((
+        parse_value(flag, Some(left.to_string()))?,
+        parse_value(flag, Some(right.to_string()))?,
+    ))
+}
+
+// WIDTHxHEIGHT, like 1920x1080
+fn parse_size(flag: &str, value: Option<String>) -> Result<(usize, usize), ConfigError> {
+    let value = value.ok_or_else(|| ConfigError::MissingValue(flag.to_string()))?;
+    let size = value.split_once(['x', 'X']).and_then(|(width, height)| {
+        Some((width.parse::<usize>().ok()?, height.parse::<usize>().ok()?))
+    });
+    match size {
+        Some((width, height)) if width > 0 && height > 0 => Ok((width, height)),
+        _ => Err(ConfigError::InvalidValue {
+            option: flag.to_string(),
+            value,
+            reason: String::from("expected WIDTHxHEIGHT, like 1920x1080"),
+        }),
+    }
+}
+
+// colors are given as #RRGGBB, RRGGBB or by name
+pub fn parse_color(flag: &str, value: Option<String>) -> Result<u32, ConfigError> {
+    let value = value.ok_or_else(|| ConfigError::MissingValue(flag.to_string()))?;
+    rust_8::parse_color(&value).map_err(|reason| ConfigError::InvalidValue {
+        option: flag.to_string(),
+        value,
+        reason,
+    })
+}
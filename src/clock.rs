@@ -0,0 +1,80 @@
+// Wall-clock time for the instructions-per-second counter and the emulation thread's
+// frames. std's Instant panics on wasm32-unknown-unknown, so there the browser's
+// performance.now() stands in for it.
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use std::time::Instant;
+
+#[cfg(all(target_arch = "wasm32", not(feature = "wasm")))]
+compile_error!("building for wasm32 needs the wasm feature: cargo build --target wasm32-unknown-unknown --features wasm");
+
+#[cfg(target_arch = "wasm32")]
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub(crate) struct Instant {
+    // milliseconds since the page loaded
+    millis: f64,
+}
+
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+impl Instant {
+    pub(crate) fn now() -> Self {
+        // a worker has no window to ask, and time stands still there
+        let millis = web_sys::window()
+            .and_then(|window| window.performance())
+            .map(|performance| performance.now())
+            .unwrap_or(0.0);
+        Instant { millis }
+    }
+
+    pub(crate) fn elapsed(&self) -> std::time::Duration {
+        std::time::Duration::from_secs_f64((Instant::now().millis - self.millis).max(0.0) / 1000.0)
+    }
+}
+
+// Paces the emulation thread's frames against a fixed period. thread::sleep (what
+// minifb's frame limiting comes down to) wakes up whenever the OS gets round to it, a
+// millisecond or so late on Linux and up to 15 on Windows. A precise clock sleeps short of
+// the deadline and spins the rest of the way, keeping a core busy for steadier frames.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct PreciseClock {
+    period: std::time::Duration,
+    next: Instant,
+    sleeper: Option<spin_sleep::SpinSleeper>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl PreciseClock {
+    pub fn new(period: std::time::Duration, precise: bool) -> Self {
+        PreciseClock {
+            period,
+            next: Instant::now() + period,
+            sleeper: precise.then(spin_sleep::SpinSleeper::default),
+        }
+    }
+
+    pub fn is_precise(&self) -> bool {
+        self.sleeper.is_some()
+    }
+
+    // after a pause, so the frames don't rush to make up for it
+    pub fn restart(&mut self) {
+        self.next = Instant::now() + self.period;
+    }
+
+    // Waits for the end of the current frame. A clock too far behind to catch up starts
+    // counting again from now instead. Returns how late it woke up.
+    pub fn wait(&mut self) -> std::time::Duration {
+        let now = Instant::now();
+        if self.next > now {
+            match &self.sleeper {
+                Some(sleeper) => sleeper.sleep(self.next - now),
+                None => std::thread::sleep(self.next - now),
+            }
+        } else {
+            self.next = now;
+        }
+        let late = Instant::now().saturating_duration_since(self.next);
+        self.next += self.period;
+        late
+    }
+}
@@ -0,0 +1,99 @@
+use std::fmt;
+
+use crate::chip8::{Chip8, InstructionResult};
+use crate::diff::{diff, Chip8Diff};
+use crate::error::Chip8Error;
+use crate::instruction::Instruction;
+use crate::keypad::Keypad;
+
+// The first instruction after which the two machines' registers no longer agree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    // instructions run on each machine, counting the one that diverged
+    pub instruction: u64,
+    pub pc: u16,
+    pub opcode: u16,
+    // from the left machine to the right one
+    pub diff: Chip8Diff,
+}
+
+// Two machines running the same ROM, with the same random numbers and keys, one
+// instruction at a time each. Built with different variants or quirks, the first place
+// they part ways shows which of those the ROM depends on.
+pub struct Comparison {
+    pub left: Chip8,
+    pub right: Chip8,
+    instructions: u64,
+    divergence: Option<Divergence>,
+}
+
+impl Comparison {
+    pub fn new(left: Chip8, right: Chip8) -> Self {
+        Comparison {
+            left,
+            right,
+            instructions: 0,
+            divergence: None,
+        }
+    }
+
+    pub fn set_keypad(&mut self, keypad: Keypad) {
+        *self.left.keypad_mut() = keypad;
+        *self.right.keypad_mut() = keypad;
+    }
+
+    // returns the left machine's result; once both have halted there is nothing more to compare
+    pub fn step(&mut self) -> Result<InstructionResult, Chip8Error> {
+        let pc = self.left.pc();
+        let opcode = self.left.word_at(pc.min(0xFFE));
+        let result = self.left.step()?;
+        self.right.step()?;
+        self.instructions += 1;
+
+        if self.divergence.is_none() && self.left.snapshot() != self.right.snapshot() {
+            self.divergence = Some(Divergence {
+                instruction: self.instructions,
+                pc,
+                opcode,
+                diff: diff(&self.left, &self.right),
+            });
+        }
+        Ok(result)
+    }
+
+    pub fn tick_timers(&mut self) {
+        self.left.tick_timers();
+        self.right.tick_timers();
+    }
+
+    pub fn instructions(&self) -> u64 {
+        self.instructions
+    }
+
+    pub fn divergence(&self) -> Option<&Divergence> {
+        self.divergence.as_ref()
+    }
+
+    // true while the two screens show different things, which can come and go
+    pub fn displays_differ(&self) -> bool {
+        self.left.display() != self.right.display()
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.left.is_halted() && self.right.is_halted()
+    }
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let instruction = Instruction::decode((self.opcode >> 8) as u8, self.opcode as u8);
+        writeln!(
+            f,
+            "diverged at instruction {}: {:04X} {:04X} {:?}",
+            self.instruction, self.pc, self.opcode, instruction
+        )?;
+        let mut registers = self.diff.clone();
+        registers.display_changed = false;
+        write!(f, "{}", registers)
+    }
+}
@@ -0,0 +1,136 @@
+use std::{fs, path::Path};
+
+use rust_8::ConfigError;
+use toml::{Table, Value};
+
+use crate::cli::{self, Options};
+
+// read when present and no --config is given
+pub const DEFAULT_PATH: &str = "rust-8.toml";
+
+// Fills options from a TOML file such as
+//
+//     [audio]
+//     waveform = "triangle"
+//     frequency = 523.25
+//     volume = 0.4
+//
+//     [display]
+//     visual_bell = true
+//     bell_color = "FF0000"
+//     filter = "scanlines"
+//     pause_on_focus_loss = false
+//     fullscreen = true
+//
+//     [session]
+//     resume = true
+//     save_dir = "saves"
+//
+// Flags on the command line are applied afterwards and win.
+pub fn load(path: &Path, options: &mut Options) -> Result<(), ConfigError> {
+    let file_error = |reason: String| ConfigError::File {
+        path: path.display().to_string(),
+        reason,
+    };
+    let text = fs::read_to_string(path).map_err(|err| file_error(err.to_string()))?;
+    let table: Table = text.parse().map_err(|err: toml::de::Error| file_error(err.message().to_string()))?;
+
+    for (section, values) in table.iter() {
+        let values = values
+            .as_table()
+            .ok_or_else(|| file_error(format!("{} should be a [section]", section)))?;
+        match section.as_str() {
+            "audio" => load_audio(values, options)?,
+            "display" => load_display(values, options)?,
+            "session" => load_session(values, options)?,
+            _ => return Err(file_error(format!("unknown section [{}]", section))),
+        }
+    }
+    Ok(())
+}
+
+fn load_audio(values: &Table, options: &mut Options) -> Result<(), ConfigError> {
+    for (key, value) in values.iter() {
+        let name = format!("audio.{}", key);
+        match key.as_str() {
+            "waveform" => options.waveform = parse_str(&name, value)?,
+            "frequency" => options.frequency = float(&name, value)?,
+            "volume" => options.volume = float(&name, value)?,
+            "mute" => options.mute = value.as_bool().ok_or_else(|| invalid(&name, value, "expected true or false"))?,
+            _ => return Err(ConfigError::UnknownOption(name)),
+        }
+    }
+    Ok(())
+}
+
+fn load_display(values: &Table, options: &mut Options) -> Result<(), ConfigError> {
+    for (key, value) in values.iter() {
+        let name = format!("display.{}", key);
+        match key.as_str() {
+            "visual_bell" => {
+                options.visual_bell = value.as_bool().ok_or_else(|| invalid(&name, value, "expected true or false"))?
+            }
+            "bell_color" => options.bell_color = color(&name, value)?,
+            "filter" => options.filter = parse_str(&name, value)?,
+            "fullscreen" => {
+                options.fullscreen = value.as_bool().ok_or_else(|| invalid(&name, value, "expected true or false"))?
+            }
+            "virtual_keyboard" => {
+                options.virtual_keyboard =
+                    value.as_bool().ok_or_else(|| invalid(&name, value, "expected true or false"))?
+            }
+            "hidpi" => options.hidpi = value.as_bool().ok_or_else(|| invalid(&name, value, "expected true or false"))?,
+            "pause_on_focus_loss" => {
+                options.pause_on_focus_loss =
+                    value.as_bool().ok_or_else(|| invalid(&name, value, "expected true or false"))?
+            }
+            _ => return Err(ConfigError::UnknownOption(name)),
+        }
+    }
+    Ok(())
+}
+
+fn load_session(values: &Table, options: &mut Options) -> Result<(), ConfigError> {
+    for (key, value) in values.iter() {
+        let name = format!("session.{}", key);
+        match key.as_str() {
+            "resume" => options.resume = value.as_bool().ok_or_else(|| invalid(&name, value, "expected true or false"))?,
+            "save_dir" => {
+                let dir = value.as_str().ok_or_else(|| invalid(&name, value, "expected a string"))?;
+                options.save_dir = Some(dir.to_string());
+            }
+            _ => return Err(ConfigError::UnknownOption(name)),
+        }
+    }
+    Ok(())
+}
+
+fn color(name: &str, value: &Value) -> Result<u32, ConfigError> {
+    let text = value.as_str().ok_or_else(|| invalid(name, value, "expected a string like \"FF8800\""))?;
+    cli::parse_color(name, Some(text.to_string()))
+}
+
+fn parse_str<T>(name: &str, value: &Value) -> Result<T, ConfigError>
+where
+    T: std::str::FromStr<Err = String>,
+{
+    let text = value.as_str().ok_or_else(|| invalid(name, value, "expected a string"))?;
+    text.parse().map_err(|reason: String| invalid(name, value, &reason))
+}
+
+// TOML keeps integers and floats apart, but 440 and 440.0 should both work
+fn float(name: &str, value: &Value) -> Result<f32, ConfigError> {
+    match value {
+        Value::Float(number) => Ok(*number as f32),
+        Value::Integer(number) => Ok(*number as f32),
+        _ => Err(invalid(name, value, "expected a number")),
+    }
+}
+
+fn invalid(name: &str, value: &Value, reason: &str) -> ConfigError {
+    ConfigError::InvalidValue {
+        option: name.to_string(),
+        value: value.to_string(),
+        reason: reason.to_string(),
+    }
+}
@@ -0,0 +1,71 @@
+use core::fmt;
+
+use crate::memory::PROGRAM_START;
+use crate::snapshot::CpuSnapshot;
+
+pub struct Cpu {
+    pub(crate) vx: [u8; 16],
+    pub(crate) pc: u16,
+    pub(crate) i: u16,
+}
+
+impl Cpu {
+    pub(crate) fn new() -> Self {
+        Cpu {
+            vx: [0; 16],
+            pc: PROGRAM_START,
+            i: 0,
+        }
+    }
+
+    pub fn dump(&self) -> CpuSnapshot {
+        CpuSnapshot {
+            vx: self.vx,
+            pc: self.pc,
+            i: self.i,
+        }
+    }
+
+    pub fn restore(&mut self, snapshot: CpuSnapshot) {
+        self.vx = snapshot.vx;
+        self.pc = snapshot.pc;
+        self.i = snapshot.i;
+    }
+
+    // Each of these writes the result first and VF last, so when X is F the flag is
+    // what VF ends up holding, and a Y of F is read before it changes.
+    pub(crate) fn add_registers(&mut self, va: u8, vb: u8) {
+        let (result, carry) = self.vx[va as usize].overflowing_add(self.vx[vb as usize]);
+        self.vx[va as usize] = result;
+        self.vx[0xF] = carry as u8;
+    }
+
+    // VF is 1 when there is no borrow, which includes equal registers
+    pub(crate) fn substract_registers(&mut self, va: u8, vb: u8, store: u8) {
+        let (result, borrow) = self.vx[va as usize].overflowing_sub(self.vx[vb as usize]);
+        self.vx[store as usize] = result;
+        self.vx[0xF] = !borrow as u8;
+    }
+
+    // VX = VY >> 1, with y being x to shift VX in place
+    pub(crate) fn half_register(&mut self, x: u8, y: u8) {
+        let flag = self.vx[y as usize] & 1;
+        self.vx[x as usize] = self.vx[y as usize] >> 1;
+        self.vx[0xF] = flag;
+    }
+
+    pub(crate) fn double_register(&mut self, x: u8, y: u8) {
+        let flag = self.vx[y as usize] >> 7;
+        self.vx[x as usize] = self.vx[y as usize] << 1;
+        self.vx[0xF] = flag;
+    }
+}
+
+impl fmt::Debug for Cpu {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (x, v) in self.vx.iter().enumerate() {
+            write!(f, "V{:X}={:02X} ", x, v)?;
+        }
+        write!(f, "PC={:04X} I={:04X}", self.pc, self.i)
+    }
+}
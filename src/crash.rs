@@ -0,0 +1,111 @@
+use std::{
+    any::Any,
+    fmt::Write,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+};
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    fs,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::chip8::{Chip8, InstructionResult};
+use crate::error::Chip8Error;
+
+// bytes of RAM dumped on each side of the PC
+const RAM_WINDOW: usize = 64;
+
+// Steps the machine, and if the step panics writes a crash report to the working
+// directory before letting the panic carry on.
+pub fn step_or_report(chip8: &mut Chip8) -> Result<InstructionResult, Chip8Error> {
+    step_or_report_to(chip8, Path::new(""))
+}
+
+// step_or_report with the report going to `dir`
+pub fn step_or_report_to(chip8: &mut Chip8, dir: &Path) -> Result<InstructionResult, Chip8Error> {
+    match panic::catch_unwind(AssertUnwindSafe(|| chip8.step())) {
+        Ok(result) => result,
+        Err(payload) => {
+            match write_crash_report(chip8, panic_message(&*payload), dir) {
+                Some(path) => log::error!("crash report written to {}", path.display()),
+                None => log::error!("could not write a crash report"),
+            }
+            panic::resume_unwind(payload)
+        }
+    }
+}
+
+pub fn crash_report(chip8: &Chip8, message: &str) -> String {
+    let mut report = String::new();
+    // writing into a String can't fail
+    let _ = write_report(&mut report, chip8, message);
+    report
+}
+
+fn write_report(report: &mut String, chip8: &Chip8, message: &str) -> std::fmt::Result {
+    writeln!(report, "rust-8 crash report")?;
+    writeln!(report, "panic: {}", message)?;
+    writeln!(report, "rom: {}", chip8.rom_name.as_deref().unwrap_or("<unnamed>"))?;
+    writeln!(report, "rom hash: {:016x}", chip8.rom_hash)?;
+    writeln!(report)?;
+    writeln!(report, "{:?}", chip8.cpu)?;
+    writeln!(report, "{:?}", chip8.stack)?;
+    writeln!(report, "{:?}", chip8.hour)?;
+
+    writeln!(report)?;
+    writeln!(report, "last {} instructions, oldest first:", chip8.history.len())?;
+    for (pc, instruction) in chip8.history.iter() {
+        writeln!(report, "{:04X}  {:?}", pc, instruction)?;
+    }
+
+    writeln!(report)?;
+    writeln!(report, "ram around PC:")?;
+    let pc = chip8.pc() as usize;
+    let end = (pc + RAM_WINDOW).min(chip8.ram.len());
+    // nothing at all for a PC that has run off the end of RAM
+    let start = (pc.saturating_sub(RAM_WINDOW) & !0xF).min(end);
+    for (n, line) in chip8.ram[start..end].chunks(16).enumerate() {
+        let address = start + n * 16;
+        write!(report, "{:04X} ", address)?;
+        for (offset, byte) in line.iter().enumerate() {
+            let marker = if address + offset == pc { '>' } else { ' ' };
+            write!(report, "{}{:02X}", marker, byte)?;
+        }
+        writeln!(report)?;
+    }
+    Ok(())
+}
+
+// the report is best effort: a panic while building or writing it only loses the report
+#[cfg(not(target_arch = "wasm32"))]
+fn write_crash_report(chip8: &Chip8, message: String, dir: &Path) -> Option<PathBuf> {
+    panic::catch_unwind(AssertUnwindSafe(|| {
+        let report = crash_report(chip8, &message);
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or(0);
+        let path = dir.join(format!("rust-8-crash-{}.txt", seconds));
+        fs::write(&path, report).ok()?;
+        Some(path)
+    }))
+    .ok()
+    .flatten()
+}
+
+// a browser has no working directory to leave the report in
+#[cfg(target_arch = "wasm32")]
+fn write_crash_report(_chip8: &Chip8, _message: String, _dir: &Path) -> Option<PathBuf> {
+    None
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        String::from("<unknown panic payload>")
+    }
+}
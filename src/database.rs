@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+use sha1::{Digest, Sha1};
+
+use crate::error::Chip8Error;
+use crate::variant::ChipVariant;
+
+// What the CHIP-8 database (github.com/chip-8/chip-8-database) knows about one ROM.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RomInfo {
+    pub title: String,
+    pub authors: Vec<String>,
+    // the platforms the ROM is meant for, best first, leaving out ones rust-8 doesn't emulate
+    pub platforms: Vec<ChipVariant>,
+    // instructions per frame
+    pub tickrate: Option<usize>,
+}
+
+impl RomInfo {
+    pub fn platform(&self) -> Option<ChipVariant> {
+        self.platforms.first().copied()
+    }
+}
+
+// The database's programs.json, keyed by the SHA-1 of each ROM file:
+//
+//     [{"title": "Pong", "authors": ["..."],
+//       "roms": {"<sha1>": {"platforms": ["originalChip8"], "tickrate": 15, ...}}}, ...]
+//
+// Everything else in it is ignored.
+#[derive(Debug, Clone, Default)]
+pub struct RomDatabase {
+    roms: HashMap<String, RomInfo>,
+}
+
+impl RomDatabase {
+    pub fn from_json(text: &str) -> Result<RomDatabase, Chip8Error> {
+        let invalid = |reason: &str| Chip8Error::InvalidState(format!("rom database: {}", reason));
+        let value: Value = serde_json::from_str(text).map_err(|err| invalid(&err.to_string()))?;
+        let programs = value.as_array().ok_or_else(|| invalid("expected a list of programs"))?;
+
+        let mut roms = HashMap::new();
+        for program in programs {
+            let title = program.get("title").and_then(Value::as_str).unwrap_or("untitled");
+            let authors: Vec<String> = program
+                .get("authors")
+                .and_then(Value::as_array)
+                .map(|authors| authors.iter().filter_map(Value::as_str).map(String::from).collect())
+                .unwrap_or_default();
+            let files = match program.get("roms").and_then(Value::as_object) {
+                Some(files) => files,
+                None => continue,
+            };
+            for (hash, rom) in files {
+                let platforms = rom
+                    .get("platforms")
+                    .and_then(Value::as_array)
+                    .map(|platforms| platforms.iter().filter_map(Value::as_str).filter_map(platform).collect())
+                    .unwrap_or_default();
+                let tickrate = rom.get("tickrate").and_then(Value::as_u64).map(|rate| rate as usize);
+                roms.insert(
+                    hash.to_ascii_lowercase(),
+                    RomInfo {
+                        title: title.to_string(),
+                        authors: authors.clone(),
+                        platforms,
+                        tickrate,
+                    },
+                );
+            }
+        }
+        Ok(RomDatabase { roms })
+    }
+
+    pub fn len(&self) -> usize {
+        self.roms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.roms.is_empty()
+    }
+
+    pub fn lookup(&self, rom: &[u8]) -> Option<&RomInfo> {
+        self.roms.get(&sha1_hex(rom))
+    }
+}
+
+pub fn sha1_hex(data: &[u8]) -> String {
+    Sha1::digest(data).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// the database's platform ids
+fn platform(id: &str) -> Option<ChipVariant> {
+    match id {
+        "originalChip8" | "hybridVIP" | "modernChip8" => Some(ChipVariant::Chip8Original),
+        "chip48" => Some(ChipVariant::Chip48),
+        "superchip1" => Some(ChipVariant::SuperChip10),
+        "superchip" => Some(ChipVariant::SuperChip11),
+        "xochip" => Some(ChipVariant::XoChip),
+        "chip8e" => Some(ChipVariant::Chip8E),
+        _ => None,
+    }
+}
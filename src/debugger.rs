@@ -0,0 +1,501 @@
+use std::{collections::BTreeSet, fmt, str::FromStr};
+
+use crate::callstack::CallStack;
+use crate::chip8::{Chip8, InstructionResult};
+use crate::error::{Chip8Error, ExprError};
+use crate::expr::WatchExpr;
+use crate::instruction::Instruction;
+use crate::keypad::Keypad;
+use crate::state::MachineState;
+
+// instructions between two checkpoints unless with_checkpoint_interval says otherwise
+pub const DEFAULT_CHECKPOINT_INTERVAL: u64 = 100;
+
+// Instructions run_to_break can stop before when there's no address to break at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakEvent {
+    // DXYN
+    Draw,
+    // FX18 with VX nonzero, which starts the buzzer
+    Sound,
+    // EX9E, EXA1 and FX0A
+    KeyQuery,
+}
+
+impl BreakEvent {
+    // whether `instruction`, about to run on `chip8`, is one of these
+    pub fn matches(self, instruction: Instruction, chip8: &Chip8) -> bool {
+        match self {
+            BreakEvent::Draw => matches!(instruction, Instruction::Drw(..)),
+            BreakEvent::Sound => matches!(instruction, Instruction::LdStVx(x) if chip8.register(x) != 0),
+            BreakEvent::KeyQuery => {
+                matches!(instruction, Instruction::Skp(_) | Instruction::Sknp(_) | Instruction::LdVxK(_))
+            }
+        }
+    }
+}
+
+impl fmt::Display for BreakEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BreakEvent::Draw => write!(f, "draw"),
+            BreakEvent::Sound => write!(f, "sound"),
+            BreakEvent::KeyQuery => write!(f, "keyquery"),
+        }
+    }
+}
+
+impl FromStr for BreakEvent {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "draw" => Ok(BreakEvent::Draw),
+            "sound" => Ok(BreakEvent::Sound),
+            "keyquery" => Ok(BreakEvent::KeyQuery),
+            _ => Err(format!("unknown event {:?}, expected draw, sound or keyquery", name)),
+        }
+    }
+}
+
+// A watched value that the instruction at `pc` changed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WatchHit {
+    pub expr: WatchExpr,
+    pub pc: u16,
+    pub old: u16,
+    pub new: u16,
+}
+
+impl fmt::Display for WatchHit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let width = self.expr.width();
+        write!(f, "{} changed at {:#05X}: {:0w$X} -> {:0w$X}", self.expr, self.pc, self.old, self.new, w = width)
+    }
+}
+
+// Why run_to_break stopped.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Break {
+    // PC reached a breakpoint
+    Address(u16),
+    // the instruction at `pc` is `event` and hasn't run yet
+    Event { event: BreakEvent, pc: u16, instruction: Instruction },
+    // a watch added with `stop` changed
+    Watch(WatchHit),
+}
+
+struct Watch {
+    expr: WatchExpr,
+    stop: bool,
+    // as of the last instruction
+    value: u16,
+}
+
+struct Checkpoint {
+    position: u64,
+    state: MachineState,
+    keypad: Keypad,
+    call_stack: CallStack,
+}
+
+// A debugging session that can also run backwards. It steps the machine itself, ticking
+// the timers every instructions_per_frame instructions, saves the whole machine every
+// checkpoint interval and logs every keypad change. Going back restores the last
+// checkpoint before the target and runs forward to it again, which gives the same
+// machine as long as the generator is seeded (see Chip8Builder::with_rng_seed).
+// Going back forgets what came after: stepping on from there records it afresh.
+pub struct Debugger {
+    chip8: Chip8,
+    instructions_per_frame: usize,
+    interval: u64,
+    // instructions run since the session started
+    position: u64,
+    checkpoints: Vec<Checkpoint>,
+    // (position, keypad) for every change, set before the instruction after `position` ran
+    inputs: Vec<(u64, Keypad)>,
+    breakpoints: BTreeSet<u16>,
+    // (event, sticky): a breakpoint that isn't sticky goes once it's hit
+    event_breaks: Vec<(BreakEvent, bool)>,
+    // where the last event break stopped, so continuing from there runs the instruction
+    event_stop: Option<u64>,
+    watches: Vec<Watch>,
+    // changes found stepping forward, until take_watch_hits
+    watch_hits: Vec<WatchHit>,
+    call_stack: CallStack,
+}
+
+impl Debugger {
+    pub fn new(chip8: Chip8, instructions_per_frame: usize) -> Result<Self, Chip8Error> {
+        if chip8.rng_seed.is_none() {
+            return Err(Chip8Error::InvalidState(String::from(
+                "reverse stepping needs a seeded generator to run the same way twice",
+            )));
+        }
+        let call_stack = CallStack::from_stack(chip8.pc(), &chip8.stack_snapshot(), &chip8.ram);
+        let mut debugger = Debugger {
+            chip8,
+            instructions_per_frame: instructions_per_frame.max(1),
+            interval: DEFAULT_CHECKPOINT_INTERVAL,
+            position: 0,
+            checkpoints: Vec::new(),
+            inputs: Vec::new(),
+            breakpoints: BTreeSet::new(),
+            event_breaks: Vec::new(),
+            event_stop: None,
+            watches: Vec::new(),
+            watch_hits: Vec::new(),
+            call_stack,
+        };
+        debugger.checkpoint();
+        Ok(debugger)
+    }
+
+    // fewer instructions between checkpoints make going back quicker and take more memory
+    pub fn with_checkpoint_interval(mut self, interval: u64) -> Self {
+        self.interval = interval.max(1);
+        self
+    }
+
+    pub fn chip8(&self) -> &Chip8 {
+        &self.chip8
+    }
+
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    // what runs on the next step and where, None when PC is off the end of memory
+    pub fn next_instruction(&self) -> Option<(u16, Instruction)> {
+        let pc = self.chip8.pc();
+        let bytes = self.chip8.ram.get(pc as usize..pc as usize + 2)?;
+        Some((pc, Instruction::decode(bytes[0], bytes[1])))
+    }
+
+    // the calls the machine is in, kept from the CALLs and RETs the session ran
+    pub fn call_stack(&self) -> &CallStack {
+        &self.call_stack
+    }
+
+    pub fn set_keypad(&mut self, keypad: Keypad) {
+        if *self.chip8.keypad_mut() != keypad {
+            self.inputs.push((self.position, keypad));
+            *self.chip8.keypad_mut() = keypad;
+        }
+    }
+
+    pub fn add_breakpoint(&mut self, address: u16) {
+        self.breakpoints.insert(address);
+    }
+
+    pub fn remove_breakpoint(&mut self, address: u16) -> bool {
+        self.breakpoints.remove(&address)
+    }
+
+    // Stops before the next instruction that is `event`, every time when `sticky` and only
+    // the next time otherwise. Breaking on an event again replaces its stickiness.
+    pub fn break_on(&mut self, event: BreakEvent, sticky: bool) {
+        self.event_breaks.retain(|(armed, _)| *armed != event);
+        self.event_breaks.push((event, sticky));
+    }
+
+    pub fn clear_break_on(&mut self, event: BreakEvent) -> bool {
+        let armed = self.event_breaks.len();
+        self.event_breaks.retain(|(other, _)| *other != event);
+        self.event_breaks.len() < armed
+    }
+
+    // Follows `expr` from here on, noting every instruction that changes it and with
+    // `stop` also stopping run_to_break after it. Watching an expression again replaces
+    // its `stop`.
+    pub fn watch(&mut self, expr: WatchExpr, stop: bool) {
+        self.unwatch(expr);
+        let value = expr.eval(&self.chip8);
+        self.watches.push(Watch { expr, stop, value });
+    }
+
+    pub fn unwatch(&mut self, expr: WatchExpr) -> bool {
+        let watched = self.watches.len();
+        self.watches.retain(|watch| watch.expr != expr);
+        self.watches.len() < watched
+    }
+
+    // the changes stepping forward found since the last call, oldest first; going back
+    // and replaying doesn't find them again
+    pub fn take_watch_hits(&mut self) -> Vec<WatchHit> {
+        std::mem::take(&mut self.watch_hits)
+    }
+
+    // A halted machine doesn't move, so Halted leaves the position where it was.
+    pub fn step(&mut self) -> Result<InstructionResult, Chip8Error> {
+        let result = self.advance()?;
+        if let (false, Some(&(pc, _))) = (matches!(result, InstructionResult::Halted { .. }), self.chip8.history.back()) {
+            for watch in self.watches.iter_mut() {
+                let new = watch.expr.eval(&self.chip8);
+                if new != watch.value {
+                    self.watch_hits.push(WatchHit { expr: watch.expr, pc, old: watch.value, new });
+                    watch.value = new;
+                }
+            }
+        }
+        if self.position.is_multiple_of(self.interval) && self.checkpoints.last().is_some_and(|last| last.position < self.position) {
+            self.checkpoint();
+        }
+        Ok(result)
+    }
+
+    // Runs until PC reaches a breakpoint, the machine halts or `limit` instructions ran.
+    // True when it stopped at a breakpoint.
+    pub fn run_to_breakpoint(&mut self, limit: u64) -> Result<bool, Chip8Error> {
+        Ok(self.run_to_break(limit)?.is_some())
+    }
+
+    // Runs like run_to_breakpoint, also stopping before an instruction break_on asked
+    // for, and says which of them it stopped at.
+    pub fn run_to_break(&mut self, limit: u64) -> Result<Option<Break>, Chip8Error> {
+        for _ in 0..limit {
+            if self.event_stop != Some(self.position) {
+                if let Some(hit) = self.event_hit() {
+                    self.event_stop = Some(self.position);
+                    return Ok(Some(hit));
+                }
+            }
+            let hits = self.watch_hits.len();
+            if let InstructionResult::Halted { .. } = self.step()? {
+                return Ok(None);
+            }
+            let stopping = self.watch_hits[hits..]
+                .iter()
+                .find(|hit| self.watches.iter().any(|watch| watch.stop && watch.expr == hit.expr));
+            if let Some(hit) = stopping {
+                return Ok(Some(Break::Watch(*hit)));
+            }
+            if self.breakpoints.contains(&self.chip8.pc()) {
+                return Ok(Some(Break::Address(self.chip8.pc())));
+            }
+        }
+        Ok(None)
+    }
+
+    // rstep n: goes back `n` instructions, or to the start of the session
+    pub fn reverse_step(&mut self, n: u64) -> Result<(), Chip8Error> {
+        self.seek(self.position.saturating_sub(n))
+    }
+
+    // reverse-continue: goes back to the last time PC was at a breakpoint. True when it
+    // found one; otherwise the session is back at its start.
+    pub fn reverse_continue(&mut self) -> Result<bool, Chip8Error> {
+        let mut end = self.position;
+        // a stretch between two checkpoints at a time, newest first, so the hit found is
+        // the latest one
+        for index in (0..self.checkpoints.len()).rev() {
+            let start = self.checkpoints[index].position;
+            if start >= end {
+                continue;
+            }
+            self.restore(index)?;
+            let mut hit = None;
+            while self.position < end {
+                if self.breakpoints.contains(&self.chip8.pc()) {
+                    hit = Some(self.position);
+                }
+                if !self.replay_one()? {
+                    break;
+                }
+            }
+            if let Some(position) = hit {
+                self.seek(position)?;
+                return Ok(true);
+            }
+            end = start;
+        }
+        self.seek(0)?;
+        Ok(false)
+    }
+
+    // Puts the machine where it was after `target` instructions, no further on than it has
+    // been.
+    pub fn seek(&mut self, target: u64) -> Result<(), Chip8Error> {
+        let target = target.min(self.position);
+        let index = self.checkpoints.iter().rposition(|checkpoint| checkpoint.position <= target).unwrap_or(0);
+        self.restore(index)?;
+        while self.position < target {
+            if !self.replay_one()? {
+                break;
+            }
+        }
+        self.apply_inputs();
+        self.checkpoints.truncate(index + 1);
+        self.inputs.retain(|(position, _)| *position <= target);
+        self.event_stop = None;
+        for watch in self.watches.iter_mut() {
+            watch.value = watch.expr.eval(&self.chip8);
+        }
+        Ok(())
+    }
+
+    // the first event the next instruction is, forgetting it unless it's sticky
+    fn event_hit(&mut self) -> Option<Break> {
+        let (pc, instruction) = self.next_instruction()?;
+        let index = self.event_breaks.iter().position(|(event, _)| event.matches(instruction, &self.chip8))?;
+        let (event, sticky) = self.event_breaks[index];
+        if !sticky {
+            self.event_breaks.remove(index);
+        }
+        Some(Break::Event { event, pc, instruction })
+    }
+
+    fn checkpoint(&mut self) {
+        self.checkpoints.push(Checkpoint {
+            position: self.position,
+            state: self.chip8.save_state(),
+            keypad: *self.chip8.keypad_mut(),
+            call_stack: self.call_stack.clone(),
+        });
+    }
+
+    fn restore(&mut self, index: usize) -> Result<(), Chip8Error> {
+        let checkpoint = &self.checkpoints[index];
+        self.chip8.load_state(checkpoint.state.clone())?;
+        *self.chip8.keypad_mut() = checkpoint.keypad;
+        self.call_stack = checkpoint.call_stack.clone();
+        self.position = checkpoint.position;
+        Ok(())
+    }
+
+    // the keypad as it was last set at the current position
+    fn apply_inputs(&mut self) {
+        if let Some((_, keypad)) = self.inputs.iter().rev().find(|(position, _)| *position == self.position) {
+            *self.chip8.keypad_mut() = *keypad;
+        }
+    }
+
+    // false once the machine halted, which the first time round it didn't before here
+    fn replay_one(&mut self) -> Result<bool, Chip8Error> {
+        self.apply_inputs();
+        Ok(!matches!(self.advance()?, InstructionResult::Halted { .. }))
+    }
+
+    fn advance(&mut self) -> Result<InstructionResult, Chip8Error> {
+        let result = self.chip8.step()?;
+        if let InstructionResult::Halted { .. } = result {
+            return Ok(result);
+        }
+        if let Some(&(pc, instruction)) = self.chip8.history.back() {
+            self.call_stack.update(pc, instruction, &self.chip8.stack_snapshot(), &self.chip8.ram);
+        }
+        self.position += 1;
+        if self.position.is_multiple_of(self.instructions_per_frame as u64) {
+            self.chip8.tick_timers();
+        }
+        Ok(result)
+    }
+}
+
+// A line typed at the rust-8 debug prompt.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DebugCommand {
+    Step(u64),
+    ReverseStep(u64),
+    Continue,
+    ReverseContinue,
+    Break(u16),
+    Delete(u16),
+    // stop before the next instruction that is the event, every time unless `once`
+    BreakOn { event: BreakEvent, once: bool },
+    DeleteOn(BreakEvent),
+    // report every change to the expression, and stop continue at one with `stop`
+    Watch { expr: WatchExpr, stop: bool },
+    Unwatch(WatchExpr),
+    Backtrace,
+    // print the screen as text, see AsciiDump
+    Screen { ruler: bool, half_blocks: bool },
+    Quit,
+}
+
+impl FromStr for DebugCommand {
+    type Err = String;
+
+    // step [n], rstep [n], continue, reverse-continue, break ADDR, delete ADDR, break-on
+    // EVENT [once], delete EVENT, watch [--break] EXPR, unwatch EXPR, backtrace, screen
+    // [ruler] [half] and quit, with s, rs, c, rc, b, d, w, bt and q for short; addresses are
+    // hex, events are draw, sound and keyquery and expressions are those of WatchExpr
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let count = |word: Option<&&str>| match word {
+            Some(word) => word.parse().map_err(|_| format!("{:?} is not a number of instructions", word)),
+            None => Ok(1),
+        };
+        let address = |word: Option<&&str>| {
+            let word = word.ok_or_else(|| String::from("which address?"))?;
+            u16::from_str_radix(word.trim_start_matches("0x"), 16)
+                .ok()
+                .filter(|address| *address < 0x1000)
+                .ok_or_else(|| format!("{:?} is not an address", word))
+        };
+        // an expression may have spaces in it, so it's the rest of the line
+        let expr = |words: &[&str]| -> Result<WatchExpr, String> {
+            if words.is_empty() {
+                return Err(String::from("watch what?"));
+            }
+            words.join(" ").parse().map_err(|err: ExprError| err.to_string())
+        };
+        match words.first().copied() {
+            Some("watch") | Some("w") => {
+                return match words.get(1).copied() {
+                    Some("--break") => Ok(DebugCommand::Watch { expr: expr(&words[2..])?, stop: true }),
+                    _ => Ok(DebugCommand::Watch { expr: expr(&words[1..])?, stop: false }),
+                }
+            }
+            Some("unwatch") => return Ok(DebugCommand::Unwatch(expr(&words[1..])?)),
+            Some("screen") => {
+                let (mut ruler, mut half_blocks) = (false, false);
+                for word in &words[1..] {
+                    match *word {
+                        "ruler" => ruler = true,
+                        "half" => half_blocks = true,
+                        other => return Err(format!("expected ruler or half, got {:?}", other)),
+                    }
+                }
+                return Ok(DebugCommand::Screen { ruler, half_blocks });
+            }
+            _ => {}
+        }
+        let command = match words.first().copied() {
+            Some("step") | Some("s") => DebugCommand::Step(count(words.get(1))?),
+            Some("rstep") | Some("rs") => DebugCommand::ReverseStep(count(words.get(1))?),
+            Some("continue") | Some("c") => DebugCommand::Continue,
+            Some("reverse-continue") | Some("rc") => DebugCommand::ReverseContinue,
+            Some("break") | Some("b") => DebugCommand::Break(address(words.get(1))?),
+            Some("delete") | Some("d") => match words.get(1).and_then(|word| word.parse().ok()) {
+                Some(event) => DebugCommand::DeleteOn(event),
+                None => DebugCommand::Delete(address(words.get(1))?),
+            },
+            Some("break-on") => {
+                let event = words.get(1).ok_or_else(|| String::from("which event?"))?.parse()?;
+                match words.get(2).copied() {
+                    Some("once") => DebugCommand::BreakOn { event, once: true },
+                    Some(other) => return Err(format!("expected once after the event, got {:?}", other)),
+                    None => DebugCommand::BreakOn { event, once: false },
+                }
+            }
+            Some("backtrace") | Some("bt") => DebugCommand::Backtrace,
+            Some("quit") | Some("q") => DebugCommand::Quit,
+            Some(other) => return Err(format!("unknown command {:?}", other)),
+            None => return Err(String::from("no command")),
+        };
+        let takes = match command {
+            DebugCommand::Step(_)
+            | DebugCommand::ReverseStep(_)
+            | DebugCommand::Break(_)
+            | DebugCommand::Delete(_)
+            | DebugCommand::DeleteOn(_) => 2,
+            DebugCommand::BreakOn { .. } => 3,
+            _ => 1,
+        };
+        if words.len() > takes {
+            return Err(format!("too many arguments to {}", words[0]));
+        }
+        Ok(command)
+    }
+}
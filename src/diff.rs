@@ -0,0 +1,71 @@
+use std::fmt;
+
+use crate::chip8::Chip8;
+
+// What differs between two machines, as (index or address, before, after) triples.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Chip8Diff {
+    pub changed_registers: Vec<(u8, u8, u8)>,
+    pub changed_ram: Vec<(u16, u8, u8)>,
+    pub pc_changed: Option<(u16, u16)>,
+    pub display_changed: bool,
+}
+
+impl Chip8Diff {
+    pub fn is_empty(&self) -> bool {
+        self.changed_registers.is_empty()
+            && self.changed_ram.is_empty()
+            && self.pc_changed.is_none()
+            && !self.display_changed
+    }
+}
+
+pub fn diff(a: &Chip8, b: &Chip8) -> Chip8Diff {
+    let changed_registers = a.cpu.vx.iter()
+        .zip(b.cpu.vx.iter())
+        .enumerate()
+        .filter(|(_, (before, after))| before != after)
+        .map(|(x, (before, after))| (x as u8, *before, *after))
+        .collect();
+
+    let changed_ram = a.ram.iter()
+        .zip(b.ram.iter())
+        .enumerate()
+        .filter(|(_, (before, after))| before != after)
+        .map(|(address, (before, after))| (address as u16, *before, *after))
+        .collect();
+
+    let pc_changed = if a.cpu.pc != b.cpu.pc {
+        Some((a.cpu.pc, b.cpu.pc))
+    } else {
+        None
+    };
+
+    Chip8Diff {
+        changed_registers,
+        changed_ram,
+        pc_changed,
+        display_changed: a.display != b.display,
+    }
+}
+
+impl fmt::Display for Chip8Diff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "no differences");
+        }
+        if let Some((before, after)) = self.pc_changed {
+            writeln!(f, "PC: {:04X} -> {:04X}", before, after)?;
+        }
+        for (x, before, after) in &self.changed_registers {
+            writeln!(f, "V{:X}: {:02X} -> {:02X}", x, before, after)?;
+        }
+        for (address, before, after) in &self.changed_ram {
+            writeln!(f, "[{:04X}]: {:02X} -> {:02X}", address, before, after)?;
+        }
+        if self.display_changed {
+            writeln!(f, "display changed")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,353 @@
+use std::fmt;
+
+// the original 64x32 screen, which is what a new Framebuffer has
+pub const WIDTH: usize = 64;
+pub const HEIGHT: usize = 32;
+// the SCHIP/XO-CHIP high resolution screen, the largest a Framebuffer can be
+pub const MAX_WIDTH: usize = 128;
+pub const MAX_HEIGHT: usize = 64;
+
+// One u128 per row holding `width` pixels in its low bits, the most significant of
+// those being the leftmost pixel. Coordinates wrap around both edges.
+//
+// Every change bumps a generation, and each row remembers the generation it last changed
+// in, so delta_since can tell a renderer what changed since the last frame it drew.
+// Changes that leave a row as it was count for nothing.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Framebuffer {
+    width: usize,
+    height: usize,
+    rows: Vec<u128>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    generation: u64,
+    // the generation each row last changed in; empty for a deserialized one, which
+    // has no history to give deltas from
+    #[cfg_attr(feature = "serde", serde(skip))]
+    changed: Vec<u64>,
+    // the generation of the last resize, which no delta can describe
+    #[cfg_attr(feature = "serde", serde(skip))]
+    resized: u64,
+}
+
+// the pixels, not how they came about
+impl PartialEq for Framebuffer {
+    fn eq(&self, other: &Self) -> bool {
+        self.width == other.width && self.height == other.height && self.rows == other.rows
+    }
+}
+
+impl Framebuffer {
+    pub fn new() -> Self {
+        Framebuffer::with_size(WIDTH, HEIGHT)
+    }
+
+    // panics past MAX_WIDTH x MAX_HEIGHT or below the 8 pixels a sprite row needs
+    pub fn with_size(width: usize, height: usize) -> Self {
+        assert!((8..=MAX_WIDTH).contains(&width) && (1..=MAX_HEIGHT).contains(&height));
+        Framebuffer {
+            width,
+            height,
+            rows: vec![0; height],
+            generation: 1,
+            changed: vec![1; height],
+            resized: 1,
+        }
+    }
+
+    // false for a deserialized one whose size with_size would have refused
+    pub(crate) fn is_valid(&self) -> bool {
+        (8..=MAX_WIDTH).contains(&self.width) && (1..=MAX_HEIGHT).contains(&self.height) && self.rows.len() == self.height
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    // switches resolution, which clears the screen like it does on real hardware
+    pub fn resize(&mut self, width: usize, height: usize) {
+        let generation = self.generation + 1;
+        *self = Framebuffer::with_size(width, height);
+        self.generation = generation;
+        self.changed.fill(generation);
+        self.resized = generation;
+    }
+
+    pub fn clear(&mut self) {
+        self.update(|rows, _| rows.fill(0));
+    }
+
+    // Takes `other`'s size and pixels at a generation past both of them, for a screen
+    // swapped out by a reset or a restored state. Like a resize, the change is too big
+    // for a delta, so whoever drew either one before gets the whole screen.
+    pub fn replace_from(&mut self, other: &Framebuffer) {
+        let generation = self.generation.max(other.generation) + 1;
+        self.width = other.width;
+        self.height = other.height;
+        self.rows.clone_from(&other.rows);
+        self.generation = generation;
+        self.changed = vec![generation; self.height];
+        self.resized = generation;
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> bool {
+        self.rows[y % self.height] >> (self.width - 1 - x % self.width) & 1 == 1
+    }
+
+    pub fn set_pixel(&mut self, x: usize, y: usize, on: bool) {
+        let bit = 1 << (self.width - 1 - x % self.width);
+        let row = self.rows[y % self.height];
+        self.set_row(y, if on { row | bit } else { row & !bit });
+    }
+
+    // flips one pixel, returning true if it was lit
+    pub fn xor_pixel(&mut self, x: usize, y: usize) -> bool {
+        let bit = 1 << (self.width - 1 - x % self.width);
+        let row = self.rows[y % self.height] ^ bit;
+        self.set_row(y, row);
+        row & bit == 0
+    }
+
+    // XORs an 8 pixel sprite row starting at (x, y), wrapping around the right edge.
+    // Returns true if any lit pixel was switched off.
+    pub fn draw_sprite_row(&mut self, x: usize, y: usize, sprite: u8) -> bool {
+        let mask = self.rotate_right((sprite as u128) << (self.width - 8), x % self.width);
+        let row = self.rows[y % self.height];
+        self.set_row(y, row ^ mask);
+        row & mask != 0
+    }
+
+    pub fn scroll_down(&mut self, n: usize) {
+        self.update(|rows, _| {
+            let (height, n) = (rows.len(), n.min(rows.len()));
+            rows.copy_within(0..height - n, n);
+            rows[..n].fill(0);
+        });
+    }
+
+    pub fn scroll_up(&mut self, n: usize) {
+        self.update(|rows, _| {
+            let (height, n) = (rows.len(), n.min(rows.len()));
+            rows.copy_within(n.., 0);
+            rows[height - n..].fill(0);
+        });
+    }
+
+    pub fn scroll_left(&mut self, n: usize) {
+        self.update(|rows, mask| {
+            for row in rows.iter_mut() {
+                *row = row.checked_shl(n as u32).unwrap_or(0) & mask;
+            }
+        });
+    }
+
+    pub fn scroll_right(&mut self, n: usize) {
+        self.update(|rows, _| {
+            for row in rows.iter_mut() {
+                *row = row.checked_shr(n as u32).unwrap_or(0);
+            }
+        });
+    }
+
+    // Row `y` packed a bit per pixel into whole bytes, the leftmost pixel in the top
+    // bit of the first byte, the way deltas and the remote protocol send it.
+    pub fn packed_row(&self, y: usize) -> Vec<u8> {
+        let bytes = self.width.div_ceil(8);
+        // shifted up so the leftmost pixel is the top bit of a whole number of bytes
+        let row = self.rows[y % self.height] << (bytes * 8 - self.width);
+        row.to_be_bytes()[16 - bytes..].to_vec()
+    }
+
+    // the whole screen packed like packed_row, one row after the other
+    pub fn packed(&self) -> Vec<u8> {
+        (0..self.height).flat_map(|y| self.packed_row(y)).collect()
+    }
+
+    // What changed since `generation`, for a renderer that last drew the screen as it was
+    // then. A generation this framebuffer never had, or one from before a resize, gets
+    // the whole screen, as does a delta that would cover more than half the rows.
+    pub fn delta_since(&self, generation: u64) -> FrameDelta {
+        let full = FrameDelta::Full {
+            generation: self.generation,
+            width: self.width,
+            height: self.height,
+            rows: self.packed(),
+        };
+        if generation < self.resized || generation > self.generation || self.changed.len() != self.height {
+            return full;
+        }
+        let changed: Vec<usize> = (0..self.height).filter(|&y| self.changed[y] > generation).collect();
+        if changed.len() * 2 > self.height {
+            return full;
+        }
+        FrameDelta::Rows {
+            generation: self.generation,
+            rows: changed.into_iter().map(|y| (y, self.packed_row(y))).collect(),
+        }
+    }
+
+    fn set_row(&mut self, y: usize, bits: u128) {
+        let y = y % self.height;
+        if self.rows[y] != bits {
+            self.rows[y] = bits;
+            self.generation += 1;
+            self.mark(y);
+        }
+    }
+
+    // applies `change` to all the rows at once, marking the ones it changed
+    fn update(&mut self, change: impl FnOnce(&mut [u128], u128)) {
+        let before = self.rows.clone();
+        let mask = self.row_mask();
+        change(&mut self.rows, mask);
+        if before != self.rows {
+            self.generation += 1;
+            let changed: Vec<usize> = (0..self.height).filter(|&y| before[y] != self.rows[y]).collect();
+            for y in changed {
+                self.mark(y);
+            }
+        }
+    }
+
+    fn mark(&mut self, y: usize) {
+        // a deserialized framebuffer starts keeping track from its first change
+        if self.changed.len() != self.height {
+            self.changed = vec![self.generation; self.height];
+            self.resized = self.generation;
+        }
+        self.changed[y] = self.generation;
+    }
+
+    // `buffer` holds width * height pixels
+    pub fn render_into(&self, buffer: &mut [u32], fg: u32, bg: u32) {
+        for (y, row) in self.rows.iter().enumerate() {
+            for x in 0..self.width {
+                let on = row >> (self.width - 1 - x) & 1 == 1;
+                buffer[y * self.width + x] = if on { fg } else { bg };
+            }
+        }
+    }
+
+    fn row_mask(&self) -> u128 {
+        u128::MAX >> (128 - self.width)
+    }
+
+    // rotates within the `width` bits of a row rather than all 128
+    fn rotate_right(&self, bits: u128, n: usize) -> u128 {
+        if n == 0 {
+            return bits;
+        }
+        ((bits >> n) | (bits << (self.width - n))) & self.row_mask()
+    }
+}
+
+// What a framebuffer changed between two generations, from Framebuffer::delta_since. Rows
+// are packed like Framebuffer::packed_row; an empty Rows means nothing changed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FrameDelta {
+    Full { generation: u64, width: usize, height: usize, rows: Vec<u8> },
+    // (row, its pixels) for each row that changed
+    Rows { generation: u64, rows: Vec<(usize, Vec<u8>)> },
+}
+
+impl FrameDelta {
+    // the generation to ask for the next delta since
+    pub fn generation(&self) -> u64 {
+        match self {
+            FrameDelta::Full { generation, .. } | FrameDelta::Rows { generation, .. } => *generation,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        matches!(self, FrameDelta::Rows { rows, .. } if rows.is_empty())
+    }
+
+    // Brings `framebuffer`, as it was at the generation the delta was taken since, up to
+    // date. Its own generations go their own way; only the pixels follow.
+    pub fn apply(&self, framebuffer: &mut Framebuffer) {
+        let set = |framebuffer: &mut Framebuffer, y: usize, packed: &[u8]| {
+            let bits = packed.iter().fold(0u128, |bits, &byte| bits << 8 | byte as u128);
+            framebuffer.set_row(y, bits >> (packed.len() * 8 - framebuffer.width));
+        };
+        match self {
+            FrameDelta::Full { width, height, rows, .. } => {
+                if (framebuffer.width, framebuffer.height) != (*width, *height) {
+                    framebuffer.resize(*width, *height);
+                }
+                let bytes = width.div_ceil(8);
+                for (y, packed) in rows.chunks(bytes).enumerate() {
+                    set(framebuffer, y, packed);
+                }
+            }
+            FrameDelta::Rows { rows, .. } => {
+                for (y, packed) in rows {
+                    set(framebuffer, *y, packed);
+                }
+            }
+        }
+    }
+}
+
+// Two rows of pixels per line of text with half blocks, so the 64x32 screen takes 64x16
+// characters. An odd last row gets a line of its own with nothing under it.
+impl fmt::Display for Framebuffer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for y in (0..self.height).step_by(2) {
+            let line: String = (0..self.width)
+                .map(|x| {
+                    let bottom = y + 1 < self.height && self.pixel(x, y + 1);
+                    match (self.pixel(x, y), bottom) {
+                        (true, true) => '█',
+                        (true, false) => '▀',
+                        (false, true) => '▄',
+                        (false, false) => ' ',
+                    }
+                })
+                .collect();
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+// Blends bg towards fg per channel, t going from 0 (bg) to 1 (fg)
+pub fn lerp_color(bg: u32, fg: u32, t: f32) -> u32 {
+    let t = t.clamp(0.0, 1.0);
+    let channel = |shift: u32| {
+        let from = (bg >> shift & 0xFF) as f32;
+        let to = (fg >> shift & 0xFF) as f32;
+        ((from + (to - from) * t).round() as u32) << shift
+    };
+    channel(16) | channel(8) | channel(0)
+}
+
+// one intensity per pixel, row by row, as kept by Chip8::intensity
+pub fn render_intensity_into(intensity: &[f32], buffer: &mut [u32], fg: u32, bg: u32) {
+    for (pixel, value) in buffer.iter_mut().zip(intensity) {
+        *pixel = intensity_color(*value, fg, bg);
+    }
+}
+
+pub(crate) fn intensity_color(value: f32, fg: u32, bg: u32) -> u32 {
+    if value >= 1.0 {
+        fg
+    } else if value <= 0.0 {
+        bg
+    } else {
+        lerp_color(bg, fg, value)
+    }
+}
+
+impl Default for Framebuffer {
+    fn default() -> Self {
+        Framebuffer::new()
+    }
+}
@@ -0,0 +1,72 @@
+#[cfg(feature = "http")]
+use std::time::Duration;
+
+#[cfg(feature = "http")]
+use crate::archive::MAX_UNPACKED;
+use crate::error::RomLoadError;
+
+// the whole download, connecting included
+#[cfg(feature = "http")]
+const TIMEOUT: Duration = Duration::from_secs(15);
+
+pub fn is_rom_url(spec: &str) -> bool {
+    let lower = spec.to_ascii_lowercase();
+    lower.starts_with("http://") || lower.starts_with("https://")
+}
+
+// Only http and https URLs with a host are tried; anything else is refused before
+// going near the network.
+pub fn validate_rom_url(url: &str) -> Result<(), RomLoadError> {
+    let invalid = |reason: &str| RomLoadError::InvalidUrl { url: url.to_string(), reason: reason.to_string() };
+    if !is_rom_url(url) {
+        return Err(invalid("only http:// and https:// are supported"));
+    }
+    if url.chars().any(char::is_whitespace) {
+        return Err(invalid("contains whitespace"));
+    }
+    let rest = &url[url.find("://").map_or(0, |at| at + 3)..];
+    let host = rest.split(['/', '?', '#']).next().unwrap_or("");
+    if host.is_empty() || host.starts_with(':') {
+        return Err(invalid("no host"));
+    }
+    Ok(())
+}
+
+// The last segment of the URL's path, like game.ch8 for https://example.com/roms/game.ch8?v=2,
+// or None when the path is empty.
+pub fn rom_url_name(url: &str) -> Option<&str> {
+    let rest = &url[url.find("://").map_or(0, |at| at + 3)..];
+    let path = rest.split(['?', '#']).next().unwrap_or("");
+    let (_, path) = path.split_once('/')?;
+    path.rsplit('/').find(|segment| !segment.is_empty())
+}
+
+#[cfg(feature = "http")]
+pub(crate) fn download_rom(url: &str) -> Result<Vec<u8>, RomLoadError> {
+    validate_rom_url(url)?;
+    let failed = |reason: String| RomLoadError::Http { url: url.to_string(), reason };
+    let agent = ureq::Agent::config_builder()
+        .timeout_global(Some(TIMEOUT))
+        .http_status_as_error(false)
+        .build()
+        .new_agent();
+
+    let mut response = agent.get(url).call().map_err(|err| failed(err.to_string()))?;
+    if response.status() != 200 {
+        return Err(failed(format!("server answered {}", response.status())));
+    }
+    match response.body_mut().with_config().limit(MAX_UNPACKED).read_to_vec() {
+        Ok(data) => Ok(data),
+        Err(ureq::Error::BodyExceedsLimit(_)) => Err(RomLoadError::TooLarge { max: MAX_UNPACKED as usize }),
+        Err(err) => Err(failed(err.to_string())),
+    }
+}
+
+#[cfg(not(feature = "http"))]
+pub(crate) fn download_rom(url: &str) -> Result<Vec<u8>, RomLoadError> {
+    validate_rom_url(url)?;
+    Err(RomLoadError::Http {
+        url: url.to_string(),
+        reason: String::from("rust-8 was built without the \"http\" feature"),
+    })
+}
@@ -0,0 +1,150 @@
+use std::convert::TryFrom;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+
+use crate::display::{Framebuffer, MAX_HEIGHT, MAX_WIDTH};
+use crate::error::Chip8Error;
+use crate::quirks::QuirkConfig;
+use crate::state::{MachineState, STATE_VERSION};
+
+// A MachineState laid out for people and scripts rather than for loading back fast.
+// The field names below are the schema; scripts depend on them, so rename a field only
+// together with STATE_VERSION.
+//
+//     {
+//       "version": 1,
+//       "registers": ["00", "1F", ...],          V0 to VF, two hex digits each
+//       "pc": "0200",
+//       "i": "0000",
+//       "stack": ["0204"],                       return addresses, oldest first
+//       "delay_timer": 0,
+//       "sound_timer": 0,
+//       "quirks": {"i_max": 4095, ...},
+//       "rng": {"seed": 0, "drawn": 12},         or null
+//       "halted": false,
+//       "ram": "8JCQkPAg...",                    base64 of all 4096 bytes
+//       "display": ["0011100...", ...]           one string of 0s and 1s per row
+//     }
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateDump {
+    pub version: u32,
+    #[serde(rename = "registers")]
+    pub vx: Vec<String>,
+    pub pc: String,
+    pub i: String,
+    pub stack: Vec<String>,
+    #[serde(rename = "delay_timer")]
+    pub delay: u8,
+    #[serde(rename = "sound_timer")]
+    pub sound: u8,
+    pub quirks: QuirkConfig,
+    pub rng: Option<RngDump>,
+    pub halted: bool,
+    pub ram: String,
+    pub display: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RngDump {
+    pub seed: u64,
+    pub drawn: u64,
+}
+
+impl StateDump {
+    pub fn to_pretty_json(&self) -> String {
+        // a struct of strings, numbers and lists always serializes
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    pub fn from_json(text: &str) -> Result<StateDump, Chip8Error> {
+        serde_json::from_str(text).map_err(|err| Chip8Error::InvalidState(err.to_string()))
+    }
+}
+
+impl From<&MachineState> for StateDump {
+    fn from(state: &MachineState) -> Self {
+        let display = (0..state.framebuffer.height())
+            .map(|y| {
+                (0..state.framebuffer.width())
+                    .map(|x| if state.framebuffer.pixel(x, y) { '1' } else { '0' })
+                    .collect()
+            })
+            .collect();
+        StateDump {
+            version: state.version,
+            vx: state.vx.iter().map(|v| format!("{:02X}", v)).collect(),
+            pc: format!("{:04X}", state.pc),
+            i: format!("{:04X}", state.i),
+            stack: state.stack[..state.sp as usize].iter().map(|address| format!("{:04X}", address)).collect(),
+            delay: state.delay,
+            sound: state.sound,
+            quirks: state.quirks,
+            rng: state.rng.map(|(seed, drawn)| RngDump { seed, drawn }),
+            halted: state.halted,
+            ram: STANDARD.encode(&state.ram),
+            display,
+        }
+    }
+}
+
+impl TryFrom<StateDump> for MachineState {
+    type Error = Chip8Error;
+
+    fn try_from(dump: StateDump) -> Result<Self, Self::Error> {
+        if dump.version != STATE_VERSION {
+            return Err(Chip8Error::InvalidState(format!("unsupported version {}", dump.version)));
+        }
+        if dump.vx.len() != 16 {
+            return Err(Chip8Error::InvalidState(format!("{} registers, expected 16", dump.vx.len())));
+        }
+        let mut vx = [0; 16];
+        for (register, text) in vx.iter_mut().zip(&dump.vx) {
+            *register = u8::from_str_radix(text, 16).map_err(|_| hex_error("registers", text))?;
+        }
+        if dump.stack.len() > 16 {
+            return Err(Chip8Error::InvalidState(format!("{} stack entries, at most 16", dump.stack.len())));
+        }
+        let mut stack = [0; 16];
+        for (entry, text) in stack.iter_mut().zip(&dump.stack) {
+            *entry = u16::from_str_radix(text, 16).map_err(|_| hex_error("stack", text))?;
+        }
+
+        let height = dump.display.len();
+        let width = dump.display.first().map_or(0, String::len);
+        if !(8..=MAX_WIDTH).contains(&width) || !(1..=MAX_HEIGHT).contains(&height) {
+            return Err(Chip8Error::InvalidState(format!("display of {}x{} pixels", width, height)));
+        }
+        let mut framebuffer = Framebuffer::with_size(width, height);
+        for (y, row) in dump.display.iter().enumerate() {
+            if row.len() != width || row.chars().any(|c| c != '0' && c != '1') {
+                return Err(Chip8Error::InvalidState(format!("display row {} is not {} 0s and 1s", y, width)));
+            }
+            for (x, c) in row.chars().enumerate() {
+                framebuffer.set_pixel(x, y, c == '1');
+            }
+        }
+
+        Ok(MachineState {
+            version: dump.version,
+            vx,
+            pc: u16::from_str_radix(&dump.pc, 16).map_err(|_| hex_error("pc", &dump.pc))?,
+            i: u16::from_str_radix(&dump.i, 16).map_err(|_| hex_error("i", &dump.i))?,
+            sp: dump.stack.len() as u8,
+            stack,
+            delay: dump.delay,
+            sound: dump.sound,
+            ram: STANDARD
+                .decode(&dump.ram)
+                .map_err(|err| Chip8Error::InvalidState(format!("ram: {}", err)))?,
+            framebuffer,
+            quirks: dump.quirks,
+            rng: dump.rng.map(|rng| (rng.seed, rng.drawn)),
+            halted: dump.halted,
+        })
+    }
+}
+
+fn hex_error(field: &str, text: &str) -> Chip8Error {
+    Chip8Error::InvalidState(format!("bad hex {:?} in {}", text, field))
+}
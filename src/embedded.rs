@@ -0,0 +1,258 @@
+use core::fmt;
+
+use crate::cpu::Cpu;
+use crate::instruction::Instruction;
+use crate::keypad::Keypad;
+use crate::memory::{sprite_address, PROGRAM_START, SPRITE_DATA};
+use crate::snapshot::CpuSnapshot;
+
+pub const CORE_WIDTH: usize = 64;
+pub const CORE_HEIGHT: usize = 32;
+const RAM_SIZE: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoreError {
+    EmptyRom,
+    RomTooLarge { size: usize, max: usize },
+    PcOutOfRange { pc: u16 },
+    StackOverflow { pc: u16 },
+    StackUnderflow { pc: u16 },
+    IRegisterOutOfBounds { i: u16, size: u16 },
+    // SCHIP and XO-CHIP opcodes, and anything that isn't an opcode at all
+    Unsupported { pc: u16, opcode: u16 },
+}
+
+impl fmt::Display for CoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CoreError::EmptyRom => write!(f, "ROM is empty"),
+            CoreError::RomTooLarge { size, max } => write!(f, "ROM is {} bytes but only {} bytes fit in memory", size, max),
+            CoreError::PcOutOfRange { pc } => write!(f, "program counter {:#06X} is outside of memory", pc),
+            CoreError::StackOverflow { pc } => write!(f, "stack overflow: more than 16 nested calls at {:#05X}", pc),
+            CoreError::StackUnderflow { pc } => write!(f, "stack underflow: return with an empty stack at {:#05X}", pc),
+            CoreError::IRegisterOutOfBounds { i, size } => {
+                write!(f, "access of {} bytes at I={:#05X} goes past the end of memory", size, i)
+            }
+            CoreError::Unsupported { pc, opcode } => write!(f, "opcode {:04X} at {:#05X} is not plain CHIP-8", opcode, pc),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CoreError {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CoreStep {
+    Executed { pc: u16 },
+    // FX0A found no key down and will run again on the next step
+    WaitingForKey,
+}
+
+// Plain CHIP-8 without std or an allocator, for microcontrollers: fixed arrays for
+// everything, no files, logging or clock, and a linear congruential generator for CXNN.
+// It behaves like a Chip8 with the default quirks, but knows no other variant and has
+// no inspector, syscall handler or undo. Call step at the ROM's speed and tick_timers
+// 60 times a second.
+pub struct Chip8Core {
+    cpu: Cpu,
+    ram: [u8; RAM_SIZE],
+    stack: [u16; 16],
+    sp: u8,
+    delay: u8,
+    sound: u8,
+    // a row per line, the leftmost pixel in the top bit
+    display: [u64; CORE_HEIGHT],
+    keypad: Keypad,
+    rng: u32,
+}
+
+impl Chip8Core {
+    pub fn new(seed: u32) -> Self {
+        let mut ram = [0; RAM_SIZE];
+        ram[..SPRITE_DATA.len()].copy_from_slice(&SPRITE_DATA);
+        Chip8Core {
+            cpu: Cpu::new(),
+            ram,
+            stack: [0; 16],
+            sp: 0,
+            delay: 0,
+            sound: 0,
+            display: [0; CORE_HEIGHT],
+            keypad: Keypad::new(),
+            rng: seed,
+        }
+    }
+
+    pub fn load_rom(&mut self, data: &[u8]) -> Result<(), CoreError> {
+        let start = PROGRAM_START as usize;
+        let max = RAM_SIZE - start;
+        if data.is_empty() {
+            return Err(CoreError::EmptyRom);
+        }
+        if data.len() > max {
+            return Err(CoreError::RomTooLarge { size: data.len(), max });
+        }
+        self.ram[start..start + data.len()].copy_from_slice(data);
+        Ok(())
+    }
+
+    pub fn step(&mut self) -> Result<CoreStep, CoreError> {
+        let pc = self.cpu.pc;
+        if pc as usize + 1 >= RAM_SIZE {
+            return Err(CoreError::PcOutOfRange { pc });
+        }
+        let instruction = Instruction::decode(self.ram[pc as usize], self.ram[pc as usize + 1]);
+        self.cpu.pc += 2;
+        self.execute(instruction, pc)?;
+        if let Instruction::LdVxK(_) = instruction {
+            if self.cpu.pc == pc {
+                return Ok(CoreStep::WaitingForKey);
+            }
+        }
+        Ok(CoreStep::Executed { pc })
+    }
+
+    pub fn tick_timers(&mut self) {
+        self.delay = self.delay.saturating_sub(1);
+        self.sound = self.sound.saturating_sub(1);
+    }
+
+    // the buzzer sounds for as long as the sound timer is counting down
+    pub fn sound_active(&self) -> bool {
+        self.sound > 0
+    }
+
+    pub fn keypad_mut(&mut self) -> &mut Keypad {
+        &mut self.keypad
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> bool {
+        x < CORE_WIDTH && y < CORE_HEIGHT && self.display[y] >> (CORE_WIDTH - 1 - x) & 1 == 1
+    }
+
+    // the screen a row at a time, for displays that take a line of bits
+    pub fn rows(&self) -> &[u64; CORE_HEIGHT] {
+        &self.display
+    }
+
+    pub fn cpu_snapshot(&self) -> CpuSnapshot {
+        self.cpu.dump()
+    }
+
+    pub fn delay_timer(&self) -> u8 {
+        self.delay
+    }
+
+    fn execute(&mut self, instruction: Instruction, pc: u16) -> Result<(), CoreError> {
+        let v = self.cpu.vx;
+        match instruction {
+            Instruction::Cls => self.display = [0; CORE_HEIGHT],
+            Instruction::Ret => {
+                if self.sp == 0 {
+                    return Err(CoreError::StackUnderflow { pc });
+                }
+                self.sp -= 1;
+                self.cpu.pc = self.stack[self.sp as usize];
+            }
+            // machine code routines don't exist here, like in Chip8 without a handler
+            Instruction::Sys(_) => {}
+            Instruction::Jp(nnn) => self.cpu.pc = nnn,
+            Instruction::Call(nnn) => {
+                if self.sp as usize == self.stack.len() {
+                    return Err(CoreError::StackOverflow { pc });
+                }
+                self.stack[self.sp as usize] = self.cpu.pc;
+                self.sp += 1;
+                self.cpu.pc = nnn;
+            }
+            Instruction::SeByte(x, kk) => self.skip_if(v[x as usize] == kk),
+            Instruction::SneByte(x, kk) => self.skip_if(v[x as usize] != kk),
+            Instruction::SeReg(x, y) => self.skip_if(v[x as usize] == v[y as usize]),
+            Instruction::SneReg(x, y) => self.skip_if(v[x as usize] != v[y as usize]),
+            Instruction::LdByte(x, kk) => self.cpu.vx[x as usize] = kk,
+            Instruction::AddByte(x, kk) => self.cpu.vx[x as usize] = v[x as usize].wrapping_add(kk),
+            Instruction::LdReg(x, y) => self.cpu.vx[x as usize] = v[y as usize],
+            Instruction::Or(x, y) => self.cpu.vx[x as usize] |= v[y as usize],
+            Instruction::And(x, y) => self.cpu.vx[x as usize] &= v[y as usize],
+            Instruction::Xor(x, y) => self.cpu.vx[x as usize] ^= v[y as usize],
+            Instruction::AddReg(x, y) => self.cpu.add_registers(x, y),
+            Instruction::Sub(x, y) => self.cpu.substract_registers(x, y, x),
+            Instruction::Shr(x, _) => self.cpu.half_register(x, x),
+            Instruction::Subn(x, y) => self.cpu.substract_registers(y, x, x),
+            Instruction::Shl(x, _) => self.cpu.double_register(x, x),
+            Instruction::LdI(nnn) => self.cpu.i = nnn,
+            Instruction::JpV0(nnn) => self.cpu.pc = nnn + v[0] as u16,
+            Instruction::Rnd(x, kk) => {
+                self.rng = self.rng.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                self.cpu.vx[x as usize] = (self.rng >> 16) as u8 & kk;
+            }
+            Instruction::Drw(x, y, n) => self.draw_sprite(v[x as usize] as usize, v[y as usize] as usize, n as u16)?,
+            Instruction::Skp(x) => self.skip_if(self.keypad.is_pressed(v[x as usize])),
+            Instruction::Sknp(x) => self.skip_if(!self.keypad.is_pressed(v[x as usize])),
+            Instruction::LdVxDt(x) => self.cpu.vx[x as usize] = self.delay,
+            Instruction::LdVxK(x) => match self.keypad.first_pressed() {
+                Some(key) => self.cpu.vx[x as usize] = key,
+                None => self.cpu.pc = pc,
+            },
+            Instruction::LdDtVx(x) => self.delay = v[x as usize],
+            Instruction::LdStVx(x) => self.sound = v[x as usize],
+            Instruction::AddI(x) => self.cpu.i = self.cpu.i.wrapping_add(v[x as usize] as u16),
+            Instruction::LdF(x) => self.cpu.i = sprite_address(v[x as usize] & 0xF),
+            Instruction::LdB(x) => {
+                let i = self.i_range(3)?;
+                let value = v[x as usize];
+                self.ram[i..i + 3].copy_from_slice(&[value / 100, value % 100 / 10, value % 10]);
+            }
+            Instruction::LdIVx(x) => {
+                let i = self.i_range(x as u16 + 1)?;
+                self.ram[i..=i + x as usize].copy_from_slice(&self.cpu.vx[..=x as usize]);
+            }
+            Instruction::LdVxI(x) => {
+                let i = self.i_range(x as u16 + 1)?;
+                self.cpu.vx[..=x as usize].copy_from_slice(&self.ram[i..=i + x as usize]);
+            }
+            _ => {
+                let opcode = (self.ram[pc as usize] as u16) << 8 | self.ram[pc as usize + 1] as u16;
+                return Err(CoreError::Unsupported { pc, opcode });
+            }
+        }
+        Ok(())
+    }
+
+    fn skip_if(&mut self, condition: bool) {
+        if condition {
+            self.cpu.pc += 2;
+        }
+    }
+
+    // I as an index, once `size` bytes from it are known to fit
+    fn i_range(&self, size: u16) -> Result<usize, CoreError> {
+        let i = self.cpu.i as usize;
+        if i + size as usize > RAM_SIZE {
+            return Err(CoreError::IRegisterOutOfBounds { i: self.cpu.i, size });
+        }
+        Ok(i)
+    }
+
+    // sprites wrap around both edges, and VF tells whether any lit pixel went out
+    fn draw_sprite(&mut self, x: usize, y: usize, n: u16) -> Result<(), CoreError> {
+        let i = self.i_range(n)?;
+        self.cpu.vx[0xF] = 0;
+        for row in 0..n as usize {
+            let sprite = (self.ram[i + row] as u64) << (CORE_WIDTH - 8);
+            let mask = sprite.rotate_right((x % CORE_WIDTH) as u32);
+            let line = &mut self.display[(y + row) % CORE_HEIGHT];
+            if *line & mask != 0 {
+                self.cpu.vx[0xF] = 1;
+            }
+            *line ^= mask;
+        }
+        Ok(())
+    }
+}
+
+impl Default for Chip8Core {
+    fn default() -> Self {
+        Chip8Core::new(0)
+    }
+}
@@ -0,0 +1,380 @@
+use std::{
+    sync::{
+        mpsc::{self, Receiver, Sender, TryRecvError},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::chip8::{Chip8, InstructionResult};
+use crate::clock::PreciseClock;
+use crate::crash::step_or_report;
+use crate::display::{Framebuffer, HEIGHT, WIDTH};
+use crate::error::Chip8Error;
+use crate::keypad::Keypad;
+use crate::speed::SpeedController;
+use crate::theme::ColorTheme;
+use crate::wav::SoundRecorder;
+
+const FRAME: Duration = Duration::from_micros(16_667);
+
+// runs on the emulation thread with the final machine when it is told to quit
+pub type ExitHook = Box<dyn FnOnce(&Chip8) + Send>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    Keypad(Keypad),
+    Pause,
+    Resume,
+    Reset,
+    // take back the last instruction, see Chip8::undo_step
+    Undo,
+    // on to the next ROM of the library, see Chip8::next_rom
+    NextRom,
+    // one key, for inputs that don't hold the whole keypad like the frontend does
+    Key { key: u8, pressed: bool },
+    // what FrameSnapshot::theme says to draw with, kept across resets
+    Theme(ColorTheme),
+    // puts Chip8::to_json in the state slot, see EmulatorRemote::request_state
+    PublishState,
+    Quit,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameSnapshot {
+    // bumped every time the emulation thread publishes a frame
+    pub generation: u64,
+    pub framebuffer: Framebuffer,
+    // Chip8::intensity at the end of the frame
+    pub intensity: Vec<f32>,
+    pub halted: bool,
+    // the sound timer was running during the last frame, so the frontend should be beeping
+    pub sound: bool,
+    // frames with the sound timer running so far, so a frontend that skipped frames
+    // can still tell it missed a beep
+    pub sound_frames: u64,
+    // frames that changed the screen so far, see Chip8::take_display_dirty; one that
+    // hasn't changed since the frontend last drew it needn't be drawn again
+    pub display_frames: u64,
+    // Chip8::current_rom
+    pub rom: Option<usize>,
+    // instructions run since the thread started, across resets
+    pub instructions: u64,
+    // the colors the frontend draws with, for anything else showing the screen
+    pub theme: ColorTheme,
+}
+
+// Runs the instruction/timer loop on its own thread at 60 frames per second so a
+// stalled window never stalls emulation. The frontend sends keypad snapshots and
+// control commands in and picks up the most recent framebuffer when it draws.
+pub struct EmulatorThread {
+    remote: EmulatorRemote,
+    handle: Option<JoinHandle<Result<(), Chip8Error>>>,
+}
+
+// The channels into a running EmulatorThread, for other threads to send commands and
+// read frames the way the frontend does.
+#[derive(Clone)]
+pub struct EmulatorRemote {
+    commands: Sender<Command>,
+    frame: Arc<Mutex<FrameSnapshot>>,
+    state: Arc<Mutex<Option<String>>>,
+}
+
+impl EmulatorRemote {
+    pub fn send(&self, command: Command) {
+        // once the thread is gone there is nobody left to tell
+        let _ = self.commands.send(command);
+    }
+
+    // the newest frame, if it is newer than the generation the caller already has
+    pub fn latest_frame(&self, seen: u64) -> Option<FrameSnapshot> {
+        let frame = lock(&self.frame);
+        if frame.generation > seen {
+            Some(frame.clone())
+        } else {
+            None
+        }
+    }
+
+    // Chip8::to_json of the machine as of its next frame, or None if the thread doesn't
+    // get to it within `timeout`; the thread is never kept waiting
+    pub fn request_state(&self, timeout: Duration) -> Option<String> {
+        lock(&self.state).take();
+        self.send(Command::PublishState);
+        let deadline = std::time::Instant::now() + timeout;
+        loop {
+            if let Some(state) = lock(&self.state).take() {
+                return Some(state);
+            }
+            if std::time::Instant::now() >= deadline {
+                return None;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    match mutex.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+impl EmulatorThread {
+    // `make` builds a fresh machine on the emulation thread, at startup and on every reset
+    pub fn spawn<F>(make: F, instructions_per_frame: usize) -> Self
+    where
+        F: Fn() -> Result<Chip8, Chip8Error> + Send + 'static,
+    {
+        EmulatorThread::spawn_recording(make, instructions_per_frame, None)
+    }
+
+    // like spawn, also writing the buzzer of every emulated frame to `recorder`
+    pub fn spawn_recording<F>(make: F, instructions_per_frame: usize, recorder: Option<SoundRecorder>) -> Self
+    where
+        F: Fn() -> Result<Chip8, Chip8Error> + Send + 'static,
+    {
+        EmulatorThread::spawn_with_exit_hook(make, instructions_per_frame, recorder, None)
+    }
+
+    // like spawn_recording, handing the machine to `on_exit` once the frontend quits
+    // (but not when emulation stops with an error)
+    pub fn spawn_with_exit_hook<F>(
+        make: F,
+        instructions_per_frame: usize,
+        recorder: Option<SoundRecorder>,
+        on_exit: Option<ExitHook>,
+    ) -> Self
+    where
+        F: Fn() -> Result<Chip8, Chip8Error> + Send + 'static,
+    {
+        EmulatorThread::spawn_with_speed(make, instructions_per_frame, recorder, on_exit, None)
+    }
+
+    // like spawn_with_exit_hook, with `speed` choosing how many instructions each frame
+    // runs instead of a fixed instructions_per_frame
+    pub fn spawn_with_speed<F>(
+        make: F,
+        instructions_per_frame: usize,
+        recorder: Option<SoundRecorder>,
+        on_exit: Option<ExitHook>,
+        speed: Option<SpeedController>,
+    ) -> Self
+    where
+        F: Fn() -> Result<Chip8, Chip8Error> + Send + 'static,
+    {
+        let (commands, receiver) = mpsc::channel();
+        let frame = Arc::new(Mutex::new(FrameSnapshot {
+            generation: 0,
+            framebuffer: Framebuffer::new(),
+            intensity: vec![0.0; WIDTH * HEIGHT],
+            halted: false,
+            sound: false,
+            sound_frames: 0,
+            display_frames: 0,
+            rom: None,
+            instructions: 0,
+            theme: ColorTheme::default(),
+        }));
+        let state = Arc::new(Mutex::new(None));
+
+        let shared = (Arc::clone(&frame), Arc::clone(&state));
+        let handle = thread::spawn(move || {
+            let mut recorder = recorder;
+            let result = emulate(make, instructions_per_frame, receiver, shared, &mut recorder, on_exit, speed);
+            if let Some(recorder) = recorder {
+                if let Err(err) = recorder.finish() {
+                    log::warn!("could not finish the sound recording: {}", err);
+                }
+            }
+            result
+        });
+
+        EmulatorThread {
+            remote: EmulatorRemote { commands, frame, state },
+            handle: Some(handle),
+        }
+    }
+
+    pub fn send(&self, command: Command) {
+        self.remote.send(command);
+    }
+
+    // the newest frame, if it is newer than the generation the caller already has
+    pub fn latest_frame(&self, seen: u64) -> Option<FrameSnapshot> {
+        self.remote.latest_frame(seen)
+    }
+
+    // for a thread besides the frontend's to control the machine too
+    pub fn remote(&self) -> EmulatorRemote {
+        self.remote.clone()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.handle.as_ref().is_some_and(|handle| !handle.is_finished())
+    }
+
+    // stops the thread and reports why it ended
+    pub fn shutdown(mut self) -> Result<(), Chip8Error> {
+        self.stop()
+    }
+
+    fn stop(&mut self) -> Result<(), Chip8Error> {
+        self.send(Command::Quit);
+        match self.handle.take() {
+            Some(handle) => handle.join().unwrap_or(Ok(())),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for EmulatorThread {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+fn emulate<F>(
+    make: F,
+    instructions_per_frame: usize,
+    commands: Receiver<Command>,
+    (frame, state): (Arc<Mutex<FrameSnapshot>>, Arc<Mutex<Option<String>>>),
+    recorder: &mut Option<SoundRecorder>,
+    on_exit: Option<ExitHook>,
+    mut speed: Option<SpeedController>,
+) -> Result<(), Chip8Error>
+where
+    F: Fn() -> Result<Chip8, Chip8Error>,
+{
+    let exit = |chip8: &Chip8| {
+        if let Some(on_exit) = on_exit {
+            on_exit(chip8);
+        }
+        Ok(())
+    };
+    let mut chip8 = make()?;
+    let mut theme = chip8.theme();
+    let mut paused = false;
+    let mut generation = 0;
+    let mut instructions = 0;
+    // Chip8Builder::with_precise_timing, which a reset can't change
+    let mut clock = PreciseClock::new(FRAME, chip8.precise_timing());
+
+    loop {
+        loop {
+            // paused or halted machines have nothing to do until the frontend says so
+            let command = if paused || chip8.is_halted() {
+                let command = match commands.recv() {
+                    Ok(command) => command,
+                    Err(_) => return exit(&chip8),
+                };
+                clock.restart();
+                command
+            } else {
+                match commands.try_recv() {
+                    Ok(command) => command,
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => return exit(&chip8),
+                }
+            };
+
+            match command {
+                Command::Keypad(keypad) => {
+                    log::debug!("keypad {:?}", keypad);
+                    *chip8.keypad_mut() = keypad;
+                }
+                Command::Pause => {
+                    log::info!("paused");
+                    paused = true;
+                }
+                Command::Resume => {
+                    log::info!("resumed");
+                    paused = false;
+                }
+                Command::Reset => {
+                    log::info!("reset");
+                    // a reset restarts the library ROM that was running, not the first one
+                    let rom = chip8.current_rom();
+                    let previous = std::mem::replace(&mut chip8, make()?);
+                    chip8.take_over_display(&previous);
+                    chip8.set_theme(theme);
+                    if let Some(index) = rom {
+                        chip8.switch_to_rom(index)?;
+                    }
+                }
+                Command::NextRom => {
+                    chip8.next_rom()?;
+                    log::info!("switched to ROM {:?}", chip8.current_rom());
+                    publish(&frame, &mut generation, instructions, &mut chip8, false);
+                }
+                Command::Undo => {
+                    chip8.undo_step();
+                    publish(&frame, &mut generation, instructions, &mut chip8, false);
+                }
+                Command::Key { key, pressed } => chip8.keypad_mut().set(key, pressed),
+                Command::Theme(new) => {
+                    theme = new;
+                    chip8.set_theme(theme);
+                    publish(&frame, &mut generation, instructions, &mut chip8, false);
+                }
+                Command::PublishState => *lock(&state) = Some(chip8.to_json()),
+                Command::Quit => return exit(&chip8),
+            }
+        }
+
+        let per_frame = speed.as_ref().map_or(instructions_per_frame, SpeedController::instructions_per_frame);
+        let draws = chip8.draws_since_reset();
+        for _i in 0..per_frame {
+            if let InstructionResult::Halted { .. } = step_or_report(&mut chip8)? {
+                break;
+            }
+            instructions += 1;
+        }
+        if let Some(speed) = speed.as_mut() {
+            speed.adjust((chip8.draws_since_reset() - draws) as u32);
+        }
+        // sampled before the tick so a beep of a single frame still counts
+        let sound = chip8.sound_active();
+        chip8.tick_timers();
+        record(recorder, sound);
+
+        publish(&frame, &mut generation, instructions, &mut chip8, sound);
+
+        clock.wait();
+    }
+}
+
+// a failing recording is dropped rather than stopping the emulation
+fn record(recorder: &mut Option<SoundRecorder>, sound: bool) {
+    if let Some(writer) = recorder {
+        if let Err(err) = writer.frame(sound) {
+            log::warn!("sound recording stopped: {}", err);
+            *recorder = None;
+        }
+    }
+}
+
+// every frame published gets the next generation, including the ones a paused or halted
+// machine publishes for a command, or the frontend would never pick them up
+fn publish(frame: &Mutex<FrameSnapshot>, generation: &mut u64, instructions: u64, chip8: &mut Chip8, sound: bool) {
+    *generation += 1;
+    let mut frame = lock(frame);
+    frame.generation = *generation;
+    frame.instructions = instructions;
+    frame.framebuffer.clone_from(chip8.display());
+    frame.intensity.clear();
+    frame.intensity.extend_from_slice(chip8.intensity());
+    frame.halted = chip8.is_halted();
+    frame.sound = sound;
+    frame.rom = chip8.current_rom();
+    frame.theme = chip8.theme();
+    if sound {
+        frame.sound_frames += 1;
+    }
+    if chip8.take_display_dirty() {
+        frame.display_frames += 1;
+    }
+}
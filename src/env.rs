@@ -0,0 +1,160 @@
+use crate::builder::Chip8Builder;
+use crate::chip8::{Chip8, InstructionResult};
+use crate::error::Chip8Error;
+use crate::keypad::Keypad;
+use crate::quirks::QuirkConfig;
+use crate::variant::ChipVariant;
+
+// How Chip8Env runs the machine. RND draws from `seed` alone, so the same config, ROM
+// and actions give the same observations every time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvConfig {
+    pub variant: ChipVariant,
+    // the variant's own when None
+    pub quirks: Option<QuirkConfig>,
+    // the variant's usual speed when None
+    pub instructions_per_frame: Option<usize>,
+    // 60 Hz frames run per step, with the action held throughout
+    pub frames_per_step: u32,
+    pub seed: u64,
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        EnvConfig {
+            variant: ChipVariant::default(),
+            quirks: None,
+            instructions_per_frame: None,
+            frames_per_step: 4,
+            seed: 0,
+        }
+    }
+}
+
+// The machine as an agent sees it after a step, the screen packed like Framebuffer::packed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Observation {
+    pub width: usize,
+    pub height: usize,
+    pub screen: Vec<u8>,
+    pub registers: [u8; 16],
+    pub i: u16,
+    pub pc: u16,
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+}
+
+impl Observation {
+    fn of(chip8: &Chip8) -> Self {
+        let display = chip8.display();
+        let cpu = chip8.cpu_snapshot();
+        Observation {
+            width: display.width(),
+            height: display.height(),
+            screen: display.packed(),
+            registers: cpu.vx,
+            i: cpu.i,
+            pc: cpu.pc,
+            delay_timer: chip8.delay_timer(),
+            sound_timer: chip8.sound_timer(),
+        }
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> bool {
+        self.screen[y * self.width.div_ceil(8) + x / 8] & 0x80 >> (x % 8) != 0
+    }
+}
+
+// What a step did besides change the machine. There is no reward: that takes knowing
+// the game, so it's up to the caller to read it off the observation or chip8().memory().
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepInfo {
+    // frames since the last reset
+    pub frame: u64,
+    pub instructions: u64,
+    // the machine halted or crashed, and steps won't change anything until a reset
+    pub done: bool,
+    pub error: Option<Chip8Error>,
+}
+
+// A CHIP-8 game wrapped up for reinforcement learning in the usual reset and step
+// shape, running without a window or a clock so it's as fast as the host allows.
+pub struct Chip8Env {
+    rom: Vec<u8>,
+    config: EnvConfig,
+    chip8: Chip8,
+    frame: u64,
+    error: Option<Chip8Error>,
+}
+
+impl Chip8Env {
+    pub fn new(rom: Vec<u8>, config: EnvConfig) -> Result<Self, Chip8Error> {
+        let chip8 = Chip8Env::machine(&rom, &config)?;
+        Ok(Chip8Env { rom, config, chip8, frame: 0, error: None })
+    }
+
+    fn machine(rom: &[u8], config: &EnvConfig) -> Result<Chip8, Chip8Error> {
+        let mut builder = Chip8Builder::new().with_variant(config.variant).with_rng_seed(config.seed);
+        if let Some(quirks) = config.quirks {
+            builder = builder.with_quirks(quirks);
+        }
+        let mut chip8 = builder.build();
+        chip8.load_rom(rom.to_vec())?;
+        Ok(chip8)
+    }
+
+    // a new machine with the ROM just loaded, and the seed starting over
+    pub fn reset(&mut self) -> Observation {
+        // the ROM loaded once already, so it will again
+        if let Ok(chip8) = Chip8Env::machine(&self.rom, &self.config) {
+            self.chip8 = chip8;
+        }
+        self.frame = 0;
+        self.error = None;
+        Observation::of(&self.chip8)
+    }
+
+    // Holds the keys of `action` for frames_per_step frames.
+    pub fn step(&mut self, action: Keypad) -> (Observation, StepInfo) {
+        let instructions_per_frame = self.config.instructions_per_frame.unwrap_or(self.config.variant.instructions_per_frame());
+        let start = self.chip8.instructions_since_reset();
+        *self.chip8.keypad_mut() = action;
+        for _ in 0..self.config.frames_per_step {
+            if self.done() {
+                break;
+            }
+            for _ in 0..instructions_per_frame {
+                match self.chip8.step() {
+                    Ok(InstructionResult::Halted { .. }) => break,
+                    Ok(_) => {}
+                    Err(err) => {
+                        self.error = Some(err);
+                        break;
+                    }
+                }
+            }
+            self.chip8.tick_timers();
+            self.frame += 1;
+        }
+        let info = StepInfo {
+            frame: self.frame,
+            instructions: self.chip8.instructions_since_reset() - start,
+            done: self.done(),
+            error: self.error.clone(),
+        };
+        (Observation::of(&self.chip8), info)
+    }
+
+    fn done(&self) -> bool {
+        self.chip8.is_halted() || self.error.is_some()
+    }
+
+    // the machine itself, for rewards read from its memory or registers
+    pub fn chip8(&self) -> &Chip8 {
+        &self.chip8
+    }
+
+    pub fn config(&self) -> &EnvConfig {
+        &self.config
+    }
+}
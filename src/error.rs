@@ -0,0 +1,165 @@
+use std::{fmt, io};
+
+use thiserror::Error;
+
+use crate::reftrace::TraceLine;
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum Chip8Error {
+    #[error("access of {size} bytes at I={i:#05X} goes past the end of memory")]
+    IRegisterOutOfBounds { i: u16, size: u16 },
+    #[error("invalid opcode {opcode:04X} at {pc:#05X}")]
+    InvalidOpcode { pc: u16, opcode: u16 },
+    #[error("stack overflow: more than 16 nested calls at {pc:#05X}")]
+    StackOverflow { pc: u16 },
+    #[error("stack underflow: return with an empty stack at {pc:#05X}")]
+    StackUnderflow { pc: u16 },
+    #[error("program counter {pc:#06X} is outside of memory")]
+    PcOutOfRange { pc: u16 },
+    #[error("ROM is {size} bytes but only {max} bytes fit in memory")]
+    RomTooLarge { size: usize, max: usize },
+    #[error("{opcode:04X} at {pc:#05X} tried to write to protected address {address:#05X}")]
+    ProtectedWrite { pc: u16, opcode: u16, address: u16 },
+    #[error("ROM is empty")]
+    EmptyRom,
+    #[error("invalid machine state: {0}")]
+    InvalidState(String),
+    #[error("no ROM {index} in a library of {count}")]
+    NoSuchRom { index: usize, count: usize },
+    #[error("{0}")]
+    Hex(#[from] HexError),
+    #[error("no register {x}, only V0 to VF")]
+    NoSuchRegister { x: u8 },
+    #[error("a buffer of {len} bytes is too small for the {needed} the screen takes")]
+    BufferTooSmall { needed: usize, len: usize },
+    #[error("{len} bytes at {address:#05X} go past the end of memory")]
+    MemoryOutOfRange { address: u16, len: usize },
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum HexError {
+    #[error("{0:?} is not hex")]
+    NotHex(String),
+    #[error("{0:?} has an odd number of digits, so it is not whole bytes")]
+    OddDigits(String),
+}
+
+// Why a ROM file, archive or compressed file couldn't be read, before load_rom sees it.
+#[derive(Debug, Error)]
+pub enum RomLoadError {
+    #[error("{path}: {source}")]
+    Io { path: String, source: io::Error },
+    #[error("{url}: {reason}")]
+    InvalidUrl { url: String, reason: String },
+    #[error("could not download {url}: {reason}")]
+    Http { url: String, reason: String },
+    #[error("bad zip archive: {0}")]
+    Zip(String),
+    #[error("bad gzip file: {0}")]
+    Gzip(String),
+    #[error("no .ch8 file in the archive")]
+    NoRomInArchive,
+    #[error("{} .ch8 files in the archive, pick one with archive.zip:name: {}", .0.len(), .0.join(", "))]
+    SeveralRoms(Vec<String>),
+    #[error("no {0} in the archive")]
+    NoSuchEntry(String),
+    #[error("unpacks to more than {max} bytes")]
+    TooLarge { max: usize },
+}
+
+// Why --ref-trace stopped before the end of the run.
+#[derive(Debug, Error)]
+pub enum RefTraceError {
+    #[error("could not read the reference trace: {0}")]
+    Io(#[from] io::Error),
+    #[error("reference trace line {line}: {reason}")]
+    Parse { line: usize, reason: String },
+    #[error(
+        "diverged from the reference trace at instruction {instruction}: {field} is {actual:#X}, expected {expected:#X}\n  expected {expected_line}\n  actual   {actual_line}"
+    )]
+    Diverged {
+        instruction: u64,
+        field: String,
+        expected: u16,
+        actual: u16,
+        expected_line: TraceLine,
+        actual_line: TraceLine,
+    },
+    #[error("the ROM stopped after {instructions} instructions but the reference trace goes on")]
+    TraceLonger { instructions: u64 },
+}
+
+// A line of an --input-script that doesn't parse.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ScriptError {
+    #[error("input script line {line}: {reason}")]
+    Parse { line: usize, reason: String },
+}
+
+// A debugger watch expression that doesn't parse, with the column of the trouble.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ExprError {
+    #[error("column {column}: {reason}")]
+    Parse { column: usize, reason: String },
+}
+
+// A line of a --symbols file that doesn't parse.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum SymbolError {
+    #[error("symbol file line {line}: {reason}")]
+    Parse { line: usize, reason: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum AudioError {
+    #[error("no audio output device available")]
+    NoDevice,
+    #[error("audio stream error: {0}")]
+    Stream(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ConfigError {
+    #[error("unknown option {0}")]
+    UnknownOption(String),
+    #[error("{0} needs a value")]
+    MissingValue(String),
+    #[error("invalid value {value} for {option}: {reason}")]
+    InvalidValue { option: String, value: String, reason: String },
+    #[error("{path}: {reason}")]
+    File { path: String, reason: String },
+    #[error("{option} needs rust-8 to be built with the \"{feature}\" feature")]
+    MissingFeature { option: String, feature: String },
+}
+
+// Everything that can stop the emulator, from the core or from the frontend around it.
+#[derive(Error)]
+pub enum EmulatorError {
+    #[error("{0}")]
+    Chip8(#[from] Chip8Error),
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    #[error("{0}")]
+    RomLoad(#[from] RomLoadError),
+    #[error("{0}")]
+    Audio(#[from] AudioError),
+    #[error("{0}")]
+    RefTrace(#[from] RefTraceError),
+    #[error("{0}")]
+    Script(#[from] ScriptError),
+    #[error("{0}")]
+    Symbols(#[from] SymbolError),
+    #[error("could not open the window: {0}")]
+    WindowInit(String),
+    #[error("window error: {0}")]
+    Window(String),
+    #[error("{0}")]
+    Config(#[from] ConfigError),
+}
+
+// main returns this, and Rust prints the Debug form, so make that the readable message
+impl fmt::Debug for EmulatorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
@@ -0,0 +1,185 @@
+use std::{fmt, str::FromStr};
+
+use crate::chip8::Chip8;
+use crate::error::ExprError;
+
+// What a debugger watch follows: a register, I, PC, the stack pointer, a timer or a byte
+// of memory. Written V0 to VF, I, PC, SP, DT, ST and mem[ADDR] with ADDR in hex, in any
+// case and with spaces anywhere between the parts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchExpr {
+    V(u8),
+    I,
+    Pc,
+    Sp,
+    Delay,
+    Sound,
+    Memory(u16),
+}
+
+impl WatchExpr {
+    pub fn parse(text: &str) -> Result<Self, ExprError> {
+        let tokens = tokenize(text)?;
+        let mut tokens = tokens.iter();
+        let expr = match tokens.next() {
+            Some(Token::Word(column, word)) => match word.to_ascii_uppercase().as_str() {
+                "MEM" => {
+                    let address = match (tokens.next(), tokens.next(), tokens.next()) {
+                        (Some(Token::Open(_)), Some(Token::Word(column, address)), Some(Token::Close(_))) => {
+                            parse_address(*column, address)?
+                        }
+                        (Some(Token::Open(_)), Some(Token::Word(..)), other) => {
+                            return Err(expected(text, other, "] to close mem["));
+                        }
+                        (Some(Token::Open(_)), other, _) => return Err(expected(text, other, "an address after mem[")),
+                        (other, _, _) => return Err(expected(text, other, "[ after mem")),
+                    };
+                    WatchExpr::Memory(address)
+                }
+                "I" => WatchExpr::I,
+                "PC" => WatchExpr::Pc,
+                "SP" => WatchExpr::Sp,
+                "DT" => WatchExpr::Delay,
+                "ST" => WatchExpr::Sound,
+                name => match name.strip_prefix('V').map(|x| u8::from_str_radix(x, 16)) {
+                    Some(Ok(x)) if name.len() == 2 => WatchExpr::V(x),
+                    _ => {
+                        return Err(ExprError::Parse {
+                            column: *column,
+                            reason: format!("unknown register {:?}", word),
+                        })
+                    }
+                },
+            },
+            other => return Err(expected(text, other, "V0-VF, I, PC, SP, DT, ST or mem[ADDR]")),
+        };
+        match tokens.next() {
+            None => Ok(expr),
+            Some(token) => Err(ExprError::Parse {
+                column: token.column(),
+                reason: format!("unexpected {:?} after {}", token.text(), expr),
+            }),
+        }
+    }
+
+    // the value on `chip8` now, bytes widened to u16
+    pub fn eval(self, chip8: &Chip8) -> u16 {
+        match self {
+            WatchExpr::V(x) => chip8.register(x) as u16,
+            WatchExpr::I => chip8.i_register(),
+            WatchExpr::Pc => chip8.pc(),
+            WatchExpr::Sp => chip8.stack_pointer() as u16,
+            WatchExpr::Delay => chip8.delay_timer() as u16,
+            WatchExpr::Sound => chip8.sound_timer() as u16,
+            WatchExpr::Memory(address) => chip8.ram[address as usize] as u16,
+        }
+    }
+
+    // digits to print a value with: 3 for addresses, 2 for bytes
+    pub fn width(self) -> usize {
+        match self {
+            WatchExpr::I | WatchExpr::Pc => 3,
+            _ => 2,
+        }
+    }
+}
+
+impl fmt::Display for WatchExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WatchExpr::V(x) => write!(f, "V{:X}", x),
+            WatchExpr::I => write!(f, "I"),
+            WatchExpr::Pc => write!(f, "PC"),
+            WatchExpr::Sp => write!(f, "SP"),
+            WatchExpr::Delay => write!(f, "DT"),
+            WatchExpr::Sound => write!(f, "ST"),
+            WatchExpr::Memory(address) => write!(f, "mem[{:#05X}]", address),
+        }
+    }
+}
+
+impl FromStr for WatchExpr {
+    type Err = ExprError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        WatchExpr::parse(text)
+    }
+}
+
+// a run of letters and digits, or a bracket, with the column it starts at
+enum Token<'a> {
+    Word(usize, &'a str),
+    Open(usize),
+    Close(usize),
+}
+
+impl Token<'_> {
+    fn column(&self) -> usize {
+        match self {
+            Token::Word(column, _) | Token::Open(column) | Token::Close(column) => *column,
+        }
+    }
+
+    fn text(&self) -> &str {
+        match self {
+            Token::Word(_, word) => word,
+            Token::Open(_) => "[",
+            Token::Close(_) => "]",
+        }
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token<'_>>, ExprError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let column = text[..start].chars().count() + 1;
+        match c {
+            '[' => tokens.push(Token::Open(column)),
+            ']' => tokens.push(Token::Close(column)),
+            c if c.is_whitespace() => {}
+            c if c.is_ascii_alphanumeric() => {
+                let mut end = start + 1;
+                while let Some(&(index, c)) = chars.peek() {
+                    if !c.is_ascii_alphanumeric() {
+                        break;
+                    }
+                    end = index + 1;
+                    chars.next();
+                }
+                tokens.push(Token::Word(column, &text[start..end]));
+            }
+            _ => {
+                return Err(ExprError::Parse {
+                    column,
+                    reason: format!("unexpected {:?}", c),
+                })
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+// what was there instead of `wanted`, at its column, or just past the end of `text`
+fn expected(text: &str, found: Option<&Token>, wanted: &str) -> ExprError {
+    match found {
+        Some(token) => ExprError::Parse {
+            column: token.column(),
+            reason: format!("expected {}, got {:?}", wanted, token.text()),
+        },
+        None => ExprError::Parse {
+            column: text.chars().count() + 1,
+            reason: format!("expected {}", wanted),
+        },
+    }
+}
+
+fn parse_address(column: usize, word: &str) -> Result<u16, ExprError> {
+    let digits = word.strip_prefix("0x").or_else(|| word.strip_prefix("0X")).unwrap_or(word);
+    let error = |reason: String| ExprError::Parse { column, reason };
+    match u32::from_str_radix(digits, 16) {
+        Ok(address) if address < 0x1000 => Ok(address as u16),
+        Ok(_) => Err(error(format!("{} is past the end of memory", word))),
+        Err(_) => Err(error(format!("{:?} is not a hex address", word))),
+    }
+}
@@ -0,0 +1,120 @@
+use std::{fmt, str::FromStr};
+
+use crate::viewport::Viewport;
+
+// brightness kept, out of 256, by the dark half of each scanline pair, by the gaps between
+// CHIP-8 pixels, and by the corners under the vignette
+const SCANLINE: u32 = 168;
+const GRID: u32 = 216;
+const VIGNETTE_CORNER: u32 = 160;
+
+// A look-of-an-old-monitor pass over the already scaled image. Only the image inside the
+// viewport is touched; the letterbox bars stay flat.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Filter {
+    #[default]
+    None,
+    // every other output row darkened, plus thin gaps between CHIP-8 pixels
+    Scanlines,
+    // scanlines with the edges and corners fading out
+    Crt,
+}
+
+impl Filter {
+    pub const ALL: [Filter; 3] = [Filter::None, Filter::Scanlines, Filter::Crt];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Filter::None => "none",
+            Filter::Scanlines => "scanlines",
+            Filter::Crt => "crt",
+        }
+    }
+
+    // the one after this in ALL, wrapping around
+    pub fn next(self) -> Filter {
+        let index = Filter::ALL.iter().position(|&filter| filter == self).unwrap_or(0);
+        Filter::ALL[(index + 1) % Filter::ALL.len()]
+    }
+
+    // `buffer` is the whole window, `width` pixels wide, with an image_width x image_height
+    // image drawn into it at `viewport`
+    pub fn apply(self, buffer: &mut [u32], width: usize, viewport: Viewport, image_width: usize, image_height: usize) {
+        if self == Filter::None || width == 0 {
+            return;
+        }
+
+        let (scaled_width, scaled_height) = viewport.size(image_width, image_height);
+        let scaled_width = scaled_width.min(width - viewport.x);
+        let scaled_height = scaled_height.min(buffer.len() / width - viewport.y);
+        // at small scales the gaps would eat the pixels, so they only show from 4x up
+        let grid = viewport.scale >= 4;
+        let vignette: Vec<u32> = if self == Filter::Crt {
+            (0..scaled_width).map(|x| falloff(x, scaled_width)).collect()
+        } else {
+            Vec::new()
+        };
+
+        for y in 0..scaled_height {
+            let mut row_factor = 256;
+            if y % 2 == 1 {
+                row_factor = row_factor * SCANLINE / 256;
+            }
+            if grid && y % viewport.scale == viewport.scale - 1 {
+                row_factor = row_factor * GRID / 256;
+            }
+            if self == Filter::Crt {
+                row_factor = row_factor * falloff(y, scaled_height) / 256;
+            }
+
+            let start = (viewport.y + y) * width + viewport.x;
+            for (x, pixel) in buffer[start..start + scaled_width].iter_mut().enumerate() {
+                let mut factor = row_factor;
+                if grid && x % viewport.scale == viewport.scale - 1 {
+                    factor = factor * GRID / 256;
+                }
+                if let Some(edge) = vignette.get(x) {
+                    factor = factor * edge / 256;
+                }
+                *pixel = darken(*pixel, factor);
+            }
+        }
+    }
+}
+
+// 256 in the middle of a span down to sqrt(VIGNETTE_CORNER / 256) at either end, so both
+// axes together give VIGNETTE_CORNER in the corners
+fn falloff(position: usize, length: usize) -> u32 {
+    let half = length as f32 / 2.0;
+    let distance = (position as f32 + 0.5 - half) / half;
+    let edge = (VIGNETTE_CORNER as f32 / 256.0).sqrt();
+    (256.0 * (1.0 - (1.0 - edge) * distance * distance)) as u32
+}
+
+// scales all three channels by factor / 256, red and blue in one multiply
+fn darken(color: u32, factor: u32) -> u32 {
+    let red_blue = (((color & 0xFF00FF) * factor) >> 8) & 0xFF00FF;
+    let green = (((color & 0x00FF00) * factor) >> 8) & 0x00FF00;
+    red_blue | green
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Filter {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Filter::ALL
+            .iter()
+            .copied()
+            .find(|filter| filter.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Filter::ALL.iter().map(|filter| filter.name()).collect();
+                format!("unknown filter {} (expected one of {})", name, names.join(", "))
+            })
+    }
+}
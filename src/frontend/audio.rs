@@ -0,0 +1,124 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use cpal::{
+    traits::{DeviceTrait, HostTrait, StreamTrait},
+    FromSample, SampleFormat, SizedSample, Stream, StreamConfig,
+};
+use rust_8::{AudioError, AudioSink, ToneGenerator, Waveform};
+
+// Plays the buzzer on the default output device. The stream runs for the whole session
+// and the audio callback only reads a flag, so switching the tone never blocks.
+struct Beeper {
+    on: Arc<AtomicBool>,
+    // set by the stream when the device goes away
+    failed: Arc<AtomicBool>,
+    _stream: Stream,
+}
+
+impl Beeper {
+    fn new(waveform: Waveform, frequency: f32, volume: f32) -> Result<Self, AudioError> {
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or(AudioError::NoDevice)?;
+        let supported = device
+            .default_output_config()
+            .map_err(|err| AudioError::Stream(err.to_string()))?;
+        let format = supported.sample_format();
+        let config: StreamConfig = supported.into();
+
+        let on = Arc::new(AtomicBool::new(false));
+        let failed = Arc::new(AtomicBool::new(false));
+        let wave = ToneGenerator::new(waveform, frequency, volume, config.sample_rate.0);
+        let stream = match format {
+            SampleFormat::F32 => build_stream::<f32>(&device, &config, wave, Arc::clone(&on), Arc::clone(&failed)),
+            SampleFormat::I16 => build_stream::<i16>(&device, &config, wave, Arc::clone(&on), Arc::clone(&failed)),
+            SampleFormat::U16 => build_stream::<u16>(&device, &config, wave, Arc::clone(&on), Arc::clone(&failed)),
+            format => return Err(AudioError::Stream(format!("unsupported sample format {}", format))),
+        }?;
+        stream.play().map_err(|err| AudioError::Stream(err.to_string()))?;
+
+        Ok(Beeper { on, failed, _stream: stream })
+    }
+}
+
+// The sound device as an AudioSink. Opening the device can fail, and the device can
+// disappear mid-session; either way the sink goes quiet with a warning and tries to
+// open the device again the next time a beep starts.
+pub struct CpalSink {
+    waveform: Waveform,
+    frequency: f32,
+    volume: f32,
+    beeper: Option<Beeper>,
+    on: bool,
+}
+
+impl CpalSink {
+    pub fn new(waveform: Waveform, frequency: f32, volume: f32) -> Self {
+        let mut sink = CpalSink {
+            waveform,
+            frequency,
+            volume,
+            beeper: None,
+            on: false,
+        };
+        sink.open();
+        sink
+    }
+
+    fn open(&mut self) {
+        match Beeper::new(self.waveform, self.frequency, self.volume) {
+            Ok(beeper) => self.beeper = Some(beeper),
+            Err(err) => log::warn!("sound disabled: {}", err),
+        }
+    }
+}
+
+impl AudioSink for CpalSink {
+    fn set_tone(&mut self, on: bool) {
+        let starting = on && !self.on;
+        self.on = on;
+
+        if self.beeper.as_ref().is_some_and(|beeper| beeper.failed.load(Ordering::Relaxed)) {
+            self.beeper = None;
+        }
+        if starting && self.beeper.is_none() {
+            self.open();
+        }
+        if let Some(beeper) = &self.beeper {
+            beeper.on.store(on, Ordering::Relaxed);
+        }
+    }
+}
+
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &StreamConfig,
+    mut wave: ToneGenerator,
+    on: Arc<AtomicBool>,
+    failed: Arc<AtomicBool>,
+) -> Result<Stream, AudioError>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let channels = config.channels as usize;
+    device
+        .build_output_stream(
+            config,
+            move |data: &mut [T], _| {
+                let on = on.load(Ordering::Relaxed);
+                for frame in data.chunks_mut(channels) {
+                    let sample = T::from_sample(wave.next_sample(on));
+                    frame.iter_mut().for_each(|out| *out = sample);
+                }
+            },
+            move |err| {
+                log::warn!("audio error: {}", err);
+                failed.store(true, Ordering::Relaxed);
+            },
+            None,
+        )
+        .map_err(|err| AudioError::Stream(err.to_string()))
+}
@@ -0,0 +1,81 @@
+use minifb::{Key, Scale, Window, WindowOptions};
+use rust_8::{blit_scaled, keypad_from, letterbox, ColorTheme, EmulatorError, Framebuffer, Keypad, HEIGHT, WIDTH};
+
+use super::window::held_keys;
+
+const DIVIDER: u32 = 0x808080;
+const DIVERGED: u32 = 0xFF0000;
+
+// The two screens of a --compare run next to each other with a line between them,
+// which turns red while they show different things. Keys go to both machines.
+pub struct CompareWindow {
+    window: Window,
+    theme: ColorTheme,
+    // each screen alone, the pair side by side, and that scaled to the window
+    screen: Vec<u32>,
+    buffer: Vec<u32>,
+    scaled: Vec<u32>,
+}
+
+impl CompareWindow {
+    pub fn new(title: &str, scale: usize, theme: ColorTheme) -> Result<Self, EmulatorError> {
+        let options = WindowOptions {
+            scale: Scale::X1,
+            resize: true,
+            ..WindowOptions::default()
+        };
+        let (width, height) = ((2 * WIDTH + 1) * scale, HEIGHT * scale);
+        let mut window = Window::new(title, width, height, options).map_err(|err| EmulatorError::WindowInit(err.to_string()))?;
+        window.set_target_fps(60);
+        Ok(CompareWindow {
+            window,
+            theme,
+            screen: Vec::new(),
+            buffer: Vec::new(),
+            scaled: Vec::new(),
+        })
+    }
+
+    pub fn is_open(&mut self) -> bool {
+        self.window.is_open() && !held_keys(&mut self.window).contains(&Key::Escape)
+    }
+
+    pub fn poll(&mut self, keypad: &mut Keypad) {
+        *keypad = keypad_from(&held_keys(&mut self.window));
+    }
+
+    pub fn present(&mut self, left: &Framebuffer, right: &Framebuffer, diverged: bool) -> Result<(), EmulatorError> {
+        let width = left.width() + 1 + right.width();
+        let height = left.height().max(right.height());
+        self.buffer.clear();
+        self.buffer.resize(width * height, self.theme.bg());
+
+        let divider = if diverged { DIVERGED } else { DIVIDER };
+        for y in 0..height {
+            self.buffer[y * width + left.width()] = divider;
+        }
+        for (framebuffer, x) in [(left, 0), (right, left.width() + 1)] {
+            self.screen.resize(framebuffer.width() * framebuffer.height(), 0);
+            framebuffer.render_into(&mut self.screen, self.theme.fg(), self.theme.bg());
+            for (y, row) in self.screen.chunks(framebuffer.width()).enumerate() {
+                let start = y * width + x;
+                self.buffer[start..start + row.len()].copy_from_slice(row);
+            }
+        }
+
+        let (window_width, window_height) = self.window.get_size();
+        if window_width == 0 || window_height == 0 {
+            // minimized
+            return self
+                .window
+                .update_with_buffer(&self.buffer, width, height)
+                .map_err(|err| EmulatorError::Window(err.to_string()));
+        }
+        let viewport = letterbox(window_width, window_height, width, height);
+        self.scaled.resize(window_width * window_height, 0);
+        blit_scaled(&self.buffer, width, &mut self.scaled, window_width, viewport, self.theme.bg());
+        self.window
+            .update_with_buffer(&self.scaled, window_width, window_height)
+            .map_err(|err| EmulatorError::Window(err.to_string()))
+    }
+}
@@ -0,0 +1,57 @@
+// The desktop's scale factor for --hidpi: 1.0 on a plain 96 DPI screen, 1.5 at 150%, 2.0 on
+// Retina. minifb has no way to ask for it, so each platform is asked directly.
+
+#[cfg(target_os = "windows")]
+pub fn dpi_scale() -> f32 {
+    #[link(name = "user32")]
+    extern "system" {
+        fn SetProcessDPIAware() -> i32;
+        fn GetDpiForSystem() -> u32;
+    }
+    // without opting in Windows reports 96 and stretches the window itself, blurring it
+    let dpi = unsafe {
+        SetProcessDPIAware();
+        GetDpiForSystem()
+    };
+    if dpi == 0 {
+        return 1.0;
+    }
+    dpi as f32 / 96.0
+}
+
+#[cfg(target_os = "macos")]
+pub fn dpi_scale() -> f32 {
+    use std::ffi::c_void;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGMainDisplayID() -> u32;
+        fn CGDisplayCopyDisplayMode(display: u32) -> *mut c_void;
+        fn CGDisplayModeGetWidth(mode: *mut c_void) -> usize;
+        fn CGDisplayModeGetPixelWidth(mode: *mut c_void) -> usize;
+        fn CGDisplayModeRelease(mode: *mut c_void);
+    }
+    // the main display's mode is as wide in pixels as its scale times its width in points
+    unsafe {
+        let mode = CGDisplayCopyDisplayMode(CGMainDisplayID());
+        if mode.is_null() {
+            return 1.0;
+        }
+        let (points, pixels) = (CGDisplayModeGetWidth(mode), CGDisplayModeGetPixelWidth(mode));
+        CGDisplayModeRelease(mode);
+        if points == 0 {
+            return 1.0;
+        }
+        pixels as f32 / points as f32
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+pub fn dpi_scale() -> f32 {
+    // X11 leaves it to the toolkits, which agree on these
+    ["GDK_SCALE", "QT_SCALE_FACTOR"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find_map(|value| value.trim().parse::<f32>().ok().filter(|scale| scale.is_finite() && *scale > 0.0))
+        .unwrap_or(1.0)
+}
@@ -0,0 +1,82 @@
+use minifb::{Key, KeyRepeat, Scale, Window, WindowOptions};
+use rust_8::{magnify, EmulatorError};
+
+// a REGION x REGION square of the screen, each pixel drawn ZOOM x ZOOM
+const REGION: usize = 16;
+const ZOOM: usize = 8;
+const SIZE: usize = REGION * ZOOM;
+const GRID_COLOR: u32 = 0x404040;
+
+// A second window with a close-up of part of the screen, for lining up sprites. Shift and
+// the arrow keys move the region, in either window. Closing it only closes it; the
+// emulator carries on in the main window.
+pub struct Magnifier {
+    window: Option<Window>,
+    // top left corner of the region, in screen pixels
+    x: usize,
+    y: usize,
+    zoomed: Vec<u32>,
+}
+
+impl Magnifier {
+    pub fn new() -> Result<Self, EmulatorError> {
+        let options = WindowOptions {
+            scale: Scale::X1,
+            ..WindowOptions::default()
+        };
+        let mut window =
+            Window::new(&title(0, 0), SIZE, SIZE, options).map_err(|err| EmulatorError::WindowInit(err.to_string()))?;
+        window.set_target_fps(60);
+        Ok(Magnifier {
+            window: Some(window),
+            x: 0,
+            y: 0,
+            zoomed: vec![0; SIZE * SIZE],
+        })
+    }
+
+    // `frame` is the screen as drawn, `frame_width` pixels wide; `main` is the emulator's
+    // window, whose keys move the region too
+    pub fn present(&mut self, frame: &[u32], frame_width: usize, main: &Window) -> Result<(), EmulatorError> {
+        let window = match &mut self.window {
+            Some(window) if window.is_open() => window,
+            _ => {
+                self.window = None;
+                return Ok(());
+            }
+        };
+
+        let frame_height = frame.len() / frame_width;
+        let (dx, dy) = movement(main).unwrap_or_else(|| movement(window).unwrap_or((0, 0)));
+        let x = self.x.saturating_add_signed(dx).min(frame_width.saturating_sub(REGION));
+        let y = self.y.saturating_add_signed(dy).min(frame_height.saturating_sub(REGION));
+        if (x, y) != (self.x, self.y) {
+            (self.x, self.y) = (x, y);
+            window.set_title(&title(x, y));
+        }
+
+        magnify(frame, frame_width, (x, y), REGION, ZOOM, GRID_COLOR, &mut self.zoomed);
+        window
+            .update_with_buffer(&self.zoomed, SIZE, SIZE)
+            .map_err(|err| EmulatorError::Window(err.to_string()))
+    }
+}
+
+fn title(x: usize, y: usize) -> String {
+    format!("Chip-8 magnifier ({}, {})", x, y)
+}
+
+// one pixel per arrow press (or repeat) while shift is held
+fn movement(window: &Window) -> Option<(isize, isize)> {
+    if !window.is_key_down(Key::LeftShift) && !window.is_key_down(Key::RightShift) {
+        return None;
+    }
+    let pressed = |key| window.is_key_pressed(key, KeyRepeat::Yes);
+    let dx = pressed(Key::Right) as isize - pressed(Key::Left) as isize;
+    let dy = pressed(Key::Down) as isize - pressed(Key::Up) as isize;
+    if (dx, dy) == (0, 0) {
+        None
+    } else {
+        Some((dx, dy))
+    }
+}
@@ -0,0 +1,16 @@
+#[cfg(feature = "audio")]
+mod audio;
+mod compare;
+mod dpi;
+mod magnifier;
+mod window;
+#[cfg(feature = "terminal")]
+mod terminal;
+
+pub use compare::CompareWindow;
+pub use dpi::dpi_scale;
+pub use window::{MinifbDisplay, ScalingMode, WindowConfig, MAX_SCALE, MIN_SCALE};
+#[cfg(feature = "terminal")]
+pub use terminal::{TerminalDisplay, TerminalInput};
+#[cfg(feature = "audio")]
+pub use audio::CpalSink;
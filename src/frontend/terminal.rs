@@ -0,0 +1,270 @@
+use std::{
+    io::{self, Write},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    terminal,
+};
+use rust_8::{ColorTheme, DisplayBackend, EmulatorError, Filter, FrameDelta, Framebuffer, InputBackend, Keypad, MAX_HEIGHT, MAX_WIDTH};
+
+const FRAME: Duration = Duration::from_micros(16600);
+
+// same layout as the window frontend, indexed by CHIP-8 key
+const KEYMAP: [char; 16] = [
+    'x', '1', '2', '3', 'q', 'w', 'e', 'a', 's', 'd', 'z', 'c', '4', 'r', 'f', 'v',
+];
+
+// terminals only report presses, so a key counts as held for this many frames after its last press
+const HOLD_FRAMES: u8 = 10;
+
+pub struct TerminalDisplay {
+    last_frame: Instant,
+    line: String,
+    theme: ColorTheme,
+    visual_bell: bool,
+    bell: bool,
+    // the theme or the visual bell changed since the last present
+    stale: bool,
+    // the framebuffer generation on the terminal, so a present only rewrites the rows
+    // that changed since
+    drawn: u64,
+}
+
+pub struct TerminalInput {
+    held: [u8; 16],
+    quit: bool,
+    reset: bool,
+    theme: bool,
+    mute: bool,
+    undo: bool,
+    next_rom: bool,
+    pause: bool,
+}
+
+impl TerminalDisplay {
+    // a terminal has no border to flash, so the visual bell swaps the colors instead
+    pub fn new(theme: ColorTheme, visual_bell: bool) -> Self {
+        TerminalDisplay {
+            last_frame: Instant::now(),
+            line: String::with_capacity((MAX_WIDTH * 3 + 2) * MAX_HEIGHT),
+            theme,
+            visual_bell,
+            bell: false,
+            stale: true,
+            drawn: 0,
+        }
+    }
+
+    fn wait_for_frame(&mut self) {
+        let elapsed = self.last_frame.elapsed();
+        if elapsed < FRAME {
+            thread::sleep(FRAME - elapsed);
+        }
+        self.last_frame = Instant::now();
+    }
+}
+
+impl DisplayBackend for TerminalDisplay {
+    fn is_open(&self) -> bool {
+        true
+    }
+
+    // block characters are either on or off, so the intensity is not used
+    fn present(&mut self, framebuffer: &Framebuffer, _intensity: &[f32]) -> Result<(), EmulatorError> {
+        let rows: Vec<usize> = match framebuffer.delta_since(self.drawn) {
+            FrameDelta::Rows { rows, .. } if !self.stale => rows.into_iter().map(|(y, _)| y).collect(),
+            _ => (0..framebuffer.height()).collect(),
+        };
+        self.line.clear();
+        if rows.len() == framebuffer.height() {
+            self.line.push_str("\x1b[2J");
+        }
+        // 24 bit foreground and background colors, reset again after the last row
+        let (mut fg, mut bg) = (self.theme.fg(), self.theme.bg());
+        if self.visual_bell && self.bell {
+            std::mem::swap(&mut fg, &mut bg);
+        }
+        self.line.push_str(&format!(
+            "\x1b[38;2;{};{};{}m\x1b[48;2;{};{};{}m",
+            fg >> 16, fg >> 8 & 0xFF, fg & 0xFF, bg >> 16, bg >> 8 & 0xFF, bg & 0xFF
+        ));
+        for y in rows {
+            // to the start of the row's line, counted from 1
+            self.line.push_str(&format!("\x1b[{};1H", y + 1));
+            for x in 0..framebuffer.width() {
+                self.line.push(if framebuffer.pixel(x, y) { '█' } else { ' ' });
+            }
+        }
+        // below the screen, where it was left before
+        self.line.push_str(&format!("\x1b[{};1H\x1b[0m", framebuffer.height() + 1));
+        self.drawn = framebuffer.generation();
+
+        let mut stdout = io::stdout();
+        stdout.write_all(self.line.as_bytes())?;
+        stdout.flush()?;
+        self.stale = false;
+        self.wait_for_frame();
+        Ok(())
+    }
+
+    fn needs_redraw(&self) -> bool {
+        self.stale
+    }
+
+    // keys are read by TerminalInput, so there is nothing to do but keep the pace
+    fn idle(&mut self) -> Result<(), EmulatorError> {
+        self.wait_for_frame();
+        Ok(())
+    }
+
+    fn set_theme(&mut self, theme: ColorTheme) {
+        self.theme = theme;
+        self.stale = true;
+    }
+
+    fn set_bell(&mut self, on: bool) {
+        // only the visual bell shows
+        self.stale |= self.visual_bell && on != self.bell;
+        self.bell = on;
+    }
+
+    // one character cell per pixel leaves nothing to filter
+    fn set_filter(&mut self, _filter: Filter) {}
+
+    // the terminal title is left alone, it belongs to the shell
+    fn set_title(&mut self, _title: &str) {}
+
+    // the font size of the terminal decides how big the screen is
+    fn change_scale(&mut self, _step: i32) -> Result<(), EmulatorError> {
+        Ok(())
+    }
+
+    // that is up to the terminal emulator
+    fn toggle_fullscreen(&mut self) -> Result<(), EmulatorError> {
+        Ok(())
+    }
+
+    // a terminal always comes with a keyboard
+    fn toggle_keyboard(&mut self) -> Result<(), EmulatorError> {
+        Ok(())
+    }
+}
+
+impl TerminalInput {
+    pub fn new() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        Ok(TerminalInput {
+            held: [0; 16],
+            quit: false,
+            reset: false,
+            theme: false,
+            mute: false,
+            undo: false,
+            next_rom: false,
+            pause: false,
+        })
+    }
+}
+
+impl InputBackend for TerminalInput {
+    fn poll(&mut self, keypad: &mut Keypad) {
+        for frames in self.held.iter_mut() {
+            *frames = frames.saturating_sub(1);
+        }
+
+        while let Ok(true) = event::poll(Duration::ZERO) {
+            let event = match event::read() {
+                Ok(event) => event,
+                Err(_) => break,
+            };
+            if let Event::Key(KeyEvent { code, modifiers, kind, .. }) = event {
+                if kind == KeyEventKind::Release {
+                    continue;
+                }
+                match code {
+                    KeyCode::Esc => self.quit = true,
+                    KeyCode::F(5) => self.reset = true,
+                    KeyCode::Char('t') | KeyCode::Char('T') => self.theme = true,
+                    KeyCode::Char('m') | KeyCode::Char('M') => self.mute = true,
+                    KeyCode::Backspace => self.undo = true,
+                    KeyCode::Tab => self.next_rom = true,
+                    KeyCode::Char('p') | KeyCode::Char('P') => self.pause = true,
+                    KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => self.quit = true,
+                    KeyCode::Char(ch) => {
+                        let ch = ch.to_ascii_lowercase();
+                        if let Some(key) = KEYMAP.iter().position(|k| *k == ch) {
+                            self.held[key] = HOLD_FRAMES;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        for (key, frames) in self.held.iter().enumerate() {
+            keypad.set(key as u8, *frames > 0);
+        }
+    }
+
+    fn is_key_pressed(&self, key: u8) -> bool {
+        self.held[(key & 0xF) as usize] > 0
+    }
+
+    fn quit_requested(&self) -> bool {
+        self.quit
+    }
+
+    // there is no portable way to ask a terminal, so it always counts as in use
+    fn has_focus(&self) -> bool {
+        true
+    }
+
+    fn reset_requested(&mut self) -> bool {
+        std::mem::take(&mut self.reset)
+    }
+
+    fn theme_requested(&mut self) -> bool {
+        std::mem::take(&mut self.theme)
+    }
+
+    fn filter_requested(&mut self) -> bool {
+        false
+    }
+
+    fn mute_requested(&mut self) -> bool {
+        std::mem::take(&mut self.mute)
+    }
+
+    fn undo_requested(&mut self) -> bool {
+        std::mem::take(&mut self.undo)
+    }
+
+    fn pause_requested(&mut self) -> bool {
+        std::mem::take(&mut self.pause)
+    }
+
+    fn next_rom_requested(&mut self) -> bool {
+        std::mem::take(&mut self.next_rom)
+    }
+
+    fn scale_requested(&mut self) -> i32 {
+        0
+    }
+
+    fn fullscreen_requested(&mut self) -> bool {
+        false
+    }
+
+    fn keyboard_requested(&mut self) -> bool {
+        false
+    }
+}
+
+impl Drop for TerminalInput {
+    fn drop(&mut self) {
+        let _ = terminal::disable_raw_mode();
+    }
+}
@@ -0,0 +1,496 @@
+use std::{cell::RefCell, rc::Rc};
+
+use minifb::{Key, KeyRepeat, MouseButton, MouseMode, Scale, Window, WindowOptions};
+use super::magnifier::Magnifier;
+use rust_8::{
+    blit_scaled, keypad_from, letterbox, render_intensity_into, Chip8, ColorTheme, Filter, DisplayBackend, DisplayTransform, EmulatorError,
+    Framebuffer, InputBackend, Keypad, Viewport, VirtualKeyboard, HEIGHT, WIDTH,
+};
+
+// the window sizes + and - step through, smallest first
+// (from 2 up, so a 128x64 screen still fits at 1x)
+pub const SCALES: [(usize, Scale); 5] = [
+    (2, Scale::X2),
+    (4, Scale::X4),
+    (8, Scale::X8),
+    (16, Scale::X16),
+    (32, Scale::X32),
+];
+
+// the range of --scale, the largest far past any screen
+pub const MIN_SCALE: usize = 2;
+pub const MAX_SCALE: usize = 64;
+
+// How the window gets its size. minifb only has power-of-two scales; anything else is
+// done here by opening a window that many times the display size at 1x. Either way
+// the image is drawn by repeating whole pixels, never filtered.
+#[derive(Debug, Clone, Copy)]
+pub enum ScalingMode {
+    Minifb(Scale),
+    Manual(usize),
+}
+
+impl ScalingMode {
+    // minifb's own scale when it has one for `factor`
+    pub fn from_factor(factor: usize) -> ScalingMode {
+        match SCALES.iter().find(|&&(n, _)| n == factor) {
+            Some(&(_, scale)) => ScalingMode::Minifb(scale),
+            None => ScalingMode::Manual(factor),
+        }
+    }
+
+    pub fn factor(self) -> usize {
+        match self {
+            ScalingMode::Minifb(Scale::X2) => 2,
+            ScalingMode::Minifb(Scale::X4) => 4,
+            ScalingMode::Minifb(Scale::X8) => 8,
+            ScalingMode::Minifb(Scale::X16) => 16,
+            ScalingMode::Minifb(Scale::X32) => 32,
+            // X1, and FitScreen which picks its own size
+            ScalingMode::Minifb(_) => 1,
+            ScalingMode::Manual(factor) => factor,
+        }
+    }
+}
+
+// how the window looks, filled in from the command line
+pub struct WindowConfig {
+    pub theme: ColorTheme,
+    // width of the frame drawn around the 64x32 display, in CHIP-8 pixels
+    pub border_pixels: u32,
+    pub border_color: u32,
+    // flash the outermost ring of the window while the buzzer sounds
+    pub visual_bell: bool,
+    pub bell_color: u32,
+    pub transform: DisplayTransform,
+    pub filter: Filter,
+    pub scaling: ScalingMode,
+    // start as a borderless window covering a screen of screen_size
+    pub fullscreen: bool,
+    pub screen_size: (usize, usize),
+    // open a second window with a close-up of part of the screen
+    pub magnifier: bool,
+    // start with the on-screen keyboard shown
+    pub virtual_keyboard: bool,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        WindowConfig {
+            theme: ColorTheme::default(),
+            border_pixels: 0,
+            border_color: 0x202020,
+            visual_bell: false,
+            bell_color: 0xFF0000,
+            transform: DisplayTransform::Normal,
+            filter: Filter::None,
+            scaling: ScalingMode::Minifb(Scale::X16),
+            fullscreen: false,
+            screen_size: (1920, 1080),
+            magnifier: false,
+            virtual_keyboard: false,
+        }
+    }
+}
+
+pub struct MinifbDisplay {
+    window: Rc<RefCell<Window>>,
+    // the CHIP-8 screen alone, the screen with its border, that turned by transform,
+    // and that scaled up to however big the window currently is
+    frame: Vec<u32>,
+    buffer: Vec<u32>,
+    output: Vec<u32>,
+    scaled: Vec<u32>,
+    transform: DisplayTransform,
+    filter: Filter,
+    // what the window is opened with, again whenever scale or fullscreen change
+    scaling: ScalingMode,
+    fullscreen: bool,
+    screen_size: (usize, usize),
+    title: String,
+    border: usize,
+    border_color: u32,
+    theme: ColorTheme,
+    // Some(color) when the visual bell is enabled
+    bell_color: Option<u32>,
+    bell: bool,
+    magnifier: Option<Magnifier>,
+    keyboard: Rc<RefCell<KeyboardState>>,
+    // output with the keyboard under it
+    composed: Vec<u32>,
+    // see needs_redraw, along with the window size and held keys the last present drew for
+    stale: bool,
+    drawn_size: (usize, usize),
+    drawn_held: Keypad,
+}
+
+pub struct MinifbInput {
+    window: Rc<RefCell<Window>>,
+    keyboard: Rc<RefCell<KeyboardState>>,
+}
+
+// The on-screen keyboard, which the display draws with the keys held and the input side
+// reads touches off.
+#[derive(Default)]
+struct KeyboardState {
+    keyboard: VirtualKeyboard,
+    // what the last poll found held, from the host keyboard or a touch
+    host: Keypad,
+    // the keys lit up, as Chip8::keys_pressed reports them off the same poll
+    held: Keypad,
+    // where the last present drew it: the viewport of the whole image, and the top row
+    // and width of the keyboard's strip within the image
+    placement: Option<(Viewport, usize, usize)>,
+}
+
+impl MinifbDisplay {
+    pub fn new(config: &WindowConfig) -> Result<Self, EmulatorError> {
+        let border = config.border_pixels as usize;
+        let (width, height) = (WIDTH + 2 * border, HEIGHT + 2 * border);
+        let (window_width, window_height) = config.transform.dimensions(width, height);
+        let keyboard = VirtualKeyboard::new(config.virtual_keyboard);
+        let window_height = window_height + keyboard.height(window_width);
+        let fullscreen = if config.fullscreen { Some(config.screen_size) } else { None };
+        let window = open_window("Chip-8", window_width, window_height, config.scaling, fullscreen)?;
+
+        Ok(MinifbDisplay {
+            window: Rc::new(RefCell::new(window)),
+            frame: vec![0; WIDTH * HEIGHT],
+            buffer: vec![config.border_color; width * height],
+            output: vec![0; width * height],
+            scaled: Vec::new(),
+            transform: config.transform,
+            filter: config.filter,
+            scaling: config.scaling,
+            fullscreen: config.fullscreen,
+            screen_size: config.screen_size,
+            title: String::from("Chip-8"),
+            border,
+            border_color: config.border_color,
+            theme: config.theme,
+            bell_color: if config.visual_bell { Some(config.bell_color) } else { None },
+            bell: false,
+            magnifier: if config.magnifier { Some(Magnifier::new()?) } else { None },
+            keyboard: Rc::new(RefCell::new(KeyboardState {
+                keyboard,
+                ..KeyboardState::default()
+            })),
+            composed: Vec::new(),
+            stale: true,
+            drawn_size: (0, 0),
+            drawn_held: Keypad::new(),
+        })
+    }
+
+    // the window is both the screen and the keyboard, so the input side shares it
+    pub fn input(&self) -> MinifbInput {
+        MinifbInput {
+            window: Rc::clone(&self.window),
+            keyboard: Rc::clone(&self.keyboard),
+        }
+    }
+}
+
+impl DisplayBackend for MinifbDisplay {
+    fn is_open(&self) -> bool {
+        self.window.borrow().is_open()
+    }
+
+    // The image is built at the framebuffer's own resolution. The window is sized for
+    // 64x32, so the letterboxing below draws high resolution screens at half the scale
+    // and both come out the same size.
+    fn present(&mut self, framebuffer: &Framebuffer, intensity: &[f32]) -> Result<(), EmulatorError> {
+        self.stale = false;
+        self.drawn_size = self.window.borrow().get_size();
+        self.drawn_held = self.keyboard.borrow().held;
+        let (frame_width, frame_height) = (framebuffer.width(), framebuffer.height());
+        // the border is counted in 64x32 pixels, so it grows with the resolution
+        let border = self.border * frame_width / WIDTH;
+        let width = frame_width + 2 * border;
+        let height = frame_height + 2 * border;
+        if self.buffer.len() != width * height {
+            self.buffer = vec![self.border_color; width * height];
+            self.output = vec![0; width * height];
+        }
+        self.frame.resize(frame_width * frame_height, 0);
+        render_intensity_into(intensity, &mut self.frame, self.theme.fg(), self.theme.bg());
+
+        // the border around it was filled in once and only changes under the bell
+        for (y, row) in self.frame.chunks(frame_width).enumerate() {
+            let start = (y + border) * width + border;
+            self.buffer[start..start + frame_width].copy_from_slice(row);
+        }
+        if let Some(bell_color) = self.bell_color {
+            // without a border the ring covers screen pixels, which were just redrawn anyway
+            let color = if self.bell { bell_color } else { self.border_color };
+            if self.bell || border > 0 {
+                draw_ring(&mut self.buffer, width, height, color);
+            }
+        }
+
+        self.transform.apply(&self.buffer, width, height, &mut self.output);
+        let (width, height) = self.transform.dimensions(width, height);
+
+        // the keyboard goes under the turned image, so its keys stay upright
+        let mut keyboard = self.keyboard.borrow_mut();
+        let strip = keyboard.keyboard.height(width);
+        let image = if strip == 0 {
+            &self.output
+        } else {
+            self.composed.resize(width * (height + strip), 0);
+            self.composed[..width * height].copy_from_slice(&self.output);
+            let held = keyboard.held;
+            keyboard.keyboard.draw(&mut self.composed[width * height..], width, &held, self.theme.fg(), self.theme.bg());
+            &self.composed
+        };
+        let (top, height) = (height, height + strip);
+
+        // the window may have been resized (or sits on a HiDPI screen), so fill it pixel for
+        // pixel with the largest whole-number scale that fits and bars around the rest
+        let mut window = self.window.borrow_mut();
+        if let Some(magnifier) = &mut self.magnifier {
+            magnifier.present(&self.frame, frame_width, &window)?;
+        }
+        let (window_width, window_height) = window.get_size();
+        if window_width == 0 || window_height == 0 {
+            // minimized
+            return window.update_with_buffer(image, width, height).map_err(|err| EmulatorError::Window(err.to_string()));
+        }
+        let viewport = letterbox(window_width, window_height, width, height);
+        keyboard.placement = if strip == 0 { None } else { Some((viewport, top, width)) };
+        self.scaled.resize(window_width * window_height, 0);
+        let bars = if self.fullscreen { 0x000000 } else { self.theme.bg() };
+        blit_scaled(image, width, &mut self.scaled, window_width, viewport, bars);
+        self.filter.apply(&mut self.scaled, window_width, viewport, width, height);
+        window
+            .update_with_buffer(&self.scaled, window_width, window_height)
+            .map_err(|err| EmulatorError::Window(err.to_string()))
+    }
+
+    // The magnifier's window is only updated from present: idle leaves it alone, so a still
+    // screen would leave its Shift+arrow moves unread and the window itself unresponsive.
+    // With it open, every frame is drawn.
+    fn needs_redraw(&self) -> bool {
+        self.stale
+            || self.magnifier.is_some()
+            || self.window.borrow().get_size() != self.drawn_size
+            || self.keyboard.borrow().held != self.drawn_held
+    }
+
+    fn idle(&mut self) -> Result<(), EmulatorError> {
+        self.window.borrow_mut().update();
+        Ok(())
+    }
+
+    fn set_theme(&mut self, theme: ColorTheme) {
+        self.theme = theme;
+        self.stale = true;
+    }
+
+    fn set_filter(&mut self, filter: Filter) {
+        self.filter = filter;
+        self.stale = true;
+    }
+
+    fn set_bell(&mut self, on: bool) {
+        self.stale |= on != self.bell;
+        self.bell = on;
+    }
+
+    fn set_title(&mut self, title: &str) {
+        self.title = title.to_string();
+        self.window.borrow_mut().set_title(title);
+    }
+
+    // a manual scale in between two of SCALES steps to the nearer one in that direction;
+    // in fullscreen it only takes effect once back in a window
+    fn change_scale(&mut self, step: i32) -> Result<(), EmulatorError> {
+        let factor = self.scaling.factor();
+        let next = if step > 0 {
+            SCALES.iter().find(|&&(n, _)| n > factor)
+        } else {
+            SCALES.iter().rev().find(|&&(n, _)| n < factor)
+        };
+        match next {
+            Some(&(_, scale)) => self.scaling = ScalingMode::Minifb(scale),
+            None => return Ok(()),
+        }
+        if self.fullscreen {
+            return Ok(());
+        }
+        self.reopen()
+    }
+
+    fn toggle_fullscreen(&mut self) -> Result<(), EmulatorError> {
+        self.fullscreen = !self.fullscreen;
+        log::info!("fullscreen {}", if self.fullscreen { "on" } else { "off" });
+        self.reopen()
+    }
+
+    // the window grows by the keyboard's strip, or in fullscreen the image shrinks to fit
+    fn toggle_keyboard(&mut self) -> Result<(), EmulatorError> {
+        self.keyboard.borrow_mut().keyboard.toggle();
+        if self.fullscreen {
+            return Ok(());
+        }
+        self.reopen()
+    }
+}
+
+impl MinifbDisplay {
+    // minifb can't resize a window or take it fullscreen, so a new one is opened in place
+    // of the old; the input side sees the new window through the shared cell
+    fn reopen(&mut self) -> Result<(), EmulatorError> {
+        let (width, height) = (WIDTH + 2 * self.border, HEIGHT + 2 * self.border);
+        let (width, height) = self.transform.dimensions(width, height);
+        let height = height + self.keyboard.borrow().keyboard.height(width);
+        let fullscreen = if self.fullscreen { Some(self.screen_size) } else { None };
+        let window = open_window(&self.title, width, height, self.scaling, fullscreen)?;
+        *self.window.borrow_mut() = window;
+        self.stale = true;
+        Ok(())
+    }
+}
+
+// `fullscreen` is the size of the screen to cover with a borderless window, which
+// replaces the normal window of width x height at `scaling`
+fn open_window(
+    title: &str,
+    width: usize,
+    height: usize,
+    scaling: ScalingMode,
+    fullscreen: Option<(usize, usize)>,
+) -> Result<Window, EmulatorError> {
+    let (width, height, scale) = match (fullscreen, scaling) {
+        (Some((width, height)), _) => (width, height, Scale::X1),
+        (None, ScalingMode::Minifb(scale)) => (width, height, scale),
+        (None, ScalingMode::Manual(factor)) => (width * factor, height * factor, Scale::X1),
+    };
+    let borderless = fullscreen.is_some();
+    let options = WindowOptions {
+        scale,
+        resize: !borderless,
+        borderless,
+        title: !borderless,
+        topmost: borderless,
+        ..WindowOptions::default()
+    };
+    let mut window = Window::new(title, width, height, options).map_err(|err| EmulatorError::WindowInit(err.to_string()))?;
+    if borderless {
+        window.set_position(0, 0);
+        window.set_cursor_visibility(false);
+    }
+    window.set_target_fps(60);
+    Ok(window)
+}
+
+fn draw_ring(buffer: &mut [u32], width: usize, height: usize, color: u32) {
+    buffer[..width].fill(color);
+    buffer[(height - 1) * width..].fill(color);
+    for y in 1..height - 1 {
+        buffer[y * width] = color;
+        buffer[y * width + width - 1] = color;
+    }
+}
+
+// All held-key queries go through here. A closed or unfocused window reports no keys,
+// which releases everything instead of leaving keys stuck down.
+pub(super) fn held_keys(window: &mut Window) -> Vec<Key> {
+    if window.is_open() && window.is_active() {
+        window.get_keys()
+    } else {
+        Vec::new()
+    }
+}
+
+// The key of the on-screen keyboard under a held left button, which is also what a
+// touchscreen reports a finger as.
+fn touched_key(window: &Window, state: &KeyboardState) -> Option<u8> {
+    let (viewport, top, width) = state.placement?;
+    if !window.is_open() || !window.get_mouse_down(MouseButton::Left) {
+        return None;
+    }
+    let (x, y) = window.get_mouse_pos(MouseMode::Discard)?;
+    let x = (x as usize).checked_sub(viewport.x)? / viewport.scale;
+    let y = (y as usize).checked_sub(viewport.y)? / viewport.scale;
+    state.keyboard.key_at(x, y.checked_sub(top)?, width)
+}
+
+impl InputBackend for MinifbInput {
+    // The host keys and the touch are read once per poll, and every key is answered from
+    // that. The on-screen keyboard lights up what the keypad gets, keys and touches alike.
+    fn poll(&mut self, keypad: &mut Keypad) {
+        {
+            let mut window = self.window.borrow_mut();
+            let mut keyboard = self.keyboard.borrow_mut();
+            let mut host = keypad_from(&held_keys(&mut window));
+            if let Some(key) = touched_key(&window, &keyboard) {
+                host.press(key);
+            }
+            keyboard.host = host;
+        }
+        *keypad = Keypad::from(Chip8::keys_pressed(self));
+        self.keyboard.borrow_mut().held = *keypad;
+    }
+
+    // as of the last poll
+    fn is_key_pressed(&self, key: u8) -> bool {
+        self.keyboard.borrow().host.is_pressed(key & 0xF)
+    }
+
+    fn quit_requested(&self) -> bool {
+        held_keys(&mut self.window.borrow_mut()).contains(&Key::Escape)
+    }
+
+    fn has_focus(&self) -> bool {
+        self.window.borrow_mut().is_active()
+    }
+
+    fn reset_requested(&mut self) -> bool {
+        self.window.borrow().is_key_pressed(Key::F5, KeyRepeat::No)
+    }
+
+    fn theme_requested(&mut self) -> bool {
+        self.window.borrow().is_key_pressed(Key::T, KeyRepeat::No)
+    }
+
+    fn filter_requested(&mut self) -> bool {
+        self.window.borrow().is_key_pressed(Key::F6, KeyRepeat::No)
+    }
+
+    fn mute_requested(&mut self) -> bool {
+        self.window.borrow().is_key_pressed(Key::M, KeyRepeat::No)
+    }
+
+    fn undo_requested(&mut self) -> bool {
+        self.window.borrow().is_key_pressed(Key::Backspace, KeyRepeat::No)
+    }
+
+    fn pause_requested(&mut self) -> bool {
+        self.window.borrow().is_key_pressed(Key::P, KeyRepeat::No)
+    }
+
+    fn next_rom_requested(&mut self) -> bool {
+        self.window.borrow().is_key_pressed(Key::Tab, KeyRepeat::No)
+    }
+
+    fn fullscreen_requested(&mut self) -> bool {
+        self.window.borrow().is_key_pressed(Key::F11, KeyRepeat::No)
+    }
+
+    fn keyboard_requested(&mut self) -> bool {
+        self.window.borrow().is_key_pressed(Key::K, KeyRepeat::No)
+    }
+
+    fn scale_requested(&mut self) -> i32 {
+        let window = self.window.borrow();
+        let pressed = |keys: [Key; 2]| keys.iter().any(|&key| window.is_key_pressed(key, KeyRepeat::No));
+        if pressed([Key::Equal, Key::NumPadPlus]) {
+            1
+        } else if pressed([Key::Minus, Key::NumPadMinus]) {
+            -1
+        } else {
+            0
+        }
+    }
+}
@@ -0,0 +1,4 @@
+// TextMate grammar for CHIP-8 assembly (.ch8asm files), usable as is by VS Code and
+// other editors that read .tmLanguage.json. Highlights mnemonics, V0-VF and the special
+// registers, 12 bit addresses, immediates, labels and ; comments.
+pub const CHIP8_TMGRAMMAR: &str = include_str!("../grammar/chip8.tmLanguage.json");
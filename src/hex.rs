@@ -0,0 +1,29 @@
+use crate::chip8::Chip8;
+use crate::error::{Chip8Error, HexError};
+
+// Bytes written out in hex and separated by whitespace, like "60 05 61 0A 80 14". A
+// group of several bytes with no space between them, like "6005", works too, so whole
+// instructions can be written as one word.
+pub fn from_hex(hex: &str) -> Result<Vec<u8>, HexError> {
+    let mut bytes = Vec::new();
+    for token in hex.split_whitespace() {
+        if !token.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(HexError::NotHex(token.to_string()));
+        }
+        if token.len() % 2 == 1 {
+            return Err(HexError::OddDigits(token.to_string()));
+        }
+        for at in (0..token.len()).step_by(2) {
+            let byte = u8::from_str_radix(&token[at..at + 2], 16).map_err(|_| HexError::NotHex(token.to_string()))?;
+            bytes.push(byte);
+        }
+    }
+    Ok(bytes)
+}
+
+impl Chip8 {
+    // load_rom for a ROM written the way from_hex reads it, mostly for tests
+    pub fn load_rom_from_hex(&mut self, hex: &str) -> Result<(), Chip8Error> {
+        self.load_rom(from_hex(hex)?)
+    }
+}
@@ -0,0 +1,174 @@
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    thread,
+    time::Duration,
+};
+
+use flate2::{write::ZlibEncoder, Compression, Crc};
+
+use serde_json::json;
+
+use crate::display::{intensity_color, FrameDelta, Framebuffer};
+use crate::emulator::{Command, EmulatorRemote};
+
+// how long a request may take to arrive, and to wait for the machine's state
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+// A small HTTP server on its own thread showing a running EmulatorThread:
+//
+//     GET  /frame.png          the screen in the frontend's colors, a pixel per pixel
+//     GET  /frame.delta?since=N  the rows changed since generation N as JSON, see delta_json
+//     GET  /state.json         Chip8::to_json
+//     POST /key/{hex}/down     holds a key, and /up lets go of it again
+//
+// It only reads frames and sends commands the way the frontend does, so a slow client
+// holds up nothing but the server. Requests are served one at a time.
+pub struct HttpServer {
+    addr: SocketAddr,
+}
+
+impl HttpServer {
+    pub fn spawn<A: ToSocketAddrs>(addr: A, emulator: EmulatorRemote) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let result = stream.and_then(|stream| serve(stream, &emulator));
+                if let Err(err) = result {
+                    log::debug!("http: {}", err);
+                }
+            }
+        });
+        Ok(HttpServer { addr })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+fn serve(mut stream: TcpStream, emulator: &EmulatorRemote) -> io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    let mut request = Vec::new();
+    let mut buffer = [0; 1024];
+    // the headers are all there is to read; no route takes a body
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        let n = stream.read(&mut buffer)?;
+        if n == 0 || request.len() > 16 * 1024 {
+            return Err(io::Error::new(ErrorKind::InvalidData, "incomplete request"));
+        }
+        request.extend_from_slice(&buffer[..n]);
+    }
+    let request = String::from_utf8_lossy(&request);
+    let mut words = request.split_whitespace();
+    let (method, path) = (words.next().unwrap_or(""), words.next().unwrap_or(""));
+    log::debug!("http: {} {}", method, path);
+    let (status, content_type, body) = route(method, path, emulator);
+    let header = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    );
+    stream.write_all(header.as_bytes())?;
+    stream.write_all(&body)
+}
+
+// (status line, content type, body)
+fn route(method: &str, path: &str, emulator: &EmulatorRemote) -> (&'static str, &'static str, Vec<u8>) {
+    let text = |status, body: &str| (status, "text/plain; charset=utf-8", body.as_bytes().to_vec());
+    let (path, query) = path.split_once('?').unwrap_or((path, ""));
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match (method, segments.as_slice()) {
+        ("GET", ["frame.png"]) => match emulator.latest_frame(0) {
+            Some(frame) => {
+                let (fg, bg) = (frame.theme.fg(), frame.theme.bg());
+                ("200 OK", "image/png", encode_png(&frame.framebuffer, &frame.intensity, fg, bg))
+            }
+            None => text("503 Service Unavailable", "no frame yet\n"),
+        },
+        ("GET", ["frame.delta"]) => {
+            let since = query.split('&').find_map(|pair| pair.strip_prefix("since=")).unwrap_or("0");
+            match (since.parse(), emulator.latest_frame(0)) {
+                (Err(_), _) => text("400 Bad Request", "since is a generation from an earlier delta\n"),
+                (Ok(_), None) => text("503 Service Unavailable", "no frame yet\n"),
+                (Ok(since), Some(frame)) => ("200 OK", "application/json", delta_json(&frame.framebuffer, since).into_bytes()),
+            }
+        }
+        ("GET", ["state.json"]) => match emulator.request_state(TIMEOUT) {
+            Some(state) => ("200 OK", "application/json", state.into_bytes()),
+            None => text("503 Service Unavailable", "the emulator didn't answer\n"),
+        },
+        ("POST", ["key", key, action]) => {
+            let key = match u8::from_str_radix(key, 16) {
+                Ok(key) if key < 16 => key,
+                _ => return text("400 Bad Request", "keys are 0 to F\n"),
+            };
+            let pressed = match *action {
+                "down" => true,
+                "up" => false,
+                _ => return text("404 Not Found", "down or up\n"),
+            };
+            emulator.send(Command::Key { key, pressed });
+            text("200 OK", "ok\n")
+        }
+        (_, ["frame.png"]) | (_, ["frame.delta"]) | (_, ["state.json"]) | (_, ["key", _, _]) => text("405 Method Not Allowed", "wrong method\n"),
+        _ => text("404 Not Found", "not found\n"),
+    }
+}
+
+// {"generation": 42, "width": 64, "height": 32, "full": false, "rows": [[3, "00F0..."]]}
+// with each row's pixels packed like Framebuffer::packed_row, in hex. A full delta lists
+// every row, in order.
+fn delta_json(framebuffer: &Framebuffer, since: u64) -> String {
+    let hex = |packed: &[u8]| packed.iter().map(|byte| format!("{:02X}", byte)).collect::<String>();
+    let (generation, full, rows): (u64, bool, Vec<(usize, String)>) = match framebuffer.delta_since(since) {
+        FrameDelta::Full { generation, width, rows, .. } => (generation, true, rows.chunks(width.div_ceil(8)).map(hex).enumerate().collect()),
+        FrameDelta::Rows { generation, rows } => (generation, false, rows.iter().map(|(y, packed)| (*y, hex(packed))).collect()),
+    };
+    json!({
+        "generation": generation,
+        "width": framebuffer.width(),
+        "height": framebuffer.height(),
+        "full": full,
+        "rows": rows,
+    })
+    .to_string()
+}
+
+// an 8 bit RGB PNG of the screen, each pixel faded between `bg` and `fg` by its intensity
+fn encode_png(framebuffer: &Framebuffer, intensity: &[f32], fg: u32, bg: u32) -> Vec<u8> {
+    let (width, height) = (framebuffer.width(), framebuffer.height());
+    let mut scanlines = Vec::with_capacity((1 + width * 3) * height);
+    for row in intensity.chunks(width).take(height) {
+        // no filter
+        scanlines.push(0);
+        for &value in row {
+            let color = intensity_color(value, fg, bg);
+            scanlines.extend_from_slice(&[(color >> 16) as u8, (color >> 8) as u8, color as u8]);
+        }
+    }
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    // writing to a Vec can't fail
+    let _ = encoder.write_all(&scanlines);
+    let data = encoder.finish().unwrap_or_default();
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    // 8 bits a channel, RGB, deflate, no filtering beyond each line's own, no interlace
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    for (kind, chunk) in [(b"IHDR", &header[..]), (b"IDAT", &data[..]), (b"IEND", &[][..])] {
+        png.extend_from_slice(&(chunk.len() as u32).to_be_bytes());
+        png.extend_from_slice(kind);
+        png.extend_from_slice(chunk);
+        let mut crc = Crc::new();
+        crc.update(kind);
+        crc.update(chunk);
+        png.extend_from_slice(&crc.sum().to_be_bytes());
+    }
+    png
+}
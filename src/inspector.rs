@@ -0,0 +1,53 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::chip8::{Chip8, InstructionResult};
+
+// Hooks around every step, for debuggers, tracers and test harnesses. `state` is the
+// machine before and after the instruction; the inspector is detached while they run.
+pub trait Chip8Inspector {
+    fn before_instruction(&mut self, pc: u16, opcode: u16, state: &Chip8);
+    fn after_instruction(&mut self, result: &InstructionResult, state: &Chip8);
+}
+
+pub struct NullInspector;
+
+impl Chip8Inspector for NullInspector {
+    fn before_instruction(&mut self, _pc: u16, _opcode: u16, _state: &Chip8) {}
+    fn after_instruction(&mut self, _result: &InstructionResult, _state: &Chip8) {}
+}
+
+// Records one line per hook call. The machine owns the inspector, so keep a handle
+// from `lines()` before attaching it to read the log afterwards.
+pub struct LoggingInspector {
+    lines: Rc<RefCell<Vec<String>>>,
+}
+
+impl LoggingInspector {
+    pub fn new() -> Self {
+        LoggingInspector {
+            lines: Rc::new(RefCell::new(Vec::new())),
+        }
+    }
+
+    pub fn lines(&self) -> Rc<RefCell<Vec<String>>> {
+        Rc::clone(&self.lines)
+    }
+}
+
+impl Default for LoggingInspector {
+    fn default() -> Self {
+        LoggingInspector::new()
+    }
+}
+
+impl Chip8Inspector for LoggingInspector {
+    fn before_instruction(&mut self, pc: u16, opcode: u16, _state: &Chip8) {
+        self.lines.borrow_mut().push(format!("before {:04X} {:04X}", pc, opcode));
+    }
+
+    fn after_instruction(&mut self, result: &InstructionResult, state: &Chip8) {
+        self.lines
+            .borrow_mut()
+            .push(format!("after {:?} PC={:04X}", result, state.pc()));
+    }
+}
@@ -0,0 +1,106 @@
+struct Opcode {
+    d1: u16,
+    d2: u16,
+    d3: u16,
+    d4: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Instruction {
+    Cls,
+    Ret,
+    ScrollDown(u8),
+    ScrollUp(u8),
+    ScrollRight,
+    ScrollLeft,
+    Exit,
+    Sys(u16),
+    Jp(u16),
+    Call(u16),
+    SeByte(u8, u8),
+    SneByte(u8, u8),
+    SeReg(u8, u8),
+    LdByte(u8, u8),
+    AddByte(u8, u8),
+    LdReg(u8, u8),
+    Or(u8, u8),
+    And(u8, u8),
+    Xor(u8, u8),
+    AddReg(u8, u8),
+    Sub(u8, u8),
+    Shr(u8, u8),
+    Subn(u8, u8),
+    Shl(u8, u8),
+    SneReg(u8, u8),
+    LdI(u16),
+    JpV0(u16),
+    Rnd(u8, u8),
+    Drw(u8, u8, u8),
+    Skp(u8),
+    Sknp(u8),
+    LdVxDt(u8),
+    LdVxK(u8),
+    LdDtVx(u8),
+    LdStVx(u8),
+    AddI(u8),
+    LdF(u8),
+    LdB(u8),
+    LdIVx(u8),
+    LdVxI(u8),
+    Unknown(u16),
+}
+
+impl Instruction {
+    pub fn decode(hb: u8, lb: u8) -> Self {
+        let opcode = Opcode {
+            d1: (hb / 16) as u16,
+            d2: (hb % 16) as u16,
+            d3: (lb / 16) as u16,
+            d4: (lb % 16) as u16
+        };
+
+        match opcode {
+            Opcode { d1:0, d2: 0, d3: 0x0E, d4: 0 } => Instruction::Cls,
+            Opcode { d1:0, d2: 0, d3: 0xE, d4: 0xE} => Instruction::Ret,
+            Opcode { d1: 0, d2: 0, d3: 0xC, d4 } => Instruction::ScrollDown(d4 as u8),
+            Opcode { d1: 0, d2: 0, d3: 0xD, d4 } => Instruction::ScrollUp(d4 as u8),
+            Opcode { d1: 0, d2: 0, d3: 0xF, d4: 0xB } => Instruction::ScrollRight,
+            Opcode { d1: 0, d2: 0, d3: 0xF, d4: 0xC } => Instruction::ScrollLeft,
+            Opcode { d1: 0, d2: 0, d3: 0xF, d4: 0xD } => Instruction::Exit,
+            Opcode { d1: 0, d2, d3, d4 } => Instruction::Sys((d2 << 8) | (d3 << 4) | (d4)),
+            Opcode { d1: 0x1, d2, d3, d4} => Instruction::Jp((d2 << 8) | (d3 << 4) | (d4)),
+            Opcode { d1: 0x2, d2, d3, d4} => Instruction::Call((d2 << 8) | (d3 << 4) | (d4)),
+            Opcode { d1: 0x3, d2, d3, d4} => Instruction::SeByte(d2 as u8, ((d3 << 4) | d4) as u8),
+            Opcode { d1: 0x4, d2, d3, d4} => Instruction::SneByte(d2 as u8, ((d3 << 4) | d4) as u8),
+            Opcode { d1: 0x5, d2, d3, d4: 0 } => Instruction::SeReg(d2 as u8, d3 as u8),
+            Opcode { d1: 0x6, d2, d3, d4 } => Instruction::LdByte(d2 as u8, ((d3 << 4) | d4) as u8),
+            Opcode { d1: 0x7, d2, d3, d4 } => Instruction::AddByte(d2 as u8, ((d3 << 4) | d4) as u8),
+            Opcode { d1: 0x8, d2, d3, d4: 0 } => Instruction::LdReg(d2 as u8, d3 as u8),
+            Opcode { d1: 0x8, d2, d3, d4: 0x1 } => Instruction::Or(d2 as u8, d3 as u8),
+            Opcode { d1: 0x8, d2, d3, d4: 0x2 } => Instruction::And(d2 as u8, d3 as u8),
+            Opcode { d1: 0x8, d2, d3, d4: 0x3 } => Instruction::Xor(d2 as u8, d3 as u8),
+            Opcode { d1: 0x8, d2, d3, d4: 0x4 } => Instruction::AddReg(d2 as u8, d3 as u8),
+            Opcode { d1: 0x8, d2, d3, d4: 0x5 } => Instruction::Sub(d2 as u8, d3 as u8),
+            Opcode { d1: 0x8, d2, d3, d4: 0x6 } => Instruction::Shr(d2 as u8, d3 as u8),
+            Opcode { d1: 0x8, d2, d3, d4: 0x7 } => Instruction::Subn(d2 as u8, d3 as u8),
+            Opcode { d1: 0x8, d2, d3, d4: 0xE } => Instruction::Shl(d2 as u8, d3 as u8),
+            Opcode { d1: 0x9, d2, d3, d4: 0 } => Instruction::SneReg(d2 as u8, d3 as u8),
+            Opcode { d1: 0xA, d2, d3, d4 } => Instruction::LdI((d2 << 8) | (d3 << 4) | (d4)),
+            Opcode { d1: 0xB, d2, d3, d4 } => Instruction::JpV0((d2 << 8) | (d3 << 4) | (d4)),
+            Opcode { d1: 0xC, d2, d3, d4} => Instruction::Rnd(d2 as u8, ((d3 << 4) | d4) as u8),
+            Opcode { d1: 0xD, d2, d3, d4 } => Instruction::Drw(d2 as u8, d3 as u8, d4 as u8),
+            Opcode { d1: 0xE, d2, d3: 0x9, d4: 0xE} => Instruction::Skp(d2 as u8),
+            Opcode { d1: 0xE, d2, d3: 0xA, d4: 0x1} => Instruction::Sknp(d2 as u8),
+            Opcode { d1: 0xF, d2, d3: 0, d4: 0x7 } => Instruction::LdVxDt(d2 as u8),
+            Opcode { d1: 0xF, d2, d3: 0, d4: 0xA } => Instruction::LdVxK(d2 as u8),
+            Opcode { d1: 0xF, d2, d3: 0x1, d4: 0x5 } => Instruction::LdDtVx(d2 as u8),
+            Opcode { d1: 0xF, d2, d3: 0x1, d4: 0x8 } => Instruction::LdStVx(d2 as u8),
+            Opcode { d1: 0xF, d2, d3: 0x1, d4: 0xE } => Instruction::AddI(d2 as u8),
+            Opcode { d1: 0xF, d2, d3: 0x2, d4: 0x9 } => Instruction::LdF(d2 as u8),
+            Opcode { d1: 0xF, d2, d3: 0x3, d4: 0x3 } => Instruction::LdB(d2 as u8),
+            Opcode { d1: 0xF, d2, d3: 0x5, d4: 0x5 } => Instruction::LdIVx(d2 as u8),
+            Opcode { d1: 0xF, d2, d3: 0x6, d4: 0x5 } => Instruction::LdVxI(d2 as u8),
+            _ => Instruction::Unknown(((hb as u16) << 8) | lb as u16),
+        }
+    }
+}
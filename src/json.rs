@@ -0,0 +1,96 @@
+use serde_json::{json, Map, Value};
+
+use crate::chip8::Chip8;
+use crate::error::Chip8Error;
+
+// bumped whenever a field changes meaning, so tools can tell old dumps apart
+const FORMAT_VERSION: u64 = 1;
+
+impl Chip8 {
+    // The machine state as one JSON object, for debuggers and scripts:
+    //
+    //     {"version": 1, "v": [16 numbers], "pc": 512, "i": 0, "sp": 0, "stack": [16 numbers],
+    //      "delay": 0, "sound": 0, "ram": "<8192 hex digits>"}
+    //
+    // Configuration (variant, quirks, theme) and the screen are not part of it.
+    pub fn to_json(&self) -> String {
+        let ram: String = self.ram.iter().map(|byte| format!("{:02X}", byte)).collect();
+        json!({
+            "version": FORMAT_VERSION,
+            "v": self.cpu.vx,
+            "pc": self.cpu.pc,
+            "i": self.cpu.i,
+            "sp": self.stack.size,
+            "stack": self.stack.mem,
+            "delay": self.hour.delay,
+            "sound": self.hour.sound,
+            "ram": ram,
+        })
+        .to_string()
+    }
+
+    // a default-configured machine in the state to_json wrote
+    pub fn from_json(text: &str) -> Result<Chip8, Chip8Error> {
+        let value: Value = serde_json::from_str(text).map_err(|err| Chip8Error::InvalidState(err.to_string()))?;
+        let state = value
+            .as_object()
+            .ok_or_else(|| Chip8Error::InvalidState(String::from("expected an object")))?;
+        let version = number(state, "version", u64::MAX)?;
+        if version != FORMAT_VERSION {
+            return Err(Chip8Error::InvalidState(format!("unsupported version {}", version)));
+        }
+
+        let mut chip8 = Chip8::new();
+        for (register, value) in chip8.cpu.vx.iter_mut().zip(array(state, "v", 16)?) {
+            *register = element("v", value, u8::MAX as u64)? as u8;
+        }
+        chip8.cpu.pc = number(state, "pc", 0xFFF)? as u16;
+        chip8.cpu.i = number(state, "i", u16::MAX as u64)? as u16;
+        chip8.stack.size = number(state, "sp", chip8.stack.mem.len() as u64)? as u8;
+        for (entry, value) in chip8.stack.mem.iter_mut().zip(array(state, "stack", 16)?) {
+            *entry = element("stack", value, 0xFFF)? as u16;
+        }
+        chip8.hour.delay = number(state, "delay", u8::MAX as u64)? as u8;
+        chip8.hour.sound = number(state, "sound", u8::MAX as u64)? as u8;
+
+        let ram = state
+            .get("ram")
+            .and_then(Value::as_str)
+            .ok_or_else(|| Chip8Error::InvalidState(String::from("ram should be a hex string")))?;
+        if ram.len() != chip8.ram.len() * 2 {
+            return Err(Chip8Error::InvalidState(format!(
+                "ram has {} hex digits, expected {}",
+                ram.len(),
+                chip8.ram.len() * 2
+            )));
+        }
+        for (n, byte) in chip8.ram.iter_mut().enumerate() {
+            let digits = ram.get(n * 2..n * 2 + 2).unwrap_or("");
+            *byte = u8::from_str_radix(digits, 16)
+                .map_err(|_| Chip8Error::InvalidState(format!("bad hex {:?} in ram at {:#05X}", digits, n)))?;
+        }
+        Ok(chip8)
+    }
+}
+
+fn number(state: &Map<String, Value>, key: &str, max: u64) -> Result<u64, Chip8Error> {
+    match state.get(key) {
+        Some(value) => element(key, value, max),
+        None => Err(Chip8Error::InvalidState(format!("{} is missing", key))),
+    }
+}
+
+fn element(key: &str, value: &Value, max: u64) -> Result<u64, Chip8Error> {
+    value
+        .as_u64()
+        .filter(|&n| n <= max)
+        .ok_or_else(|| Chip8Error::InvalidState(format!("{} should be a number from 0 to {}", key, max)))
+}
+
+fn array<'a>(state: &'a Map<String, Value>, key: &str, len: usize) -> Result<&'a Vec<Value>, Chip8Error> {
+    state
+        .get(key)
+        .and_then(Value::as_array)
+        .filter(|values| values.len() == len)
+        .ok_or_else(|| Chip8Error::InvalidState(format!("{} should be a list of {} numbers", key, len)))
+}
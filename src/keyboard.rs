@@ -0,0 +1,116 @@
+use minifb::Key;
+
+use crate::display::lerp_color;
+use crate::keypad::Keypad;
+use crate::memory::SPRITE_DATA;
+
+// CHIP-8 key (the index) to host key, using the usual 1234/QWER/ASDF/ZXCV layout
+pub const KEYMAP: [Key; 16] = [
+    Key::X,
+    Key::Key1,
+    Key::Key2,
+    Key::Key3,
+    Key::Q,
+    Key::W,
+    Key::E,
+    Key::A,
+    Key::S,
+    Key::D,
+    Key::Z,
+    Key::C,
+    Key::Key4,
+    Key::R,
+    Key::F,
+    Key::V,
+];
+
+// the keypad with the CHIP-8 keys whose host keys are among `keys` held down
+pub fn keypad_from(keys: &[Key]) -> Keypad {
+    let mut keypad = Keypad::new();
+    for (key, host_key) in KEYMAP.iter().enumerate() {
+        keypad.set(key as u8, keys.contains(host_key));
+    }
+    keypad
+}
+
+// the keys as the COSMAC VIP's keypad has them, top row first
+pub const KEYBOARD_LAYOUT: [[u8; 4]; 4] = [[0x1, 0x2, 0x3, 0xC], [0x4, 0x5, 0x6, 0xD], [0x7, 0x8, 0x9, 0xE], [0xA, 0x0, 0xB, 0xF]];
+
+// A 4x4 grid of the hex keys drawn under the screen, for tablets and other machines with
+// a touchscreen but no keyboard. Each key shows its digit in the CHIP-8 font and turns
+// the other way round while held. The frontend gives it a strip of the image to draw in
+// and asks key_at which key a touch landed on.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct VirtualKeyboard {
+    pub show_keyboard: bool,
+}
+
+impl VirtualKeyboard {
+    pub fn new(show_keyboard: bool) -> Self {
+        VirtualKeyboard { show_keyboard }
+    }
+
+    pub fn toggle(&mut self) {
+        self.show_keyboard = !self.show_keyboard;
+    }
+
+    // rows of the strip under an image `width` pixels wide, none while it's hidden: half
+    // the width like the screen, and never too short for a digit in every row
+    pub fn height(&self, width: usize) -> usize {
+        if self.show_keyboard {
+            (width / 2).max(28)
+        } else {
+            0
+        }
+    }
+
+    // Draws the grid into `strip`, `width` pixels wide and height(width) rows, with the
+    // keys held on `keypad` lit.
+    pub fn draw(&self, strip: &mut [u32], width: usize, keypad: &Keypad, fg: u32, bg: u32) {
+        let height = strip.len() / width.max(1);
+        let grid = lerp_color(bg, fg, 0.3);
+        for (row, keys) in KEYBOARD_LAYOUT.iter().enumerate() {
+            let (top, bottom) = (row * height / 4, (row + 1) * height / 4);
+            for (column, &key) in keys.iter().enumerate() {
+                let (left, right) = (column * width / 4, (column + 1) * width / 4);
+                let (face, digit) = if keypad.is_pressed(key) { (fg, bg) } else { (bg, fg) };
+                for y in top..bottom {
+                    let line = &mut strip[y * width + left..y * width + right];
+                    line.fill(face);
+                    line[0] = grid;
+                }
+                strip[top * width + left..top * width + right].fill(grid);
+                draw_digit(strip, width, (left + 1, top + 1), (right - left - 1, bottom - top - 1), key, digit);
+            }
+        }
+    }
+
+    // The key under x, y of the strip, for a strip `width` pixels wide.
+    pub fn key_at(&self, x: usize, y: usize, width: usize) -> Option<u8> {
+        let height = self.height(width);
+        if x >= width || y >= height {
+            return None;
+        }
+        Some(KEYBOARD_LAYOUT[y * 4 / height][x * 4 / width])
+    }
+}
+
+// the 4x5 font glyph of `key`, scaled by whole pixels as far as it fits with a margin
+// and centered in the cell at `origin`
+fn draw_digit(strip: &mut [u32], width: usize, origin: (usize, usize), cell: (usize, usize), key: u8, color: u32) {
+    let scale = ((cell.0 / 6).min(cell.1 / 7)).max(1);
+    let (glyph_width, glyph_height) = (4 * scale, 5 * scale);
+    if glyph_width > cell.0 || glyph_height > cell.1 {
+        return;
+    }
+    let x0 = origin.0 + (cell.0 - glyph_width) / 2;
+    let y0 = origin.1 + (cell.1 - glyph_height) / 2;
+    let glyph = &SPRITE_DATA[key as usize * 5..][..5];
+    for y in 0..glyph_height {
+        for x in 0..glyph_width {
+            if glyph[y / scale] >> (7 - x / scale) & 1 == 1 {
+                strip[(y0 + y) * width + x0 + x] = color;
+            }
+        }
+    }
+}
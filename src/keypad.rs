@@ -0,0 +1,36 @@
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Keypad {
+    keys: [bool; 16],
+}
+
+impl Keypad {
+    pub fn new() -> Self {
+        Keypad::default()
+    }
+
+    pub fn set(&mut self, key: u8, pressed: bool) {
+        self.keys[(key & 0xF) as usize] = pressed;
+    }
+
+    pub fn press(&mut self, key: u8) {
+        self.set(key, true);
+    }
+
+    pub fn release(&mut self, key: u8) {
+        self.set(key, false);
+    }
+
+    pub fn is_pressed(&self, key: u8) -> bool {
+        self.keys[(key & 0xF) as usize]
+    }
+
+    pub fn first_pressed(&self) -> Option<u8> {
+        self.keys.iter().position(|pressed| *pressed).map(|key| key as u8)
+    }
+}
+
+impl From<[bool; 16]> for Keypad {
+    fn from(keys: [bool; 16]) -> Self {
+        Keypad { keys }
+    }
+}
@@ -0,0 +1,158 @@
+// With the no_std feature (and without the default std one) only Chip8Core and the
+// pieces it is built from are left, for microcontrollers. See examples/bare_metal.rs.
+#![cfg_attr(feature = "no_std", no_std)]
+
+#[cfg(all(feature = "std", feature = "no_std"))]
+compile_error!("no_std leaves out everything std provides: build with --no-default-features --features no_std");
+#[cfg(not(any(feature = "std", feature = "no_std")))]
+compile_error!("rust-8 needs the std feature, or no_std for Chip8Core alone");
+
+mod cpu;
+mod embedded;
+mod instruction;
+mod keypad;
+mod memory;
+mod snapshot;
+
+pub use cpu::Cpu;
+pub use embedded::{Chip8Core, CoreError, CoreStep, CORE_HEIGHT, CORE_WIDTH};
+pub use instruction::Instruction;
+pub use keypad::Keypad;
+pub use memory::{sprite_address, PROGRAM_START, SPRITE_DATA};
+pub use snapshot::{Chip8Snapshot, CpuSnapshot, StackSnapshot, TimerSnapshot};
+
+// everything else needs std
+macro_rules! std_only {
+    ($($item:item)*) => {
+        $(#[cfg(feature = "std")] $item)*
+    };
+}
+
+std_only! {
+    mod analysis;
+    mod archive;
+    mod ascii;
+    #[cfg(feature = "serde")]
+    mod autosave;
+    mod backend;
+    mod builder;
+    mod builtin;
+    mod callstack;
+    mod chip8;
+    mod clock;
+    mod compare;
+    mod crash;
+    mod database;
+    mod debugger;
+    mod diff;
+    mod display;
+    mod download;
+    #[cfg(feature = "serde")]
+    mod dump;
+    // there are no threads to run it on in wasm32-unknown-unknown
+    #[cfg(not(target_arch = "wasm32"))]
+    mod emulator;
+    mod env;
+    mod error;
+    mod expr;
+    mod filter;
+    #[cfg(feature = "chip8-grammar")]
+    mod grammar;
+    mod hex;
+    #[cfg(all(feature = "http-server", not(target_arch = "wasm32")))]
+    mod httpserver;
+    mod inspector;
+    mod json;
+    mod keyboard;
+    mod library;
+    mod pause;
+    mod probe;
+    mod profiler;
+    mod quirkdetect;
+    mod quirks;
+    mod reftrace;
+    #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+    mod remote;
+    mod script;
+    mod speed;
+    mod state;
+    mod stack;
+    mod status;
+    mod symbols;
+    #[cfg(feature = "serde")]
+    mod statediff;
+    mod syscall;
+    mod testrom;
+    mod theme;
+    mod timer;
+    mod tone;
+    mod transform;
+    mod variant;
+    mod viewport;
+    mod wav;
+    #[cfg(not(target_arch = "wasm32"))]
+    mod watch;
+
+    pub use analysis::{coverage_report, opcode_pattern, DynamicOpcodeCoverage, OpcodeCoverage};
+    pub use ascii::AsciiDump;
+    pub use archive::{extract_zip_rom, gunzip_rom, read_rom, read_rom_from, split_rom_path};
+    #[cfg(feature = "serde")]
+    pub use autosave::{autosave_path, default_save_dir, read_state, restore_autosave, save_autosave};
+    pub use backend::{AudioBackend, AudioSink, DisplayBackend, InputBackend, NullAudioBackend, NullSink, SinkAudioBackend};
+    pub use builder::Chip8Builder;
+    pub use builtin::{builtin_rom, BuiltinRom, BUILTIN_ROMS};
+    pub use callstack::{CallStack, Frame, StackAnomaly};
+    pub use chip8::{Chip8, InstructionResult};
+    #[cfg(not(target_arch = "wasm32"))]
+    pub use clock::PreciseClock;
+    pub use compare::{Comparison, Divergence};
+    #[cfg(not(target_arch = "wasm32"))]
+    pub use emulator::{Command, EmulatorRemote, EmulatorThread, ExitHook, FrameSnapshot};
+    pub use env::{Chip8Env, EnvConfig, Observation, StepInfo};
+    pub use database::{sha1_hex, RomDatabase, RomInfo};
+    pub use debugger::{Break, BreakEvent, DebugCommand, Debugger, WatchHit, DEFAULT_CHECKPOINT_INTERVAL};
+    pub use diff::{diff, Chip8Diff};
+    #[cfg(feature = "serde")]
+    pub use dump::{RngDump, StateDump};
+    pub use display::{lerp_color, render_intensity_into, FrameDelta, Framebuffer, HEIGHT, MAX_HEIGHT, MAX_WIDTH, WIDTH};
+    pub use download::{is_rom_url, rom_url_name, validate_rom_url};
+    pub use error::{AudioError, Chip8Error, ConfigError, EmulatorError, ExprError, HexError, RefTraceError, RomLoadError, ScriptError, SymbolError};
+    pub use expr::WatchExpr;
+    pub use filter::Filter;
+    #[cfg(feature = "chip8-grammar")]
+    pub use grammar::CHIP8_TMGRAMMAR;
+    pub use hex::from_hex;
+    #[cfg(all(feature = "http-server", not(target_arch = "wasm32")))]
+    pub use httpserver::HttpServer;
+    pub use inspector::{Chip8Inspector, LoggingInspector, NullInspector};
+    pub use keyboard::{keypad_from, VirtualKeyboard, KEYBOARD_LAYOUT, KEYMAP};
+    pub use library::read_rom_library;
+    pub use pause::PauseState;
+    pub use state::{MachineState, STATE_VERSION};
+    #[cfg(feature = "serde")]
+    pub use statediff::{ram_spans, state_diff, RamSpan, StateDiff};
+    pub use speed::SpeedController;
+    pub use status::{write_title, RateMeter, Status, TITLE_UPDATE_INTERVAL};
+    pub use symbols::SymbolTable;
+    pub use syscall::{SyscallHandler, SyscallResult};
+    pub use testrom::Chip8TestRom;
+    pub use profiler::{CallTree, CallTreeReport, Profiler};
+    pub use reftrace::{RefTraceChecker, RefTraceWriter, TraceFormat, TraceLine};
+    #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+    pub use remote::{RemoteClient, RemoteMessage, RemoteRequest, RemoteServer};
+    pub use script::{InputScript, KeyEvent};
+    pub use quirkdetect::{
+        detect_quirks, find_glyph, glyph_at, quirks_platform, read_quirks_screen, Glyph, QuirkReading, CHECK_GLYPH, CROSS_GLYPH,
+        QUIRKS_ROM, QUIRK_LINES,
+    };
+    pub use quirks::{MemoryProtection, QuirkConfig, UnknownOpcodePolicy};
+    pub use theme::{parse_color, ColorTheme};
+    pub use tone::{ToneGenerator, Waveform};
+    pub use transform::DisplayTransform;
+    pub use variant::ChipVariant;
+    pub use viewport::{blit_scaled, hidpi_scale, letterbox, magnify, Viewport};
+    pub use crash::{crash_report, step_or_report, step_or_report_to};
+    pub use wav::{SoundRecorder, WavWriter};
+    #[cfg(not(target_arch = "wasm32"))]
+    pub use watch::RomWatcher;
+}
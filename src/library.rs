@@ -0,0 +1,41 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+// Reads a set of ROMs to switch between at runtime, titled by file stem. `path` is either
+// a directory, whose .ch8 files are taken in name order, or a playlist: a text file
+// with one ROM path per line, relative to the playlist, and # starting a comment.
+pub fn read_rom_library(path: &Path) -> io::Result<Vec<(String, Vec<u8>)>> {
+    let paths = if path.is_dir() {
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(path)? {
+            let entry = entry?.path();
+            if entry.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("ch8")) {
+                paths.push(entry);
+            }
+        }
+        paths.sort();
+        paths
+    } else {
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+        fs::read_to_string(path)?
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| base.join(line))
+            .collect::<Vec<PathBuf>>()
+    };
+
+    if paths.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "no ROMs found"));
+    }
+    paths
+        .iter()
+        .map(|rom| {
+            let data = fs::read(rom).map_err(|err| io::Error::new(err.kind(), format!("{}: {}", rom.display(), err)))?;
+            let title = rom.file_stem().map_or_else(|| rom.display().to_string(), |stem| stem.to_string_lossy().into_owned());
+            Ok((title, data))
+        })
+        .collect()
+}
@@ -1,373 +1,1118 @@
-use std::{collections::HashMap, fs::File, io::Read, time, u8};
-
-use minifb::{Key, Scale, Window, WindowOptions};
-use rand::Rng;
-
-const WIDTH: usize = 64;
-const HEIGHT: usize = 32;
-
-const PROGRAM_START: u16 = 0x200;
-
-struct Opcode {
-    d1: u16,
-    d2: u16,
-    d3: u16,
-    d4: u16,
-}
-
-struct Chip8 {
-    cpu: Cpu,
-    ram: [u8; 4096],
-    display: Vec<u32>,
-    stack: Stack,
-    keyboard: HashMap<u16, Key>,
-    hour: Timer,
-}
-
-struct Cpu {
-    vx: [u8; 16],
-    pc: u16,
-    i: u16,
-}
-
-struct Stack {
-    mem: [u16; 16],
-    size: u8,
-}
-
-impl Chip8 {
-    fn new() -> Self {
-        Chip8 {
-            cpu: Cpu::new(),
-            ram: [0; 4096],
-            display: vec![0; WIDTH * HEIGHT],
-            stack: Stack::new(),
-            keyboard: [
-                (1, Key::Key1),
-                (2, Key::Key2),
-                (3, Key::Key3),
-                (0xC, Key::Key4),
-                (4, Key::Q),
-                (5, Key::W),
-                (6, Key::E),
-                (0xD, Key::R),
-                (7, Key::A),
-                (8, Key::S),
-                (9, Key::D),
-                (0xE, Key::F),
-                (0xA, Key::Z),
-                (0, Key::X),
-                (0xB, Key::C),
-                (0xF, Key::V),
-            ].iter().cloned().collect(),
-            hour: Timer::new(),
-        }
-    }
-
-    fn load_rom(&mut self, data: Vec<u8>) {
-        for i in 0..data.len() {
-            self.ram[PROGRAM_START as usize + i] = data[i];
-        }
-    }
-
-    fn load_sprites(&mut self) {
-        let sprites: [[u8; 5]; 16] = [
-            [0xF0, 0x90, 0x90, 0x90, 0xF0],
-            [0x20, 0x60, 0x20, 0x20, 0x70],
-            [0xF0, 0x10, 0xF0, 0x80, 0xF0],
-            [0xF0, 0x10, 0xF0, 0x10, 0xF0],
-            [0x90, 0x90, 0xF0, 0x10, 0x10],
-            [0xF0, 0x80, 0xF0, 0x10, 0xF0],
-            [0xF0, 0x80, 0xF0, 0x90, 0xF0],
-            [0xF0, 0x10, 0x20, 0x40, 0x40],
-            [0xF0, 0x90, 0xF0, 0x90, 0xF0],
-            [0xF0, 0x90, 0xF0, 0x10, 0xF0],
-            [0xF0, 0x90, 0xF0, 0x90, 0x90],
-            [0xE0, 0x90, 0xE0, 0x90, 0xE0],
-            [0xF0, 0x80, 0x80, 0x80, 0xF0],
-            [0xE0, 0x90, 0x90, 0x90, 0xE0],
-            [0xF0, 0x80, 0xF0, 0x80, 0xF0],
-            [0xF0, 0x80, 0xF0, 0x80, 0x80]
-        ];
-
-        let mut i = 0;
-        for sprite in sprites.iter() {
-            for ch in sprite {
-                self.ram[i] = *ch;
-                i += 1;
+// The desktop emulator. A wasm32 build has no emulation thread to drive, so only the
+// library is of use there and most of this goes unused.
+#![cfg_attr(target_arch = "wasm32", allow(dead_code, unused_imports))]
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use rust_8::{
+    AsciiDump, AudioSink, builtin_rom, BuiltinRom, BUILTIN_ROMS,
+    coverage_report, Chip8, Chip8Builder, Chip8Error, ChipVariant, Comparison, DynamicOpcodeCoverage, DisplayBackend, EmulatorError, Framebuffer,
+    hidpi_scale, InputBackend, InputScript, InstructionResult, Keypad, RefTraceChecker, RefTraceWriter, RomDatabase, SoundRecorder, SpeedController, SymbolTable, TraceFormat,
+    is_rom_url, read_rom, read_rom_library, rom_url_name,
+    PauseState, RateMeter, Status, step_or_report, TITLE_UPDATE_INTERVAL, write_title,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use rust_8::{Command, EmulatorThread, ExitHook};
+
+mod cli;
+mod config;
+mod frontend;
+
+// how long the title says RELOADING after the ROM changed on disk
+const RELOAD_NOTICE: Duration = Duration::from_millis(750);
+// the sprite draws a second --adaptive-speed aims for
+const TARGET_DRAWS_PER_SECOND: f32 = 30.0;
+// how long selftest lets a built-in ROM run before looking at its screen
+const SELFTEST_FRAMES: u64 = 300;
+
+// the window side: forwards key changes to the emulation thread and redraws the latest frame
+#[cfg(not(target_arch = "wasm32"))]
+fn run(
+    emulator: EmulatorThread,
+    display: &mut dyn DisplayBackend,
+    input: &mut dyn InputBackend,
+    audio: &mut dyn AudioSink,
+    options: &cli::Options,
+    library: &[String],
+    mut watcher: Option<rust_8::RomWatcher>,
+) -> Result<(), EmulatorError> {
+    let mut theme = options.theme;
+    let mut filter = options.filter;
+    let mut muted = options.mute;
+    let mut reloaded: Option<Instant> = None;
+    let mut keypad = Keypad::new();
+    let mut framebuffer = Framebuffer::new();
+    let mut intensity = vec![0.0; framebuffer.width() * framebuffer.height()];
+    let mut generation = 0;
+    let mut sound_frames = 0;
+    // the screen changed since it was last drawn, see FrameSnapshot::display_frames
+    let mut display_frames = 0;
+    let mut screen_changed = true;
+    let mut instructions = 0;
+    let mut rom = None;
+    let mut pause = PauseState::default();
+    let rom_stem = options.rom_title.clone().unwrap_or_else(|| {
+        if options.rom == "-" {
+            String::from("stdin")
+        } else if is_rom_url(&options.rom) {
+            rom_url_name(&options.rom).unwrap_or(&options.rom).to_string()
+        } else {
+            Path::new(&options.rom)
+                .file_stem()
+                .map_or_else(|| options.rom.clone(), |stem| stem.to_string_lossy().into_owned())
+        }
+    });
+
+    // the title is rebuilt on every change and a few times a second for the rates
+    let mut title = String::new();
+    let mut fps = RateMeter::new();
+    let mut ips = RateMeter::new();
+    let (mut fps_now, mut ips_now) = (0.0, 0.0);
+    let mut next_status = Instant::now();
+    let mut status_changed = true;
+
+    while display.is_open() && !input.quit_requested() && emulator.is_running() {
+        let was_paused = pause.is_paused();
+        let previous = keypad;
+        input.poll(&mut keypad);
+        if keypad != previous {
+            emulator.send(Command::Keypad(keypad));
+        }
+        if input.reset_requested() {
+            emulator.send(Command::Reset);
+        }
+        if input.undo_requested() {
+            emulator.send(Command::Undo);
+        }
+        if input.next_rom_requested() {
+            emulator.send(Command::NextRom);
+        }
+        if input.pause_requested() {
+            pause.toggle_manual();
+            status_changed = true;
+        }
+        if options.pause_on_focus_loss && input.has_focus() == pause.is_unfocused() {
+            pause.set_focused(input.has_focus());
+            status_changed = true;
+        }
+        if pause.is_paused() != was_paused {
+            if pause.is_paused() {
+                emulator.send(Command::Pause);
+                // no frames come in while paused, so nothing else would stop the beep
+                audio.set_tone(false);
+                display.set_bell(false);
+            } else {
+                emulator.send(Command::Resume);
+            }
+        }
+        if input.theme_requested() {
+            theme = theme.next();
+            display.set_theme(theme);
+            emulator.send(Command::Theme(theme));
+        }
+        if input.filter_requested() {
+            filter = filter.next();
+            display.set_filter(filter);
+        }
+        let step = input.scale_requested();
+        if step != 0 {
+            display.change_scale(step)?;
+        }
+        if input.fullscreen_requested() {
+            display.toggle_fullscreen()?;
+        }
+        if input.keyboard_requested() {
+            display.toggle_keyboard()?;
+        }
+        if input.mute_requested() {
+            muted = !muted;
+            status_changed = true;
+        }
+        if watcher.as_mut().is_some_and(|watcher| watcher.reload()) {
+            emulator.send(Command::Reset);
+            reloaded = Some(Instant::now());
+            status_changed = true;
+        }
+        if reloaded.is_some_and(|at| at.elapsed() > RELOAD_NOTICE) {
+            reloaded = None;
+            status_changed = true;
+        }
+
+        if let Some(frame) = emulator.latest_frame(generation) {
+            generation = frame.generation;
+            // with phosphor decay the afterglow keeps fading between draws
+            screen_changed |= frame.display_frames != display_frames || (options.phosphor > 0.0 && frame.intensity != intensity);
+            display_frames = frame.display_frames;
+            framebuffer = frame.framebuffer;
+            intensity = frame.intensity;
+            // any beep since the last frame we saw counts, however short
+            let sound = frame.sound_frames != sound_frames;
+            sound_frames = frame.sound_frames;
+            instructions = frame.instructions;
+            if frame.rom != rom {
+                rom = frame.rom;
+                status_changed = true;
             }
+            audio.set_tone(sound && !muted);
+            display.set_bell(sound);
+        }
+
+        let now = Instant::now();
+        if now >= next_status {
+            fps_now = fps.sample(now, generation);
+            ips_now = ips.sample(now, instructions);
+            next_status = now + TITLE_UPDATE_INTERVAL;
+            status_changed = true;
+        }
+        if status_changed {
+            let status = Status {
+                rom: rom.map_or(rom_stem.as_str(), |index| library[index].as_str()),
+                library: rom.map(|index| (index, library.len())),
+                fps: fps_now,
+                ips: ips_now,
+                profile: options.variant.name(),
+                paused: pause.is_manual(),
+                unfocused: pause.is_unfocused(),
+                muted,
+                reloading: reloaded.is_some(),
+            };
+            write_title(&mut title, &status);
+            display.set_title(&title);
+            status_changed = false;
+        }
+
+        if screen_changed || display.needs_redraw() {
+            display.present(&framebuffer, &intensity)?;
+            screen_changed = false;
+        } else {
+            display.idle()?;
         }
     }
 
-    fn run_instruction(&mut self, window: &mut Window) {
-        let hb: u8 = self.ram[self.cpu.pc as usize];
-        let lb: u8 = self.ram[(self.cpu.pc + 1) as usize];
-        let opcode = Opcode {
-            d1: (hb / 16) as u16,
-            d2: (hb % 16) as u16,
-            d3: (lb / 16) as u16,
-            d4: (lb % 16) as u16
-        };
+    audio.set_tone(false);
+    emulator.shutdown()?;
+    Ok(())
+}
 
-        self.cpu.pc += 2;
-
-        match opcode {
-            Opcode { d1:0, d2: 0, d3: 0x0E, d4: 0 } => self.clear_display(),
-            Opcode { d1:0, d2: 0, d3: 0xE, d4: 0xE} => self.cpu.pc = self.stack.pop(),
-            Opcode { d1: 0x1, d2, d3, d4} => self.cpu.pc = (d2 << 8) | (d3 << 4) | (d4),
-            Opcode { d1: 0x2, d2, d3, d4} => self.call_subroutine((d2 << 8) | (d3 << 4) | (d4)),
-            Opcode { d1: 0x3, d2, d3, d4} => {
-                let kk = (d3 << 4) | d4;
-                if self.cpu.vx[d2 as usize] as u16 == kk{
-                    self.cpu.pc += 2
-                }
+#[cfg(not(target_arch = "wasm32"))]
+fn main() -> Result<(), EmulatorError> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("statediff") => return statediff(&args[1..]),
+        Some("selftest") => {
+            init_logging(0);
+            return selftest();
+        }
+        Some("debug") => {
+            init_logging(0);
+            return debug(&args[1..]);
+        }
+        Some("detect-quirks") => {
+            init_logging(0);
+            return detect_quirks(&args[1..]);
+        }
+        _ => {}
+    }
+    let options = cli::parse_args()?;
+    init_logging(options.verbosity);
+    start(options)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn main() {}
+
+// Warnings by default, each -v one level more. RUST_LOG, like RUST_LOG=rust_8::chip8=trace,
+// replaces that for whatever modules it names.
+fn init_logging(verbosity: u8) {
+    let level = match verbosity {
+        0 => log::LevelFilter::Warn,
+        1 => log::LevelFilter::Info,
+        2 => log::LevelFilter::Debug,
+        _ => log::LevelFilter::Trace,
+    };
+    env_logger::Builder::new()
+        .filter_level(level)
+        .format_timestamp(None)
+        .parse_default_env()
+        .init();
+}
+
+// rust-8 statediff a.json b.json
+#[cfg(feature = "serde")]
+fn statediff(args: &[String]) -> Result<(), EmulatorError> {
+    let (before, after) = match args {
+        [before, after] => (rust_8::read_state(Path::new(before))?, rust_8::read_state(Path::new(after))?),
+        _ => return Err(rust_8::ConfigError::MissingValue(String::from("statediff (two state files)")).into()),
+    };
+    print!("{}", rust_8::state_diff(&before, &after));
+    Ok(())
+}
+
+#[cfg(not(feature = "serde"))]
+fn statediff(_args: &[String]) -> Result<(), EmulatorError> {
+    Err(rust_8::ConfigError::MissingFeature {
+        option: String::from("statediff"),
+        feature: String::from("serde"),
+    }
+    .into())
+}
+
+// rust-8 selftest: runs every built-in ROM headlessly and checks that it runs without
+// an error and draws something
+fn selftest() -> Result<(), EmulatorError> {
+    let mut failed = 0;
+    for rom in BUILTIN_ROMS.iter() {
+        match selftest_rom(rom) {
+            Ok(frames) => println!("{:<12} ok after {} frames", rom.name, frames),
+            Err(reason) => {
+                println!("{:<12} FAILED: {}", rom.name, reason);
+                failed += 1;
             }
-            Opcode { d1: 0x4, d2, d3, d4} => {
-                let kk = (d3 << 4) | d4;
-                if self.cpu.vx[d2 as usize] as u16 != kk {
-                    self.cpu.pc += 2
-                }
+        }
+    }
+    if failed > 0 {
+        return Err(io::Error::other(format!("{} of {} built-in ROMs failed", failed, BUILTIN_ROMS.len())).into());
+    }
+    Ok(())
+}
+
+// the frames it ran, up to SELFTEST_FRAMES or until it halted
+fn selftest_rom(rom: &BuiltinRom) -> Result<u64, String> {
+    let mut chip8 = Chip8Builder::new().with_variant(rom.variant).with_rng_seed(0).build();
+    chip8.load_rom(rom.data.to_vec()).map_err(|err| err.to_string())?;
+    let mut frames = 0;
+    while frames < SELFTEST_FRAMES && !chip8.is_halted() {
+        for _i in 0..rom.variant.instructions_per_frame() {
+            if let InstructionResult::Halted { .. } = chip8.step().map_err(|err| err.to_string())? {
+                break;
             }
-            Opcode { d1:0x5, d4: 0, ..} => {
-                if self.cpu.vx[opcode.d2 as usize] == self.cpu.vx[opcode.d3 as usize] {
-                    self.cpu.pc += 2
-                }
+        }
+        chip8.tick_timers();
+        frames += 1;
+    }
+    let display = chip8.display();
+    if (0..display.height()).all(|y| (0..display.width()).all(|x| !display.pixel(x, y))) {
+        return Err(String::from("the screen is blank"));
+    }
+    Ok(frames)
+}
+
+// rust-8 debug rom.ch8 [--variant name] [--symbols file.sym]: steps the ROM from commands
+// on standard input, see rust_8::DebugCommand, printing the registers and the next
+// instruction after each one, and anything unusual the ROM did with the stack
+fn debug(args: &[String]) -> Result<(), EmulatorError> {
+    // a continue that never reaches a breakpoint gives up after this many instructions
+    const CONTINUE_LIMIT: u64 = 10_000_000;
+    let rom = match args.first() {
+        Some(rom) if !rom.starts_with("--") => rom,
+        _ => return Err(rust_8::ConfigError::MissingValue(String::from("debug (a ROM)")).into()),
+    };
+    let mut variant = ChipVariant::default();
+    let mut symbols = None;
+    let mut flags = args[1..].iter();
+    while let Some(flag) = flags.next() {
+        let value = flags.next().ok_or_else(|| rust_8::ConfigError::MissingValue(flag.clone()))?;
+        match flag.as_str() {
+            "--variant" => {
+                variant = value.parse().map_err(|reason| rust_8::ConfigError::InvalidValue {
+                    option: flag.clone(),
+                    value: value.clone(),
+                    reason,
+                })?
             }
-            Opcode { d1: 0x6, d2, d3, d4 } => self.cpu.vx[d2 as usize] = ((d3 << 4) | d4) as u8,
-            Opcode { d1: 0x7, d2, d3, d4 } => self.cpu.vx[d2 as usize] = self.cpu.vx[d2 as usize].wrapping_add(((d3 << 4) | d4) as u8),
-            Opcode { d1: 0x8, d2, d3, d4: 0 } => self.cpu.vx[d2 as usize] = self.cpu.vx[d3 as usize],
-            Opcode { d1: 0x8, d2, d3, d4: 0x1 } => self.cpu.vx[d2 as usize] = self.cpu.vx[d2 as usize] | self.cpu.vx[d3 as usize],
-            Opcode { d1: 0x8, d2, d3, d4: 0x2 } => self.cpu.vx[d2 as usize] = self.cpu.vx[d2 as usize] & self.cpu.vx[d3 as usize],
-            Opcode { d1: 0x8, d2, d3, d4: 0x3 } => self.cpu.vx[d2 as usize] = self.cpu.vx[d2 as usize] ^ self.cpu.vx[d3 as usize],
-            Opcode { d1: 0x8, d2, d3, d4: 0x4 } => self.cpu.add_registers(d2, d3),
-            Opcode { d1: 0x8, d2, d3, d4: 0x5 } => self.cpu.substract_registers(d2, d3, d2),
-            Opcode { d1: 0x8, d2, d4: 0x6, .. } => self.cpu.half_register(d2),
-            Opcode { d1: 0x8, d2, d3, d4: 0x7 } => self.cpu.substract_registers(d3, d2, d2),
-            Opcode { d1: 0x8, d2, d4: 0xE, .. } => self.cpu.double_register(d2),
-            Opcode { d1: 0x9, d4: 0, .. } => {
-                if self.cpu.vx[opcode.d2 as usize] != self.cpu.vx[opcode.d3 as usize] {
-                    self.cpu.pc += 2
+            "--symbols" => symbols = Some(read_symbols(value)?),
+            _ => return Err(rust_8::ConfigError::UnknownOption(flag.clone()).into()),
+        }
+    }
+    let mut chip8 = Chip8Builder::new().with_variant(variant).with_rng_seed(0).build();
+    chip8.load_rom(read_rom(rom)?)?;
+    chip8.set_symbols(symbols.clone());
+    let symbols = symbols.unwrap_or_default();
+    let mut debugger = rust_8::Debugger::new(chip8, variant.instructions_per_frame())?;
+
+    print_debug_position(&debugger, &symbols);
+    for line in io::stdin().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let command = match line.parse::<rust_8::DebugCommand>() {
+            Ok(command) => command,
+            Err(reason) => {
+                println!("{}", reason);
+                continue;
+            }
+        };
+        let anomalies = debugger.call_stack().anomalies().len();
+        match command {
+            rust_8::DebugCommand::Step(n) => {
+                for _ in 0..n {
+                    if let InstructionResult::Halted { .. } = debugger.step()? {
+                        println!("halted");
+                        break;
+                    }
                 }
             }
-            Opcode { d1: 0xA, d2, d3, d4 } => self.cpu.i = (d2 << 8) | (d3 << 4) | (d4),
-            Opcode { d1: 0xB, d2, d3, d4 } => self.cpu.pc = (d2 << 8) | (d3 << 4) | (d4) + self.cpu.vx[0] as u16,
-            Opcode { d1: 0xC, d2, d3, d4} => self.random_number(d2, (d3 << 4) | d4),
-            Opcode { d1: 0xD, d2, d3, d4 } => self.draw_sprite(self.cpu.i, d2 as u8, d3 as u8, d4),
-            Opcode { d1: 0xE, d2, d3: 0x9, d4: 0xE} => {
-                if window.is_key_down(*self.keyboard.get(&d2).unwrap()) {
-                    self.cpu.pc += 2;
+            rust_8::DebugCommand::ReverseStep(n) => debugger.reverse_step(n)?,
+            rust_8::DebugCommand::Continue => match debugger.run_to_break(CONTINUE_LIMIT)? {
+                Some(rust_8::Break::Address(pc)) => println!("breakpoint at {:#05X}", pc),
+                Some(rust_8::Break::Event { event, pc, instruction }) => {
+                    println!("break on {} at {}", event, symbols.annotate(pc, instruction))
+                }
+                Some(rust_8::Break::Watch(hit)) => println!("stopped by the watch on {}", hit.expr),
+                None => {}
+            },
+            rust_8::DebugCommand::ReverseContinue => {
+                if debugger.reverse_continue()? {
+                    println!("breakpoint at {:#05X}", debugger.chip8().pc());
+                } else {
+                    println!("back at the start");
                 }
             }
-            Opcode { d1: 0xE, d2, d3: 0xA, d4: 0x1} => {
-                if !window.is_key_down(*self.keyboard.get(&d2).unwrap()) {
-                    self.cpu.pc += 2;
+            rust_8::DebugCommand::Break(address) => debugger.add_breakpoint(address),
+            rust_8::DebugCommand::Delete(address) => {
+                if !debugger.remove_breakpoint(address) {
+                    println!("no breakpoint at {:#05X}", address);
                 }
             }
-            Opcode { d1: 0xF, d2, d3: 0, d4: 0x7 } => self.cpu.vx[d2 as usize] = self.hour.delay,
-            Opcode { d1: 0xF, d2, d3: 0, d4: 0xA } => self.wait_for_key(window, d2),
-            Opcode { d1: 0xF, d2, d3: 0x1, d4: 0x5 } => self.hour.delay = self.cpu.vx[d2 as usize],
-            Opcode { d1: 0xF, d2, d3: 0x1, d4: 0xE } => self.cpu.i += self.cpu.vx[d2 as usize] as u16,
-            Opcode { d1: 0xF, d2, d3: 0x2, d4: 0x9 } => self.cpu.i = d2 * 5,
-            Opcode { d1: 0xF, d2, d3: 0x3, d4: 0x3 } => {
-                self.ram[self.cpu.i as usize] = self.cpu.vx[d2 as usize] / 100;
-                self.ram[(self.cpu.i + 1) as usize] = self.cpu.vx[d2 as usize] % 100 / 10;
-                self.ram[(self.cpu.i + 1) as usize] = self.cpu.vx[d2 as usize] % 10;
+            rust_8::DebugCommand::BreakOn { event, once } => debugger.break_on(event, !once),
+            rust_8::DebugCommand::DeleteOn(event) => {
+                if !debugger.clear_break_on(event) {
+                    println!("no break on {}", event);
+                }
             }
-            Opcode { d1: 0xF, d2, d3: 0x5, d4: 0x5 } => {
-                for i in 0..=d2 {
-                    self.ram[(i + self.cpu.i) as usize] = self.cpu.vx[i as usize];
+            rust_8::DebugCommand::Watch { expr, stop } => debugger.watch(expr, stop),
+            rust_8::DebugCommand::Unwatch(expr) => {
+                if !debugger.unwatch(expr) {
+                    println!("not watching {}", expr);
                 }
             }
-            Opcode { d1: 0xF, d2, d3: 0x6, d4: 0x5 } => {
-                for i in 0..=d2 {
-                    self.cpu.vx[i as usize] = self.ram[(i + self.cpu.i) as usize];
+            rust_8::DebugCommand::Backtrace => {
+                let frames = debugger.call_stack().backtrace(Some(&symbols));
+                if frames.is_empty() {
+                    println!("not in a subroutine");
+                }
+                for frame in frames {
+                    println!("{}", frame);
                 }
+                continue;
             }
-            _ => {}
+            rust_8::DebugCommand::Screen { ruler, half_blocks } => {
+                let dump = AsciiDump { ruler, half_blocks, ..AsciiDump::default() };
+                print!("{}", dump.render(debugger.chip8().display()));
+                continue;
+            }
+            rust_8::DebugCommand::Quit => break,
+        }
+        for hit in debugger.take_watch_hits() {
+            println!("{}", hit);
+        }
+        // going back forgets anomalies, so only ones found going forward are new
+        for anomaly in debugger.call_stack().anomalies().iter().skip(anomalies) {
+            println!("warning: {}", anomaly);
+        }
+        print_debug_position(&debugger, &symbols);
+    }
+    Ok(())
+}
+
+fn print_debug_position(debugger: &rust_8::Debugger, symbols: &SymbolTable) {
+    println!("{}: {}", debugger.position(), registers(debugger.chip8()));
+    if let Some((pc, instruction)) = debugger.next_instruction() {
+        println!("{}", symbols.annotate(pc, instruction));
+    }
+}
+
+// the registers the way Cpu's Debug shows them
+fn registers(chip8: &Chip8) -> String {
+    let cpu = chip8.cpu_snapshot();
+    let vx: Vec<String> = cpu.vx.iter().enumerate().map(|(x, v)| format!("V{:X}={:02X}", x, v)).collect();
+    format!("{} PC={:04X} I={:04X}", vx.join(" "), cpu.pc, cpu.i)
+}
+
+// rust-8 detect-quirks <suite>/bin [--variant name]: runs the suite's quirks test on the
+// variant's default quirks and prints which of them the emulator has
+fn detect_quirks(args: &[String]) -> Result<(), EmulatorError> {
+    let (path, variant) = match args {
+        [path] => (path, ChipVariant::default()),
+        [path, flag, name] if flag == "--variant" => {
+            let variant = name.parse().map_err(|reason| rust_8::ConfigError::InvalidValue {
+                option: flag.clone(),
+                value: name.clone(),
+                reason,
+            })?;
+            (path, variant)
         }
+        _ => return Err(rust_8::ConfigError::MissingValue(String::from("detect-quirks (the test suite's bin directory)")).into()),
+    };
+    // the directory, or the ROM itself
+    let mut rom = PathBuf::from(path);
+    if rom.is_dir() {
+        rom.push(rust_8::QUIRKS_ROM);
     }
+    let data = fs::read(&rom).map_err(|err| io::Error::new(err.kind(), format!("{}: {}", rom.display(), err)))?;
 
-    fn clear_display(&mut self) {
-        for i in self.display.iter_mut() {
-            *i = 0xFFFFFF; // write something more funny here!
+    let readings = rust_8::detect_quirks(&data, variant, variant.default_quirks())?;
+    if readings.iter().all(|reading| reading.passed.is_none()) {
+        return Err(io::Error::other(format!("no results on the screen, is {} the quirks test?", rom.display())).into());
+    }
+    println!("{} on {}:", rom.display(), variant);
+    for reading in readings.iter() {
+        let on_off = |on: bool| if on { "on" } else { "off" };
+        match reading.exhibited() {
+            Some(on) if on == reading.expected => println!("  {:<13} {}", reading.name, on_off(on)),
+            Some(on) => println!("  {:<13} {} (the platform has it {})", reading.name, on_off(on), on_off(reading.expected)),
+            None => println!("  {:<13} unreadable", reading.name),
         }
     }
+    Ok(())
+}
+
+fn list_builtin_roms() {
+    for rom in BUILTIN_ROMS.iter() {
+        println!("{:<12} {} ({}): {}", rom.name, rom.title, rom.author, rom.description);
+    }
+}
 
-    fn call_subroutine(&mut self, address: u16) {
-        self.stack.add(self.cpu.pc);
-        self.cpu.pc = address;
+// rust-8 --test-opcode 8014 --initial-state '{"V0": 1, "V1": 2}' prints {"V0":3,"VF":0}
+fn test_opcode(opcode: u16, options: &cli::Options) -> Result<(), EmulatorError> {
+    let mut chip8 = Chip8Builder::new().with_variant(options.variant).build();
+    if let Some(state) = &options.initial_state {
+        chip8.apply_register_json(state)?;
     }
+    println!("{}", chip8.probe_opcode(opcode)?);
+    Ok(())
+}
 
-    fn random_number(&mut self, vx: u16, kk: u16) {
-        let mut rng = rand::thread_rng();
-        let number = rng.gen_range(0..=255);
-        self.cpu.vx[vx as usize] = number & kk as u8;
+#[cfg(not(target_arch = "wasm32"))]
+fn start(mut options: cli::Options) -> Result<(), EmulatorError> {
+    if options.generate_grammar {
+        return generate_grammar();
+    }
+    if options.sprite_test {
+        return sprite_test(&options);
+    }
+    if options.builtin.as_deref() == Some("list") {
+        list_builtin_roms();
+        return Ok(());
+    }
+    if let Some(opcode) = options.test_opcode {
+        return test_opcode(opcode, &options);
     }
 
-    fn draw_sprite(&mut self, i: u16, x: u8, y: u8, n: u16) {
-        let mut sprites = Vec::<u8>::new();
-        let xcord = self.cpu.vx[x as usize];
-        let ycord = self.cpu.vx[y as usize];
-        for i in i..i + n {
-            sprites.push(self.ram[i as usize]);
+    let library = match &options.rom_dir {
+        Some(path) => read_rom_library(Path::new(path)).map_err(|err| io::Error::new(err.kind(), format!("{}: {}", path, err)))?,
+        None => Vec::new(),
+    };
+    let titles: Vec<String> = library.iter().map(|(title, _)| title.clone()).collect();
+    let builtin = options.builtin.as_deref().and_then(builtin_rom);
+    if let Some(rom) = builtin {
+        options.rom = rom.name.to_string();
+        options.rom_title = Some(rom.title.to_string());
+        if !options.variant_given {
+            options.variant = rom.variant;
         }
-        self.cpu.vx[0xF] = 0;
-        
-        for j in 0..n {
-            let row = sprites[j as usize];
-            for i in 0..8 {
-                let new_value = row >> (7 - i) & 0x01;
-                if new_value == 1 {
-                    let xi = (xcord + i) as usize % WIDTH;
-                    let yi = (ycord + j as u8) as usize % HEIGHT;
-                    self.display[yi * WIDTH + xi] ^= 0xFFFFFF;
-                    if self.display[yi * WIDTH + xi] == 0 {
-                        self.cpu.vx[0xF] = 1;
-                    }
-                }
+    }
+    let data = if !library.is_empty() {
+        Vec::new()
+    } else if let Some(rom) = builtin {
+        rom.data.to_vec()
+    } else {
+        read_rom(&options.rom)?
+    };
+    if let Some(path) = options.rom_database.clone() {
+        apply_rom_database(Path::new(&path), &data, &mut options)?;
+    }
+
+    if options.coverage {
+        return print_coverage(&data, &options);
+    }
+    if let Some((left, right)) = options.compare {
+        return run_compare(&data, left, right, &options);
+    }
+
+    let variant = options.variant;
+    let instructions_per_frame = options.tickrate.unwrap_or_else(|| variant.instructions_per_frame());
+    let theme = options.theme;
+    let phosphor = options.phosphor;
+    let warn_odd_rom = options.warn_odd_rom;
+    let precise_timing = options.precise_timing;
+    let symbols = options.symbols.as_deref().map(read_symbols).transpose()?;
+    let profile_calls = options.profile_calls;
+    let rom = options.rom.clone();
+    // shared so --watch can swap in a rebuilt ROM for the next reset
+    let data = Arc::new(Mutex::new(data));
+    let shared = Arc::clone(&data);
+    let make = move || -> Result<Chip8, Chip8Error> {
+        let data = match shared.lock() {
+            Ok(data) => data.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        };
+        let mut chip8 = Chip8Builder::new()
+            .with_variant(variant)
+            .with_theme(theme)
+            .with_phosphor_decay(phosphor)
+            .with_odd_rom_warning(warn_odd_rom)
+            .with_precise_timing(precise_timing)
+            .build();
+        chip8.set_symbols(symbols.clone());
+        if profile_calls {
+            chip8.enable_profiling();
+        }
+        if library.is_empty() {
+            chip8.load_rom(data)?;
+            chip8.set_rom_name(&rom);
+        } else {
+            for (title, data) in library.iter() {
+                chip8.add_rom_to_library(title, data)?;
             }
+            chip8.switch_to_rom(0)?;
         }
+        Ok(chip8)
+    };
+    // surfaces a bad ROM here instead of from inside the emulation thread
+    let mut chip8 = make()?;
+
+    let recorder = match &options.wav_out {
+        Some(path) => Some(
+            SoundRecorder::create(path, options.waveform, options.frequency, options.volume)
+                .map_err(|err| io::Error::new(err.kind(), format!("{}: {}", path, err)))?,
+        ),
+        None => None,
+    };
+
+    if !cfg!(feature = "serde") {
+        let option = if options.dump_state_on_exit.is_some() {
+            Some("--dump-state-on-exit")
+        } else if options.listen.is_some() {
+            Some("--listen")
+        } else if options.resume {
+            Some("--resume")
+        } else {
+            None
+        };
+        if let Some(option) = option {
+            return Err(rust_8::ConfigError::MissingFeature {
+                option: String::from(option),
+                feature: String::from("serde"),
+            }
+            .into());
+        }
+    }
+    if let Some(cycles) = options.dump_state_json {
+        return dump_state_json(chip8, cycles);
+    }
+    if let Some(addr) = &options.listen {
+        return listen(chip8, instructions_per_frame, addr, options.listen_remote);
+    }
+    let script = read_input_script(&options)?;
+    if options.ref_trace.is_some() || options.emit_ref_trace.is_some() {
+        return run_ref_trace(&mut chip8, instructions_per_frame, &options, script.as_ref());
+    }
+    if options.headless {
+        let dump = options.dump_ascii.then_some(&options.ascii);
+        run_headless(&mut chip8, instructions_per_frame, options.frames, recorder, script.as_ref(), dump)?;
+        if profile_calls {
+            print_call_tree(&chip8);
+        }
+        if let Some(path) = &options.dump_state_on_exit {
+            write_state_dump(&chip8, path)?;
+        }
+        return Ok(());
     }
 
-    fn wait_for_key(&mut self, window: &mut Window, register: u16) {
-        for key in window.get_keys().unwrap().iter().enumerate() {
-            if  window.is_key_down(*key.1){
-                self.cpu.vx[register as usize] = key.0 as u8;
-                return
+    // there is no file behind a built-in ROM or a whole library
+    let watcher = if !options.watch {
+        None
+    } else if builtin.is_some() {
+        log::warn!("not watching the built-in ROM {}: only a ROM file can be watched", options.rom);
+        None
+    } else if let Some(dir) = &options.rom_dir {
+        log::warn!("not watching {}: only a ROM file can be watched, not a --rom-dir library", dir);
+        None
+    } else {
+        rust_8::RomWatcher::new(&options.rom, data)?
+    };
+    let mut audio = open_audio(&options);
+    // the session is saved whenever the window is closed, and only the first boot resumes
+    // it; a reset starts the ROM over
+    let save_dir = session_dir(&options);
+    let resume = AtomicBool::new(options.resume);
+    let restore_dir = save_dir.clone();
+    let boot = move || -> Result<Chip8, Chip8Error> {
+        let mut chip8 = make()?;
+        if let (true, Some(dir)) = (resume.swap(false, Ordering::Relaxed), &restore_dir) {
+            restore_session(dir, &mut chip8);
+        }
+        Ok(chip8)
+    };
+    let dump_path = options.dump_state_on_exit.clone();
+    let on_exit: ExitHook = Box::new(move |chip8: &Chip8| {
+        if profile_calls {
+            print_call_tree(chip8);
+        }
+        if let Some(path) = &dump_path {
+            if let Err(err) = write_state_dump(chip8, path) {
+                log::warn!("could not dump the state: {}", err);
             }
-    
-        self.cpu.pc -= 2;
+        }
+        if let Some(dir) = &save_dir {
+            save_session(dir, chip8);
+        }
+    });
+    let speed = if options.adaptive_speed {
+        let ips = (instructions_per_frame * 60) as u32;
+        Some(SpeedController::new(TARGET_DRAWS_PER_SECOND, ips, options.min_hz, options.max_hz))
+    } else {
+        None
+    };
+    let emulator = EmulatorThread::spawn_with_speed(boot, instructions_per_frame, recorder, Some(on_exit), speed);
+    if let Some(addr) = &options.http {
+        serve_http(addr, &emulator)?;
+    }
+
+    if options.terminal {
+        run_terminal(emulator, &mut *audio, &options, &titles, watcher)
+    } else {
+        let mut display = frontend::MinifbDisplay::new(&window_config(&options))?;
+        let mut input = display.input();
+        run(emulator, &mut display, &mut input, &mut *audio, &options, &titles, watcher)
     }
 }
+
+fn window_config(options: &cli::Options) -> frontend::WindowConfig {
+    frontend::WindowConfig {
+        theme: options.theme,
+        border_pixels: options.border,
+        border_color: options.border_color,
+        visual_bell: options.visual_bell,
+        bell_color: options.bell_color,
+        transform: options.transform,
+        filter: options.filter,
+        scaling: frontend::ScalingMode::from_factor(window_scale(options)),
+        fullscreen: options.fullscreen,
+        screen_size: options.screen_size,
+        magnifier: options.magnifier,
+        virtual_keyboard: options.virtual_keyboard,
+    }
 }
 
-impl Cpu {
-    fn new() -> Self {
-        Cpu {
-            vx: [0; 16],
-            pc: PROGRAM_START,
-            i: 0,
-        }
+// --scale, times the desktop's scale factor with --hidpi
+fn window_scale(options: &cli::Options) -> usize {
+    if !options.hidpi {
+        return options.scale;
     }
+    let dpi = frontend::dpi_scale();
+    let scale = hidpi_scale(options.scale, dpi);
+    log::info!("desktop scale factor {}, window scale {}", dpi, scale);
+    scale
+}
 
-    fn add_registers(&mut self, va: u16, vb: u16) {
-        if self.vx[va as usize] as u16 + self.vx[vb as usize] as u16 > 255 {
-            self.vx[0xF] = 1;
-        }
-        self.vx[va as usize] = self.vx[va as usize].wrapping_add(self.vx[vb as usize]);
+// shows the font sprites without running anything
+fn sprite_test(options: &cli::Options) -> Result<(), EmulatorError> {
+    let mut chip8 = Chip8Builder::new()
+        .with_variant(options.variant)
+        .with_theme(options.theme)
+        .build();
+    chip8.render_sprite_atlas();
+
+    if options.headless {
+        println!("{:?}", chip8);
+        return Ok(());
     }
 
-    fn substract_registers(&mut self, va: u16, vb: u16, store: u16) {
-        if self.vx[va as usize] > self.vx[vb as usize] {
-            self.vx[0xF] = 1;
-        } else {
-            self.vx[0xF] = 0;
+    let mut display = frontend::MinifbDisplay::new(&window_config(options))?;
+    let mut input = display.input();
+    let mut keypad = Keypad::new();
+    while display.is_open() && !input.quit_requested() {
+        input.poll(&mut keypad);
+        let step = input.scale_requested();
+        if step != 0 {
+            display.change_scale(step)?;
+        }
+        if input.fullscreen_requested() {
+            display.toggle_fullscreen()?;
         }
-        self.vx[store as usize] = self.vx[va as usize].wrapping_sub(self.vx[vb as usize]);
+        if input.keyboard_requested() {
+            display.toggle_keyboard()?;
+        }
+        if input.theme_requested() {
+            chip8.set_theme(chip8.theme().next());
+            display.set_theme(chip8.theme());
+        }
+        display.present(chip8.display(), chip8.intensity())?;
     }
+    Ok(())
+}
 
-    fn half_register(&mut self, x: u16) {
-        if self.vx[x as usize] & 1 == 1 {
-            self.vx[0xF] = 1;
-        } else {
-            self.vx[0xF] = 0;
+// the same ROM on two variants in lockstep, printing where their registers first differ
+fn run_compare(data: &[u8], left: ChipVariant, right: ChipVariant, options: &cli::Options) -> Result<(), EmulatorError> {
+    // both draw the same random numbers
+    const SEED: u64 = 0x5EED;
+    let make = |variant| -> Result<Chip8, Chip8Error> {
+        let mut chip8 = Chip8Builder::new().with_variant(variant).with_rng_seed(SEED).build();
+        chip8.load_rom(data.to_vec())?;
+        Ok(chip8)
+    };
+    let mut comparison = Comparison::new(make(left)?, make(right)?);
+    let mut window = if options.headless {
+        None
+    } else {
+        let title = format!("Chip-8 - {} | {}", left, right);
+        Some(frontend::CompareWindow::new(&title, options.scale, options.theme)?)
+    };
+
+    let mut keypad = Keypad::new();
+    let mut frame = 0;
+    let mut reported = false;
+    while !comparison.is_halted() {
+        match window.as_mut() {
+            Some(window) => {
+                if !window.is_open() {
+                    break;
+                }
+                window.poll(&mut keypad);
+                comparison.set_keypad(keypad);
+            }
+            None if options.frames.is_some_and(|frames| frame >= frames) => break,
+            None => {}
         }
+        for _i in 0..left.instructions_per_frame() {
+            comparison.step()?;
+        }
+        comparison.tick_timers();
+        frame += 1;
+
+        if let (Some(divergence), false) = (comparison.divergence(), reported) {
+            println!("{}", divergence);
+            reported = true;
+        }
+        if let Some(window) = window.as_mut() {
+            window.present(comparison.left.display(), comparison.right.display(), comparison.displays_differ())?;
+        }
+    }
+    if !reported {
+        println!("no divergence after {} instructions", comparison.instructions());
+    }
+    Ok(())
+}
 
-        self.vx[x as usize] /= 2;
+// A ROM the database knows gets its proper title, and its platform and speed unless
+// --variant or --tickrate chose otherwise. Unknown ROMs run as they would without it.
+fn apply_rom_database(path: &Path, data: &[u8], options: &mut cli::Options) -> Result<(), EmulatorError> {
+    let text = fs::read_to_string(path).map_err(|err| io::Error::new(err.kind(), format!("{}: {}", path.display(), err)))?;
+    let database = RomDatabase::from_json(&text)?;
+    let info = match database.lookup(data) {
+        Some(info) => info,
+        None => return Ok(()),
+    };
+    options.rom_title = Some(info.title.clone());
+    if let (false, Some(platform)) = (options.variant_given, info.platform()) {
+        options.variant = platform;
+    }
+    if options.tickrate.is_none() {
+        options.tickrate = info.tickrate;
     }
+    Ok(())
+}
 
-    fn double_register(&mut self, x: u16) {
-        if self.vx[x as usize] & 1 == 1 {
-            self.vx[0xF] = 1;
-        } else {
-            self.vx[0xF] = 0;
-        }
+fn print_coverage(data: &[u8], options: &cli::Options) -> Result<(), EmulatorError> {
+    println!("reachable opcodes by top nibble:");
+    print!("{}", coverage_report(data));
+    if !options.headless {
+        return Ok(());
+    }
 
-        self.vx[x as usize] = self.vx[x as usize].wrapping_mul(2);
+    let inspector = DynamicOpcodeCoverage::new();
+    let counts = inspector.counts();
+    let mut chip8 = Chip8Builder::new()
+        .with_variant(options.variant)
+        .with_inspector(Box::new(inspector))
+        .build();
+    chip8.load_rom(data.to_vec())?;
+    let instructions_per_frame = options.tickrate.unwrap_or_else(|| options.variant.instructions_per_frame());
+    run_headless(&mut chip8, instructions_per_frame, options.frames, None, None, None)?;
+    println!("executed opcodes:");
+    for (pattern, count) in counts.borrow().iter() {
+        println!("{} x{}", pattern, count);
     }
+    Ok(())
 }
 
-impl Stack {
-    fn new() -> Self {
-        Stack {
-            mem: [0; 16],
-            size: 0,
+// runs as fast as possible until the ROM halts (or the frame limit is hit) and prints the final
+// state, or the screen as text with `dump`
+fn run_headless(
+    chip8: &mut Chip8,
+    instructions_per_frame: usize,
+    frames: Option<u64>,
+    mut recorder: Option<SoundRecorder>,
+    script: Option<&InputScript>,
+    dump: Option<&AsciiDump>,
+) -> Result<(), EmulatorError> {
+    let mut frame = 0;
+    while frames.is_none_or(|frames| frame < frames) && !chip8.is_halted() {
+        if let Some(script) = script {
+            script.apply(frame, chip8.keypad_mut());
+        }
+        for _i in 0..instructions_per_frame {
+            if let InstructionResult::Halted { .. } = step_or_report(chip8)? {
+                break;
+            }
         }
+        let sound = chip8.sound_active();
+        chip8.tick_timers();
+        if let Some(recorder) = recorder.as_mut() {
+            recorder.frame(sound)?;
+        }
+        frame += 1;
     }
-
-    fn add(&mut self, address: u16) {
-        self.mem[self.size as usize] = address;
-        self.size += 1;
+    if let Some(recorder) = recorder {
+        recorder.finish()?;
+    }
+    match dump {
+        Some(dump) => print!("{}", dump.render(chip8.display())),
+        None => println!("{:?}", chip8),
     }
+    Ok(())
+}
 
-    fn pop(&mut self) -> u16 {
-        self.size -= 1;
-        self.mem[(self.size) as usize]
+fn print_call_tree(chip8: &Chip8) {
+    for line in chip8.profiling_report().lines(chip8.symbols()) {
+        println!("{}", line);
     }
 }
 
-struct Timer {
-    sound: u8,
-    delay: u8,
-    hour: time::SystemTime
+fn read_symbols(path: &str) -> Result<SymbolTable, EmulatorError> {
+    let text = fs::read_to_string(path).map_err(|err| io::Error::new(err.kind(), format!("{}: {}", path, err)))?;
+    Ok(SymbolTable::parse(&text)?)
+}
+
+fn read_input_script(options: &cli::Options) -> Result<Option<InputScript>, EmulatorError> {
+    let path = match &options.input_script {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+    let text = fs::read_to_string(path).map_err(|err| io::Error::new(err.kind(), format!("{}: {}", path, err)))?;
+    Ok(Some(InputScript::parse(&text)?))
 }
 
-impl Timer {
-    fn new() -> Self {
-        Timer {
-            sound: 0,
-            delay: 0,
-            hour: time::SystemTime::now(),
+// Runs headlessly like run_headless, checking every instruction against --ref-trace and
+// writing it to --emit-ref-trace. Stops at the first divergence, or when the trace or the
+// ROM runs out; a ROM that halts before the trace ends has diverged too.
+fn run_ref_trace(
+    chip8: &mut Chip8,
+    instructions_per_frame: usize,
+    options: &cli::Options,
+    script: Option<&InputScript>,
+) -> Result<(), EmulatorError> {
+    let open = |path: &str| fs::File::open(path).map_err(|err| io::Error::new(err.kind(), format!("{}: {}", path, err)));
+    let mut checker = match &options.ref_trace {
+        Some(path) => Some(RefTraceChecker::new(io::BufReader::new(open(path)?))),
+        None => None,
+    };
+    let mut writer = match &options.emit_ref_trace {
+        Some(path) => {
+            let file = fs::File::create(path).map_err(|err| io::Error::new(err.kind(), format!("{}: {}", path, err)))?;
+            Some(RefTraceWriter::new(io::BufWriter::new(file), TraceFormat::for_path(path))?)
         }
-    }
+        None => None,
+    };
 
-    fn delay_countdown(&mut self) {
-        let elapsed = self.hour.elapsed().unwrap();
-        if self.delay > 0 && elapsed.as_secs_f32() >= 1.0 / 60.0 {
-            self.delay -= 1;
-            self.hour = time::SystemTime::now();
+    let mut frame = 0;
+    let mut trace_ended = false;
+    'run: while options.frames.is_none_or(|frames| frame < frames) && !chip8.is_halted() {
+        if let Some(script) = script {
+            script.apply(frame, chip8.keypad_mut());
         }
+        for _i in 0..instructions_per_frame {
+            match step_or_report(chip8)? {
+                InstructionResult::Halted { .. } => break,
+                InstructionResult::WaitingForKey => continue,
+                InstructionResult::Executed { .. } => {}
+            }
+            if let Some(writer) = writer.as_mut() {
+                writer.record(chip8)?;
+            }
+            if let Some(checker) = checker.as_mut() {
+                if !checker.check(chip8)? {
+                    trace_ended = true;
+                    break 'run;
+                }
+            }
+        }
+        chip8.tick_timers();
+        frame += 1;
+    }
+    if let Some(writer) = writer {
+        writer.finish()?;
+    }
+    if let Some(checker) = checker {
+        let instructions = if trace_ended || !chip8.is_halted() {
+            checker.instructions()
+        } else {
+            checker.finish()?
+        };
+        println!("matched the reference trace for {} instructions", instructions);
+    }
+    Ok(())
+}
 
-        if self.sound > 0 && elapsed.as_secs_f32() >= 1.0 / 60.0 {
-            self.sound -= 1;
-            self.hour = time::SystemTime::now(); 
+// Runs `cycles` instructions, ticking the timers once per frame's worth of them, and
+// prints the machine state for other tools. Stops early if the ROM halts.
+fn dump_state_json(mut chip8: Chip8, cycles: u64) -> Result<(), EmulatorError> {
+    let per_frame = chip8.variant().instructions_per_frame() as u64;
+    for cycle in 1..=cycles {
+        if let InstructionResult::Halted { .. } = step_or_report(&mut chip8)? {
+            break;
+        }
+        if cycle % per_frame == 0 {
+            chip8.tick_timers();
         }
     }
+    println!("{}", chip8.to_json());
+    Ok(())
 }
 
-fn main() {
-    let mut rom = File::open("roms/test_opcode.ch8").expect("there is no test rom");
-    let mut data = Vec::<u8>::new();
-    rom.read_to_end(&mut data).unwrap();
+#[cfg(feature = "serde")]
+fn session_dir(options: &cli::Options) -> Option<PathBuf> {
+    options.save_dir.as_ref().map(PathBuf::from).or_else(rust_8::default_save_dir)
+}
 
-    let chip8 = &mut Chip8::new();
-    chip8.load_sprites();
-    chip8.load_rom(data);
+#[cfg(not(feature = "serde"))]
+fn session_dir(_options: &cli::Options) -> Option<PathBuf> {
+    None
+}
 
-    let options = WindowOptions {
-        scale: Scale::X16,
-        ..WindowOptions::default()
-    };
+#[cfg(feature = "serde")]
+fn restore_session(dir: &Path, chip8: &mut Chip8) {
+    rust_8::restore_autosave(dir, chip8);
+}
 
-    let window: &mut Window = &mut Window::new(
-        "Chip-8",
-        WIDTH,
-        HEIGHT,
-        options
-    ).unwrap();
+#[cfg(not(feature = "serde"))]
+fn restore_session(_dir: &Path, _chip8: &mut Chip8) {}
 
-    let instructions_per_frame = 6;
+// a ROM-less machine (an empty library) has nothing worth saving
+#[cfg(feature = "serde")]
+fn save_session(dir: &Path, chip8: &Chip8) {
+    if chip8.rom_hash() == 0 {
+        return;
+    }
+    if let Err(err) = rust_8::save_autosave(dir, chip8) {
+        log::warn!("could not save the session to {}: {}", dir.display(), err);
+    }
+}
 
-    window.limit_update_rate(Some(std::time::Duration::from_micros(16600)));
+#[cfg(not(feature = "serde"))]
+fn save_session(_dir: &Path, _chip8: &Chip8) {}
 
-    while window.is_open() && !window.is_key_down(Key::Escape) {
-        for _i in 0..instructions_per_frame {
-            chip8.run_instruction(window);
-        }
-        chip8.hour.delay_countdown();
-        // We unwrap here as we want this code to exit if it fails. Real applications may want to handle this in a different way
-        window
-            .update_with_buffer(&chip8.display, WIDTH, HEIGHT)
-            .unwrap();
+#[cfg(all(feature = "http-server", not(target_arch = "wasm32")))]
+fn serve_http(addr: &str, emulator: &EmulatorThread) -> Result<(), EmulatorError> {
+    let server = rust_8::HttpServer::spawn(addr, emulator.remote())
+        .map_err(|err| io::Error::new(err.kind(), format!("--http {}: {}", addr, err)))?;
+    println!("serving http://{}/frame.png", server.local_addr());
+    Ok(())
+}
+
+#[cfg(all(not(feature = "http-server"), not(target_arch = "wasm32")))]
+fn serve_http(_addr: &str, _emulator: &EmulatorThread) -> Result<(), EmulatorError> {
+    Err(rust_8::ConfigError::MissingFeature {
+        option: String::from("--http"),
+        feature: String::from("http-server"),
+    }
+    .into())
+}
+
+// serves the remote protocol until killed
+#[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+fn listen(chip8: Chip8, instructions_per_frame: usize, addr: &str, allow_remote: bool) -> Result<(), EmulatorError> {
+    let server = rust_8::RemoteServer::bind(addr, chip8, instructions_per_frame, allow_remote)
+        .map_err(|err| io::Error::new(err.kind(), format!("--listen {}: {}", addr, err)))?;
+    println!("listening on {}, paused until a client resumes", server.local_addr()?);
+    Ok(server.serve()?)
+}
+
+#[cfg(not(all(feature = "serde", not(target_arch = "wasm32"))))]
+fn listen(_chip8: Chip8, _instructions_per_frame: usize, _addr: &str, _allow_remote: bool) -> Result<(), EmulatorError> {
+    Err(rust_8::ConfigError::MissingFeature {
+        option: String::from("--listen"),
+        feature: String::from("serde"),
+    }
+    .into())
+}
+
+#[cfg(feature = "serde")]
+fn write_state_dump(chip8: &Chip8, path: &str) -> Result<(), EmulatorError> {
+    let dump = rust_8::StateDump::from(&chip8.save_state());
+    fs::write(path, dump.to_pretty_json() + "\n").map_err(|err| io::Error::new(err.kind(), format!("{}: {}", path, err)))?;
+    Ok(())
+}
+
+#[cfg(not(feature = "serde"))]
+fn write_state_dump(_chip8: &Chip8, _path: &str) -> Result<(), EmulatorError> {
+    Err(rust_8::ConfigError::MissingFeature {
+        option: String::from("--dump-state-on-exit"),
+        feature: String::from("serde"),
+    }
+    .into())
+}
+
+#[cfg(feature = "chip8-grammar")]
+fn generate_grammar() -> Result<(), EmulatorError> {
+    println!("{}", rust_8::CHIP8_TMGRAMMAR);
+    Ok(())
+}
+
+#[cfg(not(feature = "chip8-grammar"))]
+fn generate_grammar() -> Result<(), EmulatorError> {
+    Err(rust_8::ConfigError::MissingFeature {
+        option: String::from("--generate-grammar"),
+        feature: String::from("chip8-grammar"),
+    }
+    .into())
+}
+
+#[cfg(feature = "audio")]
+fn open_audio(options: &cli::Options) -> Box<dyn AudioSink> {
+    Box::new(frontend::CpalSink::new(options.waveform, options.frequency, options.volume))
+}
+
+#[cfg(not(feature = "audio"))]
+fn open_audio(_options: &cli::Options) -> Box<dyn AudioSink> {
+    Box::new(rust_8::NullSink::default())
+}
+
+#[cfg(all(feature = "terminal", not(target_arch = "wasm32")))]
+fn run_terminal(
+    emulator: EmulatorThread,
+    audio: &mut dyn AudioSink,
+    options: &cli::Options,
+    library: &[String],
+    watcher: Option<rust_8::RomWatcher>,
+) -> Result<(), EmulatorError> {
+    let mut display = frontend::TerminalDisplay::new(options.theme, options.visual_bell);
+    let mut input = frontend::TerminalInput::new()?;
+    run(emulator, &mut display, &mut input, audio, options, library, watcher)
+}
+
+#[cfg(all(not(feature = "terminal"), not(target_arch = "wasm32")))]
+fn run_terminal(
+    _emulator: EmulatorThread,
+    _audio: &mut dyn AudioSink,
+    _options: &cli::Options,
+    _library: &[String],
+    _watcher: Option<rust_8::RomWatcher>,
+) -> Result<(), EmulatorError> {
+    Err(rust_8::ConfigError::MissingFeature {
+        option: String::from("--terminal"),
+        feature: String::from("terminal"),
     }
+    .into())
 }
@@ -0,0 +1,30 @@
+// The two reasons emulation can be stopped: the pause key, and the window losing focus
+// (when that option is on). Either one keeps it stopped, so getting focus back never
+// undoes a pause the player asked for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PauseState {
+    manual: bool,
+    unfocused: bool,
+}
+
+impl PauseState {
+    pub fn toggle_manual(&mut self) {
+        self.manual = !self.manual;
+    }
+
+    pub fn set_focused(&mut self, focused: bool) {
+        self.unfocused = !focused;
+    }
+
+    pub fn is_paused(self) -> bool {
+        self.manual || self.unfocused
+    }
+
+    pub fn is_manual(self) -> bool {
+        self.manual
+    }
+
+    pub fn is_unfocused(self) -> bool {
+        self.unfocused
+    }
+}
@@ -0,0 +1,103 @@
+use serde_json::{Map, Value};
+
+use crate::chip8::Chip8;
+use crate::error::Chip8Error;
+use crate::instruction::Instruction;
+
+impl Chip8 {
+    // Sets registers from a JSON object like {"V0": 1, "V1": 2, "I": 768}. The keys are
+    // V0 to VF, I, PC, DT and ST; whatever is left out keeps its value.
+    pub fn apply_register_json(&mut self, text: &str) -> Result<(), Chip8Error> {
+        let value: Value = serde_json::from_str(text).map_err(|err| Chip8Error::InvalidState(err.to_string()))?;
+        let fields = value
+            .as_object()
+            .ok_or_else(|| Chip8Error::InvalidState(String::from("expected an object")))?;
+        let mut timers = self.timer_snapshot();
+        for (key, value) in fields {
+            let number = |max: u64| {
+                value
+                    .as_u64()
+                    .filter(|&n| n <= max)
+                    .ok_or_else(|| Chip8Error::InvalidState(format!("{} should be a number from 0 to {}", key, max)))
+            };
+            match key.to_ascii_uppercase().as_str() {
+                "PC" => self.set_pc(number(0xFFFF)? as u16)?,
+                "I" => self.set_i_register(number(0xFFFF)? as u16)?,
+                "DT" => timers.delay = number(0xFF)? as u8,
+                "ST" => timers.sound = number(0xFF)? as u8,
+                register => match register.strip_prefix('V').and_then(|x| u8::from_str_radix(x, 16).ok()) {
+                    Some(x) if register.len() == 2 => self.set_register(x, number(0xFF)? as u8)?,
+                    _ => return Err(Chip8Error::InvalidState(format!("unknown register {:?}", key))),
+                },
+            }
+        }
+        self.restore_timers(timers);
+        Ok(())
+    }
+
+    // Puts `opcode` at PC, runs it once and returns what it changed as a JSON object:
+    //
+    //     {"V0": 3, "VF": 0}
+    //
+    // with the keys apply_register_json takes plus SP, RAM (hex address to byte) and
+    // display (true when the screen changed). PC only shows up when the opcode went
+    // somewhere other than the next instruction, and VF whenever the opcode sets the
+    // flag, even to the value it already had.
+    pub fn probe_opcode(&mut self, opcode: u16) -> Result<String, Chip8Error> {
+        let pc = self.cpu.pc as usize;
+        self.ram[pc] = (opcode >> 8) as u8;
+        self.ram[pc + 1] = opcode as u8;
+
+        let before = self.snapshot();
+        let timers = self.timer_snapshot();
+        let ram = self.ram;
+        let display = self.display.clone();
+        let sets_flag = matches!(
+            Instruction::decode((opcode >> 8) as u8, opcode as u8),
+            Instruction::AddReg(..)
+                | Instruction::Sub(..)
+                | Instruction::Shr(..)
+                | Instruction::Subn(..)
+                | Instruction::Shl(..)
+                | Instruction::Drw(..)
+        );
+        self.step()?;
+
+        let mut changes = Map::new();
+        for (x, (old, new)) in before.vx.iter().zip(self.cpu.vx.iter()).enumerate() {
+            if old != new || (x == 0xF && sets_flag) {
+                changes.insert(format!("V{:X}", x), Value::from(*new));
+            }
+        }
+        if self.cpu.i != before.i {
+            changes.insert(String::from("I"), Value::from(self.cpu.i));
+        }
+        if self.cpu.pc != before.pc.wrapping_add(2) {
+            changes.insert(String::from("PC"), Value::from(self.cpu.pc));
+        }
+        if self.stack.size != before.sp {
+            changes.insert(String::from("SP"), Value::from(self.stack.size));
+        }
+        let after = self.timer_snapshot();
+        if after.delay != timers.delay {
+            changes.insert(String::from("DT"), Value::from(after.delay));
+        }
+        if after.sound != timers.sound {
+            changes.insert(String::from("ST"), Value::from(after.sound));
+        }
+        let written: Map<String, Value> = ram
+            .iter()
+            .zip(self.ram.iter())
+            .enumerate()
+            .filter(|(_, (old, new))| old != new)
+            .map(|(address, (_, new))| (format!("{:03X}", address), Value::from(*new)))
+            .collect();
+        if !written.is_empty() {
+            changes.insert(String::from("RAM"), Value::Object(written));
+        }
+        if self.display != display {
+            changes.insert(String::from("display"), Value::Bool(true));
+        }
+        Ok(Value::Object(changes).to_string())
+    }
+}
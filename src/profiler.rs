@@ -0,0 +1,164 @@
+use std::fmt;
+
+use crate::instruction::Instruction;
+use crate::symbols::SymbolTable;
+
+// Times subroutines by the instructions run between their CALL and RET, one cycle each.
+// Every call path gets a node of its own, so a routine called from two places shows up
+// twice, under each caller. The CALL counts to the caller and the RET to the routine.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profiler {
+    // nodes[0] is the top level, outside any subroutine
+    nodes: Vec<Node>,
+    // (node, cycle it was entered at) for each call not yet returned from
+    open: Vec<(usize, u64)>,
+    cycles: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Node {
+    address: u16,
+    children: Vec<usize>,
+    calls: u64,
+    cycles: u64,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Profiler {
+            nodes: vec![Node { address: 0, children: Vec::new(), calls: 0, cycles: 0 }],
+            open: Vec::new(),
+            cycles: 0,
+        }
+    }
+
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    // called by Chip8 after each instruction it runs
+    pub fn record(&mut self, instruction: Instruction) {
+        self.cycles += 1;
+        match instruction {
+            Instruction::Call(address) => {
+                let parent = self.open.last().map_or(0, |&(node, _)| node);
+                let node = match self.nodes[parent].children.iter().find(|&&child| self.nodes[child].address == address) {
+                    Some(&child) => child,
+                    None => {
+                        self.nodes.push(Node { address, children: Vec::new(), calls: 0, cycles: 0 });
+                        let child = self.nodes.len() - 1;
+                        self.nodes[parent].children.push(child);
+                        child
+                    }
+                };
+                self.nodes[node].calls += 1;
+                self.open.push((node, self.cycles));
+            }
+            // a RET with no CALL to match comes from a ROM that set up the stack by hand
+            Instruction::Ret => {
+                if let Some((node, entered)) = self.open.pop() {
+                    self.nodes[node].cycles += self.cycles - entered;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // the tree so far, routines that haven't returned yet counted up to now
+    pub fn report(&self) -> CallTreeReport {
+        let mut cycles: Vec<u64> = self.nodes.iter().map(|node| node.cycles).collect();
+        for &(node, entered) in &self.open {
+            cycles[node] += self.cycles - entered;
+        }
+        let mut roots: Vec<CallTree> = self.nodes[0].children.iter().map(|&child| self.tree(child, &cycles)).collect();
+        sort(&mut roots);
+        CallTreeReport { cycles: self.cycles, roots }
+    }
+
+    fn tree(&self, node: usize, cycles: &[u64]) -> CallTree {
+        let mut children: Vec<CallTree> = self.nodes[node].children.iter().map(|&child| self.tree(child, cycles)).collect();
+        sort(&mut children);
+        CallTree {
+            address: self.nodes[node].address,
+            calls: self.nodes[node].calls,
+            cycles: cycles[node],
+            children,
+        }
+    }
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Profiler::new()
+    }
+}
+
+// hottest first, then by address
+fn sort(trees: &mut [CallTree]) {
+    trees.sort_by(|a, b| b.cycles.cmp(&a.cycles).then(a.address.cmp(&b.address)));
+}
+
+// A subroutine called from one place in the tree: how often, the cycles spent in it and
+// the routines it calls in turn, which are part of `cycles`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallTree {
+    pub address: u16,
+    pub calls: u64,
+    pub cycles: u64,
+    pub children: Vec<CallTree>,
+}
+
+impl CallTree {
+    // the cycles spent in the routine itself rather than in the ones it calls
+    pub fn self_cycles(&self) -> u64 {
+        self.cycles - self.children.iter().map(|child| child.cycles).sum::<u64>()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CallTreeReport {
+    // every cycle run while profiling, in a subroutine or not
+    pub cycles: u64,
+    pub roots: Vec<CallTree>,
+}
+
+impl CallTreeReport {
+    // cycles spent outside any subroutine
+    pub fn top_level_cycles(&self) -> u64 {
+        self.cycles - self.roots.iter().map(|root| root.cycles).sum::<u64>()
+    }
+
+    // A heading, then a line per node indented two spaces a level, like
+    // "  0x20A inner: 3 calls, 120 cycles (40 self)", with names from `symbols`.
+    pub fn lines(&self, symbols: Option<&SymbolTable>) -> Vec<String> {
+        let mut lines = vec![format!("{} cycles, {} outside any subroutine", self.cycles, self.top_level_cycles())];
+        let mut pending: Vec<(usize, &CallTree)> = self.roots.iter().rev().map(|root| (0, root)).collect();
+        while let Some((depth, tree)) = pending.pop() {
+            let name = match symbols.and_then(|symbols| symbols.label(tree.address)) {
+                Some(label) => format!(" {}", label),
+                None => String::new(),
+            };
+            lines.push(format!(
+                "{}{:#05X}{}: {} call{}, {} cycles ({} self)",
+                "  ".repeat(depth),
+                tree.address,
+                name,
+                tree.calls,
+                if tree.calls == 1 { "" } else { "s" },
+                tree.cycles,
+                tree.self_cycles()
+            ));
+            pending.extend(tree.children.iter().rev().map(|child| (depth + 1, child)));
+        }
+        lines
+    }
+}
+
+impl fmt::Display for CallTreeReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for line in self.lines(None) {
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
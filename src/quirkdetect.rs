@@ -0,0 +1,142 @@
+use crate::builder::Chip8Builder;
+use crate::chip8::InstructionResult;
+use crate::display::Framebuffer;
+use crate::error::Chip8Error;
+use crate::quirks::QuirkConfig;
+use crate::variant::ChipVariant;
+
+// The quirks test of Timendus' chip8-test-suite, which rust-8 detect-quirks looks for in a
+// copy of the suite's bin/ directory. It isn't shipped here, like the other suite ROMs.
+pub const QUIRKS_ROM: &str = "5-quirks.ch8";
+
+// the ROM skips its menu and tests this platform when the byte is 1 (CHIP-8),
+// 2 (SUPER-CHIP) or 3 (XO-CHIP)
+const PLATFORM_ADDRESS: usize = 0x1FF;
+// enough for the display wait test, which counts frames, to finish
+const FRAMES: usize = 300;
+
+// A mark the quirks ROM draws, a byte per row with the leftmost pixel in the top bit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Glyph {
+    pub name: &'static str,
+    pub width: usize,
+    pub rows: &'static [u8],
+}
+
+// the quirk behaves the way the tested platform expects
+pub const CHECK_GLYPH: Glyph = Glyph {
+    name: "check",
+    width: 5,
+    rows: &[0b0000_1000, 0b0001_0000, 0b1010_0000, 0b0100_0000],
+};
+
+// it doesn't
+pub const CROSS_GLYPH: Glyph = Glyph {
+    name: "cross",
+    width: 5,
+    rows: &[0b1000_1000, 0b0101_0000, 0b0010_0000, 0b0101_0000, 0b1000_1000],
+};
+
+// the quirks in the order the ROM lists them, and the top row of each one's line
+pub const QUIRK_LINES: [(&str, usize); 6] = [
+    ("vF reset", 1),
+    ("memory", 6),
+    ("display wait", 11),
+    ("clipping", 16),
+    ("shifting", 21),
+    ("jumping", 26),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuirkReading {
+    pub name: &'static str,
+    // whether the tested platform has the quirk
+    pub expected: bool,
+    // a check or a cross on the screen, None when neither was found on the quirk's line
+    pub passed: Option<bool>,
+}
+
+impl QuirkReading {
+    // whether the emulator has the quirk: what the platform expects if the ROM passed it,
+    // the opposite if not
+    pub fn exhibited(&self) -> Option<bool> {
+        self.passed.map(|passed| passed == self.expected)
+    }
+}
+
+// the platform number the ROM tests for a variant
+pub fn quirks_platform(variant: ChipVariant) -> u8 {
+    match variant {
+        ChipVariant::Chip8Original | ChipVariant::Chip8E | ChipVariant::Chip48 => 1,
+        ChipVariant::SuperChip10 | ChipVariant::SuperChip11 => 2,
+        ChipVariant::XoChip => 3,
+    }
+}
+
+// which quirks, in QUIRK_LINES order, each platform has according to the suite
+fn platform_quirks(platform: u8) -> [bool; 6] {
+    match platform {
+        2 => [false, false, false, true, true, true],
+        3 => [false, true, false, false, false, false],
+        _ => [true, true, true, true, false, false],
+    }
+}
+
+// Runs the quirks ROM headlessly on `variant` with `quirks` and reads its results off the
+// screen.
+pub fn detect_quirks(rom: &[u8], variant: ChipVariant, quirks: QuirkConfig) -> Result<Vec<QuirkReading>, Chip8Error> {
+    let mut chip8 = Chip8Builder::new()
+        .with_variant(variant)
+        .with_quirks(quirks)
+        .with_rng_seed(0)
+        .build();
+    chip8.load_rom(rom.to_vec())?;
+    let platform = quirks_platform(variant);
+    chip8.ram[PLATFORM_ADDRESS] = platform;
+    for _ in 0..FRAMES {
+        for _ in 0..variant.instructions_per_frame() {
+            if let InstructionResult::Halted { .. } = chip8.step()? {
+                break;
+            }
+        }
+        chip8.tick_timers();
+    }
+    Ok(read_quirks_screen(chip8.display(), platform))
+}
+
+// The results the quirks ROM drew for `platform`, a check or cross being looked for along
+// each line of QUIRK_LINES.
+pub fn read_quirks_screen(display: &Framebuffer, platform: u8) -> Vec<QuirkReading> {
+    QUIRK_LINES
+        .iter()
+        .zip(platform_quirks(platform).iter())
+        .map(|(&(name, y), &expected)| {
+            let passed = find_glyph(display, y, &[CHECK_GLYPH, CROSS_GLYPH]).map(|(_, glyph)| glyph == CHECK_GLYPH);
+            QuirkReading { name, expected, passed }
+        })
+        .collect()
+}
+
+// The rightmost x where one of `glyphs` is drawn with its top row at `y`, and which one.
+pub fn find_glyph(display: &Framebuffer, y: usize, glyphs: &[Glyph]) -> Option<(usize, Glyph)> {
+    (0..display.width())
+        .rev()
+        .find_map(|x| glyphs.iter().find(|glyph| glyph_at(display, x, y, glyph)).map(|glyph| (x, *glyph)))
+}
+
+// Whether `glyph` is drawn exactly at x, y, with the columns either side of it dark so a
+// wider shape that happens to contain it doesn't count. The screen doesn't wrap here.
+pub fn glyph_at(display: &Framebuffer, x: usize, y: usize, glyph: &Glyph) -> bool {
+    if x + glyph.width > display.width() || y + glyph.rows.len() > display.height() {
+        return false;
+    }
+    let lit = |column: isize, y: usize| {
+        column >= 0 && (column as usize) < display.width() && display.pixel(column as usize, y)
+    };
+    glyph.rows.iter().enumerate().all(|(row, bits)| {
+        (-1..=glyph.width as isize).all(|dx| {
+            let wanted = dx >= 0 && (dx as usize) < glyph.width && bits >> (7 - dx) & 1 == 1;
+            lit(x as isize + dx, y + row) == wanted
+        })
+    })
+}
@@ -0,0 +1,80 @@
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UnknownOpcodePolicy {
+    // print the opcode and carry on with the next instruction
+    Log,
+    // stop with Chip8Error::InvalidOpcode
+    Halt,
+    // carry on with the next instruction without saying anything
+    Skip,
+    // jump to the given address, e.g. a handler the ROM provides
+    Trap(u16),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MemoryProtection {
+    // any address can be written, like on the original interpreter
+    Permissive,
+    // writes below PROGRAM_START (the font and interpreter area) stop with Chip8Error::ProtectedWrite
+    Strict,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QuirkConfig {
+    // highest address that I-indexed reads and writes may touch
+    pub i_max: u16,
+    pub unknown_opcode_policy: UnknownOpcodePolicy,
+    pub memory_protection: MemoryProtection,
+    // FX1E sets VF when I + Vx goes past 0xFFF
+    pub fx1e_overflow_sets_vf: bool,
+    // BNNN jumps to NNN + VX, X being the top nibble of NNN, instead of NNN + V0
+    pub bnnn_uses_vx: bool,
+    // 8XY6 and 8XYE shift VY into VX, as the COSMAC VIP did, instead of shifting VX in
+    // place; states saved before it existed load with it off
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub shift_uses_vy: bool,
+}
+
+impl QuirkConfig {
+    // the COSMAC VIP interpreter
+    pub fn chip8_original() -> Self {
+        QuirkConfig {
+            fx1e_overflow_sets_vf: true,
+            shift_uses_vy: true,
+            ..QuirkConfig::default()
+        }
+    }
+
+    // CHIP-48 on the HP48, which later interpreters mostly follow
+    pub fn chip48() -> Self {
+        QuirkConfig {
+            fx1e_overflow_sets_vf: false,
+            bnnn_uses_vx: true,
+            ..QuirkConfig::default()
+        }
+    }
+
+    // refuses to run anything that is not a valid instruction or that writes over the font
+    pub fn strict() -> Self {
+        QuirkConfig {
+            unknown_opcode_policy: UnknownOpcodePolicy::Halt,
+            memory_protection: MemoryProtection::Strict,
+            ..QuirkConfig::default()
+        }
+    }
+}
+
+impl Default for QuirkConfig {
+    fn default() -> Self {
+        QuirkConfig {
+            i_max: 0xFFF,
+            unknown_opcode_policy: UnknownOpcodePolicy::Log,
+            memory_protection: MemoryProtection::Permissive,
+            fx1e_overflow_sets_vf: false,
+            bnnn_uses_vx: false,
+            shift_uses_vy: false,
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use std::{
+    fmt,
+    io::{self, BufRead, Write},
+};
+
+use serde_json::{json, Value};
+
+use crate::chip8::Chip8;
+use crate::error::RefTraceError;
+
+// The state after one instruction, as other emulators can easily write it: one line per
+// instruction, either CSV with hex fields
+//
+//     pc,i,v0,v1,...,vf
+//     202,000,2A,00,...,00
+//
+// or JSON lines with the same names to_json uses
+//
+//     {"pc":514,"i":0,"v":[42,0,...,0]}
+//
+// Blank lines, lines starting with # and the CSV header are skipped.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TraceLine {
+    pub pc: u16,
+    pub i: u16,
+    pub vx: [u8; 16],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TraceFormat {
+    Csv,
+    Json,
+}
+
+impl TraceFormat {
+    // JSON lines for .json and .jsonl, CSV for anything else
+    pub fn for_path(path: &str) -> TraceFormat {
+        let path = path.to_ascii_lowercase();
+        if path.ends_with(".jsonl") || path.ends_with(".json") {
+            TraceFormat::Json
+        } else {
+            TraceFormat::Csv
+        }
+    }
+}
+
+const CSV_HEADER: &str = "pc,i,v0,v1,v2,v3,v4,v5,v6,v7,v8,v9,va,vb,vc,vd,ve,vf";
+
+impl TraceLine {
+    pub fn capture(chip8: &Chip8) -> Self {
+        TraceLine {
+            pc: chip8.pc(),
+            i: chip8.i_register(),
+            vx: chip8.cpu_snapshot().vx,
+        }
+    }
+
+    // None for the lines the format skips
+    pub fn parse(line: &str) -> Result<Option<TraceLine>, String> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.eq_ignore_ascii_case(CSV_HEADER) {
+            return Ok(None);
+        }
+        if line.starts_with('{') {
+            parse_json(line).map(Some)
+        } else {
+            parse_csv(line).map(Some)
+        }
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut line = format!("{:03X},{:03X}", self.pc, self.i);
+        for value in self.vx.iter() {
+            line.push_str(&format!(",{:02X}", value));
+        }
+        line
+    }
+
+    pub fn to_json(&self) -> String {
+        json!({ "pc": self.pc, "i": self.i, "v": self.vx }).to_string()
+    }
+
+    // the first field where `actual` differs from this line, in PC, I, V0..VF order
+    pub fn first_difference(&self, actual: &TraceLine) -> Option<(String, u16, u16)> {
+        if self.pc != actual.pc {
+            return Some((String::from("PC"), self.pc, actual.pc));
+        }
+        if self.i != actual.i {
+            return Some((String::from("I"), self.i, actual.i));
+        }
+        (0..16)
+            .find(|&x| self.vx[x] != actual.vx[x])
+            .map(|x| (format!("V{:X}", x), self.vx[x] as u16, actual.vx[x] as u16))
+    }
+}
+
+impl fmt::Display for TraceLine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PC={:03X} I={:03X} V={:02X?}", self.pc, self.i, self.vx)
+    }
+}
+
+fn hex_field(name: &str, text: &str, max: u16) -> Result<u16, String> {
+    let digits = text.trim();
+    let digits = digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")).unwrap_or(digits);
+    u16::from_str_radix(digits, 16)
+        .ok()
+        .filter(|&value| value <= max)
+        .ok_or_else(|| format!("{} should be hex from 0 to {:X}, not {:?}", name, max, text))
+}
+
+fn parse_csv(line: &str) -> Result<TraceLine, String> {
+    let fields: Vec<&str> = line.split(',').collect();
+    if fields.len() != 18 {
+        return Err(format!("expected 18 fields (pc, i, v0 to vf), found {}", fields.len()));
+    }
+    let mut vx = [0; 16];
+    for (x, field) in fields[2..].iter().enumerate() {
+        vx[x] = hex_field(&format!("v{:x}", x), field, 0xFF)? as u8;
+    }
+    Ok(TraceLine {
+        pc: hex_field("pc", fields[0], 0xFFFF)?,
+        i: hex_field("i", fields[1], 0xFFFF)?,
+        vx,
+    })
+}
+
+fn parse_json(line: &str) -> Result<TraceLine, String> {
+    let value: Value = serde_json::from_str(line).map_err(|err| err.to_string())?;
+    let number = |key: &str| {
+        value
+            .get(key)
+            .and_then(Value::as_u64)
+            .filter(|&n| n <= 0xFFFF)
+            .map(|n| n as u16)
+            .ok_or_else(|| format!("{} should be a number from 0 to 65535", key))
+    };
+    let registers = value
+        .get("v")
+        .and_then(Value::as_array)
+        .filter(|v| v.len() == 16)
+        .ok_or_else(|| String::from("v should be an array of 16 numbers"))?;
+    let mut vx = [0; 16];
+    for (register, value) in vx.iter_mut().zip(registers) {
+        *register = value
+            .as_u64()
+            .filter(|&n| n <= 0xFF)
+            .ok_or_else(|| String::from("v should hold numbers from 0 to 255"))? as u8;
+    }
+    Ok(TraceLine { pc: number("pc")?, i: number("i")?, vx })
+}
+
+// Writes a line for every instruction run, for --emit-ref-trace.
+pub struct RefTraceWriter<W: Write> {
+    out: W,
+    format: TraceFormat,
+}
+
+impl<W: Write> RefTraceWriter<W> {
+    pub fn new(mut out: W, format: TraceFormat) -> io::Result<Self> {
+        if format == TraceFormat::Csv {
+            writeln!(out, "{}", CSV_HEADER)?;
+        }
+        Ok(RefTraceWriter { out, format })
+    }
+
+    pub fn record(&mut self, chip8: &Chip8) -> io::Result<()> {
+        let line = TraceLine::capture(chip8);
+        match self.format {
+            TraceFormat::Csv => writeln!(self.out, "{}", line.to_csv()),
+            TraceFormat::Json => writeln!(self.out, "{}", line.to_json()),
+        }
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+// Compares the machine against a reference trace one instruction at a time, for --ref-trace.
+pub struct RefTraceChecker<R: BufRead> {
+    input: R,
+    // lines read so far, for parse errors
+    line_number: usize,
+    // instructions checked so far
+    instructions: u64,
+}
+
+impl<R: BufRead> RefTraceChecker<R> {
+    pub fn new(input: R) -> Self {
+        RefTraceChecker {
+            input,
+            line_number: 0,
+            instructions: 0,
+        }
+    }
+
+    pub fn instructions(&self) -> u64 {
+        self.instructions
+    }
+
+    // the next line of the trace, None once it has run out
+    fn next_line(&mut self) -> Result<Option<TraceLine>, RefTraceError> {
+        let mut text = String::new();
+        loop {
+            text.clear();
+            if self.input.read_line(&mut text)? == 0 {
+                return Ok(None);
+            }
+            self.line_number += 1;
+            let line = TraceLine::parse(&text).map_err(|reason| RefTraceError::Parse {
+                line: self.line_number,
+                reason,
+            })?;
+            if line.is_some() {
+                return Ok(line);
+            }
+        }
+    }
+
+    // Checks the machine right after an instruction against the next line. Ok(false)
+    // once the trace has no lines left, so there is nothing more to compare.
+    pub fn check(&mut self, chip8: &Chip8) -> Result<bool, RefTraceError> {
+        let expected = match self.next_line()? {
+            Some(line) => line,
+            None => return Ok(false),
+        };
+        self.instructions += 1;
+        let actual = TraceLine::capture(chip8);
+        match expected.first_difference(&actual) {
+            Some((field, expected_value, actual_value)) => Err(RefTraceError::Diverged {
+                instruction: self.instructions,
+                field,
+                expected: expected_value,
+                actual: actual_value,
+                expected_line: expected,
+                actual_line: actual,
+            }),
+            None => Ok(true),
+        }
+    }
+
+    // once the machine stops, the trace should have stopped too
+    pub fn finish(mut self) -> Result<u64, RefTraceError> {
+        match self.next_line()? {
+            Some(_) => Err(RefTraceError::TraceLonger {
+                instructions: self.instructions,
+            }),
+            None => Ok(self.instructions),
+        }
+    }
+}
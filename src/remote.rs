@@ -0,0 +1,388 @@
+use std::{
+    collections::{BTreeSet, VecDeque},
+    io::{self, BufRead, BufReader, ErrorKind, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    time::{Duration, Instant},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+
+use crate::chip8::{Chip8, InstructionResult};
+use crate::display::{FrameDelta, Framebuffer};
+
+const FRAME: Duration = Duration::from_micros(16_667);
+
+// What a tool sends, one JSON object a line, named by "cmd":
+//
+//     {"cmd": "set-breakpoint", "address": 522}
+//     {"cmd": "step", "count": 10}
+//     {"cmd": "write-mem", "address": 768, "bytes": [1, 2, 3]}
+//
+// Every request gets exactly one reply, in order, with events in between.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "cmd", rename_all = "kebab-case")]
+pub enum RemoteRequest {
+    Pause,
+    Resume,
+    // `count` instructions, 1 when left out; breakpoints don't stop it
+    Step {
+        #[serde(default = "one")]
+        count: u64,
+    },
+    ReadRegs,
+    ReadMem { address: u16, length: usize },
+    WriteMem { address: u16, bytes: Vec<u8> },
+    SetBreakpoint { address: u16 },
+    ClearBreakpoint { address: u16 },
+    GetFramebuffer,
+    // the rows that changed since the generation of an earlier delta, or everything for
+    // a `since` of 0
+    GetFramebufferDelta { since: u64 },
+    Press { key: u8 },
+    Release { key: u8 },
+}
+
+fn one() -> u64 {
+    1
+}
+
+// What the emulator sends back, named by "type": the reply to a request, or one of the
+// events breakpoint-hit, halted and error, which also pause the machine.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum RemoteMessage {
+    Ok,
+    Registers {
+        v: [u8; 16],
+        i: u16,
+        pc: u16,
+        sp: u8,
+        // return addresses, oldest first
+        stack: Vec<u16>,
+        delay: u8,
+        sound: u8,
+    },
+    Memory { address: u16, bytes: Vec<u8> },
+    // base64 of a bit per pixel, most significant first, each row padded to whole bytes
+    Framebuffer { width: usize, height: usize, pixels: String },
+    // see Framebuffer::delta_since; `rows` is every row in order when `full`, otherwise
+    // (row, base64 of its pixels) for the rows that changed
+    FramebufferDelta { generation: u64, width: usize, height: usize, full: bool, rows: Vec<(usize, String)> },
+    // the request couldn't be read or carried out; the machine is as it was
+    Rejected { reason: String },
+    BreakpointHit { pc: u16 },
+    Halted { pc: u16 },
+    Error { message: String },
+}
+
+impl RemoteMessage {
+    // sent on its own rather than in reply to a request
+    pub fn is_event(&self) -> bool {
+        matches!(self, RemoteMessage::BreakpointHit { .. } | RemoteMessage::Halted { .. } | RemoteMessage::Error { .. })
+    }
+
+    pub fn framebuffer(display: &Framebuffer) -> Self {
+        RemoteMessage::Framebuffer { width: display.width(), height: display.height(), pixels: STANDARD.encode(display.packed()) }
+    }
+
+    pub fn framebuffer_delta(display: &Framebuffer, since: u64) -> Self {
+        let (width, height) = (display.width(), display.height());
+        match display.delta_since(since) {
+            FrameDelta::Full { generation, rows, .. } => {
+                let rows = rows.chunks(width.div_ceil(8)).map(|packed| STANDARD.encode(packed)).enumerate().collect();
+                RemoteMessage::FramebufferDelta { generation, width, height, full: true, rows }
+            }
+            FrameDelta::Rows { generation, rows } => {
+                let rows = rows.into_iter().map(|(y, packed)| (y, STANDARD.encode(packed))).collect();
+                RemoteMessage::FramebufferDelta { generation, width, height, full: false, rows }
+            }
+        }
+    }
+}
+
+// Serves the RemoteRequest protocol on a TCP port and runs the machine at 60 frames a
+// second while a tool has it resumed. It starts paused, so breakpoints can go in before
+// anything runs. One client at a time; another one waits until the first disconnects,
+// and the machine carries on as it was left.
+pub struct RemoteServer {
+    listener: TcpListener,
+    chip8: Chip8,
+    instructions_per_frame: usize,
+    breakpoints: BTreeSet<u16>,
+    paused: bool,
+    // resuming from a breakpoint runs the instruction it stopped before
+    skip_breakpoint: bool,
+    // instructions run, for ticking the timers once a frame's worth
+    position: u64,
+}
+
+impl RemoteServer {
+    // Refuses anything but a loopback address unless `allow_remote`, as whoever connects
+    // can read and write the whole machine.
+    pub fn bind<A: ToSocketAddrs>(addr: A, chip8: Chip8, instructions_per_frame: usize, allow_remote: bool) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let local = listener.local_addr()?;
+        if !allow_remote && !local.ip().is_loopback() {
+            return Err(io::Error::new(
+                ErrorKind::PermissionDenied,
+                format!("{} is not a loopback address, and --listen-remote wasn't given", local),
+            ));
+        }
+        Ok(RemoteServer {
+            listener,
+            chip8,
+            instructions_per_frame: instructions_per_frame.max(1),
+            breakpoints: BTreeSet::new(),
+            paused: true,
+            skip_breakpoint: false,
+            position: 0,
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    // Takes clients one after another for as long as the listener works.
+    pub fn serve(mut self) -> io::Result<()> {
+        loop {
+            let (stream, peer) = self.accept()?;
+            log::info!("remote client {} connected", peer);
+            match self.serve_client(stream) {
+                Ok(()) => log::info!("remote client {} disconnected", peer),
+                Err(err) => log::warn!("remote client {} dropped: {}", peer, err),
+            }
+        }
+    }
+
+    // waits for a client, running the machine meanwhile if it was left resumed
+    fn accept(&mut self) -> io::Result<(TcpStream, SocketAddr)> {
+        self.listener.set_nonblocking(!self.paused)?;
+        loop {
+            match self.listener.accept() {
+                Ok(client) => {
+                    client.0.set_nonblocking(false)?;
+                    return Ok(client);
+                }
+                Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                    let deadline = Instant::now() + FRAME;
+                    // nobody is listening for the events
+                    let _ = self.run_frame();
+                    if self.paused {
+                        self.listener.set_nonblocking(false)?;
+                    }
+                    std::thread::sleep(deadline.saturating_duration_since(Instant::now()));
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    fn serve_client(&mut self, stream: TcpStream) -> io::Result<()> {
+        let mut reader = stream.try_clone()?;
+        let mut writer = stream;
+        let mut pending = Vec::new();
+        let mut deadline = Instant::now() + FRAME;
+        loop {
+            // a paused machine only waits on the client
+            let timeout = if self.paused { None } else { Some(deadline.saturating_duration_since(Instant::now()).max(Duration::from_millis(1))) };
+            reader.set_read_timeout(timeout)?;
+            let mut buffer = [0; 4096];
+            match reader.read(&mut buffer) {
+                Ok(0) => return Ok(()),
+                Ok(n) => pending.extend_from_slice(&buffer[..n]),
+                Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+                Err(err) if err.kind() == ErrorKind::ConnectionReset => return Ok(()),
+                Err(err) => return Err(err),
+            }
+            while let Some(end) = pending.iter().position(|&byte| byte == b'\n') {
+                let line: Vec<u8> = pending.drain(..=end).collect();
+                let line = String::from_utf8_lossy(&line);
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let messages = match serde_json::from_str(&line) {
+                    Ok(request) => self.handle(request),
+                    Err(err) => vec![RemoteMessage::Rejected { reason: err.to_string() }],
+                };
+                for message in messages {
+                    send(&mut writer, &message)?;
+                }
+            }
+            if !self.paused && Instant::now() >= deadline {
+                deadline += FRAME;
+                // after a long stall start from now rather than catching up
+                if deadline < Instant::now() {
+                    deadline = Instant::now() + FRAME;
+                }
+                if let Some(event) = self.run_frame() {
+                    send(&mut writer, &event)?;
+                }
+            }
+        }
+    }
+
+    // the reply, after any events the request caused
+    fn handle(&mut self, request: RemoteRequest) -> Vec<RemoteMessage> {
+        let reply = match request {
+            RemoteRequest::Pause => {
+                self.paused = true;
+                RemoteMessage::Ok
+            }
+            RemoteRequest::Resume => {
+                self.paused = false;
+                self.skip_breakpoint = true;
+                RemoteMessage::Ok
+            }
+            RemoteRequest::Step { count } => {
+                let mut events = Vec::new();
+                for _ in 0..count {
+                    if let Some(event) = self.execute() {
+                        events.push(event);
+                        break;
+                    }
+                }
+                self.paused = true;
+                events.push(RemoteMessage::Ok);
+                return events;
+            }
+            RemoteRequest::ReadRegs => {
+                let chip8 = &self.chip8;
+                let stack = chip8.stack_snapshot();
+                RemoteMessage::Registers {
+                    v: std::array::from_fn(|x| chip8.register(x as u8)),
+                    i: chip8.i_register(),
+                    pc: chip8.pc(),
+                    sp: stack.sp,
+                    stack: stack.stack[..stack.sp as usize].to_vec(),
+                    delay: chip8.delay_timer(),
+                    sound: chip8.sound_timer(),
+                }
+            }
+            RemoteRequest::ReadMem { address, length } => match self.chip8.memory(address, length) {
+                Ok(bytes) => RemoteMessage::Memory { address, bytes: bytes.to_vec() },
+                Err(err) => RemoteMessage::Rejected { reason: err.to_string() },
+            },
+            RemoteRequest::WriteMem { address, bytes } => match self.chip8.write_memory(address, &bytes) {
+                Ok(()) => RemoteMessage::Ok,
+                Err(err) => RemoteMessage::Rejected { reason: err.to_string() },
+            },
+            RemoteRequest::SetBreakpoint { address } | RemoteRequest::ClearBreakpoint { address } if address >= 0x1000 => {
+                RemoteMessage::Rejected { reason: format!("{:#X} is past the end of memory", address) }
+            }
+            RemoteRequest::SetBreakpoint { address } => {
+                self.breakpoints.insert(address);
+                RemoteMessage::Ok
+            }
+            RemoteRequest::ClearBreakpoint { address } => match self.breakpoints.remove(&address) {
+                true => RemoteMessage::Ok,
+                false => RemoteMessage::Rejected { reason: format!("no breakpoint at {:#05X}", address) },
+            },
+            RemoteRequest::GetFramebuffer => RemoteMessage::framebuffer(self.chip8.display()),
+            RemoteRequest::GetFramebufferDelta { since } => RemoteMessage::framebuffer_delta(self.chip8.display(), since),
+            RemoteRequest::Press { key } | RemoteRequest::Release { key } if key > 0xF => {
+                RemoteMessage::Rejected { reason: format!("no key {}, only 0 to F", key) }
+            }
+            RemoteRequest::Press { key } => {
+                self.chip8.keypad_mut().press(key);
+                RemoteMessage::Ok
+            }
+            RemoteRequest::Release { key } => {
+                self.chip8.keypad_mut().release(key);
+                RemoteMessage::Ok
+            }
+        };
+        vec![reply]
+    }
+
+    // a frame's worth of instructions, up to a breakpoint or anything else that pauses
+    fn run_frame(&mut self) -> Option<RemoteMessage> {
+        for _ in 0..self.instructions_per_frame {
+            let pc = self.chip8.pc();
+            if !std::mem::take(&mut self.skip_breakpoint) && self.breakpoints.contains(&pc) {
+                self.paused = true;
+                return Some(RemoteMessage::BreakpointHit { pc });
+            }
+            if let Some(event) = self.execute() {
+                return Some(event);
+            }
+        }
+        None
+    }
+
+    // one instruction and the timers when a frame is up, or the event that pauses
+    fn execute(&mut self) -> Option<RemoteMessage> {
+        let event = match self.chip8.step() {
+            Ok(InstructionResult::Halted { pc }) => RemoteMessage::Halted { pc },
+            Ok(_) => {
+                self.position += 1;
+                if self.position.is_multiple_of(self.instructions_per_frame as u64) {
+                    self.chip8.tick_timers();
+                }
+                return None;
+            }
+            Err(err) => RemoteMessage::Error { message: err.to_string() },
+        };
+        self.paused = true;
+        Some(event)
+    }
+}
+
+fn send(writer: &mut TcpStream, message: &RemoteMessage) -> io::Result<()> {
+    // an enum of numbers and strings always serializes
+    let line = serde_json::to_string(message).unwrap_or_default();
+    writer.write_all(line.as_bytes())?;
+    writer.write_all(b"\n")
+}
+
+// The other end of a RemoteServer, for tests and Rust tools. request waits for the reply,
+// keeping the events that came before it for next_event.
+pub struct RemoteClient {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    events: VecDeque<RemoteMessage>,
+}
+
+impl RemoteClient {
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+        Ok(RemoteClient {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+            events: VecDeque::new(),
+        })
+    }
+
+    // how long request and next_event wait before giving up with TimedOut or WouldBlock
+    pub fn set_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.writer.set_read_timeout(timeout)
+    }
+
+    pub fn request(&mut self, request: &RemoteRequest) -> io::Result<RemoteMessage> {
+        let line = serde_json::to_string(request).map_err(io::Error::other)?;
+        self.writer.write_all(line.as_bytes())?;
+        self.writer.write_all(b"\n")?;
+        loop {
+            match self.read()? {
+                message if message.is_event() => self.events.push_back(message),
+                reply => return Ok(reply),
+            }
+        }
+    }
+
+    pub fn next_event(&mut self) -> io::Result<RemoteMessage> {
+        match self.events.pop_front() {
+            Some(event) => Ok(event),
+            None => self.read(),
+        }
+    }
+
+    fn read(&mut self) -> io::Result<RemoteMessage> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(ErrorKind::UnexpectedEof, "the emulator closed the connection"));
+        }
+        serde_json::from_str(&line).map_err(|err| io::Error::new(ErrorKind::InvalidData, err))
+    }
+}
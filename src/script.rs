@@ -0,0 +1,91 @@
+use std::str::FromStr;
+
+use crate::error::ScriptError;
+use crate::keypad::Keypad;
+
+// Key presses for a headless run, one per line:
+//
+//     # hold 5 for a quarter of a second
+//     frame 30: down 5
+//     frame 45: up 5
+//
+// Frames count from 0 and a change happens before that frame's instructions run. Keys
+// are hex digits 0 to F. Blank lines and lines starting with # are skipped, and the
+// frames can't go backwards, so the file reads in the order it plays.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InputScript {
+    events: Vec<KeyEvent>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeyEvent {
+    pub frame: u64,
+    pub key: u8,
+    pub pressed: bool,
+}
+
+impl InputScript {
+    pub fn parse(text: &str) -> Result<Self, ScriptError> {
+        let mut events: Vec<KeyEvent> = Vec::new();
+        for (n, line) in text.lines().enumerate() {
+            let error = |reason: String| ScriptError::Parse { line: n + 1, reason };
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let event = parse_event(line).map_err(error)?;
+            if let Some(last) = events.last() {
+                if event.frame < last.frame {
+                    return Err(error(format!("frame {} comes after frame {}", event.frame, last.frame)));
+                }
+            }
+            events.push(event);
+        }
+        Ok(InputScript { events })
+    }
+
+    pub fn events(&self) -> &[KeyEvent] {
+        &self.events
+    }
+
+    // the frame of the last change, after which the keypad stays as it is
+    pub fn last_frame(&self) -> Option<u64> {
+        self.events.last().map(|event| event.frame)
+    }
+
+    // presses and releases the keys the script changes at `frame`
+    pub fn apply(&self, frame: u64, keypad: &mut Keypad) {
+        let start = self.events.partition_point(|event| event.frame < frame);
+        for event in self.events[start..].iter().take_while(|event| event.frame == frame) {
+            keypad.set(event.key, event.pressed);
+        }
+    }
+}
+
+impl FromStr for InputScript {
+    type Err = ScriptError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        InputScript::parse(text)
+    }
+}
+
+fn parse_event(line: &str) -> Result<KeyEvent, String> {
+    let (frame, action) = line
+        .split_once(':')
+        .ok_or_else(|| format!("expected \"frame N: down K\" or \"frame N: up K\", got {:?}", line))?;
+    let frame = match frame.split_whitespace().collect::<Vec<_>>()[..] {
+        ["frame", number] => number.parse().map_err(|_| format!("{:?} is not a frame number", number))?,
+        _ => return Err(format!("expected \"frame N\" before the colon, got {:?}", frame.trim())),
+    };
+    let (pressed, key) = match action.split_whitespace().collect::<Vec<_>>()[..] {
+        ["down", key] => (true, key),
+        ["up", key] => (false, key),
+        _ => return Err(format!("expected \"down K\" or \"up K\" after the colon, got {:?}", action.trim())),
+    };
+    let key = match (key.len(), u8::from_str_radix(key, 16)) {
+        (1, Ok(digit)) => digit,
+        _ => return Err(format!("{:?} is not a key, they go from 0 to F", key)),
+    };
+    Ok(KeyEvent { frame, key, pressed })
+}
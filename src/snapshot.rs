@@ -0,0 +1,31 @@
+// Registers and stack only, without RAM, the display or timers. Cheap enough to take
+// before every instruction, which is what single step undo needs; restoring it does not
+// bring back memory an instruction wrote or pixels it drew.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Chip8Snapshot {
+    pub vx: [u8; 16],
+    pub pc: u16,
+    pub i: u16,
+    pub sp: u8,
+    pub stack: [u16; 16],
+}
+
+// The pieces Chip8Snapshot is made of, for saving and restoring one part on its own.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CpuSnapshot {
+    pub vx: [u8; 16],
+    pub pc: u16,
+    pub i: u16,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackSnapshot {
+    pub sp: u8,
+    pub stack: [u16; 16],
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimerSnapshot {
+    pub delay: u8,
+    pub sound: u8,
+}
@@ -0,0 +1,62 @@
+pub(crate) const FRAMES_PER_SECOND: usize = 60;
+
+// how much of each new frame goes into the average draw rate
+const SMOOTHING: f32 = 0.1;
+// the most the speed changes in a single frame, as a fraction
+const MAX_STEP: f32 = 0.01;
+
+// Speeds a ROM up or slows it down until it draws about `target_draws_per_second`
+// sprites a second. Games written for slow machines draw every frame they can, so this
+// reins them in; games that draw less than the target run faster, up to max_ips.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpeedController {
+    pub target_draws_per_second: f32,
+    // instructions per second, always between min_ips and max_ips
+    pub current_ips: u32,
+    pub min_ips: u32,
+    pub max_ips: u32,
+    // draws per frame, averaged over the last several frames
+    average_draws: f32,
+}
+
+impl SpeedController {
+    pub fn new(target_draws_per_second: f32, ips: u32, min_ips: u32, max_ips: u32) -> Self {
+        SpeedController {
+            target_draws_per_second,
+            current_ips: ips.clamp(min_ips, max_ips),
+            min_ips,
+            max_ips,
+            average_draws: target_draws_per_second / FRAMES_PER_SECOND as f32,
+        }
+    }
+
+    // Takes the number of DXYN instructions the last frame ran and returns the speed for
+    // the next one. A frame without draws only pulls the average down, so a ROM waiting
+    // on a key or a timer speeds up slowly rather than all at once.
+    pub fn adjust(&mut self, draws_in_last_frame: u32) -> u32 {
+        self.average_draws += (draws_in_last_frame as f32 - self.average_draws) * SMOOTHING;
+        let draws_per_second = self.average_draws * FRAMES_PER_SECOND as f32;
+        let ratio = if draws_per_second > 0.0 {
+            (self.target_draws_per_second / draws_per_second).clamp(1.0 - MAX_STEP, 1.0 + MAX_STEP)
+        } else {
+            1.0 + MAX_STEP
+        };
+        let change = self.current_ips as f32 * (ratio - 1.0);
+        // at least one instruction a second, or a slow speed could never change
+        let change = if ratio == 1.0 {
+            0.0
+        } else if change.abs() < 1.0 {
+            change.signum()
+        } else {
+            change.round()
+        };
+        let next = (self.current_ips as f32 + change).max(0.0) as u32;
+        self.current_ips = next.clamp(self.min_ips, self.max_ips);
+        self.current_ips
+    }
+
+    // the instructions a frame runs at the current speed
+    pub fn instructions_per_frame(&self) -> usize {
+        (self.current_ips as usize / FRAMES_PER_SECOND).max(1)
+    }
+}
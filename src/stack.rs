@@ -0,0 +1,61 @@
+use std::fmt;
+
+use crate::error::Chip8Error;
+use crate::snapshot::StackSnapshot;
+
+pub(crate) struct Stack {
+    pub(crate) mem: [u16; 16],
+    pub(crate) size: u8,
+}
+
+impl Stack {
+    pub(crate) fn new() -> Self {
+        Stack {
+            mem: [0; 16],
+            size: 0,
+        }
+    }
+
+    pub(crate) fn dump(&self) -> StackSnapshot {
+        StackSnapshot {
+            sp: self.size,
+            stack: self.mem,
+        }
+    }
+
+    pub(crate) fn restore(&mut self, snapshot: StackSnapshot) {
+        self.size = snapshot.sp;
+        self.mem = snapshot.stack;
+    }
+
+    // `address` is the return address, so the call itself sits two bytes before it
+    pub(crate) fn add(&mut self, address: u16) -> Result<(), Chip8Error> {
+        if self.size as usize == self.mem.len() {
+            return Err(Chip8Error::StackOverflow { pc: address.wrapping_sub(2) });
+        }
+        self.mem[self.size as usize] = address;
+        self.size += 1;
+        Ok(())
+    }
+
+    pub(crate) fn pop(&mut self, pc: u16) -> Result<u16, Chip8Error> {
+        if self.size == 0 {
+            return Err(Chip8Error::StackUnderflow { pc });
+        }
+        self.size -= 1;
+        Ok(self.mem[(self.size) as usize])
+    }
+}
+
+impl fmt::Debug for Stack {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SP={} [", self.size)?;
+        for (n, address) in self.mem[..self.size as usize].iter().enumerate() {
+            if n > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{:04X}", address)?;
+        }
+        write!(f, "]")
+    }
+}
@@ -0,0 +1,109 @@
+use crate::chip8::Chip8;
+use crate::display::Framebuffer;
+use crate::error::Chip8Error;
+use crate::quirks::{QuirkConfig, UnknownOpcodePolicy};
+
+// bumped whenever a field changes meaning; load_state refuses any other version
+pub const STATE_VERSION: u32 = 1;
+// the most random numbers load_state will replay, about ten days of a RND at every
+// instruction at 1000 a second, and a few seconds to draw again
+const MAX_RNG_DRAWN: u64 = 1_000_000_000;
+
+// Everything a running machine needs to carry on exactly where it was, in a form any
+// serde format can write. Unlike to_json this includes the screen, the quirks and the
+// random number generator, so a ROM restored from it draws the same screens.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MachineState {
+    pub version: u32,
+    pub vx: [u8; 16],
+    pub pc: u16,
+    pub i: u16,
+    pub sp: u8,
+    pub stack: [u16; 16],
+    pub delay: u8,
+    pub sound: u8,
+    // as bytes rather than a list of 4096 numbers
+    #[cfg_attr(feature = "serde", serde(with = "serde_bytes"))]
+    pub ram: Vec<u8>,
+    pub framebuffer: Framebuffer,
+    pub quirks: QuirkConfig,
+    // (seed, numbers drawn) for a machine seeded with seed_rng; None restores onto
+    // whatever generator the machine already has
+    pub rng: Option<(u64, u64)>,
+    pub halted: bool,
+}
+
+impl Chip8 {
+    pub fn save_state(&self) -> MachineState {
+        MachineState {
+            version: STATE_VERSION,
+            vx: self.cpu.vx,
+            pc: self.cpu.pc,
+            i: self.cpu.i,
+            sp: self.stack.size,
+            stack: self.stack.mem,
+            delay: self.hour.delay,
+            sound: self.hour.sound,
+            ram: self.ram.to_vec(),
+            framebuffer: self.display.clone(),
+            quirks: self.quirks,
+            rng: self.rng_seed,
+            halted: self.halted,
+        }
+    }
+
+    // checks the whole state before changing anything, so a bad one leaves the machine as it was
+    pub fn load_state(&mut self, state: MachineState) -> Result<(), Chip8Error> {
+        if state.version != STATE_VERSION {
+            return Err(Chip8Error::InvalidState(format!("unsupported version {}", state.version)));
+        }
+        if state.ram.len() != self.ram.len() {
+            return Err(Chip8Error::InvalidState(format!(
+                "ram is {} bytes, expected {}",
+                state.ram.len(),
+                self.ram.len()
+            )));
+        }
+        if state.sp as usize > self.stack.mem.len() {
+            return Err(Chip8Error::InvalidState(format!("stack pointer {} is past the stack", state.sp)));
+        }
+        if !state.framebuffer.is_valid() {
+            return Err(Chip8Error::InvalidState(String::from("framebuffer has an impossible size")));
+        }
+        // a jump can leave the PC odd, but never past the last whole instruction
+        if state.pc as usize + 1 >= self.ram.len() {
+            return Err(Chip8Error::InvalidState(format!("program counter {:#06X} is outside of memory", state.pc)));
+        }
+        if state.quirks.i_max as usize >= self.ram.len() {
+            return Err(Chip8Error::InvalidState(format!("i_max {:#06X} is outside of memory", state.quirks.i_max)));
+        }
+        if let UnknownOpcodePolicy::Trap(address) = state.quirks.unknown_opcode_policy {
+            if address as usize + 1 >= self.ram.len() {
+                return Err(Chip8Error::InvalidState(format!("trap address {:#06X} is outside of memory", address)));
+            }
+        }
+        if let Some((_, drawn)) = state.rng {
+            if drawn > MAX_RNG_DRAWN {
+                return Err(Chip8Error::InvalidState(format!("{} random numbers drawn, at most {} can be replayed", drawn, MAX_RNG_DRAWN)));
+            }
+        }
+
+        self.cpu.vx = state.vx;
+        self.cpu.pc = state.pc;
+        self.cpu.i = state.i;
+        self.stack.size = state.sp;
+        self.stack.mem = state.stack;
+        self.hour.delay = state.delay;
+        self.hour.sound = state.sound;
+        self.ram.copy_from_slice(&state.ram);
+        self.display.replace_from(&state.framebuffer);
+        self.set_quirks(state.quirks);
+        if let Some((seed, drawn)) = state.rng {
+            self.replay_rng(seed, drawn);
+        }
+        self.halted = state.halted;
+        self.refresh();
+        Ok(())
+    }
+}
@@ -0,0 +1,158 @@
+use std::fmt;
+
+use crate::state::MachineState;
+
+// differences this close together are reported as one span, equal bytes in between included
+const SPAN_GAP: usize = 2;
+
+// A run of RAM where two states differ, with the bytes of each.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RamSpan {
+    pub start: u16,
+    pub before: Vec<u8>,
+    pub after: Vec<u8>,
+}
+
+// Everything that differs between two savestates, before (the first) to after (the second).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StateDiff {
+    pub registers: Vec<(u8, u8, u8)>,
+    pub pc: Option<(u16, u16)>,
+    pub i: Option<(u16, u16)>,
+    pub delay: Option<(u8, u8)>,
+    pub sound: Option<(u8, u8)>,
+    // the live part of each stack, when they differ
+    pub stack: Option<(Vec<u16>, Vec<u16>)>,
+    pub ram: Vec<RamSpan>,
+    // pixels lit in one and not the other; None when the screens are different sizes
+    pub pixels: Option<usize>,
+    pub screen_size: Option<((usize, usize), (usize, usize))>,
+}
+
+impl StateDiff {
+    pub fn is_empty(&self) -> bool {
+        *self == StateDiff { pixels: Some(0), ..StateDiff::default() }
+    }
+}
+
+fn changed<T: PartialEq + Copy>(before: T, after: T) -> Option<(T, T)> {
+    if before != after {
+        Some((before, after))
+    } else {
+        None
+    }
+}
+
+pub fn state_diff(before: &MachineState, after: &MachineState) -> StateDiff {
+    let registers = before
+        .vx
+        .iter()
+        .zip(after.vx.iter())
+        .enumerate()
+        .filter(|(_, (a, b))| a != b)
+        .map(|(x, (a, b))| (x as u8, *a, *b))
+        .collect();
+
+    let before_stack = &before.stack[..(before.sp as usize).min(16)];
+    let after_stack = &after.stack[..(after.sp as usize).min(16)];
+    let stack = if before_stack != after_stack {
+        Some((before_stack.to_vec(), after_stack.to_vec()))
+    } else {
+        None
+    };
+
+    let (a, b) = (&before.framebuffer, &after.framebuffer);
+    let (pixels, screen_size) = if (a.width(), a.height()) == (b.width(), b.height()) {
+        let count = (0..a.height())
+            .map(|y| (0..a.width()).filter(|&x| a.pixel(x, y) != b.pixel(x, y)).count())
+            .sum();
+        (Some(count), None)
+    } else {
+        (None, Some(((a.width(), a.height()), (b.width(), b.height()))))
+    };
+
+    StateDiff {
+        registers,
+        pc: changed(before.pc, after.pc),
+        i: changed(before.i, after.i),
+        delay: changed(before.delay, after.delay),
+        sound: changed(before.sound, after.sound),
+        stack,
+        ram: ram_spans(&before.ram, &after.ram, SPAN_GAP),
+        pixels,
+        screen_size,
+    }
+}
+
+// Differing addresses grouped into spans. Two differences at most `gap` equal bytes apart
+// share a span. Past the end of the shorter of the two, every byte counts as different.
+pub fn ram_spans(before: &[u8], after: &[u8], gap: usize) -> Vec<RamSpan> {
+    let len = before.len().max(after.len());
+    let differs = |address: usize| before.get(address) != after.get(address);
+
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for address in (0..len).filter(|&address| differs(address)) {
+        match ranges.last_mut() {
+            Some((_, end)) if address - *end <= gap => *end = address + 1,
+            _ => ranges.push((address, address + 1)),
+        }
+    }
+
+    let slice = |bytes: &[u8], start: usize, end: usize| bytes[start.min(bytes.len())..end.min(bytes.len())].to_vec();
+    ranges
+        .into_iter()
+        .map(|(start, end)| RamSpan {
+            start: start as u16,
+            before: slice(before, start, end),
+            after: slice(after, start, end),
+        })
+        .collect()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02X}", byte)).collect::<Vec<_>>().join(" ")
+}
+
+fn addresses(stack: &[u16]) -> String {
+    let entries: Vec<String> = stack.iter().map(|address| format!("{:04X}", address)).collect();
+    format!("[{}]", entries.join(", "))
+}
+
+impl fmt::Display for StateDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "no differences");
+        }
+        for (x, before, after) in &self.registers {
+            writeln!(f, "V{:X}: {:02X} -> {:02X}", x, before, after)?;
+        }
+        if let Some((before, after)) = self.pc {
+            writeln!(f, "PC: {:04X} -> {:04X}", before, after)?;
+        }
+        if let Some((before, after)) = self.i {
+            writeln!(f, "I: {:04X} -> {:04X}", before, after)?;
+        }
+        if let Some((before, after)) = self.delay {
+            writeln!(f, "DT: {:02X} -> {:02X}", before, after)?;
+        }
+        if let Some((before, after)) = self.sound {
+            writeln!(f, "ST: {:02X} -> {:02X}", before, after)?;
+        }
+        if let Some((before, after)) = &self.stack {
+            writeln!(f, "stack: {} -> {}", addresses(before), addresses(after))?;
+        }
+        for span in &self.ram {
+            let end = span.start as usize + span.before.len().max(span.after.len()) - 1;
+            writeln!(f, "RAM {:04X}-{:04X}:", span.start, end)?;
+            writeln!(f, "  - {}", hex(&span.before))?;
+            writeln!(f, "  + {}", hex(&span.after))?;
+        }
+        match (self.pixels, self.screen_size) {
+            (Some(0), _) => {}
+            (Some(pixels), _) => writeln!(f, "display: {} pixels differ", pixels)?,
+            (None, Some(((w1, h1), (w2, h2)))) => writeln!(f, "display: {}x{} -> {}x{}", w1, h1, w2, h2)?,
+            (None, None) => {}
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+use std::{
+    collections::VecDeque,
+    fmt::Write,
+    time::{Duration, Instant},
+};
+
+// how often the title is brought up to date
+pub const TITLE_UPDATE_INTERVAL: Duration = Duration::from_millis(250);
+// rates are averaged over this many updates, so about the last second
+const AVERAGE_OVER: usize = 4;
+
+// everything the window title shows
+pub struct Status<'a> {
+    // file stem of the ROM, or its title in the library
+    pub rom: &'a str,
+    // (index, count) when the ROM came from the library
+    pub library: Option<(usize, usize)>,
+    pub fps: f64,
+    pub ips: f64,
+    // the platform whose quirks are active
+    pub profile: &'a str,
+    // paused with the pause key, or because the window lost focus
+    pub paused: bool,
+    pub unfocused: bool,
+    pub muted: bool,
+    pub reloading: bool,
+}
+
+// Rewrites `title` in place, so after the first call it only allocates when the
+// title grows past anything it held before.
+pub fn write_title(title: &mut String, status: &Status) {
+    title.clear();
+    // writing into a String can't fail
+    let _ = write!(title, "Chip-8 - {}", status.rom);
+    if let Some((index, count)) = status.library {
+        let _ = write!(title, " ({}/{})", index + 1, count);
+    }
+    let _ = write!(title, " | {:.0} fps | {:.0} ips | {}", status.fps, status.ips, status.profile);
+    if status.paused {
+        title.push_str(" [paused]");
+    } else if status.unfocused {
+        title.push_str(" [paused: no focus]");
+    }
+    if status.muted {
+        title.push_str(" [muted]");
+    }
+    if status.reloading {
+        title.push_str(" RELOADING...");
+    }
+}
+
+// per-second rate of an ever-growing counter, averaged over the last few samples
+pub struct RateMeter {
+    samples: VecDeque<(Instant, u64)>,
+}
+
+impl RateMeter {
+    pub fn new() -> Self {
+        RateMeter {
+            samples: VecDeque::with_capacity(AVERAGE_OVER + 1),
+        }
+    }
+
+    pub fn sample(&mut self, at: Instant, count: u64) -> f64 {
+        if self.samples.len() > AVERAGE_OVER {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((at, count));
+
+        let (start, first) = self.samples[0];
+        let elapsed = at.saturating_duration_since(start).as_secs_f64();
+        if elapsed > 0.0 {
+            count.saturating_sub(first) as f64 / elapsed
+        } else {
+            0.0
+        }
+    }
+}
+
+impl Default for RateMeter {
+    fn default() -> Self {
+        RateMeter::new()
+    }
+}
@@ -0,0 +1,107 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use crate::error::SymbolError;
+use crate::instruction::Instruction;
+
+// Names for addresses in a ROM, one per line:
+//
+//     # the game loop and what it calls
+//     0x0200 main
+//     0x0456 draw_paddle
+//
+// Addresses are hex, with or without the 0x, and names are a single word. Blank lines and
+// lines starting with # are skipped. An address or a name can only be given once.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SymbolTable {
+    names: BTreeMap<u16, String>,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        SymbolTable::default()
+    }
+
+    pub fn parse(text: &str) -> Result<Self, SymbolError> {
+        let mut symbols = SymbolTable::new();
+        for (n, line) in text.lines().enumerate() {
+            let error = |reason: String| SymbolError::Parse { line: n + 1, reason };
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (address, name) = match line.split_whitespace().collect::<Vec<_>>()[..] {
+                [address, name] => (address, name),
+                _ => return Err(error(format!("expected \"0x0456 name\", got {:?}", line))),
+            };
+            let address = u16::from_str_radix(address.trim_start_matches("0x").trim_start_matches("0X"), 16)
+                .map_err(|_| error(format!("{:?} is not an address", address)))?;
+            symbols.insert(address, name).map_err(error)?;
+        }
+        Ok(symbols)
+    }
+
+    // names `address`, unless it or the name already has a symbol
+    pub fn insert(&mut self, address: u16, name: &str) -> Result<(), String> {
+        if let Some(existing) = self.names.get(&address) {
+            return Err(format!("{:#06X} is already called {}", address, existing));
+        }
+        if let Some(existing) = self.address(name) {
+            return Err(format!("{} already names {:#06X}", name, existing));
+        }
+        self.names.insert(address, name.to_string());
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn name(&self, address: u16) -> Option<&str> {
+        self.names.get(&address).map(String::as_str)
+    }
+
+    pub fn address(&self, name: &str) -> Option<u16> {
+        self.names.iter().find(|(_, symbol)| *symbol == name).map(|(address, _)| *address)
+    }
+
+    // The nearest symbol at or before `address`, and how far past it: "draw_paddle" or
+    // "draw_paddle+6". None before the first symbol.
+    pub fn label(&self, address: u16) -> Option<String> {
+        let (start, name) = self.names.range(..=address).next_back()?;
+        Some(match address - start {
+            0 => name.clone(),
+            offset => format!("{}+{}", name, offset),
+        })
+    }
+
+    // A disassembled instruction as the trace log shows it, "0x456: Call(1280)", with the
+    // labels of the instruction and of the address it jumps, calls or points I to when
+    // there are any: "0x456 <draw_paddle>: Call(1280) -> draw_ball".
+    pub fn annotate(&self, pc: u16, instruction: Instruction) -> String {
+        let mut line = format!("{:#05X}", pc);
+        if let Some(label) = self.label(pc) {
+            line.push_str(&format!(" <{}>", label));
+        }
+        line.push_str(&format!(": {:?}", instruction));
+        let target = match instruction {
+            Instruction::Jp(nnn) | Instruction::Call(nnn) | Instruction::JpV0(nnn) | Instruction::LdI(nnn) => Some(nnn),
+            _ => None,
+        };
+        if let Some(name) = target.and_then(|nnn| self.label(nnn)) {
+            line.push_str(&format!(" -> {}", name));
+        }
+        line
+    }
+}
+
+impl FromStr for SymbolTable {
+    type Err = SymbolError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        SymbolTable::parse(text)
+    }
+}
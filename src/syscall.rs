@@ -0,0 +1,17 @@
+use crate::cpu::Cpu;
+
+// What a host routine reports back after 0NNN called it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyscallResult {
+    // the call did its work, carry on with the next instruction
+    Done,
+    // not a call the host knows; it goes to the unknown opcode policy like any other
+    Unhandled,
+    // stop the program here
+    Halt,
+}
+
+// Stands in for the machine-language subroutines 0NNN jumped to on the COSMAC VIP,
+// so programs can call into the embedding application. Gets NNN and the CPU, whose
+// registers it reads and changes through dump and restore.
+pub type SyscallHandler = Box<dyn FnMut(u16, &mut Cpu) -> SyscallResult>;
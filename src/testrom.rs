@@ -0,0 +1,105 @@
+use crate::memory::PROGRAM_START;
+
+// A ROM written as Rust calls instead of hex, for tests:
+//
+//     Chip8TestRom::new().ld(0, 5).ld(1, 7).add(0, 1).jp(0x206).build()
+//
+// Each call appends its instruction's two bytes. Registers are 0 to F and addresses 12 bits;
+// anything bigger panics, as it's a mistake in the test. address() is where the next
+// instruction goes once the ROM is loaded, for jumps back to it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Chip8TestRom {
+    bytes: Vec<u8>,
+}
+
+impl Chip8TestRom {
+    pub fn new() -> Self {
+        Chip8TestRom { bytes: Vec::new() }
+    }
+
+    pub fn address(&self) -> u16 {
+        PROGRAM_START + self.bytes.len() as u16
+    }
+
+    // 00E0
+    pub fn cls(self) -> Self {
+        self.opcode(0x00E0)
+    }
+
+    // 00EE
+    pub fn ret(self) -> Self {
+        self.opcode(0x00EE)
+    }
+
+    // 1NNN
+    pub fn jp(self, addr: u16) -> Self {
+        self.opcode(0x1000 | address(addr))
+    }
+
+    // 2NNN
+    pub fn call(self, addr: u16) -> Self {
+        self.opcode(0x2000 | address(addr))
+    }
+
+    // 3XNN, skips when Vx is byte
+    pub fn se(self, vx: u8, byte: u8) -> Self {
+        self.opcode(0x3000 | register(vx) << 8 | byte as u16)
+    }
+
+    // 4XNN
+    pub fn sne(self, vx: u8, byte: u8) -> Self {
+        self.opcode(0x4000 | register(vx) << 8 | byte as u16)
+    }
+
+    // 6XNN
+    pub fn ld(self, vx: u8, byte: u8) -> Self {
+        self.opcode(0x6000 | register(vx) << 8 | byte as u16)
+    }
+
+    // 7XNN
+    pub fn add_byte(self, vx: u8, byte: u8) -> Self {
+        self.opcode(0x7000 | register(vx) << 8 | byte as u16)
+    }
+
+    // 8XY4, Vx += Vy with the carry in VF
+    pub fn add(self, vx: u8, vy: u8) -> Self {
+        self.opcode(0x8004 | register(vx) << 8 | register(vy) << 4)
+    }
+
+    // ANNN
+    pub fn ld_i(self, addr: u16) -> Self {
+        self.opcode(0xA000 | address(addr))
+    }
+
+    // DXYN
+    pub fn drw(self, vx: u8, vy: u8, n: u8) -> Self {
+        assert!(n < 16, "sprite height {} doesn't fit in a nibble", n);
+        self.opcode(0xD000 | register(vx) << 8 | register(vy) << 4 | n as u16)
+    }
+
+    // bytes as they are, for sprites and other data among the code
+    pub fn data(mut self, bytes: &[u8]) -> Self {
+        self.bytes.extend_from_slice(bytes);
+        self
+    }
+
+    // the ROM, for Chip8::load_rom
+    pub fn build(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    fn opcode(mut self, opcode: u16) -> Self {
+        self.bytes.extend_from_slice(&opcode.to_be_bytes());
+        self
+    }
+}
+
+fn register(v: u8) -> u16 {
+    assert!(v < 16, "there is no register V{:X}", v);
+    v as u16
+}
+
+fn address(addr: u16) -> u16 {
+    assert!(addr < 0x1000, "{:#X} is past the end of memory", addr);
+    addr
+}
@@ -0,0 +1,152 @@
+use std::{fmt, str::FromStr};
+
+use crate::display::lerp_color;
+
+// Colors used to turn the 1 bit framebuffer into pixels, as 0xRRGGBB
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorTheme {
+    #[default]
+    Classic,
+    GreenPhosphor,
+    AmberPhosphor,
+    Blue,
+    Inverted,
+    PaperWhite,
+    HighContrast,
+    // foreground, background
+    Custom(u32, u32),
+}
+
+impl ColorTheme {
+    pub const PRESETS: [ColorTheme; 7] = [
+        ColorTheme::Classic,
+        ColorTheme::GreenPhosphor,
+        ColorTheme::AmberPhosphor,
+        ColorTheme::Blue,
+        ColorTheme::Inverted,
+        ColorTheme::PaperWhite,
+        ColorTheme::HighContrast,
+    ];
+
+    pub fn fg(self) -> u32 {
+        match self {
+            ColorTheme::Classic => 0xFFFFFF,
+            ColorTheme::GreenPhosphor => 0x33FF33,
+            ColorTheme::AmberPhosphor => 0xFF8800,
+            ColorTheme::Blue => 0x5599FF,
+            ColorTheme::Inverted => 0x000000,
+            ColorTheme::PaperWhite => 0x222222,
+            ColorTheme::HighContrast => 0xFFFF00,
+            ColorTheme::Custom(fg, _) => fg,
+        }
+    }
+
+    pub fn bg(self) -> u32 {
+        match self {
+            ColorTheme::Classic => 0x000000,
+            ColorTheme::GreenPhosphor => 0x001100,
+            ColorTheme::AmberPhosphor => 0x110500,
+            ColorTheme::Blue => 0x001133,
+            ColorTheme::Inverted => 0xFFFFFF,
+            ColorTheme::PaperWhite => 0xF2EFE6,
+            ColorTheme::HighContrast => 0x000000,
+            ColorTheme::Custom(_, bg) => bg,
+        }
+    }
+
+    // XO-CHIP draws on two bit planes, so a pixel can be in one of four colors:
+    // neither plane, plane 1 only, plane 2 only, both
+    pub fn colors(self) -> [u32; 4] {
+        let (fg, bg) = (self.fg(), self.bg());
+        let plane2 = match self {
+            ColorTheme::Classic => 0xAAAAAA,
+            ColorTheme::GreenPhosphor => 0x118811,
+            ColorTheme::AmberPhosphor => 0x884400,
+            ColorTheme::Blue => 0x224488,
+            ColorTheme::Inverted => 0x555555,
+            ColorTheme::PaperWhite => 0x8A8070,
+            ColorTheme::HighContrast => 0x00FFFF,
+            ColorTheme::Custom(..) => lerp_color(bg, fg, 0.5),
+        };
+        let both = match self {
+            ColorTheme::HighContrast => 0xFFFFFF,
+            _ => lerp_color(plane2, fg, 0.5),
+        };
+        [bg, fg, plane2, both]
+    }
+
+    // the preset after this one, wrapping around; custom colors go back to the first preset
+    pub fn next(self) -> Self {
+        let current = ColorTheme::PRESETS.iter().position(|theme| *theme == self);
+        match current {
+            Some(n) => ColorTheme::PRESETS[(n + 1) % ColorTheme::PRESETS.len()],
+            None => ColorTheme::PRESETS[0],
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorTheme::Classic => "classic",
+            ColorTheme::GreenPhosphor => "green",
+            ColorTheme::AmberPhosphor => "amber",
+            ColorTheme::Blue => "blue",
+            ColorTheme::Inverted => "inverted",
+            ColorTheme::PaperWhite => "paper-white",
+            ColorTheme::HighContrast => "high-contrast",
+            ColorTheme::Custom(..) => "custom",
+        }
+    }
+}
+
+impl fmt::Display for ColorTheme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for ColorTheme {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        ColorTheme::PRESETS
+            .iter()
+            .copied()
+            .find(|theme| theme.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = ColorTheme::PRESETS.iter().map(|theme| theme.name()).collect();
+                format!("unknown theme {} (expected one of {})", name, names.join(", "))
+            })
+    }
+}
+
+// colors that can be given by name instead of as hex
+const NAMED_COLORS: [(&str, u32); 10] = [
+    ("black", 0x000000),
+    ("white", 0xFFFFFF),
+    ("gray", 0x808080),
+    ("red", 0xFF0000),
+    ("green", 0x00FF00),
+    ("blue", 0x0000FF),
+    ("yellow", 0xFFFF00),
+    ("cyan", 0x00FFFF),
+    ("magenta", 0xFF00FF),
+    ("amber", 0xFF8800),
+];
+
+// Reads "#RRGGBB", "RRGGBB" or a color name into 0xRRGGBB
+pub fn parse_color(text: &str) -> Result<u32, String> {
+    let text = text.trim();
+    if let Some((_, color)) = NAMED_COLORS.iter().find(|(name, _)| name.eq_ignore_ascii_case(text)) {
+        return Ok(*color);
+    }
+
+    let digits = text.strip_prefix('#').unwrap_or(text);
+    if digits.len() != 6 {
+        return Err(format!("expected #RRGGBB, RRGGBB or a color name, got {:?}", text));
+    }
+    if let Some(bad) = digits.chars().find(|ch| !ch.is_ascii_hexdigit()) {
+        return Err(format!("{:?} is not a hex digit in {:?}", bad, text));
+    }
+    // six hex digits always fit
+    Ok(u32::from_str_radix(digits, 16).unwrap_or(0))
+}
@@ -0,0 +1,54 @@
+use std::fmt;
+
+use crate::backend::AudioBackend;
+use crate::snapshot::TimerSnapshot;
+
+pub(crate) struct Timer {
+    pub(crate) sound: u8,
+    pub(crate) delay: u8,
+}
+
+impl Timer {
+    pub(crate) fn new() -> Self {
+        Timer {
+            sound: 0,
+            delay: 0,
+        }
+    }
+
+    pub(crate) fn dump(&self) -> TimerSnapshot {
+        TimerSnapshot {
+            delay: self.delay,
+            sound: self.sound,
+        }
+    }
+
+    pub(crate) fn restore(&mut self, snapshot: TimerSnapshot) {
+        self.delay = snapshot.delay;
+        self.sound = snapshot.sound;
+    }
+
+    // called once per 60 Hz tick by whoever drives the emulator; the buzzer sounds
+    // for as long as the sound timer is above zero
+    pub(crate) fn delay_countdown(&mut self, audio: &mut dyn AudioBackend) {
+        if self.delay > 0 {
+            self.delay -= 1;
+        }
+
+        if self.sound > 0 {
+            if !audio.is_beeping() {
+                audio.start_beep();
+            }
+            self.sound -= 1;
+        }
+        if self.sound == 0 && audio.is_beeping() {
+            audio.stop_beep();
+        }
+    }
+}
+
+impl fmt::Debug for Timer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "DT={:02X} ST={:02X}", self.delay, self.sound)
+    }
+}
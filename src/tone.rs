@@ -0,0 +1,108 @@
+use std::{fmt, str::FromStr, time::Duration};
+
+// how long the tone takes to fade in or out, short enough to sound instant but long
+// enough that starting and stopping mid-cycle does not click
+const FADE_SECONDS: f32 = 0.002;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Waveform {
+    #[default]
+    Square,
+    Sine,
+    Triangle,
+    Sawtooth,
+}
+
+impl Waveform {
+    pub const ALL: [Waveform; 4] = [Waveform::Square, Waveform::Sine, Waveform::Triangle, Waveform::Sawtooth];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Waveform::Square => "square",
+            Waveform::Sine => "sine",
+            Waveform::Triangle => "triangle",
+            Waveform::Sawtooth => "sawtooth",
+        }
+    }
+
+    // level from -1 to 1 at a point in the cycle, phase going from 0 to 1
+    fn level(self, phase: f32) -> f32 {
+        match self {
+            Waveform::Square => if phase < 0.5 { 1.0 } else { -1.0 },
+            Waveform::Sine => (phase * std::f32::consts::TAU).sin(),
+            Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+            Waveform::Sawtooth => 2.0 * phase - 1.0,
+        }
+    }
+}
+
+impl fmt::Display for Waveform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Waveform {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Waveform::ALL
+            .iter()
+            .copied()
+            .find(|waveform| waveform.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Waveform::ALL.iter().map(|waveform| waveform.name()).collect();
+                format!("unknown waveform {} (expected one of {})", name, names.join(", "))
+            })
+    }
+}
+
+// Generates the buzzer tone, one sample per call at a fixed sample rate, without knowing
+// anything about the audio device. Amplitude ramps towards volume while the tone is on
+// and towards silence when it is off.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToneGenerator {
+    waveform: Waveform,
+    frequency: f32,
+    volume: f32,
+    sample_rate: f32,
+    // position in the current cycle, 0 to 1
+    phase: f32,
+    gain: f32,
+    fade_step: f32,
+}
+
+impl ToneGenerator {
+    pub fn new(waveform: Waveform, frequency: f32, volume: f32, sample_rate: u32) -> Self {
+        let sample_rate = sample_rate as f32;
+        ToneGenerator {
+            waveform,
+            frequency,
+            volume: volume.clamp(0.0, 1.0),
+            sample_rate,
+            phase: 0.0,
+            gain: 0.0,
+            fade_step: 1.0 / (FADE_SECONDS * sample_rate).max(1.0),
+        }
+    }
+
+    pub fn next_sample(&mut self, on: bool) -> f32 {
+        let target = if on { 1.0 } else { 0.0 };
+        if self.gain < target {
+            self.gain = (self.gain + self.fade_step).min(target);
+        } else if self.gain > target {
+            self.gain = (self.gain - self.fade_step).max(target);
+        }
+
+        let level = self.waveform.level(self.phase);
+        self.phase += self.frequency / self.sample_rate;
+        self.phase -= self.phase.floor();
+        level * self.volume * self.gain
+    }
+
+    // the next `duration` worth of samples
+    pub fn samples(&mut self, duration: Duration, on: bool) -> Vec<f32> {
+        let count = (duration.as_secs_f32() * self.sample_rate).round() as usize;
+        (0..count).map(|_| self.next_sample(on)).collect()
+    }
+}
@@ -0,0 +1,88 @@
+use std::{fmt, str::FromStr};
+
+// How the finished image is turned before it reaches the screen, for hardware that
+// mounted its display sideways or mirrored. Rotations are clockwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DisplayTransform {
+    #[default]
+    Normal,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    FlipH,
+    FlipV,
+}
+
+impl DisplayTransform {
+    pub const ALL: [DisplayTransform; 6] = [
+        DisplayTransform::Normal,
+        DisplayTransform::Rotate90,
+        DisplayTransform::Rotate180,
+        DisplayTransform::Rotate270,
+        DisplayTransform::FlipH,
+        DisplayTransform::FlipV,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DisplayTransform::Normal => "normal",
+            DisplayTransform::Rotate90 => "rotate90",
+            DisplayTransform::Rotate180 => "rotate180",
+            DisplayTransform::Rotate270 => "rotate270",
+            DisplayTransform::FlipH => "fliph",
+            DisplayTransform::FlipV => "flipv",
+        }
+    }
+
+    // size of the transformed image
+    pub fn dimensions(self, width: usize, height: usize) -> (usize, usize) {
+        match self {
+            DisplayTransform::Rotate90 | DisplayTransform::Rotate270 => (height, width),
+            _ => (width, height),
+        }
+    }
+
+    // copies a width x height image from `src` into `dst`, which has to be just as big
+    pub fn apply(self, src: &[u32], width: usize, height: usize, dst: &mut [u32]) {
+        if self == DisplayTransform::Normal {
+            dst.copy_from_slice(src);
+            return;
+        }
+
+        let (out_width, _) = self.dimensions(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let (tx, ty) = match self {
+                    DisplayTransform::Normal => (x, y),
+                    DisplayTransform::Rotate90 => (height - 1 - y, x),
+                    DisplayTransform::Rotate180 => (width - 1 - x, height - 1 - y),
+                    DisplayTransform::Rotate270 => (y, width - 1 - x),
+                    DisplayTransform::FlipH => (width - 1 - x, y),
+                    DisplayTransform::FlipV => (x, height - 1 - y),
+                };
+                dst[ty * out_width + tx] = src[y * width + x];
+            }
+        }
+    }
+}
+
+impl fmt::Display for DisplayTransform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for DisplayTransform {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        DisplayTransform::ALL
+            .iter()
+            .copied()
+            .find(|transform| transform.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = DisplayTransform::ALL.iter().map(|transform| transform.name()).collect();
+                format!("unknown transform {} (expected one of {})", name, names.join(", "))
+            })
+    }
+}
@@ -0,0 +1,104 @@
+use std::{fmt, str::FromStr};
+
+use crate::instruction::Instruction;
+use crate::quirks::QuirkConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChipVariant {
+    #[default]
+    Chip8Original,
+    Chip48,
+    SuperChip10,
+    SuperChip11,
+    XoChip,
+    Chip8E,
+}
+
+impl ChipVariant {
+    pub const ALL: [ChipVariant; 6] = [
+        ChipVariant::Chip8Original,
+        ChipVariant::Chip48,
+        ChipVariant::SuperChip10,
+        ChipVariant::SuperChip11,
+        ChipVariant::XoChip,
+        ChipVariant::Chip8E,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ChipVariant::Chip8Original => "chip8",
+            ChipVariant::Chip48 => "chip48",
+            ChipVariant::SuperChip10 => "schip10",
+            ChipVariant::SuperChip11 => "schip11",
+            ChipVariant::XoChip => "xochip",
+            ChipVariant::Chip8E => "chip8e",
+        }
+    }
+
+    pub fn default_quirks(self) -> QuirkConfig {
+        match self {
+            ChipVariant::Chip8Original | ChipVariant::Chip8E => QuirkConfig::chip8_original(),
+            _ => QuirkConfig::chip48(),
+        }
+    }
+
+    // largest resolution the variant can switch to
+    pub fn display_width(self) -> usize {
+        match self {
+            ChipVariant::SuperChip10 | ChipVariant::SuperChip11 | ChipVariant::XoChip => 128,
+            _ => 64,
+        }
+    }
+
+    pub fn display_height(self) -> usize {
+        match self {
+            ChipVariant::SuperChip10 | ChipVariant::SuperChip11 | ChipVariant::XoChip => 64,
+            _ => 32,
+        }
+    }
+
+    // instructions run per 60 Hz frame, roughly matching the speed of the original machines
+    pub fn instructions_per_frame(self) -> usize {
+        match self {
+            ChipVariant::Chip8Original | ChipVariant::Chip8E => 6,
+            ChipVariant::Chip48 | ChipVariant::SuperChip10 => 12,
+            ChipVariant::SuperChip11 => 30,
+            ChipVariant::XoChip => 100,
+        }
+    }
+
+    pub(crate) fn supports(self, instruction: Instruction) -> bool {
+        match instruction {
+            Instruction::ScrollDown(_)
+            | Instruction::ScrollRight
+            | Instruction::ScrollLeft
+            | Instruction::Exit => matches!(
+                self,
+                ChipVariant::SuperChip10 | ChipVariant::SuperChip11 | ChipVariant::XoChip
+            ),
+            Instruction::ScrollUp(_) => self == ChipVariant::XoChip,
+            _ => true,
+        }
+    }
+}
+
+impl fmt::Display for ChipVariant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for ChipVariant {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        ChipVariant::ALL
+            .iter()
+            .copied()
+            .find(|variant| variant.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = ChipVariant::ALL.iter().map(|variant| variant.name()).collect();
+                format!("unknown variant {} (expected one of {})", name, names.join(", "))
+            })
+    }
+}
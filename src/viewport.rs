@@ -0,0 +1,80 @@
+// Where a fixed-size image lands inside a window of any size: the largest whole-number
+// scale that fits, centered, with the rest of the window left as letterbox bars.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Viewport {
+    pub scale: usize,
+    pub x: usize,
+    pub y: usize,
+}
+
+impl Viewport {
+    // size of the scaled image inside the window
+    pub fn size(&self, image_width: usize, image_height: usize) -> (usize, usize) {
+        (image_width * self.scale, image_height * self.scale)
+    }
+}
+
+// A window smaller than the image still gets scale 1 at the top left corner; blit
+// crops whatever doesn't fit.
+pub fn letterbox(window_width: usize, window_height: usize, image_width: usize, image_height: usize) -> Viewport {
+    let scale = (window_width / image_width.max(1))
+        .min(window_height / image_height.max(1))
+        .max(1);
+    Viewport {
+        scale,
+        x: window_width.saturating_sub(image_width * scale) / 2,
+        y: window_height.saturating_sub(image_height * scale) / 2,
+    }
+}
+
+// draws `src` into `dst` at the viewport's position and scale, filling the bars with `fill`;
+// both heights follow from the buffer lengths
+pub fn blit_scaled(src: &[u32], src_width: usize, dst: &mut [u32], dst_width: usize, viewport: Viewport, fill: u32) {
+    dst.fill(fill);
+    // a minimized window can be 0 pixels wide
+    if src_width == 0 || dst_width == 0 {
+        return;
+    }
+    let src_height = src.len() / src_width;
+    let dst_height = dst.len() / dst_width;
+    let (width, height) = viewport.size(src_width, src_height);
+    let visible_width = width.min(dst_width - viewport.x);
+    let visible_height = height.min(dst_height - viewport.y);
+    for y in 0..visible_height {
+        let src_row = &src[(y / viewport.scale) * src_width..][..src_width];
+        let start = (viewport.y + y) * dst_width + viewport.x;
+        for (x, pixel) in dst[start..start + visible_width].iter_mut().enumerate() {
+            *pixel = src_row[x / viewport.scale];
+        }
+    }
+}
+
+// The size x size square of `frame` with its top left at `corner`, each pixel drawn as a
+// factor x factor block with a one pixel grid line along its top and left, into `out`.
+// Whatever falls off the right or bottom edge of the frame is black.
+pub fn magnify(frame: &[u32], frame_width: usize, corner: (usize, usize), size: usize, factor: usize, grid_color: u32, out: &mut [u32]) {
+    let frame_height = frame.len().checked_div(frame_width).unwrap_or(0);
+    for (row, line) in out.chunks_mut(size * factor).enumerate() {
+        let y = corner.1 + row / factor;
+        for (column, pixel) in line.iter_mut().enumerate() {
+            let x = corner.0 + column / factor;
+            *pixel = if row % factor == 0 || column % factor == 0 {
+                grid_color
+            } else if x < frame_width && y < frame_height {
+                frame[y * frame_width + x]
+            } else {
+                0
+            };
+        }
+    }
+}
+
+// The window scale on a desktop scaled by `dpi`, so a CHIP-8 pixel covers as much of the
+// screen as `scale` host pixels would at 96 DPI. Pixels are whole, so 150% and other
+// fractional factors round to the nearest one, and a bogus factor leaves `scale` as it is.
+pub fn hidpi_scale(scale: usize, dpi: f32) -> usize {
+    if !dpi.is_finite() || dpi <= 0.0 {
+        return scale;
+    }
+    ((scale as f32 * dpi).round() as usize).max(1)
+}
@@ -0,0 +1,99 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Receiver},
+        Arc, Mutex,
+    },
+};
+
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use crate::{is_rom_url, read_rom, split_rom_path, Chip8, EmulatorError};
+
+// Watches the ROM file for --watch. Editors and assemblers often replace the file
+// rather than writing into it, so the watch is on the directory and events are
+// filtered down to the ROM. Only a ROM read from a file on disk can be watched.
+pub struct RomWatcher {
+    _watcher: RecommendedWatcher,
+    events: Receiver<()>,
+    path: PathBuf,
+    // the ROM inside the archive, for an "archive.zip:entry" ROM
+    entry: Option<String>,
+    // the bytes the emulation thread loads on every reset
+    rom: Arc<Mutex<Vec<u8>>>,
+    // the file changed but couldn't be loaded yet
+    pending: bool,
+}
+
+impl RomWatcher {
+    // None, with a warning, for standard input, a URL or anything else that isn't a file
+    pub fn new(spec: &str, rom: Arc<Mutex<Vec<u8>>>) -> Result<Option<Self>, EmulatorError> {
+        let (path, entry) = split_rom_path(spec);
+        if spec == "-" || is_rom_url(spec) || !Path::new(path).is_file() {
+            log::warn!("not watching {}: only a ROM file can be watched", spec);
+            return Ok(None);
+        }
+        let path = Path::new(path).canonicalize()?;
+        let dir = path.parent().unwrap_or(Path::new(".")).to_path_buf();
+        let (sender, events) = mpsc::channel();
+
+        let target = path.clone();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            if let Ok(event) = event {
+                if !event.kind.is_access() && event.paths.contains(&target) {
+                    let _ = sender.send(());
+                }
+            }
+        })
+        .map_err(watch_error)?;
+        watcher.watch(&dir, RecursiveMode::NonRecursive).map_err(watch_error)?;
+
+        Ok(Some(RomWatcher {
+            _watcher: watcher,
+            events,
+            path,
+            entry: entry.map(String::from),
+            rom,
+            pending: false,
+        }))
+    }
+
+    // True when the file changed and now holds a ROM that loads, which is then used from
+    // the next reset on. A file caught half written (or missing, mid replace) is tried
+    // again on every call until it loads.
+    pub fn reload(&mut self) -> bool {
+        if self.events.try_iter().count() > 0 {
+            self.pending = true;
+        }
+        if !self.pending {
+            return false;
+        }
+
+        let mut spec = self.path.display().to_string();
+        if let Some(entry) = &self.entry {
+            spec = format!("{}:{}", spec, entry);
+        }
+        let data = match read_rom(&spec) {
+            Ok(data) => data,
+            Err(_) => return false,
+        };
+        if Chip8::check_rom(&data).is_err() {
+            return false;
+        }
+        self.pending = false;
+
+        let mut rom = match self.rom.lock() {
+            Ok(rom) => rom,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if *rom == data {
+            return false;
+        }
+        *rom = data;
+        true
+    }
+}
+
+fn watch_error(err: notify::Error) -> EmulatorError {
+    io::Error::other(format!("cannot watch the ROM: {}", err)).into()
+}
@@ -0,0 +1,99 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use crate::tone::{ToneGenerator, Waveform};
+
+pub const SAMPLE_RATE: u32 = 44100;
+// 44100 / 60 comes out even, so every frame gets exactly the same number of samples
+const SAMPLES_PER_FRAME: usize = SAMPLE_RATE as usize / 60;
+const HEADER_LEN: u32 = 44;
+
+// 16 bit mono PCM. The header is rewritten on every update_header, so the file stays
+// playable even if the process is killed before finish.
+pub struct WavWriter<W: Write + Seek> {
+    out: W,
+    sample_rate: u32,
+    samples: u32,
+}
+
+impl<W: Write + Seek> WavWriter<W> {
+    pub fn new(out: W, sample_rate: u32) -> io::Result<Self> {
+        let mut writer = WavWriter {
+            out,
+            sample_rate,
+            samples: 0,
+        };
+        writer.write_header()?;
+        Ok(writer)
+    }
+
+    pub fn write_sample(&mut self, sample: f32) -> io::Result<()> {
+        let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        self.out.write_all(&value.to_le_bytes())?;
+        self.samples += 1;
+        Ok(())
+    }
+
+    pub fn update_header(&mut self) -> io::Result<()> {
+        self.out.seek(SeekFrom::Start(0))?;
+        self.write_header()?;
+        self.out.seek(SeekFrom::End(0))?;
+        self.out.flush()
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        self.update_header()?;
+        Ok(self.out)
+    }
+
+    fn write_header(&mut self) -> io::Result<()> {
+        let data_len = self.samples * 2;
+        let out = &mut self.out;
+        out.write_all(b"RIFF")?;
+        out.write_all(&(HEADER_LEN - 8 + data_len).to_le_bytes())?;
+        out.write_all(b"WAVEfmt ")?;
+        out.write_all(&16u32.to_le_bytes())?;
+        // PCM, one channel
+        out.write_all(&1u16.to_le_bytes())?;
+        out.write_all(&1u16.to_le_bytes())?;
+        out.write_all(&self.sample_rate.to_le_bytes())?;
+        out.write_all(&(self.sample_rate * 2).to_le_bytes())?;
+        // bytes per sample and bits per sample
+        out.write_all(&2u16.to_le_bytes())?;
+        out.write_all(&16u16.to_le_bytes())?;
+        out.write_all(b"data")?;
+        out.write_all(&data_len.to_le_bytes())
+    }
+}
+
+// Renders the buzzer offline, one 60 Hz frame at a time, so the recording follows the
+// sound timer exactly no matter what the sound device is doing.
+pub struct SoundRecorder {
+    tone: ToneGenerator,
+    wav: WavWriter<BufWriter<File>>,
+}
+
+impl SoundRecorder {
+    pub fn create<P: AsRef<Path>>(path: P, waveform: Waveform, frequency: f32, volume: f32) -> io::Result<Self> {
+        let file = BufWriter::new(File::create(path)?);
+        Ok(SoundRecorder {
+            tone: ToneGenerator::new(waveform, frequency, volume, SAMPLE_RATE),
+            wav: WavWriter::new(file, SAMPLE_RATE)?,
+        })
+    }
+
+    // `sound` is whether the sound timer was running during the frame
+    pub fn frame(&mut self, sound: bool) -> io::Result<()> {
+        for _ in 0..SAMPLES_PER_FRAME {
+            self.wav.write_sample(self.tone.next_sample(sound))?;
+        }
+        self.wav.update_header()
+    }
+
+    pub fn finish(self) -> io::Result<()> {
+        self.wav.finish()?.flush()
+    }
+}
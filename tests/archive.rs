@@ -0,0 +1,69 @@
+// ROMs packed in zip archives and gzip files, built in memory.
+
+use std::io::{Cursor, Write};
+
+use flate2::{write::GzEncoder, Compression};
+use rust_8::{extract_zip_rom, gunzip_rom, split_rom_path, RomLoadError};
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+const PONG: [u8; 4] = [0x00, 0xE0, 0x12, 0x02];
+const TETRIS: [u8; 2] = [0x12, 0x00];
+
+fn zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, data) in files {
+        writer.start_file(*name, SimpleFileOptions::default()).unwrap();
+        writer.write_all(data).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
+
+#[test]
+fn zip_without_a_rom_is_an_error() {
+    let archive = zip(&[("README.txt", b"no games here")]);
+    assert!(matches!(extract_zip_rom(&archive, None), Err(RomLoadError::NoRomInArchive)));
+}
+
+#[test]
+fn zip_with_one_rom_loads_it() {
+    let archive = zip(&[("README.txt", b"pong"), ("games/PONG.CH8", &PONG)]);
+    assert_eq!(extract_zip_rom(&archive, None).unwrap(), PONG);
+}
+
+#[test]
+fn zip_with_several_roms_lists_them() {
+    let archive = zip(&[("tetris.ch8", &TETRIS), ("pong.ch8", &PONG)]);
+    match extract_zip_rom(&archive, None) {
+        Err(RomLoadError::SeveralRoms(names)) => assert_eq!(names, ["pong.ch8", "tetris.ch8"]),
+        other => panic!("expected SeveralRoms, got {:?}", other),
+    }
+    assert_eq!(extract_zip_rom(&archive, Some("tetris.ch8")).unwrap(), TETRIS);
+    assert!(matches!(extract_zip_rom(&archive, Some("brix.ch8")), Err(RomLoadError::NoSuchEntry(_))));
+}
+
+#[test]
+fn not_a_zip_is_an_error() {
+    assert!(matches!(extract_zip_rom(&PONG, None), Err(RomLoadError::Zip(_))));
+}
+
+#[test]
+fn gzip_is_decompressed() {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&PONG).unwrap();
+    assert_eq!(gunzip_rom(&encoder.finish().unwrap()).unwrap(), PONG);
+}
+
+#[test]
+fn gzip_that_unpacks_too_large_is_an_error() {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&vec![0; 0x20000]).unwrap();
+    assert!(matches!(gunzip_rom(&encoder.finish().unwrap()), Err(RomLoadError::TooLarge { .. })));
+}
+
+#[test]
+fn archive_entry_is_split_off() {
+    assert_eq!(split_rom_path("roms/pack.zip:games/pong.ch8"), ("roms/pack.zip", Some("games/pong.ch8")));
+    assert_eq!(split_rom_path("roms/PACK.ZIP:pong.ch8"), ("roms/PACK.ZIP", Some("pong.ch8")));
+    assert_eq!(split_rom_path("roms/pack.zip"), ("roms/pack.zip", None));
+    assert_eq!(split_rom_path("roms/pong.ch8"), ("roms/pong.ch8", None));
+}
@@ -0,0 +1,70 @@
+// The screen as text: a character per pixel or per pair of rows, with or without a ruler.
+
+use rust_8::{AsciiDump, DebugCommand, Framebuffer};
+
+fn framebuffer(width: usize, height: usize, lit: &[(usize, usize)]) -> Framebuffer {
+    let mut display = Framebuffer::with_size(width, height);
+    for &(x, y) in lit {
+        display.set_pixel(x, y, true);
+    }
+    display
+}
+
+fn dump(on: char, off: char, ruler: bool, half_blocks: bool) -> AsciiDump {
+    AsciiDump { on, off, ruler, half_blocks }
+}
+
+#[test]
+fn a_character_per_pixel() {
+    let display = framebuffer(64, 32, &[(0, 0), (63, 0), (1, 1), (62, 31)]);
+    let text = AsciiDump::default().render(&display);
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 32);
+    assert!(lines.iter().all(|line| line.chars().count() == 64));
+    assert_eq!(lines[0], format!("█{}█", "·".repeat(62)));
+    assert_eq!(lines[1], format!("·█{}", "·".repeat(62)));
+    assert_eq!(lines[2], "·".repeat(64));
+    assert_eq!(lines[31], format!("{}█·", "·".repeat(62)));
+    assert!(text.ends_with('\n'));
+}
+
+#[test]
+fn custom_characters_and_a_ruler() {
+    let display = framebuffer(12, 3, &[(0, 0), (10, 1), (11, 2)]);
+    assert_eq!(
+        dump('#', '.', true, false).render(&display),
+        "  0         1\n  012345678901\n0 #...........\n1 ..........#.\n2 ...........#\n"
+    );
+}
+
+#[test]
+fn the_ruler_lines_up_with_wide_screens() {
+    let display = framebuffer(128, 64, &[(127, 63)]);
+    let text = dump('#', '.', true, false).render(&display);
+    let lines: Vec<&str> = text.lines().collect();
+    // hundreds, tens and units, then the 64 rows
+    assert_eq!(lines.len(), 3 + 64);
+    assert_eq!(lines[0], format!("   0{}1", " ".repeat(99)));
+    assert_eq!(&lines[1][3..14], "0         1");
+    assert_eq!(&lines[2][3..16], "0123456789012");
+    assert_eq!(lines[3], format!(" 0 {}", ".".repeat(128)));
+    assert_eq!(lines[66], format!("63 {}#", ".".repeat(127)));
+}
+
+#[test]
+fn half_blocks_fold_rows_in_pairs() {
+    let display = framebuffer(8, 4, &[(0, 0), (1, 1), (2, 0), (2, 1), (3, 3)]);
+    assert_eq!(dump('#', ' ', false, true).render(&display), "▀▄█     \n   ▄    \n");
+    // hires folds to 32 lines of 128
+    let text = AsciiDump { half_blocks: true, ..AsciiDump::default() }.render(&framebuffer(128, 64, &[]));
+    assert_eq!(text, format!("{}\n", "·".repeat(128)).repeat(32));
+    // an odd last row has nothing under it
+    assert_eq!(dump('#', '.', true, true).render(&framebuffer(8, 3, &[(0, 2), (1, 1)])), "  01234567\n0 .▄......\n2 ▀.......\n");
+}
+
+#[test]
+fn parses_the_screen_command() {
+    assert_eq!("screen".parse(), Ok(DebugCommand::Screen { ruler: false, half_blocks: false }));
+    assert_eq!("screen half ruler".parse(), Ok(DebugCommand::Screen { ruler: true, half_blocks: true }));
+    assert_eq!("screen big".parse::<DebugCommand>(), Err(String::from("expected ruler or half, got \"big\"")));
+}
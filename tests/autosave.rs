@@ -0,0 +1,101 @@
+// Sessions saved on exit and picked up by --resume, and the ways a save falls back to a
+// fresh boot.
+#![cfg(feature = "serde")]
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use rust_8::{autosave_path, restore_autosave, save_autosave, Chip8, Chip8Builder};
+
+// 200: RND V0, FF
+// 202: ADD V1, 01
+// 204: JP 200
+const COUNTER: [u8; 6] = [0xC0, 0xFF, 0x71, 0x01, 0x12, 0x00];
+// 200: ADD V2, 01
+// 202: JP 200
+const OTHER: [u8; 4] = [0x72, 0x01, 0x12, 0x00];
+
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rust-8-autosave-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+fn booted(rom: &[u8]) -> Chip8 {
+    let mut chip8 = Chip8Builder::new().with_rng_seed(3).build();
+    chip8.load_rom(rom.to_vec()).unwrap();
+    chip8
+}
+
+fn played(rom: &[u8], steps: usize) -> Chip8 {
+    let mut chip8 = booted(rom);
+    for _ in 0..steps {
+        chip8.step().unwrap();
+    }
+    chip8
+}
+
+// restoring from dir is refused and leaves the freshly booted machine as it was
+fn boots_fresh(dir: &Path, rom: &[u8]) {
+    let mut chip8 = booted(rom);
+    let fresh = chip8.save_state();
+    assert!(!restore_autosave(dir, &mut chip8));
+    assert_eq!(chip8.save_state(), fresh);
+}
+
+#[test]
+fn a_saved_session_resumes_where_it_stopped() {
+    let dir = scratch("resume");
+    let session = played(&COUNTER, 31);
+    save_autosave(&dir, &session).unwrap();
+    assert!(autosave_path(&dir, session.rom_hash()).is_file());
+
+    let mut chip8 = booted(&COUNTER);
+    assert!(restore_autosave(&dir, &mut chip8));
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(chip8.save_state(), session.save_state());
+    assert_eq!(chip8.register(1), 10);
+}
+
+#[test]
+fn no_save_boots_fresh() {
+    let dir = scratch("missing");
+    boots_fresh(&dir, &COUNTER);
+}
+
+#[test]
+fn a_save_from_another_rom_boots_fresh() {
+    let dir = scratch("mismatch");
+    let other = played(&OTHER, 9);
+    save_autosave(&dir, &other).unwrap();
+    // the file sits where this ROM's save would, but was written by a different ROM
+    let counter = booted(&COUNTER);
+    fs::rename(autosave_path(&dir, other.rom_hash()), autosave_path(&dir, counter.rom_hash())).unwrap();
+
+    boots_fresh(&dir, &COUNTER);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn a_corrupt_save_boots_fresh() {
+    let dir = scratch("corrupt");
+    let session = played(&COUNTER, 31);
+    save_autosave(&dir, &session).unwrap();
+    let file = autosave_path(&dir, session.rom_hash());
+    let text = fs::read_to_string(&file).unwrap();
+
+    // cut off halfway through writing
+    fs::write(&file, &text[..text.len() / 2]).unwrap();
+    boots_fresh(&dir, &COUNTER);
+    // not JSON at all
+    fs::write(&file, [0xFF, 0x00, 0x13, 0x37]).unwrap();
+    boots_fresh(&dir, &COUNTER);
+    // well-formed, but the state inside it is impossible
+    let mut json: serde_json::Value = serde_json::from_str(&text).unwrap();
+    json["state"]["pc"] = 0xFFFF.into();
+    fs::write(&file, json.to_string()).unwrap();
+    boots_fresh(&dir, &COUNTER);
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,34 @@
+// Every ROM compiled into the binary runs headlessly without an error.
+
+use rust_8::{builtin_rom, Chip8Builder, BUILTIN_ROMS};
+
+const FRAMES: usize = 300;
+
+#[test]
+fn every_builtin_rom_runs() {
+    for rom in BUILTIN_ROMS.iter() {
+        let mut chip8 = Chip8Builder::new().with_variant(rom.variant).with_rng_seed(0).build();
+        chip8.load_rom(rom.data.to_vec()).unwrap_or_else(|err| panic!("{}: {}", rom.name, err));
+        for _ in 0..FRAMES {
+            for _ in 0..rom.variant.instructions_per_frame() {
+                chip8.step().unwrap_or_else(|err| panic!("{}: {}", rom.name, err));
+            }
+            chip8.tick_timers();
+        }
+        let display = chip8.display();
+        assert!(
+            (0..display.height()).any(|y| (0..display.width()).any(|x| display.pixel(x, y))),
+            "{} left the screen blank",
+            rom.name
+        );
+    }
+}
+
+#[test]
+fn builtin_roms_are_found_by_name() {
+    for rom in BUILTIN_ROMS.iter() {
+        assert_eq!(builtin_rom(rom.name), Some(rom));
+    }
+    assert_eq!(builtin_rom("list"), None);
+    assert_eq!(builtin_rom("tetris"), None);
+}
@@ -0,0 +1,162 @@
+// Symbol files, and the call stack Debugger keeps from the CALLs and RETs a ROM runs.
+
+use rust_8::{CallStack, Chip8Builder, Debugger, Frame, Instruction, StackAnomaly, StackSnapshot, SymbolError, SymbolTable};
+
+// 200: CALL 206
+// 202: CALL 20A
+// 204: JP 204
+// 206: CALL 20A
+// 208: RET
+// 20A: RET
+const NESTED: [u8; 12] = [0x22, 0x06, 0x22, 0x0A, 0x12, 0x04, 0x22, 0x0A, 0x00, 0xEE, 0x00, 0xEE];
+
+// 200: CALL 204
+// 202: JP 202
+// 204: JP 200         leaves the routine without returning
+const LEAKY: [u8; 6] = [0x22, 0x04, 0x12, 0x02, 0x12, 0x00];
+
+const SYMBOLS: &str = "# nested calls\n0x200 main\n0x0206 outer\n20A inner\n";
+
+fn debugger(rom: &[u8]) -> Debugger {
+    let mut chip8 = Chip8Builder::new().with_rng_seed(0).build();
+    chip8.load_rom(rom.to_vec()).unwrap();
+    Debugger::new(chip8, 9).unwrap()
+}
+
+fn step(debugger: &mut Debugger, n: usize) {
+    for _ in 0..n {
+        debugger.step().unwrap();
+    }
+}
+
+fn error_line(text: &str) -> usize {
+    match SymbolTable::parse(text) {
+        Err(SymbolError::Parse { line, .. }) => line,
+        Ok(symbols) => panic!("{:?} parsed as {:?}", text, symbols),
+    }
+}
+
+fn stack(addresses: &[u16]) -> StackSnapshot {
+    let mut stack = [0; 16];
+    stack[..addresses.len()].copy_from_slice(addresses);
+    StackSnapshot {
+        sp: addresses.len() as u8,
+        stack,
+    }
+}
+
+#[test]
+fn parses_a_symbol_file() {
+    let symbols = SymbolTable::parse(SYMBOLS).unwrap();
+    assert_eq!(symbols.len(), 3);
+    assert_eq!(symbols.name(0x206), Some("outer"));
+    assert_eq!(symbols.address("inner"), Some(0x20A));
+    assert_eq!(symbols.name(0x208), None);
+    assert_eq!(symbols.label(0x208).as_deref(), Some("outer+2"));
+    assert_eq!(symbols.label(0x20A).as_deref(), Some("inner"));
+    assert_eq!(symbols.label(0x1FE), None);
+}
+
+#[test]
+fn symbol_file_errors_give_the_line() {
+    assert_eq!(error_line("0x200 main\n0x204\n"), 2);
+    assert_eq!(error_line("main 0x200\n"), 1);
+    assert_eq!(error_line("0x200 main extra\n"), 1);
+    assert_eq!(error_line("0x200 main\n\n0x200 again\n"), 3);
+    assert_eq!(error_line("0x200 main\n0x204 main\n"), 2);
+    assert_eq!(error_line("0x10000 far\n"), 1);
+}
+
+#[test]
+fn annotates_instructions_with_labels() {
+    let symbols = SymbolTable::parse(SYMBOLS).unwrap();
+    assert_eq!(symbols.annotate(0x200, Instruction::Call(0x206)), "0x200 <main>: Call(518) -> outer");
+    assert_eq!(symbols.annotate(0x208, Instruction::Ret), "0x208 <outer+2>: Ret");
+    // without a symbol it's the trace log's usual line
+    assert_eq!(SymbolTable::new().annotate(0x200, Instruction::Call(0x206)), "0x200: Call(518)");
+}
+
+#[test]
+fn calls_and_returns_make_frames() {
+    let mut debugger = debugger(&NESTED);
+    step(&mut debugger, 2);
+    assert_eq!(
+        debugger.call_stack().frames(),
+        [
+            Frame { entry: Some(0x206), call_site: 0x200 },
+            Frame { entry: Some(0x20A), call_site: 0x206 },
+        ]
+    );
+    let symbols = SymbolTable::parse(SYMBOLS).unwrap();
+    assert_eq!(
+        debugger.call_stack().backtrace(Some(&symbols)),
+        ["#0 0x020A inner (called from 0x0206 outer)", "#1 0x0206 outer (called from 0x0200 main)"]
+    );
+    assert_eq!(
+        debugger.call_stack().backtrace(None),
+        ["#0 0x020A (called from 0x0206)", "#1 0x0206 (called from 0x0200)"]
+    );
+
+    step(&mut debugger, 2);
+    assert_eq!(debugger.call_stack().depth(), 0);
+    step(&mut debugger, 1);
+    assert_eq!(debugger.call_stack().frames(), [Frame { entry: Some(0x20A), call_site: 0x202 }]);
+    step(&mut debugger, 10);
+    assert_eq!(debugger.call_stack().depth(), 0);
+    assert!(debugger.call_stack().anomalies().is_empty());
+}
+
+#[test]
+fn going_back_restores_the_frames() {
+    let mut debugger = debugger(&NESTED).with_checkpoint_interval(3);
+    step(&mut debugger, 4);
+    assert_eq!(debugger.call_stack().depth(), 0);
+    debugger.reverse_step(2).unwrap();
+    assert_eq!(debugger.call_stack().depth(), 2);
+    debugger.reverse_step(1).unwrap();
+    assert_eq!(debugger.call_stack().frames(), [Frame { entry: Some(0x206), call_site: 0x200 }]);
+}
+
+#[test]
+fn a_routine_left_by_a_jump_is_reentered() {
+    let mut debugger = debugger(&LEAKY);
+    step(&mut debugger, 2);
+    assert!(debugger.call_stack().anomalies().is_empty());
+    step(&mut debugger, 1);
+    assert_eq!(debugger.call_stack().anomalies(), [StackAnomaly::Reentered { pc: 0x200 }]);
+    assert_eq!(debugger.call_stack().depth(), 2);
+}
+
+#[test]
+fn a_stack_changed_behind_its_back_is_read_again() {
+    let mut ram = vec![0; 4096];
+    ram[0x300..0x302].copy_from_slice(&[0x25, 0x00]);
+    let mut call_stack = CallStack::new();
+    call_stack.update(0x300, Instruction::Call(0x500), &stack(&[0x302]), &ram);
+    assert!(call_stack.anomalies().is_empty());
+
+    // a machine code routine pushes a return address that follows a CALL, then one that
+    // doesn't
+    call_stack.update(0x500, Instruction::Sys(0x600), &stack(&[0x302, 0x302, 0x402]), &ram);
+    assert_eq!(
+        call_stack.anomalies(),
+        [
+            StackAnomaly::Rewritten { pc: 0x500 },
+            StackAnomaly::NotACall { pc: 0x500, return_address: 0x402 },
+        ]
+    );
+    assert_eq!(
+        call_stack.frames(),
+        [
+            Frame { entry: Some(0x500), call_site: 0x300 },
+            Frame { entry: Some(0x500), call_site: 0x300 },
+            Frame { entry: None, call_site: 0x400 },
+        ]
+    );
+    assert_eq!(call_stack.backtrace(None)[0], "#0 ? (called from 0x0400)");
+
+    // and a state restored with an empty stack
+    call_stack.update(0x502, Instruction::Cls, &stack(&[]), &ram);
+    assert_eq!(call_stack.depth(), 0);
+    assert_eq!(call_stack.anomalies().last(), Some(&StackAnomaly::Rewritten { pc: 0x502 }));
+}
@@ -0,0 +1,32 @@
+// Colors as --fg, --bg and the config file take them.
+
+use rust_8::parse_color;
+
+#[test]
+fn hex_colors_with_and_without_the_hash() {
+    assert_eq!(parse_color("#FF8800"), Ok(0xFF8800));
+    assert_eq!(parse_color("FF8800"), Ok(0xFF8800));
+    assert_eq!(parse_color("#1a2b3c"), Ok(0x1A2B3C));
+    assert_eq!(parse_color("000000"), Ok(0x000000));
+    assert_eq!(parse_color("  #FFFFFF\n"), Ok(0xFFFFFF));
+}
+
+#[test]
+fn color_names_in_any_case() {
+    assert_eq!(parse_color("amber"), Ok(0xFF8800));
+    assert_eq!(parse_color("Black"), Ok(0x000000));
+    assert_eq!(parse_color("WHITE"), Ok(0xFFFFFF));
+    assert_eq!(parse_color(" green "), Ok(0x00FF00));
+}
+
+#[test]
+fn malformed_colors_say_what_is_wrong() {
+    for text in ["", "#", "#FFF", "FFFFF", "#FF88001", "##FF8800", "pink", "0xFF8800"] {
+        let err = parse_color(text).unwrap_err();
+        assert!(err.starts_with("expected #RRGGBB, RRGGBB or a color name"), "{:?}: {}", text, err);
+    }
+    assert_eq!(parse_color("#FF88G0"), Err(String::from("'G' is not a hex digit in \"#FF88G0\"")));
+    assert_eq!(parse_color("-12345"), Err(String::from("'-' is not a hex digit in \"-12345\"")));
+    // six bytes, but not six digits
+    assert!(parse_color("ééé").unwrap_err().contains("is not a hex digit"));
+}
@@ -0,0 +1,94 @@
+// Two machines in lockstep, built alike or with different quirks.
+
+use rust_8::{Chip8, Chip8Builder, Comparison, QuirkConfig, BUILTIN_ROMS};
+
+fn machine(rom: &[u8], quirks: QuirkConfig) -> Chip8 {
+    let mut chip8 = Chip8Builder::new().with_rng_seed(7).with_quirks(quirks).build();
+    chip8.load_rom(rom.to_vec()).unwrap();
+    chip8
+}
+
+fn run(comparison: &mut Comparison, instructions: usize) {
+    for _ in 0..instructions {
+        comparison.step().unwrap();
+    }
+}
+
+#[test]
+fn identical_profiles_never_diverge() {
+    for rom in BUILTIN_ROMS.iter() {
+        let make = || {
+            let mut chip8 = Chip8Builder::new().with_variant(rom.variant).with_rng_seed(7).build();
+            chip8.load_rom(rom.data.to_vec()).unwrap();
+            chip8
+        };
+        let mut comparison = Comparison::new(make(), make());
+        for _frame in 0..300 {
+            run(&mut comparison, rom.variant.instructions_per_frame());
+            comparison.tick_timers();
+        }
+        assert_eq!(comparison.divergence(), None, "{}", rom.name);
+        assert!(!comparison.displays_differ(), "{}", rom.name);
+    }
+}
+
+#[test]
+fn bnnn_diverges_at_the_jump() {
+    // 200: LD V0, 04
+    // 202: LD V2, 08
+    // 204: JP V0, 220
+    let rom = [0x60, 0x04, 0x62, 0x08, 0xB2, 0x20];
+    let mut comparison = Comparison::new(machine(&rom, QuirkConfig::chip8_original()), machine(&rom, QuirkConfig::chip48()));
+    run(&mut comparison, 2);
+    assert_eq!(comparison.divergence(), None);
+    run(&mut comparison, 1);
+
+    let divergence = comparison.divergence().unwrap();
+    assert_eq!((divergence.instruction, divergence.pc, divergence.opcode), (3, 0x204, 0xB220));
+    assert_eq!(divergence.diff.pc_changed, Some((0x224, 0x228)));
+    assert!(divergence.diff.changed_registers.is_empty());
+    assert!(divergence.to_string().starts_with("diverged at instruction 3: 0204 B220 JpV0(544)"), "{}", divergence);
+}
+
+#[test]
+fn fx1e_diverges_at_the_add_and_stays_put() {
+    // 200: LD VF, AA
+    // 202: LD V0, 01
+    // 204: LD I, FFF
+    // 206: ADD I, V0
+    // 208: ADD V1, 01
+    // 20A: JP 208
+    let rom = [0x6F, 0xAA, 0x60, 0x01, 0xAF, 0xFF, 0xF0, 0x1E, 0x71, 0x01, 0x12, 0x08];
+    let mut comparison = Comparison::new(machine(&rom, QuirkConfig::chip8_original()), machine(&rom, QuirkConfig::chip48()));
+    run(&mut comparison, 3);
+    assert_eq!(comparison.divergence(), None);
+    // the first divergence is kept however much longer they run
+    run(&mut comparison, 100);
+
+    let divergence = comparison.divergence().unwrap();
+    assert_eq!((divergence.instruction, divergence.pc, divergence.opcode), (4, 0x206, 0xF01E));
+    assert_eq!(divergence.diff.changed_registers, vec![(0xF, 0x01, 0xAA)]);
+    assert_eq!(divergence.diff.pc_changed, None);
+    assert_eq!(comparison.instructions(), 103);
+}
+
+#[test]
+fn the_shift_quirk_diverges_at_the_shift() {
+    // 200: LD V1, 03
+    // 202: LD V2, 10
+    // 204: SHR V1, V2
+    // 206: JP 206
+    let rom = [0x61, 0x03, 0x62, 0x10, 0x81, 0x26, 0x12, 0x06];
+    let vy = QuirkConfig { shift_uses_vy: true, ..QuirkConfig::default() };
+    let mut comparison = Comparison::new(machine(&rom, vy), machine(&rom, QuirkConfig::default()));
+    run(&mut comparison, 2);
+    assert_eq!(comparison.divergence(), None);
+    run(&mut comparison, 10);
+
+    let divergence = comparison.divergence().unwrap();
+    assert_eq!((divergence.instruction, divergence.pc, divergence.opcode), (3, 0x204, 0x8126));
+    // V2 shifted into V1, against V1 shifted in place
+    assert_eq!(divergence.diff.changed_registers, vec![(0x1, 0x08, 0x01), (0xF, 0x00, 0x01)]);
+    assert_eq!(divergence.diff.pc_changed, None);
+    assert!(divergence.to_string().starts_with("diverged at instruction 3: 0204 8126 Shr(1, 2)"), "{}", divergence);
+}
@@ -0,0 +1,39 @@
+use std::{fs, panic};
+
+use rust_8::{crash_report, step_or_report_to, Chip8Builder, CpuSnapshot};
+
+#[test]
+fn a_panicking_step_leaves_a_report_behind() {
+    let dir = std::env::temp_dir().join(format!("rust-8-crash-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    // a host routine with a bug in it stands in for a broken opcode
+    let mut chip8 = Chip8Builder::new()
+        .with_syscall_handler(Box::new(|address, _| panic!("no routine at {:#05X}", address)))
+        .build();
+    // 200: LD V3, 2A
+    // 202: SYS 123
+    chip8.load_rom(vec![0x63, 0x2A, 0x01, 0x23]).unwrap();
+    step_or_report_to(&mut chip8, &dir).unwrap();
+
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| step_or_report_to(&mut chip8, &dir)));
+    assert!(result.is_err(), "the panic carries on once the report is written");
+    let reports: Vec<_> = fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().path()).collect();
+    assert_eq!(reports.len(), 1);
+    let report = fs::read_to_string(&reports[0]).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(report.starts_with("rust-8 crash report\npanic: no routine at 0x123\n"), "{}", report);
+    assert!(report.contains("V3=2A"), "{}", report);
+    // the instruction that panicked made it into the history, and the PC had moved past it
+    assert!(report.contains("0202  Sys(291)"), "{}", report);
+    assert!(report.contains("0200  63 2A 01 23>00"), "{}", report);
+}
+
+#[test]
+fn a_pc_past_the_end_of_ram_still_gets_a_report() {
+    let mut chip8 = Chip8Builder::new().build();
+    chip8.restore_cpu(CpuSnapshot { vx: [0; 16], pc: 0xFFFE, i: 0 });
+    let report = crash_report(&chip8, "ran off the end");
+    assert!(report.contains("PC=FFFE"), "{}", report);
+    assert!(report.ends_with("ram around PC:\n"), "{}", report);
+}
@@ -0,0 +1,267 @@
+// Reverse stepping with Debugger, checked against machines that only ever ran forward.
+
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use rust_8::{Break, BreakEvent, Chip8, Chip8Builder, DebugCommand, Debugger, Instruction, Keypad};
+
+// 200: RND V0, 3F
+// 202: RND V1, 1F
+// 204: LD V2, DT
+// 206: SE V2, 00
+// 208: JP 210
+// 20A: LD V3, 05
+// 20C: LD DT, V3
+// 20E: ADD V4, 01
+// 210: LD F, V0
+// 212: DRW V0, V1, 5
+// 214: SKP V5           key 0
+// 216: ADD V6, 01
+// 218: JP 200
+const ROM: [u8; 26] = [
+    0xC0, 0x3F, 0xC1, 0x1F, 0xF2, 0x07, 0x32, 0x00, 0x12, 0x10, 0x63, 0x05, 0xF3, 0x15, 0x74, 0x01, 0xF0, 0x29, 0xD0,
+    0x15, 0xE5, 0x9E, 0x76, 0x01, 0x12, 0x00,
+];
+const INSTRUCTIONS_PER_FRAME: usize = 9;
+
+// one event each, at instructions 2, 6 and 7 of every 9
+// 200: LD V0, 05
+// 202: LD F, V0
+// 204: DRW V0, V0, 5    draw
+// 206: LD V1, 00
+// 208: LD ST, V1        quiet, the timer stays 0
+// 20A: LD V1, 03
+// 20C: LD ST, V1        sound
+// 20E: SKP V0           keyquery
+// 210: JP 200
+const EVENTS: [u8; 18] = [
+    0x60, 0x05, 0xF0, 0x29, 0xD0, 0x05, 0x61, 0x00, 0xF1, 0x18, 0x61, 0x03, 0xF1, 0x18, 0xE0, 0x9E, 0x12, 0x00,
+];
+
+fn machine() -> Chip8 {
+    let mut chip8 = Chip8Builder::new().with_rng_seed(7).build();
+    chip8.load_rom(ROM.to_vec()).unwrap();
+    chip8
+}
+
+// key 0 goes down before instruction 121 and up before instruction 301
+fn keypad_at(position: u64) -> Option<Keypad> {
+    let mut keypad = Keypad::new();
+    match position {
+        120 => {
+            keypad.press(0);
+            Some(keypad)
+        }
+        300 => Some(keypad),
+        _ => None,
+    }
+}
+
+fn run_forward(instructions: u64) -> Chip8 {
+    let mut chip8 = machine();
+    for position in 0..instructions {
+        if let Some(keypad) = keypad_at(position) {
+            *chip8.keypad_mut() = keypad;
+        }
+        chip8.step().unwrap();
+        if (position + 1) % INSTRUCTIONS_PER_FRAME as u64 == 0 {
+            chip8.tick_timers();
+        }
+    }
+    chip8
+}
+
+fn debugger(interval: u64, instructions: u64) -> Debugger {
+    let mut debugger = Debugger::new(machine(), INSTRUCTIONS_PER_FRAME).unwrap().with_checkpoint_interval(interval);
+    for position in 0..instructions {
+        if let Some(keypad) = keypad_at(position) {
+            debugger.set_keypad(keypad);
+        }
+        debugger.step().unwrap();
+    }
+    debugger
+}
+
+#[test]
+fn stepping_back_matches_a_shorter_run() {
+    let mut expected = run_forward(463);
+    for interval in [1, 7, 100, 1000] {
+        let mut debugger = debugger(interval, 500);
+        debugger.reverse_step(37).unwrap();
+        assert_eq!(debugger.position(), 463);
+        assert_eq!(debugger.chip8().save_state(), expected.save_state(), "every {} instructions", interval);
+        assert_eq!(*Chip8::keypad_mut(&mut expected), Keypad::new());
+    }
+}
+
+#[test]
+fn stepping_forward_again_gets_back_to_the_same_place() {
+    let expected = run_forward(500);
+    let mut debugger = debugger(50, 500);
+    debugger.reverse_step(37).unwrap();
+    for _ in 0..37 {
+        debugger.step().unwrap();
+    }
+    assert_eq!(debugger.chip8().save_state(), expected.save_state());
+
+    // back past a key press, which is still replayed on the way to the target
+    debugger.reverse_step(390).unwrap();
+    assert_eq!(debugger.chip8().save_state(), run_forward(110).save_state());
+    debugger.reverse_step(1000).unwrap();
+    assert_eq!(debugger.position(), 0);
+    assert_eq!(debugger.chip8().save_state(), machine().save_state());
+}
+
+#[test]
+fn reverse_continue_stops_at_the_last_breakpoint_hit() {
+    // the last time PC was at 20A, the delay timer setup, in 500 forward steps
+    let mut chip8 = run_forward(0);
+    let mut last_hit = None;
+    for position in 0..500u64 {
+        if chip8.pc() == 0x20A {
+            last_hit = Some(position);
+        }
+        if let Some(keypad) = keypad_at(position) {
+            *chip8.keypad_mut() = keypad;
+        }
+        chip8.step().unwrap();
+        if (position + 1) % INSTRUCTIONS_PER_FRAME as u64 == 0 {
+            chip8.tick_timers();
+        }
+    }
+    let last_hit = last_hit.unwrap();
+
+    let mut debugger = debugger(64, 500);
+    debugger.add_breakpoint(0x20A);
+    assert!(debugger.reverse_continue().unwrap());
+    assert_eq!(debugger.position(), last_hit);
+    assert_eq!(debugger.chip8().pc(), 0x20A);
+    assert_eq!(debugger.chip8().save_state(), run_forward(last_hit).save_state());
+
+    // and forward again to the next one, which is the same place
+    debugger.reverse_step(1).unwrap();
+    assert!(debugger.run_to_breakpoint(1000).unwrap());
+    assert_eq!(debugger.position(), last_hit);
+
+    debugger.remove_breakpoint(0x20A);
+    assert!(!debugger.reverse_continue().unwrap());
+    assert_eq!(debugger.position(), 0);
+}
+
+fn events_debugger() -> Debugger {
+    let mut chip8 = Chip8Builder::new().with_rng_seed(0).build();
+    chip8.load_rom(EVENTS.to_vec()).unwrap();
+    Debugger::new(chip8, INSTRUCTIONS_PER_FRAME).unwrap()
+}
+
+#[test]
+fn breaks_before_each_event() {
+    let expected = [
+        (BreakEvent::Draw, 2, 0x204, Instruction::Drw(0, 0, 5)),
+        (BreakEvent::Sound, 6, 0x20C, Instruction::LdStVx(1)),
+        (BreakEvent::KeyQuery, 7, 0x20E, Instruction::Skp(0)),
+    ];
+    for (event, position, pc, instruction) in expected {
+        let mut debugger = events_debugger();
+        debugger.break_on(event, true);
+        assert_eq!(debugger.run_to_break(100).unwrap(), Some(Break::Event { event, pc, instruction }));
+        assert_eq!(debugger.position(), position, "{}", event);
+        // the instruction hasn't run
+        assert_eq!(debugger.chip8().pc(), pc);
+        // sticky, so continuing stops at the same place a loop later
+        assert_eq!(debugger.run_to_break(100).unwrap(), Some(Break::Event { event, pc, instruction }));
+        assert_eq!(debugger.position(), position + 9, "{}", event);
+    }
+}
+
+#[test]
+fn a_one_shot_event_break_goes_once_hit() {
+    let mut debugger = events_debugger();
+    debugger.break_on(BreakEvent::KeyQuery, false);
+    debugger.break_on(BreakEvent::Draw, false);
+    assert!(matches!(debugger.run_to_break(100).unwrap(), Some(Break::Event { event: BreakEvent::Draw, .. })));
+    assert!(matches!(debugger.run_to_break(100).unwrap(), Some(Break::Event { event: BreakEvent::KeyQuery, .. })));
+    assert_eq!(debugger.position(), 7);
+    assert_eq!(debugger.run_to_break(100).unwrap(), None);
+    assert_eq!(debugger.position(), 107);
+
+    debugger.break_on(BreakEvent::Sound, true);
+    assert!(debugger.clear_break_on(BreakEvent::Sound));
+    assert!(!debugger.clear_break_on(BreakEvent::Sound));
+    assert_eq!(debugger.run_to_break(100).unwrap(), None);
+}
+
+#[test]
+fn waiting_for_a_key_is_a_key_query() {
+    // 200: LD V3, K
+    let mut chip8 = Chip8Builder::new().with_rng_seed(0).build();
+    chip8.load_rom(vec![0xF3, 0x0A]).unwrap();
+    assert!(BreakEvent::KeyQuery.matches(Instruction::LdVxK(3), &chip8));
+    assert!(!BreakEvent::KeyQuery.matches(Instruction::LdVxDt(3), &chip8));
+    let mut debugger = Debugger::new(chip8, INSTRUCTIONS_PER_FRAME).unwrap();
+    debugger.break_on(BreakEvent::KeyQuery, true);
+    assert_eq!(
+        debugger.run_to_break(10).unwrap(),
+        Some(Break::Event { event: BreakEvent::KeyQuery, pc: 0x200, instruction: Instruction::LdVxK(3) })
+    );
+}
+
+#[test]
+fn needs_a_seeded_generator() {
+    let mut chip8 = Chip8Builder::new().build();
+    chip8.load_rom(ROM.to_vec()).unwrap();
+    assert!(Debugger::new(chip8, INSTRUCTIONS_PER_FRAME).is_err());
+}
+
+#[test]
+fn parses_commands() {
+    assert_eq!("rstep".parse(), Ok(DebugCommand::ReverseStep(1)));
+    assert_eq!("rstep 37".parse(), Ok(DebugCommand::ReverseStep(37)));
+    assert_eq!(" s 5 ".parse(), Ok(DebugCommand::Step(5)));
+    assert_eq!("reverse-continue".parse(), Ok(DebugCommand::ReverseContinue));
+    assert_eq!("rc".parse(), Ok(DebugCommand::ReverseContinue));
+    assert_eq!("break 0x2A0".parse(), Ok(DebugCommand::Break(0x2A0)));
+    assert_eq!("d 20a".parse(), Ok(DebugCommand::Delete(0x20A)));
+    assert!("rstep x".parse::<DebugCommand>().is_err());
+    assert!("break".parse::<DebugCommand>().is_err());
+    assert!("break 1000".parse::<DebugCommand>().is_err());
+    assert!("step 1 2".parse::<DebugCommand>().is_err());
+    assert!("jump".parse::<DebugCommand>().is_err());
+    assert_eq!("break-on draw".parse(), Ok(DebugCommand::BreakOn { event: BreakEvent::Draw, once: false }));
+    assert_eq!("break-on keyquery once".parse(), Ok(DebugCommand::BreakOn { event: BreakEvent::KeyQuery, once: true }));
+    assert_eq!("d sound".parse(), Ok(DebugCommand::DeleteOn(BreakEvent::Sound)));
+    assert!("break-on".parse::<DebugCommand>().is_err());
+    assert!("break-on beep".parse::<DebugCommand>().is_err());
+    assert!("break-on draw twice".parse::<DebugCommand>().is_err());
+}
+
+#[test]
+fn the_binary_steps_backwards() {
+    let path = std::env::temp_dir().join(format!("rust-8-debugger-{}.ch8", std::process::id()));
+    std::fs::write(&path, ROM).unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rust-8"))
+        .arg("debug")
+        .arg(&path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(b"step 4\nrstep 2\nbogus\nb 204\nc\nq\n").unwrap();
+    let output = child.wait_with_output().unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    // the registers, then the next instruction
+    assert!(lines[0].starts_with("0: V0=00"), "{}", stdout);
+    assert_eq!(lines[1], "0x200: Rnd(0, 63)");
+    assert!(lines[2].starts_with("4: ") && lines[2].ends_with("PC=020A I=0000"), "{}", stdout);
+    assert!(lines[4].starts_with("2: ") && lines[4].ends_with("PC=0204 I=0000"), "{}", stdout);
+    assert_eq!(lines[5], "0x204: LdVxDt(2)");
+    assert_eq!(lines[6], "unknown command \"bogus\"");
+    assert!(lines[7].starts_with("2: "), "{}", stdout);
+    assert_eq!(lines[9], "breakpoint at 0x204");
+}
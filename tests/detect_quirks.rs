@@ -0,0 +1,136 @@
+// Reading the quirks test's results off the screen. The suite's ROM isn't in the tree
+// (see tests/roms/README.md), so these screens are drawn the way it lays out its results:
+// a label and then a check or cross on each line of QUIRK_LINES.
+
+use std::process::Command;
+
+use rust_8::{
+    detect_quirks, find_glyph, glyph_at, read_quirks_screen, ChipVariant, Framebuffer, Glyph, QuirkConfig, CHECK_GLYPH,
+    CROSS_GLYPH, QUIRK_LINES,
+};
+
+const MARK_X: usize = 56;
+
+fn draw(display: &mut Framebuffer, x: usize, y: usize, glyph: &Glyph) {
+    for (row, bits) in glyph.rows.iter().enumerate() {
+        for dx in 0..glyph.width {
+            if bits >> (7 - dx) & 1 == 1 {
+                display.set_pixel(x + dx, y + row, true);
+            }
+        }
+    }
+}
+
+// a line per quirk, with a solid block standing in for the label and then the mark
+fn results_screen(passed: [bool; 6]) -> Framebuffer {
+    let mut display = Framebuffer::new();
+    for (&(_, y), passed) in QUIRK_LINES.iter().zip(passed.iter()) {
+        for x in 2..30 {
+            for row in 0..4 {
+                display.set_pixel(x, y + row, x % 4 != 1);
+            }
+        }
+        draw(&mut display, MARK_X, y, if *passed { &CHECK_GLYPH } else { &CROSS_GLYPH });
+    }
+    display
+}
+
+fn exhibited(display: &Framebuffer, platform: u8) -> Vec<(&'static str, Option<bool>)> {
+    read_quirks_screen(display, platform)
+        .iter()
+        .map(|reading| (reading.name, reading.exhibited()))
+        .collect()
+}
+
+#[test]
+fn every_quirk_as_the_platform_expects() {
+    let display = results_screen([true; 6]);
+    assert_eq!(
+        exhibited(&display, 1),
+        [
+            ("vF reset", Some(true)),
+            ("memory", Some(true)),
+            ("display wait", Some(true)),
+            ("clipping", Some(true)),
+            ("shifting", Some(false)),
+            ("jumping", Some(false)),
+        ]
+    );
+}
+
+#[test]
+fn failed_quirks_are_the_other_way_round() {
+    // CHIP-48 quirks tested as CHIP-8: VX shifted in place and BXNN jumps
+    let display = results_screen([true, true, true, true, false, false]);
+    assert_eq!(exhibited(&display, 1)[4..], [("shifting", Some(true)), ("jumping", Some(true))]);
+
+    // the platform decides what a check means
+    assert_eq!(
+        exhibited(&display, 3),
+        [
+            ("vF reset", Some(false)),
+            ("memory", Some(true)),
+            ("display wait", Some(false)),
+            ("clipping", Some(false)),
+            ("shifting", Some(true)),
+            ("jumping", Some(true)),
+        ]
+    );
+}
+
+#[test]
+fn a_line_without_a_mark_is_unreadable() {
+    let mut display = results_screen([true; 6]);
+    for x in MARK_X..MARK_X + 5 {
+        for row in 0..5 {
+            display.set_pixel(x, QUIRK_LINES[2].1 + row, false);
+        }
+    }
+    assert_eq!(exhibited(&display, 1)[2], ("display wait", None));
+    assert_eq!(exhibited(&Framebuffer::new(), 1).iter().filter(|(_, on)| on.is_some()).count(), 0);
+}
+
+#[test]
+fn glyphs_only_match_on_their_own() {
+    let mut display = Framebuffer::new();
+    draw(&mut display, 10, 3, &CROSS_GLYPH);
+    assert!(glyph_at(&display, 10, 3, &CROSS_GLYPH));
+    assert!(!glyph_at(&display, 11, 3, &CROSS_GLYPH));
+    assert!(!glyph_at(&display, 10, 3, &CHECK_GLYPH));
+    assert_eq!(find_glyph(&display, 3, &[CHECK_GLYPH, CROSS_GLYPH]), Some((10, CROSS_GLYPH)));
+
+    // touching another lit pixel it's part of a bigger shape
+    display.set_pixel(15, 3, true);
+    assert_eq!(find_glyph(&display, 3, &[CHECK_GLYPH, CROSS_GLYPH]), None);
+
+    // nor does it match cut off at the right edge
+    let mut display = Framebuffer::new();
+    draw(&mut display, 61, 0, &CROSS_GLYPH);
+    assert_eq!(find_glyph(&display, 0, &[CROSS_GLYPH]), None);
+}
+
+#[test]
+fn a_rom_that_draws_nothing_reads_as_nothing() {
+    // 200: JP 200
+    let readings = detect_quirks(&[0x12, 0x00], ChipVariant::Chip8Original, QuirkConfig::chip8_original()).unwrap();
+    assert_eq!(readings.len(), QUIRK_LINES.len());
+    assert!(readings.iter().all(|reading| reading.passed.is_none()));
+}
+
+#[test]
+fn the_binary_wants_the_quirks_rom() {
+    let dir = std::env::temp_dir().join(format!("rust-8-detect-quirks-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let run = |args: &[&str]| Command::new(env!("CARGO_BIN_EXE_rust-8")).arg("detect-quirks").args(args).output().unwrap();
+
+    let output = run(&[dir.to_str().unwrap()]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("5-quirks.ch8"));
+
+    std::fs::write(dir.join("5-quirks.ch8"), [0x12, 0x00]).unwrap();
+    let output = run(&[dir.to_str().unwrap(), "--variant", "chip8"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("the quirks test?"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
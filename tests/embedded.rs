@@ -0,0 +1,108 @@
+// Chip8Core, the no_std subset, run here with std: it has to agree with Chip8 on the
+// built-in ROMs and report the same errors without an allocator to describe them.
+
+use rust_8::{Chip8, Chip8Core, CoreError, CoreStep, BUILTIN_ROMS, CORE_HEIGHT, CORE_WIDTH, PROGRAM_START};
+
+fn core_with(rom: &[u8]) -> Chip8Core {
+    let mut core = Chip8Core::new(0);
+    core.load_rom(rom).unwrap();
+    core
+}
+
+#[test]
+fn runs_the_builtin_roms_like_chip8() {
+    for rom in BUILTIN_ROMS.iter() {
+        let mut chip8 = Chip8::new();
+        chip8.load_rom(rom.data.to_vec()).unwrap();
+        let mut core = core_with(rom.data);
+        for _ in 0..1000 {
+            chip8.step().unwrap();
+            core.step().unwrap();
+            assert_eq!(core.cpu_snapshot(), chip8.cpu_snapshot(), "{}", rom.name);
+        }
+        for y in 0..CORE_HEIGHT {
+            for x in 0..CORE_WIDTH {
+                assert_eq!(core.pixel(x, y), chip8.display().pixel(x, y), "{} at {},{}", rom.name, x, y);
+            }
+        }
+    }
+}
+
+#[test]
+fn draws_with_wrapping_and_collisions() {
+    // LD V0, 3E; LD V1, 1F; LD F, V0 (digit E); DRW V0, V1, 5; DRW V0, V1, 5
+    let mut core = core_with(&[0x60, 0x3E, 0x61, 0x1F, 0xF0, 0x29, 0xD0, 0x15, 0xD0, 0x15]);
+    for _ in 0..4 {
+        core.step().unwrap();
+    }
+    // the top row of E is F0, cut at the right edge and carried over to the left
+    assert!(core.pixel(62, 31) && core.pixel(63, 31) && core.pixel(0, 31) && core.pixel(1, 31));
+    assert!(!core.pixel(2, 31));
+    assert!(core.pixel(62, 0));
+    assert_eq!(core.cpu_snapshot().vx[0xF], 0);
+
+    core.step().unwrap();
+    assert!(core.rows().iter().all(|row| *row == 0));
+    assert_eq!(core.cpu_snapshot().vx[0xF], 1);
+}
+
+#[test]
+fn waits_for_a_key() {
+    // LD V2, K; SKP V2; JP 200
+    let mut core = core_with(&[0xF2, 0x0A, 0xE2, 0x9E, 0x12, 0x00]);
+    assert_eq!(core.step(), Ok(CoreStep::WaitingForKey));
+    assert_eq!(core.step(), Ok(CoreStep::WaitingForKey));
+
+    core.keypad_mut().press(7);
+    assert_eq!(core.step(), Ok(CoreStep::Executed { pc: PROGRAM_START }));
+    assert_eq!(core.cpu_snapshot().vx[2], 7);
+    // SKP looks at the key in V2, not at key 2
+    core.step().unwrap();
+    assert_eq!(core.cpu_snapshot().pc, PROGRAM_START + 6);
+}
+
+#[test]
+fn timers_count_down_at_each_tick() {
+    // LD V0, 02; LD DT, V0; LD ST, V0
+    let mut core = core_with(&[0x60, 0x02, 0xF0, 0x15, 0xF0, 0x18]);
+    for _ in 0..3 {
+        core.step().unwrap();
+    }
+    assert!(core.sound_active());
+    core.tick_timers();
+    assert_eq!(core.delay_timer(), 1);
+    core.tick_timers();
+    core.tick_timers();
+    assert_eq!(core.delay_timer(), 0);
+    assert!(!core.sound_active());
+}
+
+#[test]
+fn reports_errors() {
+    let mut core = Chip8Core::new(0);
+    assert_eq!(core.load_rom(&[]), Err(CoreError::EmptyRom));
+    assert_eq!(core.load_rom(&[0; 4000]), Err(CoreError::RomTooLarge { size: 4000, max: 3584 }));
+
+    assert_eq!(core_with(&[0x00, 0xEE]).step(), Err(CoreError::StackUnderflow { pc: 0x200 }));
+    assert_eq!(core_with(&[0x22, 0x00]).nth_error(17), CoreError::StackOverflow { pc: 0x200 });
+    assert_eq!(core_with(&[0xAF, 0xFF, 0xF2, 0x55]).nth_error(2), CoreError::IRegisterOutOfBounds { i: 0xFFF, size: 3 });
+    assert_eq!(core_with(&[0x00, 0xFB]).step(), Err(CoreError::Unsupported { pc: 0x200, opcode: 0x00FB }));
+    assert_eq!(core_with(&[0x1F, 0xFF]).nth_error(2), CoreError::PcOutOfRange { pc: 0xFFF });
+
+    let message = CoreError::Unsupported { pc: 0x200, opcode: 0x5001 }.to_string();
+    assert_eq!(message, "opcode 5001 at 0x200 is not plain CHIP-8");
+}
+
+trait NthError {
+    // steps `n` times, expecting the last one and only that one to fail
+    fn nth_error(&mut self, n: usize) -> CoreError;
+}
+
+impl NthError for Chip8Core {
+    fn nth_error(&mut self, n: usize) -> CoreError {
+        for _ in 1..n {
+            self.step().unwrap();
+        }
+        self.step().unwrap_err()
+    }
+}
@@ -0,0 +1,185 @@
+// The Command protocol of EmulatorThread, driven the way a frontend would: commands in,
+// the latest frame polled out by generation.
+
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use rust_8::{Chip8Builder, ColorTheme, Command, EmulatorThread, FrameSnapshot, Keypad, SoundRecorder, Waveform};
+
+// 200: ADD V1, 1  202: SKP V2  204: JP 200  206: JP 206
+// counts until key 0 is held, then halts
+fn emulator() -> EmulatorThread {
+    EmulatorThread::spawn(
+        || {
+            let mut chip8 = Chip8Builder::new().build();
+            chip8.load_rom(vec![0x71, 0x01, 0xE2, 0x9E, 0x12, 0x00, 0x12, 0x06])?;
+            Ok(chip8)
+        },
+        10,
+    )
+}
+
+// the first frame past `seen` that `until` accepts, as a frontend polling each frame sees them
+fn wait_for(emulator: &EmulatorThread, mut seen: u64, until: impl Fn(&FrameSnapshot) -> bool) -> FrameSnapshot {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    loop {
+        if let Some(frame) = emulator.latest_frame(seen) {
+            assert!(frame.generation > seen);
+            if until(&frame) {
+                return frame;
+            }
+            seen = frame.generation;
+        }
+        assert!(Instant::now() < deadline, "no such frame after generation {}", seen);
+        thread::sleep(Duration::from_millis(2));
+    }
+}
+
+// the generation once frames have stopped coming
+fn settle(emulator: &EmulatorThread) -> u64 {
+    let mut generation = 0;
+    loop {
+        thread::sleep(Duration::from_millis(100));
+        match emulator.latest_frame(generation) {
+            Some(frame) => generation = frame.generation,
+            None => return generation,
+        }
+    }
+}
+
+#[test]
+fn frames_come_with_rising_generations() {
+    let emulator = emulator();
+    let first = wait_for(&emulator, 0, |_| true);
+    let later = wait_for(&emulator, first.generation, |_| true);
+    assert!(later.instructions > first.instructions);
+    assert!(!later.halted);
+    // nothing is newer than a generation still to come
+    assert!(emulator.latest_frame(u64::MAX).is_none());
+}
+
+#[test]
+fn pause_stops_the_frames_and_resume_brings_them_back() {
+    let emulator = emulator();
+    wait_for(&emulator, 0, |_| true);
+    emulator.send(Command::Pause);
+    let paused = settle(&emulator);
+    thread::sleep(Duration::from_millis(100));
+    assert!(emulator.latest_frame(paused).is_none());
+
+    emulator.send(Command::Resume);
+    wait_for(&emulator, paused, |_| true);
+}
+
+#[test]
+fn commands_to_a_paused_machine_still_publish_a_frame() {
+    let emulator = EmulatorThread::spawn(
+        || {
+            let mut chip8 = Chip8Builder::new().build();
+            // 200: ADD V1, 1  202: JP 200, and the same counting V2
+            chip8.add_rom_to_library("ones", &[0x71, 0x01, 0x12, 0x00])?;
+            chip8.add_rom_to_library("twos", &[0x72, 0x01, 0x12, 0x00])?;
+            chip8.switch_to_rom(0)?;
+            Ok(chip8)
+        },
+        10,
+    );
+    let running = wait_for(&emulator, 0, |_| true);
+    assert_eq!(running.rom, Some(0));
+    emulator.send(Command::Pause);
+    let paused = settle(&emulator);
+
+    emulator.send(Command::NextRom);
+    let switched = wait_for(&emulator, paused, |frame| frame.rom == Some(1));
+    emulator.send(Command::Undo);
+    let undone = wait_for(&emulator, switched.generation, |_| true);
+    assert_eq!(undone.instructions, switched.instructions);
+    let theme = undone.theme.next();
+    assert_ne!(theme, ColorTheme::default());
+    emulator.send(Command::Theme(theme));
+    let themed = wait_for(&emulator, undone.generation, |frame| frame.theme == theme);
+    // and none of them set it running again
+    assert_eq!(settle(&emulator), themed.generation);
+}
+
+#[test]
+fn the_keypad_reaches_the_machine_and_reset_starts_it_over() {
+    let emulator = emulator();
+    let running = wait_for(&emulator, 0, |_| true);
+    let mut keypad = Keypad::new();
+    keypad.press(0);
+    emulator.send(Command::Keypad(keypad));
+    let halted = wait_for(&emulator, running.generation, |frame| frame.halted);
+    // a halted machine publishes nothing until told something
+    assert_eq!(settle(&emulator), halted.generation);
+
+    emulator.send(Command::Keypad(Keypad::new()));
+    emulator.send(Command::Reset);
+    let reset = wait_for(&emulator, halted.generation, |frame| !frame.halted);
+    // instructions count across resets
+    assert!(reset.instructions > halted.instructions);
+}
+
+#[test]
+fn quit_ends_the_thread() {
+    let emulator = emulator();
+    wait_for(&emulator, 0, |_| true);
+    emulator.send(Command::Quit);
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while emulator.is_running() {
+        assert!(Instant::now() < deadline, "still running after Quit");
+        thread::sleep(Duration::from_millis(2));
+    }
+    assert_eq!(emulator.shutdown(), Ok(()));
+}
+
+#[test]
+fn the_bell_follows_the_sound_timer_frame_by_frame() {
+    // 200: LD V0, 01      a one frame beep
+    // 202: LD ST, V0
+    // 204: LD V1, 03
+    // 206: LD DT, V1
+    // 208: LD V2, DT      until DT runs out
+    // 20A: SE V2, 00
+    // 20C: JP 208
+    // 20E: LD V0, 04      a four frame beep
+    // 210: LD ST, V0
+    // 212: LD V1, 06
+    // 214: LD DT, V1
+    // 216: LD V2, DT      until DT runs out again
+    // 218: SE V2, 00
+    // 21A: JP 216
+    // 21C: JP 21C
+    let rom = vec![
+        0x60, 0x01, 0xF0, 0x18, 0x61, 0x03, 0xF1, 0x15, 0xF2, 0x07, 0x32, 0x00, 0x12, 0x08, 0x60, 0x04, 0xF0, 0x18, 0x61, 0x06, 0xF1, 0x15,
+        0xF2, 0x07, 0x32, 0x00, 0x12, 0x16, 0x12, 0x1C,
+    ];
+    let path = std::env::temp_dir().join(format!("rust-8-bell-{}.wav", std::process::id()));
+    // the recording gets the same per-frame sound as the frame the bell is set from
+    let recorder = SoundRecorder::create(&path, Waveform::Square, 440.0, 1.0).unwrap();
+    let emulator = EmulatorThread::spawn_recording(
+        move || {
+            let mut chip8 = Chip8Builder::new().build();
+            chip8.load_rom(rom.clone())?;
+            Ok(chip8)
+        },
+        10,
+        Some(recorder),
+    );
+    let halted = wait_for(&emulator, 0, |frame| frame.halted);
+    emulator.shutdown().unwrap();
+    let wav = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let expected = [true, false, false, true, true, true, true, false, false, false];
+    assert_eq!(halted.generation, expected.len() as u64);
+    assert_eq!(halted.sound_frames, 5);
+    assert!(!halted.sound);
+    // 735 samples a frame, looked at past the fades in and out at either end
+    let samples: Vec<i16> = wav[44..].chunks(2).map(|pair| i16::from_le_bytes([pair[0], pair[1]])).collect();
+    assert_eq!(samples.len(), expected.len() * 735);
+    let sounding: Vec<bool> = samples.chunks(735).map(|frame| frame[400] != 0).collect();
+    assert_eq!(sounding, expected);
+}
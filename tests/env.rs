@@ -0,0 +1,57 @@
+use rust_8::{Chip8Env, Chip8TestRom, EnvConfig, Keypad};
+
+// 200: RND V0, FF  202: LD V1, 0  204: LD I, 20E  206: DRW V0, V1, 1  208: SKP V2  20A: JP 200  20C: JP 20C  20E: a one pixel sprite
+// scatters a pixel a frame until key 0 (V2) is held, then halts
+fn rom() -> Vec<u8> {
+    Chip8TestRom::new().data(&[0xC0, 0x3F]).ld(1, 0).ld_i(0x20E).drw(0, 1, 1).data(&[0xE2, 0x9E]).jp(0x200).jp(0x20C).data(&[0x80]).build()
+}
+
+fn config(seed: u64) -> EnvConfig {
+    EnvConfig { instructions_per_frame: Some(6), frames_per_step: 2, seed, ..EnvConfig::default() }
+}
+
+fn run(env: &mut Chip8Env, steps: usize) -> Vec<Vec<u8>> {
+    (0..steps).map(|_| env.step(Keypad::new()).0.screen).collect()
+}
+
+#[test]
+fn the_same_seed_plays_the_same_game() {
+    let mut a = Chip8Env::new(rom(), config(7)).unwrap();
+    let mut b = Chip8Env::new(rom(), config(7)).unwrap();
+    let screens = run(&mut a, 20);
+    assert_eq!(screens, run(&mut b, 20));
+    assert_ne!(screens[0], screens[19]);
+
+    // and so does the same env after a reset
+    let observation = a.reset();
+    assert!(observation.screen.iter().all(|&byte| byte == 0));
+    assert_eq!(observation.pc, 0x200);
+    assert_eq!(run(&mut a, 20), screens);
+
+    let mut c = Chip8Env::new(rom(), config(8)).unwrap();
+    assert_ne!(run(&mut c, 20), screens);
+}
+
+#[test]
+fn steps_run_whole_frames_with_the_action_held() {
+    let mut env = Chip8Env::new(rom(), config(7)).unwrap();
+    let (observation, info) = env.step(Keypad::new());
+    assert_eq!((info.frame, info.instructions, info.done), (2, 12, false));
+    assert_eq!((observation.width, observation.height, observation.screen.len()), (64, 32, 8 * 32));
+    // a pixel a frame, on row 0 where RND put them
+    let lit: Vec<usize> = (0..64).filter(|&x| observation.pixel(x, 0)).collect();
+    assert_eq!(lit.len(), 2);
+    let bits: u32 = observation.screen.iter().map(|byte| byte.count_ones()).sum();
+    assert_eq!(bits, 2);
+    assert_ne!(observation.screen[lit[0] / 8] & 0x80 >> (lit[0] % 8), 0);
+
+    let mut keys = Keypad::new();
+    keys.press(0);
+    let (observation, info) = env.step(keys);
+    assert!(info.done);
+    assert_eq!(info.error, None);
+    assert_eq!(observation.pc, 0x20C);
+    let (_, info) = env.step(keys);
+    // it halted during the first frame, and time stops with it
+    assert_eq!((info.frame, info.instructions, info.done), (3, 0, true));
+}
@@ -0,0 +1,101 @@
+// The presentation filters over a small known frame, against tests/golden/filter-<name>.txt:
+// one line per window row, each pixel as RRGGBB. Run with UPDATE_GOLDENS=1 to write the
+// files again after an intended change.
+
+use std::{env, fs, path::PathBuf};
+
+use rust_8::{blit_scaled, letterbox, Filter, Viewport};
+
+const IMAGE_WIDTH: usize = 8;
+const IMAGE_HEIGHT: usize = 4;
+// scale 4 with two pixels of bar all round
+const WINDOW_WIDTH: usize = 36;
+const WINDOW_HEIGHT: usize = 20;
+const BAR: u32 = 0x102030;
+
+// a checkerboard of white and a color with all three channels different
+fn frame() -> (Vec<u32>, Viewport) {
+    let image: Vec<u32> = (0..IMAGE_WIDTH * IMAGE_HEIGHT)
+        .map(|n| if (n % IMAGE_WIDTH + n / IMAGE_WIDTH).is_multiple_of(2) { 0xFFFFFF } else { 0x80C040 })
+        .collect();
+    let viewport = letterbox(WINDOW_WIDTH, WINDOW_HEIGHT, IMAGE_WIDTH, IMAGE_HEIGHT);
+    let mut window = vec![0; WINDOW_WIDTH * WINDOW_HEIGHT];
+    blit_scaled(&image, IMAGE_WIDTH, &mut window, WINDOW_WIDTH, viewport, BAR);
+    (window, viewport)
+}
+
+fn filtered(filter: Filter) -> Vec<u32> {
+    let (mut window, viewport) = frame();
+    filter.apply(&mut window, WINDOW_WIDTH, viewport, IMAGE_WIDTH, IMAGE_HEIGHT);
+    window
+}
+
+fn encode(window: &[u32]) -> String {
+    window
+        .chunks(WINDOW_WIDTH)
+        .map(|row| row.iter().map(|pixel| format!("{:06X}", pixel)).collect::<Vec<_>>().join(" ") + "\n")
+        .collect()
+}
+
+fn golden(filter: Filter) {
+    let actual = encode(&filtered(filter));
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(format!("filter-{}.txt", filter));
+    if env::var_os("UPDATE_GOLDENS").is_some_and(|value| value == "1") {
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path)
+        .unwrap_or_else(|err| panic!("{}: {}, run with UPDATE_GOLDENS=1 to create it", path.display(), err));
+    for (y, (want, got)) in expected.lines().zip(actual.lines()).enumerate() {
+        assert_eq!(want, got, "{} row {}", filter, y);
+    }
+    assert_eq!(expected.lines().count(), actual.lines().count());
+}
+
+#[test]
+fn scanlines_match_their_golden_image() {
+    golden(Filter::Scanlines);
+}
+
+#[test]
+fn crt_matches_its_golden_image() {
+    golden(Filter::Crt);
+}
+
+#[test]
+fn no_filter_leaves_the_frame_alone() {
+    assert_eq!(filtered(Filter::None), frame().0);
+}
+
+#[test]
+fn the_bars_are_never_filtered() {
+    let (unfiltered, viewport) = frame();
+    for filter in Filter::ALL {
+        let window = filtered(filter);
+        for (n, (pixel, before)) in window.iter().zip(&unfiltered).enumerate() {
+            let (x, y) = (n % WINDOW_WIDTH, n / WINDOW_WIDTH);
+            let outside = x < viewport.x || x >= viewport.x + 32 || y < viewport.y || y >= viewport.y + 16;
+            if outside {
+                assert_eq!(pixel, before, "{} at ({}, {})", filter, x, y);
+            }
+        }
+    }
+}
+
+#[test]
+fn filters_only_ever_darken() {
+    let (unfiltered, _) = frame();
+    for filter in Filter::ALL {
+        for (pixel, before) in filtered(filter).iter().zip(&unfiltered) {
+            for shift in [0, 8, 16] {
+                assert!(pixel >> shift & 0xFF <= before >> shift & 0xFF, "{}: {:06X} from {:06X}", filter, pixel, before);
+            }
+        }
+    }
+}
+
+#[test]
+fn a_zero_width_window_is_left_alone() {
+    let mut window = Vec::new();
+    Filter::Crt.apply(&mut window, 0, letterbox(0, 0, IMAGE_WIDTH, IMAGE_HEIGHT), IMAGE_WIDTH, IMAGE_HEIGHT);
+}
@@ -0,0 +1,208 @@
+use std::{
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use rust_8::{Chip8Builder, Command, EmulatorThread, FrameDelta, Framebuffer};
+
+// a copy that only ever sees the deltas, the way a remote renderer would
+struct Renderer {
+    screen: Framebuffer,
+    generation: u64,
+}
+
+impl Renderer {
+    fn new() -> Self {
+        Renderer { screen: Framebuffer::new(), generation: 0 }
+    }
+
+    fn catch_up(&mut self, framebuffer: &Framebuffer) -> FrameDelta {
+        let delta = framebuffer.delta_since(self.generation);
+        delta.apply(&mut self.screen);
+        self.generation = delta.generation();
+        assert_eq!(&self.screen, framebuffer);
+        delta
+    }
+}
+
+fn changed_rows(delta: &FrameDelta) -> Vec<usize> {
+    match delta {
+        FrameDelta::Rows { rows, .. } => rows.iter().map(|(y, _)| *y).collect(),
+        FrameDelta::Full { .. } => panic!("a full refresh instead of rows"),
+    }
+}
+
+#[test]
+fn deltas_across_draws_clears_and_scrolls_rebuild_the_screen() {
+    let mut framebuffer = Framebuffer::new();
+    let mut renderer = Renderer::new();
+    assert!(matches!(renderer.catch_up(&framebuffer), FrameDelta::Full { width: 64, height: 32, .. }));
+    assert!(renderer.catch_up(&framebuffer).is_empty());
+
+    framebuffer.draw_sprite_row(60, 3, 0xF0);
+    framebuffer.draw_sprite_row(62, 7, 0xA5);
+    assert_eq!(changed_rows(&renderer.catch_up(&framebuffer)), [3, 7]);
+
+    framebuffer.scroll_down(4);
+    assert_eq!(changed_rows(&renderer.catch_up(&framebuffer)), [3, 7, 11]);
+    framebuffer.scroll_right(4);
+    renderer.catch_up(&framebuffer);
+    framebuffer.scroll_left(6);
+    renderer.catch_up(&framebuffer);
+    framebuffer.scroll_up(2);
+    assert_eq!(changed_rows(&renderer.catch_up(&framebuffer)), [9, 11]);
+
+    // a row drawn and undrawn again between deltas still counts as changed
+    framebuffer.draw_sprite_row(0, 20, 0xFF);
+    framebuffer.draw_sprite_row(0, 20, 0xFF);
+    assert_eq!(changed_rows(&renderer.catch_up(&framebuffer)), [20]);
+    framebuffer.clear();
+    assert_eq!(changed_rows(&renderer.catch_up(&framebuffer)), [9]);
+}
+
+#[test]
+fn changes_that_change_nothing_keep_the_generation() {
+    let mut framebuffer = Framebuffer::new();
+    let generation = framebuffer.generation();
+    framebuffer.clear();
+    framebuffer.scroll_down(3);
+    framebuffer.set_pixel(5, 5, false);
+    assert_eq!(framebuffer.generation(), generation);
+    framebuffer.set_pixel(5, 5, true);
+    assert!(framebuffer.generation() > generation);
+    assert!(framebuffer.delta_since(framebuffer.generation()).is_empty());
+}
+
+#[test]
+fn consumers_track_their_own_generations() {
+    let mut framebuffer = Framebuffer::new();
+    let (mut fast, mut slow) = (Renderer::new(), Renderer::new());
+    fast.catch_up(&framebuffer);
+    slow.catch_up(&framebuffer);
+    framebuffer.set_pixel(1, 1, true);
+    assert_eq!(changed_rows(&fast.catch_up(&framebuffer)), [1]);
+    framebuffer.set_pixel(2, 2, true);
+    assert_eq!(changed_rows(&fast.catch_up(&framebuffer)), [2]);
+    assert_eq!(changed_rows(&slow.catch_up(&framebuffer)), [1, 2]);
+}
+
+#[test]
+fn too_much_change_or_a_resize_is_a_full_refresh() {
+    let mut framebuffer = Framebuffer::new();
+    let mut renderer = Renderer::new();
+    renderer.catch_up(&framebuffer);
+    for y in 0..17 {
+        framebuffer.set_pixel(0, y, true);
+    }
+    assert!(matches!(renderer.catch_up(&framebuffer), FrameDelta::Full { .. }));
+
+    framebuffer.resize(128, 64);
+    framebuffer.set_pixel(127, 63, true);
+    assert!(matches!(renderer.catch_up(&framebuffer), FrameDelta::Full { width: 128, height: 64, .. }));
+    framebuffer.set_pixel(100, 40, true);
+    assert_eq!(changed_rows(&renderer.catch_up(&framebuffer)), [40]);
+
+    // a generation from another framebuffer, further along than this one
+    let other = Framebuffer::new();
+    assert!(matches!(other.delta_since(renderer.generation), FrameDelta::Full { .. }));
+}
+
+#[test]
+fn packed_rows_put_the_leftmost_pixel_on_top() {
+    let mut framebuffer = Framebuffer::with_size(12, 2);
+    framebuffer.set_pixel(0, 0, true);
+    framebuffer.set_pixel(11, 0, true);
+    framebuffer.set_pixel(8, 1, true);
+    assert_eq!(framebuffer.packed_row(0), [0x80, 0x10]);
+    assert_eq!(framebuffer.packed(), [0x80, 0x10, 0x00, 0x80]);
+}
+
+#[test]
+fn a_replaced_screen_moves_past_both_generations() {
+    let mut framebuffer = Framebuffer::new();
+    let mut renderer = Renderer::new();
+    for y in 0..4 {
+        framebuffer.set_pixel(0, y, true);
+    }
+    renderer.catch_up(&framebuffer);
+
+    let mut other = Framebuffer::with_size(128, 64);
+    for x in 0..10 {
+        other.set_pixel(x, 9, true);
+    }
+    framebuffer.replace_from(&other);
+    assert_eq!(framebuffer, other);
+    assert!(framebuffer.generation() > other.generation());
+    assert!(matches!(renderer.catch_up(&framebuffer), FrameDelta::Full { width: 128, height: 64, .. }));
+    framebuffer.set_pixel(3, 3, true);
+    assert_eq!(changed_rows(&renderer.catch_up(&framebuffer)), [3]);
+}
+
+#[test]
+fn a_loaded_state_never_takes_the_generation_back() {
+    // 200: LD F, V0  202: DRW V1, V1, 5
+    // 204: CLS  206: LD V0, 1  208: LD F, V0  20A: DRW V1, V1, 5  20C: JP 20C
+    let rom = vec![0xF0, 0x29, 0xD1, 0x15, 0x00, 0xE0, 0x60, 0x01, 0xF0, 0x29, 0xD1, 0x15, 0x12, 0x0C];
+    let mut chip8 = Chip8Builder::new().build();
+    chip8.load_rom(rom).unwrap();
+    chip8.step().unwrap();
+    chip8.step().unwrap();
+    let zero = chip8.save_state();
+    for _ in 0..4 {
+        chip8.step().unwrap();
+    }
+    let mut renderer = Renderer::new();
+    renderer.catch_up(chip8.display());
+
+    // back to the 0, then enough drawing elsewhere to have counted past the renderer again
+    let seen = chip8.display().generation();
+    chip8.load_state(zero).unwrap();
+    assert!(chip8.display().generation() > seen);
+    for x in 0..8 {
+        chip8.set_display_pixel(x, 20, true);
+    }
+    assert!(matches!(renderer.catch_up(chip8.display()), FrameDelta::Full { .. }));
+}
+
+#[test]
+fn a_reset_never_takes_the_generation_back() {
+    // 200: LD V0, n  202: LD F, V0  204: DRW V1, V1, 5  206: JP 206
+    // with n counting the boots, so the screen after a reset is a different glyph drawn
+    // in as many changes as the first
+    let boots = Arc::new(AtomicU8::new(0));
+    let emulator = {
+        let boots = Arc::clone(&boots);
+        EmulatorThread::spawn(
+            move || {
+                let n = boots.fetch_add(1, Ordering::SeqCst);
+                let mut chip8 = Chip8Builder::new().build();
+                chip8.load_rom(vec![0x60, n, 0xF0, 0x29, 0xD1, 0x15, 0x12, 0x06])?;
+                Ok(chip8)
+            },
+            10,
+        )
+    };
+    let halted = |seen: u64| {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            if let Some(frame) = emulator.latest_frame(seen).filter(|frame| frame.halted) {
+                return frame;
+            }
+            assert!(Instant::now() < deadline, "no halted frame after generation {}", seen);
+            thread::sleep(Duration::from_millis(2));
+        }
+    };
+
+    let before = halted(0);
+    let mut renderer = Renderer::new();
+    renderer.catch_up(&before.framebuffer);
+    emulator.send(Command::Reset);
+    let after = halted(before.generation);
+    assert_eq!(boots.load(Ordering::SeqCst), 2);
+    assert!(after.framebuffer.generation() > before.framebuffer.generation());
+    assert!(matches!(renderer.catch_up(&after.framebuffer), FrameDelta::Full { .. }));
+}
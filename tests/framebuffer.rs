@@ -0,0 +1,64 @@
+use rust_8::Framebuffer;
+
+// the sprite row drawn a pixel at a time, the way the display used to
+fn draw_per_pixel(framebuffer: &mut Framebuffer, x: usize, y: usize, sprite: u8) -> bool {
+    let mut collision = false;
+    for bit in 0..8 {
+        if sprite >> (7 - bit) & 1 == 1 {
+            collision |= framebuffer.xor_pixel(x + bit, y);
+        }
+    }
+    collision
+}
+
+// a screen with a few pixels already lit, so draws have something to collide with
+fn scattered(width: usize, height: usize) -> Framebuffer {
+    let mut framebuffer = Framebuffer::with_size(width, height);
+    for n in 0..width * height / 3 {
+        framebuffer.set_pixel(n * 7 % width, n * 5 % height, true);
+    }
+    framebuffer
+}
+
+#[test]
+fn sprite_rows_draw_like_pixel_by_pixel_xor() {
+    for (width, height) in [(64, 32), (128, 64)] {
+        for sprite in [0x01, 0x80, 0xF0, 0xA5, 0xFF] {
+            for x in 0..width {
+                for y in [0, 3, height - 1] {
+                    let mut fast = scattered(width, height);
+                    let mut slow = fast.clone();
+                    let collision = fast.draw_sprite_row(x, y, sprite);
+                    assert_eq!(collision, draw_per_pixel(&mut slow, x, y, sprite), "{:#04X} at ({}, {})", sprite, x, y);
+                    assert_eq!(fast, slow, "{:#04X} at ({}, {})", sprite, x, y);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn sprite_rows_wrap_around_the_right_edge() {
+    for x in 60..64 {
+        let mut framebuffer = Framebuffer::new();
+        assert!(!framebuffer.draw_sprite_row(x, 0, 0xFF));
+        let lit: Vec<usize> = (0..64).filter(|&column| framebuffer.pixel(column, 0)).collect();
+        let mut expected: Vec<usize> = (0..8).map(|bit| (x + bit) % 64).collect();
+        expected.sort_unstable();
+        assert_eq!(lit, expected);
+        // drawn again, the row is gone and every pixel collided
+        assert!(framebuffer.draw_sprite_row(x, 0, 0xFF));
+        assert_eq!(framebuffer, Framebuffer::new());
+    }
+}
+
+#[test]
+fn a_single_shared_pixel_is_a_collision() {
+    let mut framebuffer = Framebuffer::new();
+    framebuffer.set_pixel(2, 5, true);
+    // 0x81 at x = 58 lights 58 and 1, missing 2
+    assert!(!framebuffer.draw_sprite_row(58, 5, 0x81));
+    // 0x40 at x = 1 lights 2
+    assert!(framebuffer.draw_sprite_row(1, 5, 0x40));
+    assert!(!framebuffer.pixel(2, 5));
+}
@@ -0,0 +1,21 @@
+// Runs every input in fuzz/regressions through the fuzz target's harness, so inputs that
+// once crashed the emulator keep being checked without cargo fuzz.
+
+use std::fs;
+
+#[path = "../fuzz/harness.rs"]
+mod harness;
+
+#[test]
+fn regression_inputs_pass_the_fuzz_checks() {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/fuzz/regressions");
+    let mut inputs = 0;
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        let data = fs::read(&path).unwrap();
+        println!("{}", path.display());
+        harness::run(&data);
+        inputs += 1;
+    }
+    assert!(inputs > 0, "no inputs in {}", dir);
+}
@@ -0,0 +1,108 @@
+// Every built-in ROM runs for FRAMES frames with a fixed seed, and its screen has to match
+// tests/golden/<name>.txt: one line per row, four pixels to a hex digit with the leftmost as
+// the top bit. RAM is checked the same way against tests/golden/checksums.txt, a line of
+// "<name> <memory_checksum> <rom_checksum>" per ROM. Run with UPDATE_GOLDENS=1 to write the
+// files again after an intended change.
+
+use std::{env, fs, path::PathBuf};
+
+use rust_8::{BuiltinRom, Chip8, Chip8Builder, Framebuffer, BUILTIN_ROMS};
+
+const FRAMES: usize = 120;
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(format!("{}.txt", name))
+}
+
+fn run(rom: &BuiltinRom) -> Chip8 {
+    let mut chip8 = Chip8Builder::new().with_variant(rom.variant).with_rng_seed(0).build();
+    chip8.load_rom(rom.data.to_vec()).unwrap();
+    for _ in 0..FRAMES {
+        for _ in 0..rom.variant.instructions_per_frame() {
+            chip8.step().unwrap();
+        }
+        chip8.tick_timers();
+    }
+    chip8
+}
+
+fn update() -> bool {
+    env::var_os("UPDATE_GOLDENS").is_some_and(|value| value == "1")
+}
+
+fn encode(display: &Framebuffer) -> Vec<String> {
+    (0..display.height())
+        .map(|y| {
+            (0..display.width())
+                .step_by(4)
+                .map(|x| {
+                    let nibble = (0..4).fold(0, |nibble, bit| nibble << 1 | display.pixel(x + bit, y) as u32);
+                    char::from_digit(nibble, 16).unwrap().to_ascii_uppercase()
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn ascii(row: &str) -> String {
+    row.chars()
+        .map(|digit| digit.to_digit(16).unwrap_or(0))
+        .flat_map(|nibble| (0..4).rev().map(move |bit| if nibble >> bit & 1 == 1 { '#' } else { '.' }))
+        .collect()
+}
+
+// the rows that differ, each shown as expected and actual pixels
+fn pixel_diff(expected: &[String], actual: &[String]) -> String {
+    let mut diff = String::new();
+    for y in 0..expected.len().max(actual.len()) {
+        let (want, got) = (expected.get(y), actual.get(y));
+        if want != got {
+            diff.push_str(&format!("row {:2} expected {}\n", y, want.map(|row| ascii(row)).unwrap_or_default()));
+            diff.push_str(&format!("       actual   {}\n", got.map(|row| ascii(row)).unwrap_or_default()));
+        }
+    }
+    diff
+}
+
+#[test]
+fn builtin_roms_match_their_golden_frames() {
+    let mut failures = Vec::new();
+    for rom in BUILTIN_ROMS.iter() {
+        let actual = encode(run(rom).display());
+
+        let path = golden_path(rom.name);
+        if update() {
+            fs::write(&path, actual.join("\n") + "\n").unwrap();
+            continue;
+        }
+        let expected: Vec<String> = match fs::read_to_string(&path) {
+            Ok(text) => text.lines().map(String::from).collect(),
+            Err(err) => panic!("{}: {}, run with UPDATE_GOLDENS=1 to create it", path.display(), err),
+        };
+        if expected != actual {
+            failures.push(format!("{} differs from {}:\n{}", rom.name, path.display(), pixel_diff(&expected, &actual)));
+        }
+    }
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+#[test]
+fn builtin_roms_match_their_memory_checksums() {
+    let actual: Vec<String> = BUILTIN_ROMS
+        .iter()
+        .map(|rom| {
+            let chip8 = run(rom);
+            format!("{} {:08X} {:08X}", rom.name, chip8.memory_checksum(), chip8.rom_checksum())
+        })
+        .collect();
+    let path = golden_path("checksums");
+    if update() {
+        fs::write(&path, actual.join("\n") + "\n").unwrap();
+        return;
+    }
+    let expected: Vec<String> = match fs::read_to_string(&path) {
+        Ok(text) => text.lines().map(String::from).collect(),
+        Err(err) => panic!("{}: {}, run with UPDATE_GOLDENS=1 to create it", path.display(), err),
+    };
+    assert_eq!(expected, actual, "RAM differs from {}", path.display());
+}
@@ -0,0 +1,97 @@
+// Short ROMs written as hex strings and loaded with Chip8::load_rom_from_hex. Each one
+// starts at PROGRAM_START (0x200).
+
+use rust_8::{from_hex, Chip8, Chip8Builder, Chip8Error, HexError};
+
+fn run(hex: &str, cycles: usize) -> Chip8 {
+    let mut chip8 = Chip8Builder::new().with_rng_seed(0).build();
+    chip8.load_rom_from_hex(hex).unwrap();
+    for _ in 0..cycles {
+        chip8.step().unwrap();
+    }
+    chip8
+}
+
+#[test]
+fn hex_is_read_as_bytes() {
+    assert_eq!(from_hex("60 05 61 0a\n\t80 14").unwrap(), [0x60, 0x05, 0x61, 0x0A, 0x80, 0x14]);
+    assert_eq!(from_hex("6005 610A 8014").unwrap(), [0x60, 0x05, 0x61, 0x0A, 0x80, 0x14]);
+    assert!(from_hex("  ").unwrap().is_empty());
+    assert_eq!(from_hex("60 0G"), Err(HexError::NotHex(String::from("0G"))));
+    assert_eq!(from_hex("+1"), Err(HexError::NotHex(String::from("+1"))));
+    assert_eq!(from_hex("600"), Err(HexError::OddDigits(String::from("600"))));
+
+    let mut chip8 = Chip8::new();
+    assert_eq!(chip8.load_rom_from_hex("60 0"), Err(Chip8Error::Hex(HexError::OddDigits(String::from("0")))));
+    assert_eq!(chip8.load_rom_from_hex(""), Err(Chip8Error::EmptyRom));
+}
+
+#[test]
+fn add_registers() {
+    // LD V0, 05; LD V1, 0A; ADD V0, V1
+    let chip8 = run("60 05 61 0A 80 14", 3);
+    assert_eq!(chip8.register(0), 0x0F);
+    assert_eq!(chip8.register(0xF), 0);
+}
+
+#[test]
+fn sub_without_borrow_sets_vf() {
+    // LD V0, 0A; LD V1, 03; SUB V0, V1
+    let chip8 = run("60 0A 61 03 80 15", 3);
+    assert_eq!(chip8.register(0), 0x07);
+    assert_eq!(chip8.register(0xF), 1);
+}
+
+#[test]
+fn xor_registers() {
+    // LD V0, F0; LD V1, FF; XOR V0, V1
+    let chip8 = run("60 F0 61 FF 80 13", 3);
+    assert_eq!(chip8.register(0), 0x0F);
+}
+
+#[test]
+fn skip_if_equal_skips_one_instruction() {
+    // LD V0, 05; SE V0, 05; LD V1, 01; LD V2, 02
+    let chip8 = run("60 05 30 05 61 01 62 02", 3);
+    assert_eq!(chip8.register(1), 0);
+    assert_eq!(chip8.register(2), 2);
+    assert_eq!(chip8.pc(), 0x208);
+}
+
+#[test]
+fn skip_if_registers_differ() {
+    // LD V0, 01; LD V1, 02; SNE V0, V1; LD V2, 07; LD V3, 08
+    let chip8 = run("6001 6102 9010 6207 6308", 4);
+    assert_eq!(chip8.register(2), 0);
+    assert_eq!(chip8.register(3), 8);
+}
+
+#[test]
+fn add_to_i() {
+    // LD I, 300; LD V0, 10; ADD I, V0
+    let chip8 = run("A3 00 60 10 F0 1E", 3);
+    assert_eq!(chip8.i_register(), 0x310);
+}
+
+#[test]
+fn font_address_of_a_digit() {
+    // LD V0, 0A; LD F, V0
+    let chip8 = run("60 0A F0 29", 2);
+    assert_eq!(chip8.i_register(), 0x0A * 5);
+}
+
+#[test]
+fn jump_plus_v0() {
+    // LD V0, 04; JP V0, 202; (skipped); LD V1, 01
+    let chip8 = run("60 04 B2 02 00 E0 61 01", 3);
+    assert_eq!(chip8.register(1), 1);
+    assert_eq!(chip8.pc(), 0x208);
+}
+
+#[test]
+fn seventeen_nested_calls_overflow_the_stack() {
+    // CALL 200, calling itself forever
+    let mut chip8 = run("22 00", 16);
+    assert_eq!(chip8.stack_pointer(), 16);
+    assert_eq!(chip8.step(), Err(Chip8Error::StackOverflow { pc: 0x200 }));
+}
@@ -0,0 +1,113 @@
+// The HTTP server in front of a running EmulatorThread, on an ephemeral loopback port.
+#![cfg(feature = "http-server")]
+
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpStream},
+    thread,
+    time::{Duration, Instant},
+};
+
+use rust_8::{Chip8Builder, Chip8TestRom, ColorTheme, EmulatorThread, HttpServer};
+
+// 200: LD V0, K  202: LD F, V0  204: LD V1, 0  206: DRW V1, V1, 5  208: JP 208
+// so the screen stays dark until a key is pressed, then shows its digit
+fn emulator() -> EmulatorThread {
+    EmulatorThread::spawn(
+        || {
+            let rom = Chip8TestRom::new().data(&[0xF0, 0x0A, 0xF0, 0x29]).ld(1, 0).drw(1, 1, 5).jp(0x208).build();
+            let mut chip8 = Chip8Builder::new().build();
+            chip8.load_rom(rom)?;
+            Ok(chip8)
+        },
+        10,
+    )
+}
+
+// (status code, body)
+fn request(addr: SocketAddr, method: &str, path: &str) -> (u16, Vec<u8>) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(stream, "{} {} HTTP/1.1\r\nHost: localhost\r\n\r\n", method, path).unwrap();
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).unwrap();
+    let end = response.windows(4).position(|window| window == b"\r\n\r\n").unwrap();
+    let head = String::from_utf8_lossy(&response[..end]);
+    let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+    (status, response[end + 4..].to_vec())
+}
+
+// the RGB pixels of frame.png, once there is a frame
+fn frame(addr: SocketAddr) -> (u32, u32, Vec<u8>) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    loop {
+        match request(addr, "GET", "/frame.png") {
+            (200, body) => {
+                let mut reader = png::Decoder::new(&body[..]).read_info().unwrap();
+                let mut pixels = vec![0; reader.output_buffer_size()];
+                let info = reader.next_frame(&mut pixels).unwrap();
+                assert_eq!(info.color_type, png::ColorType::Rgb);
+                return (info.width, info.height, pixels);
+            }
+            (503, _) if Instant::now() < deadline => thread::sleep(Duration::from_millis(10)),
+            (status, _) => panic!("frame.png answered {}", status),
+        }
+    }
+}
+
+fn rgb(color: u32) -> [u8; 3] {
+    [(color >> 16) as u8, (color >> 8) as u8, color as u8]
+}
+
+#[test]
+fn serves_frames_and_takes_key_presses() {
+    let emulator = emulator();
+    let server = HttpServer::spawn("127.0.0.1:0", emulator.remote()).unwrap();
+    let addr = server.local_addr();
+    let theme = ColorTheme::default();
+
+    let (width, height, pixels) = frame(addr);
+    assert_eq!((width, height), (64, 32));
+    assert!(pixels.chunks(3).all(|pixel| pixel == rgb(theme.bg())));
+
+    assert_eq!(request(addr, "POST", "/key/a/down"), (200, b"ok\n".to_vec()));
+    // the top row of the A glyph, 1111 then dark
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let pixels = loop {
+        let (_, _, pixels) = frame(addr);
+        if pixels[..3] == rgb(theme.fg()) || Instant::now() > deadline {
+            break pixels;
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+    let top: Vec<bool> = pixels[..5 * 3].chunks(3).map(|pixel| pixel == rgb(theme.fg())).collect();
+    assert_eq!(top, [true, true, true, true, false]);
+    assert_eq!(request(addr, "POST", "/key/A/up").0, 200);
+
+    let (status, body) = request(addr, "GET", "/frame.delta?since=0");
+    assert_eq!(status, 200);
+    let delta: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(delta["full"], true);
+    assert_eq!(delta["rows"][0], serde_json::json!([0, "F000000000000000"]));
+    let since = format!("/frame.delta?since={}", delta["generation"]);
+    let delta: serde_json::Value = serde_json::from_slice(&request(addr, "GET", &since).1).unwrap();
+    assert_eq!((delta["full"].clone(), delta["rows"].clone()), (serde_json::json!(false), serde_json::json!([])));
+
+    let (status, body) = request(addr, "GET", "/state.json");
+    assert_eq!(status, 200);
+    let state: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(state["pc"], 0x208);
+    assert_eq!(state["v"][0], 0xA);
+    emulator.shutdown().unwrap();
+}
+
+#[test]
+fn refuses_what_it_doesnt_serve() {
+    let emulator = emulator();
+    let addr = HttpServer::spawn("127.0.0.1:0", emulator.remote()).unwrap().local_addr();
+    assert_eq!(request(addr, "GET", "/").0, 404);
+    assert_eq!(request(addr, "GET", "/frame.delta?since=soon").0, 400);
+    assert_eq!(request(addr, "POST", "/key/10/down").0, 400);
+    assert_eq!(request(addr, "POST", "/key/1/sideways").0, 404);
+    assert_eq!(request(addr, "POST", "/frame.png").0, 405);
+    assert_eq!(request(addr, "GET", "/key/1/down").0, 405);
+}
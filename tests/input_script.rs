@@ -0,0 +1,116 @@
+// Key presses played back from an input script, through the library and through
+// --headless --input-script, driving the FX0A, EX9E and EXA1 paths.
+
+use std::{fs, process::Command};
+
+use rust_8::{Chip8Builder, InputScript, KeyEvent, ScriptError};
+
+// 200: LD V3, K
+// 202: LD F, V3
+// 204: LD V0, 00
+// 206: DRW V0, V0, 5
+// 208: JP 208
+const WAIT_FOR_KEY: [u8; 10] = [0xF3, 0x0A, 0xF3, 0x29, 0x60, 0x00, 0xD0, 0x05, 0x12, 0x08];
+
+// 200: LD V1, 07
+// 202: SKP V1
+// 204: JP 202
+// 206: SKNP V1
+// 208: JP 206
+// 20A: LD V2, 01
+// 20C: JP 20C
+const PRESS_AND_RELEASE: [u8; 14] = [0x61, 0x07, 0xE1, 0x9E, 0x12, 0x02, 0xE1, 0xA1, 0x12, 0x06, 0x62, 0x01, 0x12, 0x0C];
+
+fn error_line(text: &str) -> usize {
+    match InputScript::parse(text) {
+        Err(ScriptError::Parse { line, .. }) => line,
+        Ok(script) => panic!("{:?} parsed as {:?}", text, script),
+    }
+}
+
+#[test]
+fn parses_key_changes_by_frame() {
+    let script = InputScript::parse("# hold 5\nframe 30: down 5\n\n  frame 45:up  5 \nframe 45: down a\n").unwrap();
+    assert_eq!(
+        script.events(),
+        [
+            KeyEvent { frame: 30, key: 5, pressed: true },
+            KeyEvent { frame: 45, key: 5, pressed: false },
+            KeyEvent { frame: 45, key: 0xA, pressed: true },
+        ]
+    );
+    assert_eq!(script.last_frame(), Some(45));
+    assert_eq!(InputScript::parse("").unwrap().last_frame(), None);
+}
+
+#[test]
+fn reports_bad_lines_by_number() {
+    assert_eq!(error_line("frame 1: down 1\nframe 2 down 1"), 2);
+    assert_eq!(error_line("frame x: down 1"), 1);
+    assert_eq!(error_line("frames 1: down 1"), 1);
+    assert_eq!(error_line("frame 1: press 1"), 1);
+    assert_eq!(error_line("frame 1: down 10"), 1);
+    assert_eq!(error_line("frame 1: down G"), 1);
+    assert_eq!(error_line("frame 1: down"), 1);
+
+    let err = InputScript::parse("frame 45: up 5\n# back in time\nframe 30: down 5").unwrap_err();
+    assert_eq!(err.to_string(), "input script line 3: frame 30 comes after frame 45");
+}
+
+#[test]
+fn a_script_answers_fx0a() {
+    let script = InputScript::parse("frame 3: down 5\nframe 4: up 5").unwrap();
+    let mut chip8 = Chip8Builder::new().build();
+    chip8.load_rom(WAIT_FOR_KEY.to_vec()).unwrap();
+    for frame in 0..6 {
+        script.apply(frame, chip8.keypad_mut());
+        for _ in 0..10 {
+            chip8.step().unwrap();
+        }
+        chip8.tick_timers();
+        if frame < 3 {
+            assert_eq!(chip8.pc(), 0x200, "FX0A returned at frame {}", frame);
+        }
+    }
+    assert_eq!(chip8.cpu_snapshot().vx[3], 5);
+    assert!(!chip8.keypad_mut().is_pressed(5));
+    // the top of the 5 glyph is F0
+    assert!((0..4).all(|x| chip8.display().pixel(x, 0)));
+    assert!(!chip8.display().pixel(4, 0));
+}
+
+#[test]
+fn the_binary_plays_a_script_headlessly() {
+    let dir = std::env::temp_dir().join(format!("rust-8-input-script-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let rom = dir.join("keys.ch8");
+    fs::write(&rom, PRESS_AND_RELEASE).unwrap();
+    let run = |script: &str| {
+        let path = dir.join("keys.txt");
+        fs::write(&path, script).unwrap();
+        Command::new(env!("CARGO_BIN_EXE_rust-8"))
+            .arg(&rom)
+            .args(["--headless", "--frames", "6", "--input-script"])
+            .arg(&path)
+            .output()
+            .unwrap()
+    };
+
+    // EX9E sees 7 go down and EXA1 sees it come back up
+    let output = run("frame 2: down 7\nframe 4: up 7\n");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("V2=01"));
+
+    // still held, and a different key, never get past the loops
+    for script in ["frame 2: down 7\n", "frame 2: down 1\nframe 4: up 1\n"] {
+        let output = run(script);
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        assert!(String::from_utf8_lossy(&output.stdout).contains("V2=00"), "{}", script);
+    }
+
+    let output = run("frame 2: down 7\nframe 4 up 7\n");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("input script line 2"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,580 @@
+// Small hand-assembled ROMs run headlessly, checking the registers, memory and screen
+// they leave behind. Each ROM is loaded at PROGRAM_START (0x200).
+
+use rust_8::{hidpi_scale, Chip8, Chip8Builder, Chip8Error, ColorTheme, EmulatorError, InstructionResult, MemoryProtection, QuirkConfig};
+
+fn run(rom: &[u8], cycles: usize) -> Chip8 {
+    let mut chip8 = Chip8Builder::new().with_rng_seed(0).build();
+    chip8.load_rom(rom.to_vec()).unwrap();
+    for _ in 0..cycles {
+        chip8.step().unwrap();
+    }
+    chip8
+}
+
+#[test]
+fn jump_to_itself_keeps_pc_in_place() {
+    // 200: CLS
+    // 202: JP 202
+    let rom = [0x00, 0xE0, 0x12, 0x02];
+    let mut chip8 = run(&rom, 1);
+    for _ in 0..20 {
+        assert_eq!(chip8.step().unwrap(), InstructionResult::Halted { pc: 0x202 });
+        assert_eq!(chip8.snapshot().pc, 0x202);
+    }
+    assert!(chip8.is_halted());
+}
+
+#[test]
+fn jump_to_itself_only_halts_once_the_delay_timer_is_out() {
+    // 200: LD V0, 03
+    // 202: LD DT, V0
+    // 204: JP 204        a wait for the timer, not the end
+    let mut chip8 = run(&[0x60, 0x03, 0xF0, 0x15, 0x12, 0x04], 2);
+    for _ in 0..3 {
+        for _ in 0..10 {
+            assert_eq!(chip8.step().unwrap(), InstructionResult::Executed { pc: 0x204 });
+        }
+        assert!(!chip8.is_halted());
+        chip8.tick_timers();
+    }
+    assert_eq!(chip8.delay_timer(), 0);
+    assert_eq!(chip8.step().unwrap(), InstructionResult::Halted { pc: 0x204 });
+}
+
+#[test]
+fn polling_the_delay_timer_never_halts() {
+    // 200: LD V0, 03
+    // 202: LD DT, V0
+    // 204: LD V0, DT
+    // 206: SE V0, 00
+    // 208: JP 204
+    // 20A: JP 20A
+    let mut chip8 = run(&[0x60, 0x03, 0xF0, 0x15, 0xF0, 0x07, 0x30, 0x00, 0x12, 0x04, 0x12, 0x0A], 2);
+    let mut steps = 0;
+    while chip8.pc() != 0x20A {
+        assert!(!matches!(chip8.step().unwrap(), InstructionResult::Halted { .. }));
+        steps += 1;
+        if steps % 9 == 0 {
+            chip8.tick_timers();
+        }
+        assert!(steps < 100, "never left the polling loop");
+    }
+    // three frames of polling until the third tick, then the read of 0 and the skip
+    assert_eq!(steps, 3 * 9 + 2);
+    assert_eq!(chip8.step().unwrap(), InstructionResult::Halted { pc: 0x20A });
+}
+
+#[test]
+fn call_and_return_restore_pc() {
+    // 200: CALL 206
+    // 202: LD V1, 02
+    // 204: JP 204
+    // 206: LD V0, 01
+    // 208: RET
+    let rom = [0x22, 0x06, 0x61, 0x02, 0x12, 0x04, 0x60, 0x01, 0x00, 0xEE];
+
+    let chip8 = run(&rom, 1);
+    let snapshot = chip8.snapshot();
+    assert_eq!(snapshot.pc, 0x206);
+    assert_eq!(snapshot.sp, 1);
+
+    let chip8 = run(&rom, 4);
+    let snapshot = chip8.snapshot();
+    assert_eq!(snapshot.pc, 0x204);
+    assert_eq!(snapshot.sp, 0);
+    assert_eq!(snapshot.vx[0], 1);
+    assert_eq!(snapshot.vx[1], 2);
+}
+
+#[test]
+fn sprite_draw_sets_pixels_and_collision_flag() {
+    // 200: LD V0, 00
+    // 202: LD F, V0         the built-in "0" sprite
+    // 204: LD V1, 02
+    // 206: LD V2, 03
+    // 208: DRW V1, V2, 5
+    // 20A: DRW V1, V2, 5    drawing it again erases it
+    let rom = [0x60, 0x00, 0xF0, 0x29, 0x61, 0x02, 0x62, 0x03, 0xD1, 0x25, 0xD1, 0x25];
+
+    let chip8 = run(&rom, 5);
+    // 0xF0, 0x90, 0x90, 0x90, 0xF0
+    let expected = ["####", "#..#", "#..#", "#..#", "####"];
+    let display = chip8.display();
+    for (row, pattern) in expected.iter().enumerate() {
+        for (column, cell) in pattern.chars().enumerate() {
+            assert_eq!(display.pixel(2 + column, 3 + row), cell == '#', "pixel ({}, {})", 2 + column, 3 + row);
+        }
+    }
+    assert!(!display.pixel(1, 3));
+    assert!(!display.pixel(6, 3));
+    assert_eq!(chip8.snapshot().vx[0xF], 0);
+
+    let chip8 = run(&rom, 6);
+    let display = chip8.display();
+    assert!((0..display.height()).all(|y| (0..display.width()).all(|x| !display.pixel(x, y))));
+    assert_eq!(chip8.snapshot().vx[0xF], 1);
+}
+
+#[test]
+fn display_renders_two_rows_per_line() {
+    // 200: LD V0, 00
+    // 202: LD F, V0
+    // 204: LD V1, 3C
+    // 206: DRW V0, V0, 5    the "0" sprite in the top left corner
+    // 208: DRW V1, V0, 1    its top row again in the top right
+    let rom = [0x60, 0x00, 0xF0, 0x29, 0x61, 0x3C, 0xD0, 0x05, 0xD1, 0x01];
+    let text = run(&rom, 5).to_string();
+
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 16);
+    assert!(lines.iter().all(|line| line.chars().count() == 64));
+    assert!(lines[0].starts_with("█▀▀█ "));
+    assert!(lines[0].ends_with(" ▀▀▀▀"));
+    assert!(lines[1].starts_with("█  █ "));
+    assert!(lines[2].starts_with("▀▀▀▀ "));
+    assert!(lines[3..].iter().all(|line| line.trim().is_empty()));
+}
+
+#[test]
+fn rgba_and_rgb_bytes_follow_the_theme() {
+    // 200: LD V0, 00
+    // 202: LD F, V0
+    // 204: DRW V0, V0, 5    the "0" sprite in the top left corner
+    let rom = [0x60, 0x00, 0xF0, 0x29, 0xD0, 0x05];
+    let mut chip8 = Chip8Builder::new().with_theme(ColorTheme::Custom(0x336699, 0x102030)).build();
+    chip8.load_rom(rom.to_vec()).unwrap();
+    for _ in 0..3 {
+        chip8.step().unwrap();
+    }
+    // drawn by step() alone, with no frame since to bring the afterglow up to date
+
+    let mut rgba = vec![0; 64 * 32 * 4];
+    chip8.render_to_rgba(&mut rgba).unwrap();
+    assert_eq!(rgba[..8], [0x33, 0x66, 0x99, 0xFF, 0x33, 0x66, 0x99, 0xFF]);
+    // the "0" is hollow in its second row
+    assert_eq!(rgba[(64 + 1) * 4..(64 + 2) * 4], [0x10, 0x20, 0x30, 0xFF]);
+
+    let mut rgb = vec![0; 64 * 32 * 3 + 5];
+    chip8.render_to_rgb(&mut rgb).unwrap();
+    assert_eq!(rgb[..6], [0x33, 0x66, 0x99, 0x33, 0x66, 0x99]);
+    assert_eq!(rgb[64 * 3..64 * 3 + 3], [0x33, 0x66, 0x99]);
+    assert_eq!(rgb[(64 + 1) * 3..(64 + 2) * 3], [0x10, 0x20, 0x30]);
+    // past the screen the buffer is left alone
+    assert_eq!(rgb[64 * 32 * 3..], [0; 5]);
+
+    // and a pixel set by hand shows up just the same
+    chip8.set_display_pixel(1, 1, true);
+    chip8.set_display_pixel(0, 0, false);
+    chip8.render_to_rgb(&mut rgb).unwrap();
+    assert_eq!(rgb[..3], [0x10, 0x20, 0x30]);
+    assert_eq!(rgb[(64 + 1) * 3..(64 + 2) * 3], [0x33, 0x66, 0x99]);
+    let mut pixels = vec![0; 64 * 32];
+    chip8.render_into(&mut pixels);
+    assert_eq!(pixels[..2], [0x102030, 0x336699]);
+    assert_eq!(pixels[64 + 1], 0x336699);
+}
+
+#[test]
+fn render_buffers_must_fit_the_screen() {
+    let chip8 = Chip8Builder::new().build();
+    let mut small = vec![0; 64 * 32 * 4 - 1];
+    assert_eq!(
+        chip8.render_to_rgba(&mut small),
+        Err(Chip8Error::BufferTooSmall { needed: 64 * 32 * 4, len: 64 * 32 * 4 - 1 })
+    );
+    assert!(chip8.render_to_rgb(&mut small).is_ok());
+    assert!(chip8.render_to_rgb(&mut small[..100]).is_err());
+}
+
+#[test]
+fn hidpi_scale_rounds_to_whole_pixels() {
+    assert_eq!(hidpi_scale(16, 1.0), 16);
+    assert_eq!(hidpi_scale(16, 2.0), 32);
+    assert_eq!(hidpi_scale(10, 1.25), 13);
+    assert_eq!(hidpi_scale(3, 1.5), 5);
+    assert_eq!(hidpi_scale(1, 0.4), 1);
+    // a factor the desktop can't have leaves the scale alone
+    assert_eq!(hidpi_scale(16, 0.0), 16);
+    assert_eq!(hidpi_scale(16, f32::NAN), 16);
+}
+
+#[test]
+fn checksums_follow_ram() {
+    // 200: LD V0, 2A
+    // 202: LD I, 300
+    // 204: LD [I], V0
+    // 206: JP 206
+    let rom = [0x60, 0x2A, 0xA3, 0x00, 0xF0, 0x55, 0x12, 0x06];
+    let before = run(&rom, 0);
+    assert_eq!(before.memory_checksum(), run(&rom, 0).memory_checksum());
+    let after = run(&rom, 3);
+    assert_ne!(after.memory_checksum(), before.memory_checksum());
+    // the store went past the end of the ROM
+    assert_eq!(after.rom_checksum(), before.rom_checksum());
+    assert_ne!(run(&[0x60, 0x2B, 0xA3, 0x00, 0xF0, 0x55, 0x12, 0x06], 0).rom_checksum(), before.rom_checksum());
+}
+
+#[test]
+fn only_draws_and_clears_dirty_the_display() {
+    // 200: LD V0, 1
+    // 202: DRW V0, V0, 1     the first font row, as I is 0
+    // 204: ADD V0, 1
+    // 206: CLS
+    let mut chip8 = run(&[0x60, 0x01, 0xD0, 0x01, 0x70, 0x01, 0x00, 0xE0], 0);
+    // nothing has shown the new machine's screen yet
+    assert!(chip8.take_display_dirty());
+    assert!(!chip8.take_display_dirty());
+    let dirty: Vec<bool> = (0..4)
+        .map(|_| {
+            chip8.step().unwrap();
+            chip8.take_display_dirty()
+        })
+        .collect();
+    assert_eq!(dirty, [false, true, false, true]);
+    chip8.reset();
+    assert!(chip8.take_display_dirty());
+}
+
+#[test]
+fn drawing_over_a_pixel_set_by_hand_collides() {
+    // 200: LD V0, 8
+    // 202: DRW V0, V0, 1     the first font row, 1111 at (8, 8), as I is 0
+    let mut chip8 = run(&[0x60, 0x08, 0xD0, 0x01], 1);
+    chip8.take_display_dirty();
+    chip8.set_display_pixel(9, 8, true);
+    chip8.set_display_pixel(30, 20, true);
+    assert!(chip8.take_display_dirty());
+    assert!(chip8.get_display_pixel(9, 8));
+    assert_eq!(chip8.count_lit_pixels(), 2);
+
+    chip8.step().unwrap();
+    assert_eq!(chip8.register(0xF), 1);
+    let row: Vec<bool> = (8..12).map(|x| chip8.get_display_pixel(x, 8)).collect();
+    assert_eq!(row, [true, false, true, true]);
+    assert_eq!(chip8.count_lit_pixels(), 4);
+    chip8.set_display_pixel(64 + 30, 32 + 20, false);
+    assert_eq!(chip8.count_lit_pixels(), 3);
+}
+
+#[test]
+fn custom_font_replaces_the_built_in_one() {
+    let mut font = [[0; 5]; 16];
+    // a "0" with a slash through it
+    font[0] = [0x60, 0xB0, 0x90, 0xD0, 0x60];
+    let mut chip8 = Chip8Builder::new().with_custom_font(font).build();
+    // 200: LD V0, 00
+    // 202: LD F, V0
+    // 204: DRW V0, V0, 5
+    chip8.load_rom(vec![0x60, 0x00, 0xF0, 0x29, 0xD0, 0x05]).unwrap();
+    for _ in 0..3 {
+        chip8.step().unwrap();
+    }
+
+    let expected = [".##.", "#.##", "#..#", "##.#", ".##."];
+    for (row, pattern) in expected.iter().enumerate() {
+        for (column, cell) in pattern.chars().enumerate() {
+            assert_eq!(chip8.display().pixel(column, row), cell == '#', "pixel ({}, {})", column, row);
+        }
+    }
+
+    // and it comes back after a reset
+    chip8.reset();
+    assert_eq!(chip8.snapshot().pc, 0x200);
+    chip8.load_rom(vec![0x60, 0x00, 0xF0, 0x29, 0xD0, 0x05]).unwrap();
+    for _ in 0..3 {
+        chip8.step().unwrap();
+    }
+    assert!(!chip8.display().pixel(0, 0));
+    assert!(chip8.display().pixel(1, 0));
+}
+
+#[test]
+fn delay_timer_counts_down_once_per_tick() {
+    // 200: LD V0, 0A
+    // 202: LD DT, V0
+    // 204: LD V1, DT
+    let rom = [0x60, 0x0A, 0xF0, 0x15, 0xF1, 0x07];
+    let mut chip8 = run(&rom, 2);
+    for _ in 0..4 {
+        chip8.tick_timers();
+    }
+    chip8.step().unwrap();
+    assert_eq!(chip8.snapshot().vx[1], 6);
+}
+
+#[test]
+fn sound_timer_stops_at_zero() {
+    // 200: LD V0, 02
+    // 202: LD ST, V0
+    let rom = [0x60, 0x02, 0xF0, 0x18];
+    let mut chip8 = run(&rom, 2);
+    assert!(chip8.sound_active());
+    for _ in 0..5 {
+        chip8.tick_timers();
+    }
+    assert!(!chip8.sound_active());
+}
+
+#[test]
+fn bcd_of_255() {
+    // 200: LD V0, FF
+    // 202: LD I, 300
+    // 204: LD B, V0
+    // 206: LD V2, [I]       reads the three digits back into V0-V2
+    let rom = [0x60, 0xFF, 0xA3, 0x00, 0xF0, 0x33, 0xF2, 0x65];
+    let chip8 = run(&rom, 4);
+    let snapshot = chip8.snapshot();
+    assert_eq!(snapshot.vx[..3], [2, 5, 5]);
+    assert_eq!(snapshot.i, 0x300);
+}
+
+#[test]
+fn registers_survive_store_and_load() {
+    // 200: LD V0, 11
+    // 202: LD V1, 22
+    // 204: LD V2, 33
+    // 206: LD V3, 44
+    // 208: LD I, 300
+    // 20A: LD [I], V3
+    // 20C: LD V0, 00
+    // 20E: LD V1, 00
+    // 210: LD V2, 00
+    // 212: LD V3, 00
+    // 214: LD V3, [I]
+    let rom = [
+        0x60, 0x11, 0x61, 0x22, 0x62, 0x33, 0x63, 0x44, 0xA3, 0x00, 0xF3, 0x55, 0x60, 0x00, 0x61, 0x00, 0x62, 0x00,
+        0x63, 0x00, 0xF3, 0x65,
+    ];
+    let chip8 = run(&rom, 6);
+    assert_eq!(chip8.snapshot().vx[..4], [0x11, 0x22, 0x33, 0x44]);
+
+    let chip8 = run(&rom, 10);
+    assert_eq!(chip8.snapshot().vx[..4], [0; 4]);
+
+    let chip8 = run(&rom, 11);
+    let snapshot = chip8.snapshot();
+    assert_eq!(snapshot.vx[..4], [0x11, 0x22, 0x33, 0x44]);
+    assert_eq!(snapshot.i, 0x300);
+}
+
+#[test]
+fn add_without_carry_clears_vf() {
+    // 200: LD VF, 01
+    // 202: LD V0, 10
+    // 204: LD V1, 20
+    // 206: ADD V0, V1
+    let rom = [0x6F, 0x01, 0x60, 0x10, 0x61, 0x20, 0x80, 0x14];
+    let snapshot = run(&rom, 4).snapshot();
+    assert_eq!(snapshot.vx[0], 0x30);
+    assert_eq!(snapshot.vx[0xF], 0);
+
+    // 200: LD V0, F0
+    // 202: LD V1, 20
+    // 204: ADD V0, V1
+    let rom = [0x60, 0xF0, 0x61, 0x20, 0x80, 0x14];
+    let snapshot = run(&rom, 3).snapshot();
+    assert_eq!(snapshot.vx[0], 0x10);
+    assert_eq!(snapshot.vx[0xF], 1);
+}
+
+#[test]
+fn shift_left_puts_the_top_bit_in_vf() {
+    // 200: LD V0, 81
+    // 202: SHL V0
+    let rom = [0x60, 0x81, 0x80, 0x0E];
+    let snapshot = run(&rom, 2).snapshot();
+    assert_eq!(snapshot.vx[0], 0x02);
+    assert_eq!(snapshot.vx[0xF], 1);
+
+    // 200: LD V0, 41
+    // 202: SHL V0
+    let rom = [0x60, 0x41, 0x80, 0x0E];
+    let snapshot = run(&rom, 2).snapshot();
+    assert_eq!(snapshot.vx[0], 0x82);
+    assert_eq!(snapshot.vx[0xF], 0);
+}
+
+#[test]
+fn results_format_for_verbose_output() {
+    // 200: LD V0, 01
+    // 202: LD V1, K
+    // 204: JP 204
+    let rom = [0x60, 0x01, 0xF1, 0x0A, 0x12, 0x04];
+    let mut chip8 = run(&rom, 0);
+    assert_eq!(chip8.step().unwrap().to_string(), "Instruction executed at PC=0x200");
+    assert_eq!(chip8.step().unwrap().to_string(), "Waiting for key press");
+    chip8.keypad_mut().press(5);
+    chip8.step().unwrap();
+    assert_eq!(chip8.step().unwrap().to_string(), "Halted at PC=0x204");
+}
+
+#[test]
+fn i_stays_within_ram_whatever_i_max_says() {
+    // an i_max past the end of RAM, as a hand-edited state or config could have
+    let quirks = QuirkConfig { i_max: 0xFFFF, ..QuirkConfig::default() };
+    let step_at_the_end = |opcode: [u8; 2]| {
+        let mut chip8 = Chip8Builder::new().with_quirks(quirks).build();
+        chip8.load_rom(opcode.to_vec()).unwrap();
+        chip8.set_i_register(0xFFF).unwrap();
+        chip8.step()
+    };
+    // FX33, FX55 and FX65 with X = 1, and a 2 row DRW, all reach past 0xFFF
+    assert_eq!(step_at_the_end([0xF0, 0x33]), Err(Chip8Error::IRegisterOutOfBounds { i: 0xFFF, size: 3 }));
+    assert_eq!(step_at_the_end([0xF1, 0x55]), Err(Chip8Error::IRegisterOutOfBounds { i: 0xFFF, size: 2 }));
+    assert_eq!(step_at_the_end([0xF1, 0x65]), Err(Chip8Error::IRegisterOutOfBounds { i: 0xFFF, size: 2 }));
+    assert_eq!(step_at_the_end([0xD0, 0x02]), Err(Chip8Error::IRegisterOutOfBounds { i: 0xFFF, size: 2 }));
+    // one byte still fits
+    assert!(step_at_the_end([0xF0, 0x65]).is_ok());
+
+    let mut chip8 = Chip8Builder::new().with_quirks(quirks).build();
+    assert_eq!(chip8.set_i_register(0x1000), Err(Chip8Error::IRegisterOutOfBounds { i: 0x1000, size: 1 }));
+}
+
+#[test]
+fn empty_and_oversized_roms_are_refused() {
+    let mut chip8 = Chip8Builder::new().build();
+    assert_eq!(chip8.load_rom(Vec::new()), Err(Chip8Error::EmptyRom));
+    assert_eq!(chip8.load_rom(vec![0; 4000]), Err(Chip8Error::RomTooLarge { size: 4000, max: 3584 }));
+
+    let path = std::env::temp_dir().join(format!("rust-8-empty-{}.ch8", std::process::id()));
+    std::fs::write(&path, b"").unwrap();
+    let result = chip8.load_rom_file(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert!(matches!(result, Err(EmulatorError::Chip8(Chip8Error::EmptyRom))), "{:?}", result.err());
+}
+
+#[test]
+fn roms_fit_right_up_to_the_end_of_memory() {
+    let load = |len: usize| Chip8Builder::new().build().load_rom(vec![0xAB; len]);
+    assert_eq!(load(0), Err(Chip8Error::EmptyRom));
+    assert_eq!(load(1), Ok(()));
+    // 0x200 to 0xFFF
+    assert_eq!(load(3584), Ok(()));
+    assert_eq!(load(3585), Err(Chip8Error::RomTooLarge { size: 3585, max: 3584 }));
+
+    let mut chip8 = Chip8Builder::new().build();
+    chip8.load_rom(vec![0xAB; 3584]).unwrap();
+    assert_eq!(chip8.memory(0xFFF, 1).unwrap(), [0xAB]);
+    assert_eq!(chip8.memory(0x1FF, 1).unwrap(), [0x00]);
+}
+
+#[test]
+fn a_bad_library_rom_never_stops_the_running_one() {
+    // 200: ADD V1, 1  202: JP 200
+    let mut chip8 = run(&[0x71, 0x01, 0x12, 0x00], 9);
+    assert_eq!(chip8.add_rom_to_library("empty", &[]), Err(Chip8Error::EmptyRom));
+    assert_eq!(
+        chip8.add_rom_to_library("huge", &[0; 4000]),
+        Err(Chip8Error::RomTooLarge { size: 4000, max: 3584 })
+    );
+    assert!(chip8.rom_library().is_empty());
+    chip8.add_rom_to_library("idle", &[0x12, 0x00]).unwrap();
+
+    let before = chip8.memory_checksum();
+    assert_eq!(chip8.switch_to_rom(1), Err(Chip8Error::NoSuchRom { index: 1, count: 1 }));
+    assert_eq!(chip8.memory_checksum(), before);
+    assert_eq!(chip8.register(1), 5);
+    assert_eq!(chip8.current_rom(), None);
+
+    chip8.switch_to_rom(0).unwrap();
+    assert_eq!(chip8.register(1), 0);
+    assert_eq!(chip8.current_rom(), Some(0));
+}
+
+fn protected(rom: &[u8], protection: MemoryProtection, steps: usize) -> (Chip8, Result<InstructionResult, Chip8Error>) {
+    let mut chip8 = Chip8Builder::new().with_rng_seed(0).with_memory_protection(protection).build();
+    chip8.load_rom(rom.to_vec()).unwrap();
+    for _ in 1..steps {
+        chip8.step().unwrap();
+    }
+    let last = chip8.step();
+    (chip8, last)
+}
+
+#[test]
+fn register_stores_below_the_program_are_stopped_only_when_strict() {
+    // 200: LD I, 100
+    // 202: LD V0, 12
+    // 204: LD V1, 34
+    // 206: LD [I], V1
+    let rom = [0xA1, 0x00, 0x60, 0x12, 0x61, 0x34, 0xF1, 0x55];
+
+    let (chip8, result) = protected(&rom, MemoryProtection::Strict, 4);
+    assert_eq!(result, Err(Chip8Error::ProtectedWrite { pc: 0x206, opcode: 0xF155, address: 0x100 }));
+    assert_eq!(chip8.memory(0x100, 2).unwrap(), [0x00, 0x00]);
+
+    let (chip8, result) = protected(&rom, MemoryProtection::Permissive, 4);
+    assert!(result.is_ok(), "{:?}", result);
+    assert_eq!(chip8.memory(0x100, 2).unwrap(), [0x12, 0x34]);
+}
+
+#[test]
+fn bcd_below_the_program_is_stopped_only_when_strict() {
+    // 200: LD I, 1FF
+    // 202: LD V0, FE
+    // 204: LD B, V0
+    let rom = [0xA1, 0xFF, 0x60, 0xFE, 0xF0, 0x33];
+
+    // the hundreds digit is the one that lands below 0x200
+    let (chip8, result) = protected(&rom, MemoryProtection::Strict, 3);
+    assert_eq!(result, Err(Chip8Error::ProtectedWrite { pc: 0x204, opcode: 0xF033, address: 0x1FF }));
+    assert_eq!(chip8.memory(0x1FF, 3).unwrap(), [0x00, 0xA1, 0xFF]);
+
+    let (chip8, result) = protected(&rom, MemoryProtection::Permissive, 3);
+    assert!(result.is_ok(), "{:?}", result);
+    assert_eq!(chip8.memory(0x1FF, 3).unwrap(), [2, 5, 4]);
+}
+
+#[test]
+fn strict_protection_still_allows_writes_from_the_program_start_up() {
+    // 200: LD I, 200
+    // 202: LD V0, FE
+    // 204: LD B, V0
+    let rom = [0xA2, 0x00, 0x60, 0xFE, 0xF0, 0x33];
+    let (chip8, result) = protected(&rom, MemoryProtection::Strict, 3);
+    assert!(result.is_ok(), "{:?}", result);
+    assert_eq!(chip8.memory(0x200, 3).unwrap(), [2, 5, 4]);
+}
+
+fn run_with(quirks: QuirkConfig, rom: &[u8], cycles: usize) -> Chip8 {
+    let mut chip8 = Chip8Builder::new().with_rng_seed(0).with_quirks(quirks).build();
+    chip8.load_rom(rom.to_vec()).unwrap();
+    for _ in 0..cycles {
+        chip8.step().unwrap();
+    }
+    chip8
+}
+
+#[test]
+fn fx1e_overflow_sets_vf() {
+    for (name, quirks, flag_on_overflow) in [("chip8_original", QuirkConfig::chip8_original(), 0x01), ("chip48", QuirkConfig::chip48(), 0xAA)] {
+        // 200: LD VF, AA
+        // 202: LD V0, 01
+        // 204: LD I, FFF
+        // 206: ADD I, V0
+        let chip8 = run_with(quirks, &[0x6F, 0xAA, 0x60, 0x01, 0xAF, 0xFF, 0xF0, 0x1E], 4);
+        assert_eq!(chip8.cpu_snapshot().i, 0x1000, "{}", name);
+        assert_eq!(chip8.register(0xF), flag_on_overflow, "{}", name);
+
+        // 204: LD I, FFE, which only reaches FFF
+        let chip8 = run_with(quirks, &[0x6F, 0xAA, 0x60, 0x01, 0xAF, 0xFE, 0xF0, 0x1E], 4);
+        assert_eq!(chip8.cpu_snapshot().i, 0xFFF, "{}", name);
+        let flag_otherwise = if quirks.fx1e_overflow_sets_vf { 0x00 } else { 0xAA };
+        assert_eq!(chip8.register(0xF), flag_otherwise, "{}", name);
+    }
+}
+
+#[test]
+fn bnnn_uses_vx() {
+    // 200: LD V0, 04
+    // 202: LD V2, 08
+    // 204: JP V0, 220, which is JP V2, 220 with the quirk on
+    let rom = [0x60, 0x04, 0x62, 0x08, 0xB2, 0x20];
+    for (bnnn_uses_vx, target) in [(false, 0x224), (true, 0x228)] {
+        let quirks = QuirkConfig { bnnn_uses_vx, ..QuirkConfig::default() };
+        assert_eq!(run_with(quirks, &rom, 3).pc(), target, "bnnn_uses_vx: {}", bnnn_uses_vx);
+    }
+    assert_eq!(run_with(QuirkConfig::chip8_original(), &rom, 3).pc(), 0x224);
+    assert_eq!(run_with(QuirkConfig::chip48(), &rom, 3).pc(), 0x228);
+}
@@ -0,0 +1,49 @@
+// Host keys held in the window to the CHIP-8 keypad they stand for.
+
+use minifb::Key;
+use rust_8::{keypad_from, Keypad, KEYBOARD_LAYOUT, KEYMAP};
+
+#[test]
+fn no_keys_is_an_empty_keypad() {
+    assert_eq!(keypad_from(&[]), Keypad::new());
+}
+
+#[test]
+fn each_host_key_presses_only_its_own_chip8_key() {
+    for key in 0..16u8 {
+        let keypad = keypad_from(&[KEYMAP[key as usize]]);
+        assert_eq!(keypad.first_pressed(), Some(key));
+        for other in (0..16).filter(|other| *other != key) {
+            assert!(!keypad.is_pressed(other), "{:X} pressed {:X} too", key, other);
+        }
+    }
+}
+
+#[test]
+fn the_host_keys_sit_where_the_cosmac_keys_do() {
+    let rows = [
+        [Key::Key1, Key::Key2, Key::Key3, Key::Key4],
+        [Key::Q, Key::W, Key::E, Key::R],
+        [Key::A, Key::S, Key::D, Key::F],
+        [Key::Z, Key::X, Key::C, Key::V],
+    ];
+    for (row, layout) in rows.iter().zip(KEYBOARD_LAYOUT) {
+        for (host_key, key) in row.iter().zip(layout) {
+            assert_eq!(keypad_from(&[*host_key]).first_pressed(), Some(key));
+        }
+    }
+}
+
+#[test]
+fn other_host_keys_are_ignored() {
+    let keypad = keypad_from(&[Key::Escape, Key::Space, Key::W, Key::F5, Key::V]);
+    let mut expected = Keypad::new();
+    expected.press(0x5);
+    expected.press(0xF);
+    assert_eq!(keypad, expected);
+}
+
+#[test]
+fn every_key_held_at_once() {
+    assert_eq!(keypad_from(&KEYMAP), Keypad::from([true; 16]));
+}
@@ -0,0 +1,64 @@
+use rust_8::{Chip8, InputBackend, Keypad};
+
+// an input backend holding whatever keys the test says, and asking for nothing else
+struct HeldKeys(Keypad);
+
+impl InputBackend for HeldKeys {
+    fn poll(&mut self, keypad: &mut Keypad) {
+        *keypad = self.0;
+    }
+    fn is_key_pressed(&self, key: u8) -> bool {
+        self.0.is_pressed(key)
+    }
+    fn quit_requested(&self) -> bool {
+        false
+    }
+    fn has_focus(&self) -> bool {
+        true
+    }
+    fn reset_requested(&mut self) -> bool {
+        false
+    }
+    fn theme_requested(&mut self) -> bool {
+        false
+    }
+    fn filter_requested(&mut self) -> bool {
+        false
+    }
+    fn mute_requested(&mut self) -> bool {
+        false
+    }
+    fn undo_requested(&mut self) -> bool {
+        false
+    }
+    fn pause_requested(&mut self) -> bool {
+        false
+    }
+    fn next_rom_requested(&mut self) -> bool {
+        false
+    }
+    fn scale_requested(&mut self) -> i32 {
+        0
+    }
+    fn fullscreen_requested(&mut self) -> bool {
+        false
+    }
+    fn keyboard_requested(&mut self) -> bool {
+        false
+    }
+}
+
+#[test]
+fn reports_the_keys_the_input_holds() {
+    let mut input = HeldKeys(Keypad::new());
+    assert_eq!(Chip8::keys_pressed(&input), [false; 16]);
+    assert!(!Chip8::any_key_pressed(&input));
+
+    input.0.press(0x3);
+    input.0.press(0xF);
+    let keys = Chip8::keys_pressed(&input);
+    assert_eq!(keys.iter().filter(|&&held| held).count(), 2);
+    assert!(keys[0x3] && keys[0xF]);
+    assert!(Chip8::any_key_pressed(&input));
+    assert_eq!(Keypad::from(keys), input.0);
+}
@@ -0,0 +1,49 @@
+// What the core logs through the log crate, captured by a logger that keeps every record.
+
+use std::sync::Mutex;
+
+use log::{Level, Log, Metadata, Record};
+use rust_8::Chip8Builder;
+
+static RECORDS: Mutex<Vec<(Level, String)>> = Mutex::new(Vec::new());
+
+struct Capture;
+
+impl Log for Capture {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        RECORDS.lock().unwrap().push((record.level(), record.args().to_string()));
+    }
+
+    fn flush(&self) {}
+}
+
+static CAPTURE: Capture = Capture;
+
+#[test]
+fn unknown_opcode_warns_once_per_address() {
+    log::set_logger(&CAPTURE).unwrap();
+    log::set_max_level(log::LevelFilter::Trace);
+
+    // 200: FFFF             not an instruction
+    // 202: JP 200
+    let mut chip8 = Chip8Builder::new().build();
+    chip8.load_rom(vec![0xFF, 0xFF, 0x12, 0x00]).unwrap();
+    for _ in 0..20 {
+        chip8.step().unwrap();
+    }
+
+    let records = RECORDS.lock().unwrap();
+    let warnings: Vec<&String> = records
+        .iter()
+        .filter(|(level, _)| *level == Level::Warn)
+        .map(|(_, message)| message)
+        .collect();
+    assert_eq!(warnings, ["unknown opcode FFFF at 0x200"]);
+    // and every instruction is traced
+    assert_eq!(records.iter().filter(|(level, _)| *level == Level::Trace).count(), 20);
+    assert!(records.iter().any(|(level, message)| *level == Level::Info && message == "loaded a 4-byte ROM"));
+}
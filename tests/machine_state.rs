@@ -0,0 +1,106 @@
+// MachineState saved, checked on the way back in, and carried through serde formats.
+
+use rust_8::{Chip8, Chip8Builder, Chip8Error, MachineState, QuirkConfig, UnknownOpcodePolicy};
+
+// 200: RND V0, 3F
+// 202: RND V1, 1F
+// 204: LD F, V0
+// 206: DRW V0, V1, 5
+// 208: JP 200
+const SCRIBBLE: [u8; 10] = [0xC0, 0x3F, 0xC1, 0x1F, 0xF0, 0x29, 0xD0, 0x15, 0x12, 0x00];
+
+fn machine() -> Chip8 {
+    let mut chip8 = Chip8Builder::new().with_rng_seed(99).with_quirks(QuirkConfig::chip48()).build();
+    chip8.load_rom(SCRIBBLE.to_vec()).unwrap();
+    chip8
+}
+
+// a machine some way into drawing random glyphs all over the screen
+fn scribbled() -> Chip8 {
+    let mut chip8 = machine();
+    for step in 0..1000 {
+        chip8.step().unwrap();
+        if step % 10 == 9 {
+            chip8.tick_timers();
+        }
+    }
+    chip8
+}
+
+fn refused(state: MachineState, reason: &str) {
+    let mut chip8 = machine();
+    let before = chip8.save_state();
+    match chip8.load_state(state) {
+        Err(Chip8Error::InvalidState(message)) => assert!(message.contains(reason), "{}", message),
+        other => panic!("expected InvalidState about {}, got {:?}", reason, other),
+    }
+    assert_eq!(chip8.save_state(), before, "a refused state changed the machine");
+}
+
+#[test]
+fn a_pc_past_the_end_of_memory_is_refused() {
+    refused(MachineState { pc: 0xFFF, ..scribbled().save_state() }, "program counter 0x0FFF");
+    refused(MachineState { pc: 0xFFFF, ..scribbled().save_state() }, "program counter 0xFFFF");
+}
+
+#[test]
+fn quirks_reaching_past_memory_are_refused() {
+    let state = scribbled().save_state();
+    let quirks = QuirkConfig { i_max: 0x1000, ..state.quirks };
+    refused(MachineState { quirks, ..state.clone() }, "i_max 0x1000");
+    let quirks = QuirkConfig { unknown_opcode_policy: UnknownOpcodePolicy::Trap(0xFFFF), ..state.quirks };
+    refused(MachineState { quirks, ..state }, "trap address 0xFFFF");
+}
+
+#[test]
+fn more_random_numbers_than_can_be_replayed_are_refused() {
+    let state = scribbled().save_state();
+    refused(MachineState { rng: Some((99, u64::MAX)), ..state }, "random numbers drawn");
+}
+
+#[test]
+fn an_odd_pc_from_a_jump_is_fine() {
+    let mut chip8 = machine();
+    chip8.load_state(MachineState { pc: 0x201, ..scribbled().save_state() }).unwrap();
+    assert_eq!(chip8.pc(), 0x201);
+}
+
+// the restored machine is the saved one, and both carry on drawing the same things
+fn assert_restores(state: MachineState) {
+    let mut original = scribbled();
+    assert_eq!(original.save_state(), state);
+    let mut restored = machine();
+    restored.load_state(state).unwrap();
+    assert_eq!(restored.save_state(), original.save_state());
+    for _ in 0..500 {
+        original.step().unwrap();
+        restored.step().unwrap();
+    }
+    assert_eq!(restored.save_state(), original.save_state());
+    assert_eq!(restored.display(), original.display());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn bincode_round_trips_bit_for_bit() {
+    let state = scribbled().save_state();
+    let bytes = bincode::serialize(&state).unwrap();
+    let back: MachineState = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(bincode::serialize(&back).unwrap(), bytes);
+    assert_restores(back);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn json_round_trips_bit_for_bit() {
+    let state = scribbled().save_state();
+    let text = serde_json::to_string(&state).unwrap();
+    let back: MachineState = serde_json::from_str(&text).unwrap();
+    assert_eq!(serde_json::to_string(&back).unwrap(), text);
+    assert_restores(back);
+}
+
+#[test]
+fn a_state_restores_without_going_through_a_format() {
+    assert_restores(scribbled().save_state());
+}
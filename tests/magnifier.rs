@@ -0,0 +1,67 @@
+// The magnifier's close-up of part of the screen, grid and all.
+
+use rust_8::{magnify, HEIGHT, WIDTH};
+
+const GRID: u32 = 0x404040;
+const SIZE: usize = 16;
+const ZOOM: usize = 8;
+const OUT: usize = SIZE * ZOOM;
+
+// every pixel of the screen different, and none black or the grid color
+fn frame() -> Vec<u32> {
+    (0..WIDTH * HEIGHT).map(|n| 0x100000 + n as u32).collect()
+}
+
+fn at(x: usize, y: usize) -> u32 {
+    0x100000 + (y * WIDTH + x) as u32
+}
+
+fn magnified(corner: (usize, usize)) -> Vec<u32> {
+    let mut out = vec![0x123456; OUT * OUT];
+    magnify(&frame(), WIDTH, corner, SIZE, ZOOM, GRID, &mut out);
+    out
+}
+
+#[test]
+fn each_cell_is_one_screen_pixel_inside_its_grid_lines() {
+    let out = magnified((10, 5));
+    for y in 0..OUT {
+        for x in 0..OUT {
+            let expected = if x % ZOOM == 0 || y % ZOOM == 0 { GRID } else { at(10 + x / ZOOM, 5 + y / ZOOM) };
+            assert_eq!(out[y * OUT + x], expected, "({}, {})", x, y);
+        }
+    }
+}
+
+#[test]
+fn the_grid_runs_along_the_top_and_left_of_each_cell() {
+    let out = magnified((0, 0));
+    // the top left corner of the first cell and its first pixel inside the lines
+    assert_eq!(out[0], GRID);
+    assert_eq!(out[OUT + 1], at(0, 0));
+    // the last pixel of the first cell, and the line starting the next one
+    assert_eq!(out[7 * OUT + 7], at(0, 0));
+    assert_eq!(out[7 * OUT + 8], GRID);
+    assert_eq!(out[8 * OUT + 7], GRID);
+    assert_eq!(out[9 * OUT + 9], at(1, 1));
+    // no line closing off the right and bottom edges
+    assert_eq!(out[(OUT - 1) * OUT + OUT - 1], at(15, 15));
+}
+
+#[test]
+fn a_region_hanging_off_the_screen_is_black_past_the_edge() {
+    let out = magnified((WIDTH - 4, HEIGHT - 2));
+    // cell (3, 1) is still on screen, cells (4, 1) and (3, 2) are past the edges
+    assert_eq!(out[(ZOOM + 1) * OUT + 3 * ZOOM + 1], at(WIDTH - 1, HEIGHT - 1));
+    assert_eq!(out[(ZOOM + 1) * OUT + 4 * ZOOM + 1], 0);
+    assert_eq!(out[(2 * ZOOM + 1) * OUT + 3 * ZOOM + 1], 0);
+    // and the grid carries on over the black
+    assert_eq!(out[(2 * ZOOM) * OUT + 5 * ZOOM + 3], GRID);
+}
+
+#[test]
+fn an_empty_frame_magnifies_to_grid_and_black() {
+    let mut out = vec![0x123456; OUT * OUT];
+    magnify(&[], 0, (0, 0), SIZE, ZOOM, GRID, &mut out);
+    assert!(out.iter().all(|pixel| *pixel == GRID || *pixel == 0));
+}
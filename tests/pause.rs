@@ -0,0 +1,48 @@
+// The pause key and window focus, each able to stop emulation on its own.
+
+use rust_8::PauseState;
+
+fn state(manual: bool, focused: bool) -> PauseState {
+    let mut pause = PauseState::default();
+    if manual {
+        pause.toggle_manual();
+    }
+    pause.set_focused(focused);
+    pause
+}
+
+#[test]
+fn every_combination_of_pause_key_and_focus() {
+    // (paused with the key, window focused, paused at all)
+    let cases = [(false, true, false), (false, false, true), (true, true, true), (true, false, true)];
+    for (manual, focused, paused) in cases {
+        let pause = state(manual, focused);
+        assert_eq!(pause.is_paused(), paused, "manual {} focused {}", manual, focused);
+        assert_eq!(pause.is_manual(), manual, "manual {} focused {}", manual, focused);
+        assert_eq!(pause.is_unfocused(), !focused, "manual {} focused {}", manual, focused);
+    }
+}
+
+#[test]
+fn getting_focus_back_keeps_a_manual_pause() {
+    let mut pause = state(true, false);
+    pause.set_focused(true);
+    assert!(pause.is_paused());
+    pause.toggle_manual();
+    assert!(!pause.is_paused());
+}
+
+#[test]
+fn unpausing_by_key_while_unfocused_stays_paused_until_focus_returns() {
+    let mut pause = state(true, false);
+    pause.toggle_manual();
+    assert!(pause.is_paused());
+    pause.set_focused(true);
+    assert!(!pause.is_paused());
+}
+
+#[test]
+fn a_new_state_runs() {
+    let pause = PauseState::default();
+    assert!(!pause.is_paused() && !pause.is_manual() && !pause.is_unfocused());
+}
@@ -0,0 +1,39 @@
+use rust_8::{Chip8, Chip8Builder};
+
+// 200: RND V3, FF
+// 202: LD I, 20B          the byte after ADD V2's opcode
+// 204: LD V0, [I]
+// 206: ADD V0, 1
+// 208: LD [I], V0         rewrites the instruction just below
+// 20A: ADD V2, 00         adds one more each time round
+// 20C: LD F, V2
+// 20E: DRW V2, V3, 5
+// 210: JP 200
+const SELF_MODIFYING: [u8; 18] = [
+    0xC3, 0xFF, 0xA2, 0x0B, 0xF0, 0x65, 0x70, 0x01, 0xF0, 0x55, 0x72, 0x00, 0xF2, 0x29, 0xD2, 0x35, 0x12, 0x00,
+];
+
+fn machine(predecode: bool) -> Chip8 {
+    let mut chip8 = Chip8Builder::new().with_predecode(predecode).with_rng_seed(3).build();
+    chip8.load_rom(SELF_MODIFYING.to_vec()).unwrap();
+    chip8
+}
+
+#[test]
+fn predecoding_never_changes_what_a_rom_does() {
+    let (mut decoded, mut interpreted) = (machine(true), machine(false));
+    for step in 1..=20_000 {
+        assert_eq!(decoded.step(), interpreted.step(), "step {}", step);
+        if step % 10 == 0 {
+            decoded.tick_timers();
+            interpreted.tick_timers();
+        }
+        if step % 1000 == 0 {
+            assert_eq!(decoded.memory_checksum(), interpreted.memory_checksum(), "step {}", step);
+            assert_eq!(decoded.cpu_snapshot(), interpreted.cpu_snapshot(), "step {}", step);
+            assert_eq!(decoded.display(), interpreted.display(), "step {}", step);
+        }
+    }
+    // the ADD really was rewritten, wrapping round a few times by now
+    assert_ne!(decoded.memory(0x20B, 1).unwrap(), [0]);
+}
@@ -0,0 +1,90 @@
+// Profiling subroutines: calls and cycles per node of the call tree, and the report printed.
+
+use rust_8::{CallTree, Chip8, Chip8Builder, Chip8TestRom, SymbolTable};
+
+// 200: CALL outer twice, then JP 204 to halt
+// 20A outer: LD V0, 1, CALL inner, RET
+// 210 inner: ADD V0, 1, RET
+fn chip8() -> Chip8 {
+    let rom = Chip8TestRom::new()
+        .call(0x20A)
+        .call(0x20A)
+        .jp(0x204)
+        .data(&[0; 4])
+        .ld(0, 1)
+        .call(0x210)
+        .ret()
+        .add_byte(0, 1)
+        .ret();
+    assert_eq!(rom.address(), 0x214);
+    let mut chip8 = Chip8Builder::new().build();
+    chip8.load_rom(rom.build()).unwrap();
+    chip8
+}
+
+fn run(chip8: &mut Chip8, steps: usize) {
+    for _ in 0..steps {
+        chip8.step().unwrap();
+    }
+}
+
+fn tree(address: u16, calls: u64, cycles: u64, children: Vec<CallTree>) -> CallTree {
+    CallTree { address, calls, cycles, children }
+}
+
+#[test]
+fn times_each_subroutine_under_its_caller() {
+    let mut chip8 = chip8();
+    chip8.enable_profiling();
+    // the jump to itself halts without running
+    run(&mut chip8, 20);
+    let report = chip8.profiling_report();
+    assert_eq!(report.cycles, 12);
+    // the two CALLs of outer
+    assert_eq!(report.top_level_cycles(), 2);
+    assert_eq!(report.roots, [tree(0x20A, 2, 10, vec![tree(0x210, 2, 4, vec![])])]);
+    assert_eq!(report.roots[0].self_cycles(), 6);
+}
+
+#[test]
+fn open_calls_count_up_to_now() {
+    let mut chip8 = chip8();
+    chip8.enable_profiling();
+    // into inner for the first time, before its RET
+    run(&mut chip8, 4);
+    let report = chip8.profiling_report();
+    assert_eq!(report.roots, [tree(0x20A, 1, 3, vec![tree(0x210, 1, 1, vec![])])]);
+}
+
+#[test]
+fn nothing_is_recorded_until_enabled_and_a_reset_starts_over() {
+    let mut chip8 = chip8();
+    run(&mut chip8, 6);
+    assert_eq!(chip8.profiling_report().cycles, 0);
+    assert_eq!(chip8.enable_profiling().cycles(), 0);
+    run(&mut chip8, 6);
+    assert_eq!(chip8.profiling_report().roots, [tree(0x20A, 1, 5, vec![tree(0x210, 1, 2, vec![])])]);
+    chip8.reset();
+    assert!(chip8.profiling_report().roots.is_empty());
+}
+
+#[test]
+fn prints_an_indented_tree_with_names() {
+    let mut chip8 = chip8();
+    chip8.enable_profiling();
+    run(&mut chip8, 20);
+    let report = chip8.profiling_report();
+    assert_eq!(
+        report.to_string(),
+        "12 cycles, 2 outside any subroutine\n0x20A: 2 calls, 10 cycles (6 self)\n  0x210: 2 calls, 4 cycles (4 self)\n"
+    );
+    let symbols: SymbolTable = "0x20A outer\n0x210 inner\n".parse().unwrap();
+    assert_eq!(
+        report.lines(Some(&symbols)),
+        [
+            "12 cycles, 2 outside any subroutine",
+            "0x20A outer: 2 calls, 10 cycles (6 self)",
+            "  0x210 inner: 2 calls, 4 cycles (4 self)",
+        ]
+    );
+}
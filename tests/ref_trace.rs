@@ -0,0 +1,135 @@
+// Reference traces written by one run and checked against another, through the library
+// and through --emit-ref-trace and --ref-trace.
+
+use std::{fs, io::Cursor, process::Command};
+
+use rust_8::{Chip8, Chip8Builder, InstructionResult, RefTraceChecker, RefTraceError, RefTraceWriter, TraceFormat, TraceLine};
+
+// 200: LD V0, 05
+// 202: LD V1, 0A
+// 204: ADD V0, V1
+// 206: LD I, 300
+// 208: JP 208
+const ROM: [u8; 10] = [0x60, 0x05, 0x61, 0x0A, 0x80, 0x14, 0xA3, 0x00, 0x12, 0x08];
+
+fn machine() -> Chip8 {
+    let mut chip8 = Chip8Builder::new().with_rng_seed(0).build();
+    chip8.load_rom(ROM.to_vec()).unwrap();
+    chip8
+}
+
+fn trace(format: TraceFormat) -> String {
+    let mut chip8 = machine();
+    let mut writer = RefTraceWriter::new(Vec::new(), format).unwrap();
+    while let InstructionResult::Executed { .. } = chip8.step().unwrap() {
+        writer.record(&chip8).unwrap();
+    }
+    String::from_utf8(writer.finish().unwrap()).unwrap()
+}
+
+// checks a fresh run against `trace`, returning how many instructions matched
+fn check(trace: &str) -> Result<u64, RefTraceError> {
+    let mut chip8 = machine();
+    let mut checker = RefTraceChecker::new(Cursor::new(trace.as_bytes()));
+    while let InstructionResult::Executed { .. } = chip8.step().unwrap() {
+        if !checker.check(&chip8)? {
+            return Ok(checker.instructions());
+        }
+    }
+    checker.finish()
+}
+
+#[test]
+fn writes_a_line_per_instruction() {
+    let csv = trace(TraceFormat::Csv);
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 5);
+    assert_eq!(lines[0], "pc,i,v0,v1,v2,v3,v4,v5,v6,v7,v8,v9,va,vb,vc,vd,ve,vf");
+    assert_eq!(lines[1], "202,000,05,00,00,00,00,00,00,00,00,00,00,00,00,00,00,00");
+    assert_eq!(lines[4], "208,300,0F,0A,00,00,00,00,00,00,00,00,00,00,00,00,00,00");
+
+    let json = trace(TraceFormat::Json);
+    assert_eq!(json.lines().count(), 4);
+    assert_eq!(
+        TraceLine::parse(json.lines().last().unwrap()).unwrap(),
+        TraceLine::parse(lines[4]).unwrap()
+    );
+}
+
+#[test]
+fn a_run_matches_its_own_trace() {
+    assert_eq!(check(&trace(TraceFormat::Csv)).unwrap(), 4);
+    assert_eq!(check(&trace(TraceFormat::Json)).unwrap(), 4);
+    // with comments, blank lines and 0x prefixes another emulator might write
+    let trace = "# from elsewhere\n\n0x202,0x000,05,00,00,00,00,00,00,00,00,00,00,00,00,00,00,00\n";
+    assert_eq!(check(trace).unwrap(), 1);
+}
+
+#[test]
+fn reports_the_first_divergence() {
+    let trace = trace(TraceFormat::Csv).replace("\n208,300,0F,", "\n208,300,0E,");
+    match check(&trace) {
+        Err(RefTraceError::Diverged { instruction, field, expected, actual, .. }) => {
+            assert_eq!((instruction, field.as_str(), expected, actual), (4, "V0", 0x0E, 0x0F));
+        }
+        other => panic!("expected a divergence, got {:?}", other),
+    }
+
+    // PC is compared before the registers
+    let trace = "206,000,05,0A,00,00,00,00,00,00,00,00,00,00,00,00,00,00\n";
+    match check(trace) {
+        Err(err @ RefTraceError::Diverged { .. }) => {
+            let message = err.to_string();
+            assert!(message.starts_with("diverged from the reference trace at instruction 1: PC is 0x202, expected 0x206"), "{}", message);
+        }
+        other => panic!("expected a divergence, got {:?}", other),
+    }
+}
+
+#[test]
+fn a_trace_longer_than_the_run_diverges() {
+    let mut trace = trace(TraceFormat::Csv);
+    trace.push_str("20A,300,0F,0A,00,00,00,00,00,00,00,00,00,00,00,00,00,00\n");
+    assert!(matches!(check(&trace), Err(RefTraceError::TraceLonger { instructions: 4 })));
+}
+
+#[test]
+fn bad_lines_are_reported_by_number() {
+    let trace = "pc,i,v0,v1,v2,v3,v4,v5,v6,v7,v8,v9,va,vb,vc,vd,ve,vf\n202,000,05\n";
+    assert!(matches!(check(trace), Err(RefTraceError::Parse { line: 2, .. })));
+    assert!(TraceLine::parse("{\"pc\":514,\"i\":0,\"v\":[256]}").is_err());
+    assert!(TraceLine::parse("202,000,05,00,00,00,00,00,00,00,00,00,00,00,00,00,00,1FF").is_err());
+}
+
+#[test]
+fn the_binary_checks_a_trace_it_wrote() {
+    let dir = std::env::temp_dir().join(format!("rust-8-ref-trace-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let rom = dir.join("game.ch8");
+    let trace = dir.join("trace.csv");
+    fs::write(&rom, ROM).unwrap();
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_rust-8"))
+            .arg(&rom)
+            .args(args)
+            .args(["--frames", "5"])
+            .output()
+            .unwrap()
+    };
+
+    let output = run(&["--emit-ref-trace", trace.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(fs::read_to_string(&trace).unwrap(), self::trace(TraceFormat::Csv));
+
+    let output = run(&["--ref-trace", trace.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "matched the reference trace for 4 instructions\n");
+
+    let tampered = fs::read_to_string(&trace).unwrap().replace("\n206,000,0F,", "\n206,000,10,");
+    fs::write(&trace, tampered).unwrap();
+    let output = run(&["--ref-trace", trace.to_str().unwrap()]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("at instruction 3: V0 is 0xF, expected 0x10"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
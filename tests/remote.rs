@@ -0,0 +1,136 @@
+// The remote protocol end to end: a server on a loopback port driven by RemoteClient.
+#![cfg(feature = "serde")]
+
+use std::{net::SocketAddr, sync::mpsc, thread, time::Duration};
+
+use rust_8::{Chip8Builder, Chip8TestRom, RemoteClient, RemoteMessage, RemoteRequest, RemoteServer};
+
+// 200: LD V0, 0  202: LD V1, 0  204: LD I, 20C  206: DRW V0, V1, 1
+// 208: ADD V2, 1  20A: JP 208  20C: a one pixel sprite
+fn rom() -> Vec<u8> {
+    Chip8TestRom::new().ld(0, 0).ld(1, 0).ld_i(0x20C).drw(0, 1, 1).add_byte(2, 1).jp(0x208).data(&[0x80]).build()
+}
+
+// a server for `rom` on its own thread, the machine being built there too
+fn serve(rom: Vec<u8>) -> SocketAddr {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut chip8 = Chip8Builder::new().with_rng_seed(0).build();
+        chip8.load_rom(rom).unwrap();
+        let server = RemoteServer::bind("127.0.0.1:0", chip8, 10, false).unwrap();
+        sender.send(server.local_addr().unwrap()).unwrap();
+        server.serve()
+    });
+    receiver.recv().unwrap()
+}
+
+fn connect(addr: SocketAddr) -> RemoteClient {
+    let client = RemoteClient::connect(addr).unwrap();
+    client.set_timeout(Some(Duration::from_secs(5))).unwrap();
+    client
+}
+
+fn registers(client: &mut RemoteClient) -> ([u8; 16], u16) {
+    match client.request(&RemoteRequest::ReadRegs).unwrap() {
+        RemoteMessage::Registers { v, pc, .. } => (v, pc),
+        other => panic!("read-regs got {:?}", other),
+    }
+}
+
+#[test]
+fn a_breakpoint_set_remotely_is_hit() {
+    let mut client = connect(serve(rom()));
+    // paused until resumed, so nothing has run yet
+    assert_eq!(registers(&mut client).1, 0x200);
+    assert_eq!(client.request(&RemoteRequest::SetBreakpoint { address: 0x208 }).unwrap(), RemoteMessage::Ok);
+    assert_eq!(client.request(&RemoteRequest::Resume).unwrap(), RemoteMessage::Ok);
+    assert_eq!(client.next_event().unwrap(), RemoteMessage::BreakpointHit { pc: 0x208 });
+    assert_eq!(registers(&mut client), ([0; 16], 0x208));
+
+    // and again once round the loop, with V2 counted up
+    client.request(&RemoteRequest::Resume).unwrap();
+    assert_eq!(client.next_event().unwrap(), RemoteMessage::BreakpointHit { pc: 0x208 });
+    assert_eq!(registers(&mut client).0[2], 1);
+
+    client.request(&RemoteRequest::ClearBreakpoint { address: 0x208 }).unwrap();
+    assert!(matches!(client.request(&RemoteRequest::ClearBreakpoint { address: 0x208 }).unwrap(), RemoteMessage::Rejected { .. }));
+}
+
+#[test]
+fn reads_and_writes_the_machine() {
+    let mut client = connect(serve(rom()));
+    assert_eq!(client.request(&RemoteRequest::Step { count: 4 }).unwrap(), RemoteMessage::Ok);
+    assert_eq!(registers(&mut client).1, 0x208);
+    match client.request(&RemoteRequest::GetFramebuffer).unwrap() {
+        RemoteMessage::Framebuffer { width, height, pixels } => {
+            assert_eq!((width, height), (64, 32));
+            // the top left pixel, then the rest of the 256 bytes dark
+            assert!(pixels.starts_with("gAAAAAAA"));
+            assert_eq!(pixels.len(), 256 / 3 * 4 + 4);
+        }
+        other => panic!("get-framebuffer got {:?}", other),
+    }
+    let generation = match client.request(&RemoteRequest::GetFramebufferDelta { since: 0 }).unwrap() {
+        RemoteMessage::FramebufferDelta { generation, full: true, rows, .. } => {
+            assert_eq!(rows.len(), 32);
+            assert_eq!(rows[0], (0, "gAAAAAAAAAA=".to_string()));
+            generation
+        }
+        other => panic!("get-framebuffer-delta got {:?}", other),
+    };
+    match client.request(&RemoteRequest::GetFramebufferDelta { since: generation }).unwrap() {
+        RemoteMessage::FramebufferDelta { full: false, rows, .. } => assert!(rows.is_empty()),
+        other => panic!("get-framebuffer-delta got {:?}", other),
+    }
+
+    let write = RemoteRequest::WriteMem { address: 0x300, bytes: vec![1, 2, 3] };
+    assert_eq!(client.request(&write).unwrap(), RemoteMessage::Ok);
+    assert_eq!(
+        client.request(&RemoteRequest::ReadMem { address: 0x2FF, length: 4 }).unwrap(),
+        RemoteMessage::Memory { address: 0x2FF, bytes: vec![0, 1, 2, 3] }
+    );
+    let past_the_end = RemoteRequest::ReadMem { address: 0xFFE, length: 4 };
+    assert!(matches!(client.request(&past_the_end).unwrap(), RemoteMessage::Rejected { .. }));
+
+    assert_eq!(client.request(&RemoteRequest::Press { key: 0xA }).unwrap(), RemoteMessage::Ok);
+    assert!(matches!(client.request(&RemoteRequest::Press { key: 16 }).unwrap(), RemoteMessage::Rejected { .. }));
+}
+
+#[test]
+fn halting_is_an_event() {
+    let mut client = connect(serve(Chip8TestRom::new().ld(0, 1).jp(0x202).build()));
+    client.request(&RemoteRequest::Resume).unwrap();
+    assert_eq!(client.next_event().unwrap(), RemoteMessage::Halted { pc: 0x202 });
+    // paused by it
+    assert_eq!(registers(&mut client), ([1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0], 0x202));
+}
+
+#[test]
+fn the_machine_outlives_a_client() {
+    let addr = serve(rom());
+    let mut client = connect(addr);
+    client.request(&RemoteRequest::Step { count: 2 }).unwrap();
+    drop(client);
+    let mut client = connect(addr);
+    assert_eq!(registers(&mut client).1, 0x204);
+}
+
+#[test]
+fn malformed_requests_are_rejected() {
+    use std::io::{BufRead, BufReader, Write};
+
+    let addr = serve(rom());
+    let mut stream = std::net::TcpStream::connect(addr).unwrap();
+    stream.write_all(b"{\"cmd\": \"fly\"}\nnot json\n{\"cmd\": \"step\"}\n").unwrap();
+    let mut lines = BufReader::new(stream).lines();
+    assert!(lines.next().unwrap().unwrap().starts_with("{\"type\":\"rejected\",\"reason\":\"unknown variant `fly`"));
+    assert!(lines.next().unwrap().unwrap().starts_with("{\"type\":\"rejected\""));
+    assert_eq!(lines.next().unwrap().unwrap(), "{\"type\":\"ok\"}");
+}
+
+#[test]
+fn only_loopback_unless_allowed() {
+    let chip8 = Chip8Builder::new().build();
+    let err = RemoteServer::bind("0.0.0.0:0", chip8, 10, false).err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied);
+}
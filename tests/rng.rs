@@ -0,0 +1,36 @@
+use rust_8::Chip8Builder;
+
+// what RND V0, FF draws from seed 42, pinned so a change to the generator or how CXNN
+// uses it shows up here rather than as replays and recordings quietly going different
+const SEED_42: [u8; 100] = [
+    66, 229, 98, 112, 136, 154, 231, 77, 250, 124, 132, 155, 34, 68, 156, 214, 19, 142, 45, 207, 194, 120, 212, 72, 76,
+    98, 52, 218, 182, 174, 127, 127, 21, 114, 211, 129, 79, 172, 5, 232, 180, 50, 239, 230, 159, 65, 96, 71, 107, 3,
+    211, 129, 239, 237, 246, 191, 47, 111, 133, 116, 235, 207, 54, 156, 76, 250, 59, 91, 183, 177, 155, 249, 157, 96,
+    133, 254, 85, 202, 237, 4, 209, 248, 46, 221, 38, 175, 85, 139, 57, 25, 117, 210, 36, 39, 58, 126, 58, 157, 55, 201,
+];
+
+// 200: RND V0, NN  202: JP 200
+fn draws(seed: u64, mask: u8, count: usize) -> Vec<u8> {
+    let mut chip8 = Chip8Builder::new().with_rng_seed(seed).build();
+    chip8.load_rom(vec![0xC0, mask, 0x12, 0x00]).unwrap();
+    (0..count)
+        .map(|_| {
+            chip8.step().unwrap();
+            let value = chip8.register(0);
+            chip8.step().unwrap();
+            value
+        })
+        .collect()
+}
+
+#[test]
+fn seed_42_draws_a_known_sequence() {
+    assert_eq!(draws(42, 0xFF, 100), SEED_42);
+}
+
+#[test]
+fn the_mask_is_anded_with_the_same_draws() {
+    let masked: Vec<u8> = SEED_42.iter().map(|value| value & 0x0F).collect();
+    assert_eq!(draws(42, 0x0F, 100), masked);
+    assert_ne!(draws(43, 0xFF, 100), SEED_42);
+}
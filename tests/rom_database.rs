@@ -0,0 +1,56 @@
+// ROM lookups against a trimmed copy of the CHIP-8 database's programs.json.
+
+use std::fs;
+
+use rust_8::{sha1_hex, Chip8Error, ChipVariant, RomDatabase, RomInfo};
+
+fn database() -> RomDatabase {
+    RomDatabase::from_json(&fs::read_to_string("tests/database/programs.json").unwrap()).unwrap()
+}
+
+fn ibm_logo() -> Vec<u8> {
+    fs::read("tests/roms/2-ibm-logo.ch8").unwrap()
+}
+
+#[test]
+fn the_fixture_lists_every_rom_with_a_hash() {
+    // a program without any roms has nothing to look up
+    assert_eq!(database().len(), 2);
+}
+
+#[test]
+fn a_known_rom_is_found_by_its_hash() {
+    let rom = ibm_logo();
+    assert_eq!(sha1_hex(&rom), "1ba58656810b67fd131eb9af3e3987863bf26c90");
+
+    let database = database();
+    let info = database.lookup(&rom).expect("the IBM logo is in the fixture");
+    assert_eq!(
+        *info,
+        RomInfo {
+            title: String::from("IBM Logo"),
+            authors: vec![String::from("Timendus")],
+            platforms: vec![ChipVariant::Chip8Original, ChipVariant::Chip8Original],
+            tickrate: Some(15),
+        }
+    );
+    assert_eq!(info.platform(), Some(ChipVariant::Chip8Original));
+}
+
+#[test]
+fn an_unknown_rom_is_not_found() {
+    let mut rom = ibm_logo();
+    rom[0] ^= 1;
+    assert_eq!(database().lookup(&rom), None);
+    assert_eq!(database().lookup(&[]), None);
+}
+
+#[test]
+fn malformed_databases_are_refused() {
+    for text in ["", "{\"title\": \"Pong\"}", "[{\"title\": "] {
+        match RomDatabase::from_json(text) {
+            Err(Chip8Error::InvalidState(message)) => assert!(message.starts_with("rom database: "), "{}", message),
+            other => panic!("expected InvalidState for {:?}, got {:?}", text, other),
+        }
+    }
+}
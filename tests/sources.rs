@@ -0,0 +1,113 @@
+// ROMs read from standard input and from URLs rather than from files.
+
+use std::{
+    io::{Cursor, Write},
+    process::{Command, Stdio},
+};
+
+use rust_8::{is_rom_url, read_rom_from, rom_url_name, validate_rom_url, RomLoadError};
+
+// 200: LD V0, 2A
+// 202: JP 202
+const ROM: [u8; 4] = [0x60, 0x2A, 0x12, 0x02];
+
+#[test]
+fn reads_a_rom_from_a_reader() {
+    assert_eq!(read_rom_from(Cursor::new(ROM)).unwrap(), ROM);
+}
+
+#[test]
+fn reader_past_the_size_cap_is_an_error() {
+    let data = vec![0; 0x20000];
+    assert!(matches!(read_rom_from(Cursor::new(data)), Err(RomLoadError::TooLarge { .. })));
+}
+
+#[test]
+fn dash_runs_the_rom_on_standard_input() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rust-8"))
+        .args(["-", "--headless", "--frames", "1"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(&ROM).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("V0=2A "));
+}
+
+#[test]
+fn recognizes_urls() {
+    assert!(is_rom_url("https://example.com/game.ch8"));
+    assert!(is_rom_url("HTTP://example.com/game.ch8"));
+    assert!(!is_rom_url("ftp://example.com/game.ch8"));
+    assert!(!is_rom_url("roms/game.ch8"));
+    assert!(!is_rom_url("-"));
+}
+
+#[test]
+fn validates_urls() {
+    assert!(validate_rom_url("https://example.com/game.ch8").is_ok());
+    assert!(validate_rom_url("http://localhost:8000/game.ch8").is_ok());
+    for url in ["ftp://example.com/game.ch8", "https:///game.ch8", "https://:80/game.ch8", "https://example.com/my game.ch8"] {
+        assert!(matches!(validate_rom_url(url), Err(RomLoadError::InvalidUrl { .. })), "{}", url);
+    }
+}
+
+#[test]
+fn names_a_url_by_its_last_path_segment() {
+    assert_eq!(rom_url_name("https://example.com/roms/game.ch8"), Some("game.ch8"));
+    assert_eq!(rom_url_name("https://example.com/roms/game.ch8?v=2#top"), Some("game.ch8"));
+    assert_eq!(rom_url_name("https://example.com/roms/"), Some("roms"));
+    assert_eq!(rom_url_name("https://example.com"), None);
+    assert_eq!(rom_url_name("https://example.com/"), None);
+}
+
+#[cfg(feature = "http")]
+mod http {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    use rust_8::{read_rom, RomLoadError};
+
+    use super::ROM;
+
+    // answers a single request with `status` and `body`, returning the URL to ask
+    fn serve(status: &'static str, body: Vec<u8>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request);
+            let head = format!("HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", status, body.len());
+            stream.write_all(head.as_bytes()).unwrap();
+            stream.write_all(&body).unwrap();
+        });
+        format!("http://{}/roms/game.ch8", address)
+    }
+
+    #[test]
+    fn downloads_a_rom() {
+        let url = serve("200 OK", ROM.to_vec());
+        assert_eq!(read_rom(&url).unwrap(), ROM);
+    }
+
+    #[test]
+    fn other_statuses_are_errors() {
+        let url = serve("404 Not Found", b"not here".to_vec());
+        match read_rom(&url) {
+            Err(RomLoadError::Http { reason, .. }) => assert!(reason.contains("404"), "{}", reason),
+            other => panic!("expected an Http error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn downloads_past_the_size_cap_are_errors() {
+        let url = serve("200 OK", vec![0; 0x20000]);
+        assert!(matches!(read_rom(&url), Err(RomLoadError::TooLarge { .. })));
+    }
+}
@@ -0,0 +1,158 @@
+// statediff on synthetic states: RAM differences merged into spans, and the rest of the
+// machine compared field by field.
+#![cfg(feature = "serde")]
+
+use rust_8::{ram_spans, state_diff, Chip8, Framebuffer, MachineState, RamSpan};
+
+fn span(start: u16, before: &[u8], after: &[u8]) -> RamSpan {
+    RamSpan {
+        start,
+        before: before.to_vec(),
+        after: after.to_vec(),
+    }
+}
+
+// 16 bytes of 0x00 with the given addresses set to 0xFF
+fn poked(addresses: &[usize]) -> Vec<u8> {
+    let mut ram = vec![0; 16];
+    for &address in addresses {
+        ram[address] = 0xFF;
+    }
+    ram
+}
+
+fn blank() -> MachineState {
+    Chip8::new().save_state()
+}
+
+#[test]
+fn equal_ram_has_no_spans() {
+    assert_eq!(ram_spans(&poked(&[3]), &poked(&[3]), 2), vec![]);
+    assert_eq!(ram_spans(&[], &[], 2), vec![]);
+}
+
+#[test]
+fn neighbouring_differences_share_a_span() {
+    let before = poked(&[]);
+    assert_eq!(ram_spans(&before, &poked(&[4, 5, 6]), 0), vec![span(4, &[0, 0, 0], &[0xFF, 0xFF, 0xFF])]);
+}
+
+#[test]
+fn a_gap_of_up_to_gap_equal_bytes_is_bridged() {
+    let before = poked(&[]);
+    // two equal bytes between 4 and 7, carried along in both halves of the span
+    assert_eq!(
+        ram_spans(&before, &poked(&[4, 7]), 2),
+        vec![span(4, &[0, 0, 0, 0], &[0xFF, 0, 0, 0xFF])]
+    );
+    // three is one too many
+    assert_eq!(
+        ram_spans(&before, &poked(&[4, 8]), 2),
+        vec![span(4, &[0], &[0xFF]), span(8, &[0], &[0xFF])]
+    );
+    // with no gap allowed, only touching differences merge
+    assert_eq!(
+        ram_spans(&before, &poked(&[4, 6]), 0),
+        vec![span(4, &[0], &[0xFF]), span(6, &[0], &[0xFF])]
+    );
+}
+
+#[test]
+fn bridging_chains_through_several_differences() {
+    let before = poked(&[]);
+    assert_eq!(
+        ram_spans(&before, &poked(&[0, 2, 4, 10, 15]), 1),
+        vec![
+            span(0, &[0, 0, 0, 0, 0], &[0xFF, 0, 0xFF, 0, 0xFF]),
+            span(10, &[0], &[0xFF]),
+            span(15, &[0], &[0xFF]),
+        ]
+    );
+}
+
+#[test]
+fn the_first_and_last_bytes_are_compared() {
+    assert_eq!(
+        ram_spans(&poked(&[0, 15]), &poked(&[]), 2),
+        vec![span(0, &[0xFF], &[0]), span(15, &[0xFF], &[0])]
+    );
+}
+
+#[test]
+fn bytes_past_the_shorter_side_differ() {
+    // before runs out at 4; the span keeps only the bytes each side has
+    assert_eq!(ram_spans(&[1, 2, 3, 4], &[1, 2, 3, 4, 0, 0], 2), vec![span(4, &[], &[0, 0])]);
+    // and a difference just before the end joins up with them
+    assert_eq!(ram_spans(&[1, 2, 9, 4], &[1, 2, 3, 4, 5], 2), vec![span(2, &[9, 4], &[3, 4, 5])]);
+}
+
+#[test]
+fn identical_states_have_no_differences() {
+    let diff = state_diff(&blank(), &blank());
+    assert!(diff.is_empty(), "{:?}", diff);
+    assert_eq!(diff.to_string(), "no differences\n");
+}
+
+#[test]
+fn every_field_shows_up_in_the_diff() {
+    let before = blank();
+    let mut after = blank();
+    after.vx[0x3] = 0x2A;
+    after.vx[0xF] = 1;
+    after.pc = 0x20A;
+    after.i = 0x300;
+    after.delay = 9;
+    after.sound = 4;
+    after.sp = 1;
+    after.stack[0] = 0x208;
+    after.ram[0x300] = 0xAB;
+    after.ram[0x302] = 0xCD;
+    after.ram[0x400] = 0x01;
+    after.framebuffer.set_pixel(0, 0, true);
+    after.framebuffer.set_pixel(63, 31, true);
+
+    let diff = state_diff(&before, &after);
+    assert_eq!(diff.registers, vec![(0x3, 0, 0x2A), (0xF, 0, 1)]);
+    assert_eq!(diff.pc, Some((0x200, 0x20A)));
+    assert_eq!(diff.i, Some((0, 0x300)));
+    assert_eq!(diff.stack, Some((vec![], vec![0x208])));
+    assert_eq!(diff.ram, vec![span(0x300, &[0, 0, 0], &[0xAB, 0, 0xCD]), span(0x400, &[0], &[1])]);
+    assert_eq!(diff.pixels, Some(2));
+    assert_eq!(
+        diff.to_string(),
+        "V3: 00 -> 2A\n\
+         VF: 00 -> 01\n\
+         PC: 0200 -> 020A\n\
+         I: 0000 -> 0300\n\
+         DT: 00 -> 09\n\
+         ST: 00 -> 04\n\
+         stack: [] -> [0208]\n\
+         RAM 0300-0302:\n  - 00 00 00\n  + AB 00 CD\n\
+         RAM 0400-0400:\n  - 00\n  + 01\n\
+         display: 2 pixels differ\n"
+    );
+}
+
+#[test]
+fn only_the_live_part_of_the_stack_is_compared() {
+    let mut before = blank();
+    let mut after = blank();
+    before.sp = 1;
+    after.sp = 1;
+    before.stack[0] = 0x208;
+    after.stack[0] = 0x208;
+    // left behind by an earlier RET, and not part of either stack
+    after.stack[1] = 0x40C;
+    assert!(state_diff(&before, &after).is_empty());
+}
+
+#[test]
+fn screens_of_different_sizes_report_their_sizes() {
+    let before = blank();
+    let mut after = blank();
+    after.framebuffer = Framebuffer::with_size(128, 64);
+    let diff = state_diff(&before, &after);
+    assert_eq!(diff.pixels, None);
+    assert_eq!(diff.screen_size, Some(((64, 32), (128, 64))));
+    assert_eq!(diff.to_string(), "display: 64x32 -> 128x64\n");
+}
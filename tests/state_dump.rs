@@ -0,0 +1,96 @@
+// StateDump, the JSON of --dump-state-on-exit, there and back again.
+#![cfg(feature = "serde")]
+
+use std::convert::TryFrom;
+
+use rust_8::{Chip8, Chip8Builder, Chip8Error, Framebuffer, MachineState, StateDump};
+
+// 200: RND V0, 3F
+// 202: RND V1, 1F
+// 204: LD F, V0
+// 206: CALL 20C
+// 208: JP 200
+// 20C: DRW V0, V1, 5
+// 20E: LD DT, V0
+// 210: LD ST, V1
+// 212: RET
+fn machine() -> Chip8 {
+    let mut chip8 = Chip8Builder::new().with_rng_seed(5).build();
+    chip8
+        .load_rom(vec![0xC0, 0x3F, 0xC1, 0x1F, 0xF0, 0x29, 0x22, 0x0C, 0x12, 0x00, 0x00, 0x00, 0xD0, 0x15, 0xF0, 0x15, 0xF1, 0x18, 0x00, 0xEE])
+        .unwrap();
+    // stopped inside the subroutine, so there is a return address on the stack
+    for _ in 0..303 {
+        chip8.step().unwrap();
+    }
+    chip8
+}
+
+fn round_trip(state: &MachineState) -> MachineState {
+    let text = StateDump::from(state).to_pretty_json();
+    MachineState::try_from(StateDump::from_json(&text).unwrap()).unwrap()
+}
+
+#[test]
+fn a_dump_reads_back_as_the_same_state() {
+    let state = machine().save_state();
+    assert_eq!(state.sp, 1);
+    assert!(state.rng.is_some_and(|(_, drawn)| drawn > 0));
+    assert_eq!(round_trip(&state), state);
+}
+
+#[test]
+fn a_dump_restores_a_machine_that_carries_on_the_same() {
+    let mut original = machine();
+    let mut restored = Chip8Builder::new().build();
+    restored.load_state(round_trip(&original.save_state())).unwrap();
+    for _ in 0..300 {
+        original.step().unwrap();
+        restored.step().unwrap();
+    }
+    assert_eq!(restored.save_state(), original.save_state());
+}
+
+#[test]
+fn a_high_resolution_screen_survives_the_trip() {
+    let mut framebuffer = Framebuffer::with_size(128, 64);
+    framebuffer.set_pixel(0, 0, true);
+    framebuffer.set_pixel(127, 63, true);
+    framebuffer.set_pixel(64, 31, true);
+    let state = MachineState { framebuffer, ..machine().save_state() };
+    assert_eq!(round_trip(&state), state);
+}
+
+#[test]
+fn the_json_uses_the_documented_field_names() {
+    let json: serde_json::Value = serde_json::from_str(&StateDump::from(&machine().save_state()).to_pretty_json()).unwrap();
+    for field in ["version", "registers", "pc", "i", "stack", "delay_timer", "sound_timer", "quirks", "rng", "halted", "ram", "display"] {
+        assert!(json.get(field).is_some(), "no {} in {}", field, json);
+    }
+    assert_eq!(json["registers"].as_array().unwrap().len(), 16);
+    assert_eq!(json["stack"], serde_json::json!(["0208"]));
+    assert_eq!(json["display"].as_array().unwrap().len(), 32);
+}
+
+fn broken(change: impl Fn(&mut serde_json::Value), reason: &str) {
+    let mut json: serde_json::Value = serde_json::from_str(&StateDump::from(&machine().save_state()).to_pretty_json()).unwrap();
+    change(&mut json);
+    let result = StateDump::from_json(&json.to_string()).and_then(MachineState::try_from);
+    match result {
+        Err(Chip8Error::InvalidState(message)) => assert!(message.contains(reason), "{}", message),
+        other => panic!("expected InvalidState about {}, got {:?}", reason, other),
+    }
+}
+
+#[test]
+fn malformed_dumps_are_refused() {
+    broken(|json| json["version"] = 2.into(), "unsupported version 2");
+    broken(|json| json["registers"][3] = "G0".into(), "bad hex \"G0\" in registers");
+    broken(|json| json["registers"].as_array_mut().unwrap().pop().map(drop).unwrap(), "15 registers");
+    broken(|json| json["stack"] = serde_json::json!(vec!["0200"; 17]), "17 stack entries");
+    broken(|json| json["pc"] = "xyz".into(), "bad hex \"xyz\" in pc");
+    broken(|json| json["ram"] = "not base64!".into(), "ram:");
+    broken(|json| json["display"][5] = "0101".into(), "display row 5");
+    broken(|json| json["display"] = serde_json::json!([]), "display of 0x0 pixels");
+    broken(|json| json["halted"] = "no".into(), "expected a boolean");
+}
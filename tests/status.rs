@@ -0,0 +1,79 @@
+// The window title, from fixed inputs.
+
+use std::time::{Duration, Instant};
+
+use rust_8::{write_title, RateMeter, Status};
+
+fn status() -> Status<'static> {
+    Status {
+        rom: "pong",
+        library: None,
+        fps: 59.6,
+        ips: 699.5,
+        profile: "chip-8",
+        paused: false,
+        unfocused: false,
+        muted: false,
+        reloading: false,
+    }
+}
+
+fn title(status: &Status) -> String {
+    let mut title = String::new();
+    write_title(&mut title, status);
+    title
+}
+
+#[test]
+fn a_running_rom() {
+    assert_eq!(title(&status()), "Chip-8 - pong | 60 fps | 700 ips | chip-8");
+}
+
+#[test]
+fn a_library_rom_counts_from_one() {
+    let status = Status { library: Some((0, 3)), ..status() };
+    assert_eq!(title(&status), "Chip-8 - pong (1/3) | 60 fps | 700 ips | chip-8");
+}
+
+#[test]
+fn a_manual_pause_wins_over_lost_focus() {
+    let paused = Status { paused: true, unfocused: true, ..status() };
+    assert_eq!(title(&paused), "Chip-8 - pong | 60 fps | 700 ips | chip-8 [paused]");
+    let unfocused = Status { unfocused: true, ..status() };
+    assert_eq!(title(&unfocused), "Chip-8 - pong | 60 fps | 700 ips | chip-8 [paused: no focus]");
+}
+
+#[test]
+fn every_flag_at_once() {
+    let status = Status {
+        library: Some((4, 12)),
+        paused: true,
+        muted: true,
+        reloading: true,
+        profile: "superchip",
+        ..status()
+    };
+    assert_eq!(title(&status), "Chip-8 - pong (5/12) | 60 fps | 700 ips | superchip [paused] [muted] RELOADING...");
+}
+
+#[test]
+fn the_title_is_rewritten_not_appended_to() {
+    let mut title = String::from("something much longer than any title, left over from before");
+    write_title(&mut title, &status());
+    assert_eq!(title, "Chip-8 - pong | 60 fps | 700 ips | chip-8");
+}
+
+#[test]
+fn rates_average_over_the_last_few_samples() {
+    let start = Instant::now();
+    let mut meter = RateMeter::new();
+    let at = |millis| start + Duration::from_millis(millis);
+    // nothing to go on yet
+    assert_eq!(meter.sample(at(0), 0), 0.0);
+    assert_eq!(meter.sample(at(250), 15), 60.0);
+    assert_eq!(meter.sample(at(500), 30), 60.0);
+    assert_eq!(meter.sample(at(750), 45), 60.0);
+    assert_eq!(meter.sample(at(1000), 60), 60.0);
+    // the first sample has dropped out, so one slow interval only counts for a quarter
+    assert_eq!(meter.sample(at(1250), 60), 45.0);
+}
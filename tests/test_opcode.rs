@@ -0,0 +1,51 @@
+// Single opcodes run with Chip8::probe_opcode and --test-opcode, checking the changes
+// they report.
+
+use std::process::Command;
+
+use rust_8::{Chip8, Chip8Error};
+
+fn probe(opcode: u16, state: &str) -> String {
+    let mut chip8 = Chip8::new();
+    chip8.apply_register_json(state).unwrap();
+    chip8.probe_opcode(opcode).unwrap()
+}
+
+#[test]
+fn reports_only_what_changed() {
+    assert_eq!(probe(0x8014, r#"{"V0": 1, "V1": 2}"#), r#"{"V0":3,"VF":0}"#);
+    assert_eq!(probe(0x8014, r#"{"V0": 255, "V1": 2}"#), r#"{"V0":1,"VF":1}"#);
+    assert_eq!(probe(0x6A2B, "{}"), r#"{"VA":43}"#);
+    assert_eq!(probe(0xF015, r#"{"V0": 9}"#), r#"{"DT":9}"#);
+    assert_eq!(probe(0xF033, r#"{"V0": 123, "I": 768}"#), r#"{"RAM":{"300":1,"301":2,"302":3}}"#);
+}
+
+#[test]
+fn reports_where_jumps_and_skips_go() {
+    assert_eq!(probe(0x2300, "{}"), r#"{"PC":768,"SP":1}"#);
+    assert_eq!(probe(0x3005, r#"{"V0": 5}"#), r#"{"PC":516}"#);
+    assert_eq!(probe(0x3005, r#"{"V0": 5, "PC": 1024}"#), r#"{"PC":1028}"#);
+    assert_eq!(probe(0x3005, r#"{"V0": 6}"#), "{}");
+}
+
+#[test]
+fn bad_initial_states_are_errors() {
+    let mut chip8 = Chip8::new();
+    assert!(matches!(chip8.apply_register_json("[1, 2]"), Err(Chip8Error::InvalidState(_))));
+    assert!(matches!(chip8.apply_register_json(r#"{"V10": 1}"#), Err(Chip8Error::InvalidState(_))));
+    assert!(matches!(chip8.apply_register_json(r#"{"V0": 256}"#), Err(Chip8Error::InvalidState(_))));
+    assert_eq!(chip8.apply_register_json(r#"{"PC": 513}"#), Err(Chip8Error::PcOutOfRange { pc: 513 }));
+}
+
+#[test]
+fn the_binary_prints_the_changes() {
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-8"))
+        .args(["--test-opcode", "8014", "--initial-state", r#"{"V0": 1, "V1": 2}"#])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "{\"V0\":3,\"VF\":0}\n");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-8")).args(["--test-opcode", "801"]).output().unwrap();
+    assert!(!output.status.success());
+}
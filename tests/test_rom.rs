@@ -0,0 +1,81 @@
+// ROMs built with Chip8TestRom: the bytes each call encodes, and a program built with it run.
+
+use rust_8::{Chip8Builder, Chip8TestRom, Instruction};
+
+fn decode(rom: &[u8]) -> Vec<Instruction> {
+    rom.chunks(2).map(|pair| Instruction::decode(pair[0], pair[1])).collect()
+}
+
+#[test]
+fn each_call_appends_its_instruction() {
+    let rom = Chip8TestRom::new()
+        .cls()
+        .ld(0xA, 0x2F)
+        .add(0xA, 0xB)
+        .add_byte(3, 0xFF)
+        .se(1, 2)
+        .sne(0xF, 0)
+        .ld_i(0x123)
+        .drw(4, 5, 15)
+        .call(0x20A)
+        .jp(0xFFF)
+        .ret()
+        .build();
+    assert_eq!(rom[..6], [0x00, 0xE0, 0x6A, 0x2F, 0x8A, 0xB4]);
+    assert_eq!(
+        decode(&rom),
+        [
+            Instruction::Cls,
+            Instruction::LdByte(0xA, 0x2F),
+            Instruction::AddReg(0xA, 0xB),
+            Instruction::AddByte(3, 0xFF),
+            Instruction::SeByte(1, 2),
+            Instruction::SneByte(0xF, 0),
+            Instruction::LdI(0x123),
+            Instruction::Drw(4, 5, 15),
+            Instruction::Call(0x20A),
+            Instruction::Jp(0xFFF),
+            Instruction::Ret,
+        ]
+    );
+}
+
+#[test]
+fn addresses_count_from_the_program_start() {
+    let rom = Chip8TestRom::new();
+    assert_eq!(rom.address(), 0x200);
+    let rom = rom.ld(0, 1).data(&[0xF0, 0x90, 0xF0]);
+    assert_eq!(rom.address(), 0x205);
+    assert_eq!(rom.build(), [0x60, 0x01, 0xF0, 0x90, 0xF0]);
+}
+
+#[test]
+#[should_panic(expected = "there is no register V10")]
+fn registers_past_vf_are_refused() {
+    Chip8TestRom::new().ld(0x10, 0);
+}
+
+#[test]
+#[should_panic(expected = "0x1000 is past the end of memory")]
+fn addresses_past_memory_are_refused() {
+    Chip8TestRom::new().jp(0x1000);
+}
+
+#[test]
+fn a_built_rom_runs() {
+    // 200: CALL 20A, 202: LD I, 212, 204: DRW V0, V1, 1, 206: JP 206, 208: padding,
+    // 20A: the subroutine adding 5 and 7 into V0, 212: a one pixel sprite
+    let rom = Chip8TestRom::new().call(0x20A).ld_i(0x212).drw(0, 1, 1);
+    let halt = rom.address();
+    let rom = rom.jp(halt).data(&[0, 0]).ld(0, 5).ld(1, 7).add(0, 1).ret().data(&[0x80]);
+    assert_eq!(rom.address(), 0x213);
+    let mut chip8 = Chip8Builder::new().build();
+    chip8.load_rom(rom.build()).unwrap();
+    for _ in 0..10 {
+        chip8.step().unwrap();
+    }
+    assert_eq!((chip8.register(0), chip8.register(1)), (12, 7));
+    assert_eq!(chip8.pc(), 0x206);
+    assert!(chip8.display().pixel(12, 7));
+    assert!(!chip8.display().pixel(13, 7));
+}
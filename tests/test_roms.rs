@@ -0,0 +1,135 @@
+// Runs ROMs from Timendus' chip8-test-suite headlessly and compares the final screen with
+// tests/roms/expected/<rom>.txt, one line per row, four pixels to a hex digit with the
+// leftmost as the top bit. Only the IBM logo ships with this repository (see
+// tests/roms/README.md); any other ROM missing from tests/roms is skipped. Run with
+// UPDATE_GOLDENS=1 to write the expected screens of the ROMs present.
+#![cfg(feature = "test-roms")]
+
+use std::{env, fs, path::PathBuf};
+
+use rust_8::{Chip8Builder, ChipVariant, Framebuffer};
+
+const FRAMES: usize = 180;
+// each CHIP-8 pixel becomes a square this size in the saved PNGs
+const PNG_SCALE: usize = 4;
+
+const ROMS: [&str; 4] = ["1-chip8-logo.ch8", "2-ibm-logo.ch8", "3-corax+.ch8", "4-flags.ch8"];
+
+// in the diff PNGs: pixels lit in both are grey, ones only the expected screen has are
+// red and ones only this run drew are green
+const BOTH: [u8; 3] = [0x60, 0x60, 0x60];
+const MISSING: [u8; 3] = [0xFF, 0x00, 0x00];
+const EXTRA: [u8; 3] = [0x00, 0xFF, 0x00];
+
+#[test]
+fn test_suite_screens_match() {
+    let mut failures = Vec::new();
+    let mut ran = 0;
+
+    for name in ROMS.iter() {
+        let rom = match fs::read(roms_dir().join(name)) {
+            Ok(rom) => rom,
+            Err(_) => {
+                eprintln!("skipping {}: not in {}", name, roms_dir().display());
+                continue;
+            }
+        };
+        ran += 1;
+        let actual = encode(&run(&rom));
+
+        let path = roms_dir().join("expected").join(format!("{}.txt", name));
+        if env::var_os("UPDATE_GOLDENS").is_some_and(|value| value == "1") {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, actual.join("\n") + "\n").unwrap();
+            continue;
+        }
+        let expected: Vec<String> = match fs::read_to_string(&path) {
+            Ok(text) => text.lines().map(String::from).collect(),
+            Err(err) => {
+                // against nothing, everything it drew shows up as extra
+                let png = save_diff_png(name, &[], &actual);
+                failures.push(format!(
+                    "{}: {} (screen saved to {}), run with UPDATE_GOLDENS=1 to create it",
+                    path.display(),
+                    err,
+                    png.display()
+                ));
+                continue;
+            }
+        };
+        if expected != actual {
+            let png = save_diff_png(name, &expected, &actual);
+            failures.push(format!("{}: the screen differs from {} (diff saved to {})", name, path.display(), png.display()));
+        }
+    }
+
+    assert!(ran > 0, "none of the test ROMs are in {}", roms_dir().display());
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+fn run(rom: &[u8]) -> Framebuffer {
+    let variant = ChipVariant::Chip8Original;
+    let mut chip8 = Chip8Builder::new().with_variant(variant).with_rng_seed(0).build();
+    chip8.load_rom(rom.to_vec()).unwrap();
+    for _ in 0..FRAMES {
+        for _ in 0..variant.instructions_per_frame() {
+            chip8.step().unwrap();
+        }
+        chip8.tick_timers();
+    }
+    chip8.display().clone()
+}
+
+fn encode(display: &Framebuffer) -> Vec<String> {
+    (0..display.height())
+        .map(|y| {
+            (0..display.width())
+                .step_by(4)
+                .map(|x| {
+                    let nibble = (0..4).fold(0, |nibble, bit| nibble << 1 | display.pixel(x + bit, y) as u32);
+                    char::from_digit(nibble, 16).unwrap().to_ascii_uppercase()
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// pixel (x, y) of an encoded screen, off past its edges
+fn lit(rows: &[String], x: usize, y: usize) -> bool {
+    rows.get(y)
+        .and_then(|row| row.chars().nth(x / 4))
+        .and_then(|digit| digit.to_digit(16))
+        .is_some_and(|nibble| nibble >> (3 - x % 4) & 1 == 1)
+}
+
+// writes where the screen went wrong, so the failing check can be found by eye
+fn save_diff_png(name: &str, expected: &[String], actual: &[String]) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("test-roms");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(format!("{}.diff.png", name));
+
+    let rows = expected.len().max(actual.len());
+    let columns = expected.iter().chain(actual).map(|row| row.len() * 4).max().unwrap_or(0);
+    let (width, height) = (columns * PNG_SCALE, rows * PNG_SCALE);
+    let mut pixels = vec![0u8; width * height * 3];
+    for (n, pixel) in pixels.chunks_mut(3).enumerate() {
+        let (x, y) = (n % width / PNG_SCALE, n / width / PNG_SCALE);
+        match (lit(expected, x, y), lit(actual, x, y)) {
+            (true, true) => pixel.copy_from_slice(&BOTH),
+            (true, false) => pixel.copy_from_slice(&MISSING),
+            (false, true) => pixel.copy_from_slice(&EXTRA),
+            (false, false) => {}
+        }
+    }
+
+    let file = fs::File::create(&path).unwrap();
+    let mut encoder = png::Encoder::new(file, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header().unwrap().write_image_data(&pixels).unwrap();
+    path
+}
+
+fn roms_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("roms")
+}
@@ -0,0 +1,44 @@
+// Frame pacing with PreciseClock. Only lower bounds and generous upper ones, since a
+// loaded machine can always wake a thread late.
+
+use std::time::{Duration, Instant};
+
+use rust_8::{Chip8Builder, PreciseClock};
+
+const PERIOD: Duration = Duration::from_millis(5);
+
+fn run_frames(precise: bool, frames: u32) -> Duration {
+    let start = Instant::now();
+    let mut clock = PreciseClock::new(PERIOD, precise);
+    for _ in 0..frames {
+        clock.wait();
+    }
+    start.elapsed()
+}
+
+#[test]
+fn frames_take_their_period() {
+    for precise in [false, true] {
+        let elapsed = run_frames(precise, 20);
+        assert!(elapsed >= PERIOD * 20, "{:?} for 20 frames, precise: {}", elapsed, precise);
+        assert!(elapsed < PERIOD * 20 + Duration::from_secs(1), "{:?} for 20 frames, precise: {}", elapsed, precise);
+    }
+}
+
+#[test]
+fn a_late_frame_is_not_made_up_for() {
+    let mut clock = PreciseClock::new(PERIOD, true);
+    std::thread::sleep(PERIOD * 4);
+    clock.wait();
+    // the next frame gets a whole period again instead of running straight away
+    let start = Instant::now();
+    clock.wait();
+    assert!(start.elapsed() >= PERIOD - Duration::from_millis(1));
+}
+
+#[test]
+fn the_builder_chooses_the_clock() {
+    assert!(!Chip8Builder::new().build().precise_timing());
+    assert!(Chip8Builder::new().with_precise_timing(true).build().precise_timing());
+    assert!(PreciseClock::new(PERIOD, true).is_precise());
+}
@@ -0,0 +1,120 @@
+// The buzzer tone as samples, without an audio device in sight.
+
+use std::time::Duration;
+
+use rust_8::{ToneGenerator, Waveform};
+
+const SAMPLE_RATE: u32 = 48_000;
+// 2 ms at 48 kHz
+const FADE_SAMPLES: usize = 96;
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-4
+}
+
+#[test]
+fn a_tone_that_is_never_on_is_silent() {
+    let mut tone = ToneGenerator::new(Waveform::Square, 440.0, 1.0, SAMPLE_RATE);
+    assert!(tone.samples(Duration::from_millis(50), false).iter().all(|sample| *sample == 0.0));
+}
+
+#[test]
+fn samples_cover_the_duration_asked_for() {
+    let mut tone = ToneGenerator::new(Waveform::Square, 440.0, 1.0, SAMPLE_RATE);
+    assert_eq!(tone.samples(Duration::from_secs(1), true).len(), 48_000);
+    // one 60 Hz tick
+    assert_eq!(tone.samples(Duration::from_secs_f64(1.0 / 60.0), true).len(), 800);
+    assert!(tone.samples(Duration::ZERO, true).is_empty());
+}
+
+#[test]
+fn the_tone_fades_in_instead_of_clicking_on() {
+    // 1 Hz, so the square wave stays high for the whole fade
+    let mut tone = ToneGenerator::new(Waveform::Square, 1.0, 0.5, SAMPLE_RATE);
+    let samples = tone.samples(Duration::from_millis(10), true);
+    for (n, sample) in samples.iter().enumerate().take(FADE_SAMPLES) {
+        assert!(close(*sample, 0.5 * (n + 1) as f32 / FADE_SAMPLES as f32), "sample {} is {}", n, sample);
+    }
+    assert!(samples[FADE_SAMPLES..].iter().all(|sample| close(*sample, 0.5)));
+}
+
+#[test]
+fn the_tone_fades_out_instead_of_clicking_off() {
+    let mut tone = ToneGenerator::new(Waveform::Square, 1.0, 1.0, SAMPLE_RATE);
+    tone.samples(Duration::from_millis(10), true);
+    let samples = tone.samples(Duration::from_millis(10), false);
+    // no jump bigger than one fade step on the way down
+    let mut previous = 1.0;
+    for sample in &samples {
+        assert!(previous - sample <= 1.0 / FADE_SAMPLES as f32 + 1e-4, "{} to {}", previous, sample);
+        previous = *sample;
+    }
+    assert!(samples[FADE_SAMPLES..].iter().all(|sample| *sample == 0.0));
+}
+
+#[test]
+fn volume_is_clamped_to_full_scale() {
+    let mut loud = ToneGenerator::new(Waveform::Square, 1.0, 3.0, SAMPLE_RATE);
+    let mut full = ToneGenerator::new(Waveform::Square, 1.0, 1.0, SAMPLE_RATE);
+    assert_eq!(loud.samples(Duration::from_millis(10), true), full.samples(Duration::from_millis(10), true));
+
+    let mut silent = ToneGenerator::new(Waveform::Square, 1.0, -1.0, SAMPLE_RATE);
+    assert!(silent.samples(Duration::from_millis(10), true).iter().all(|sample| *sample == 0.0));
+}
+
+// 75 samples to a cycle, odd so no sample lands right on the half-cycle
+const FREQUENCY: f32 = 640.0;
+const PERIOD: usize = 75;
+
+// a second of the tone once the fade in is over
+fn steady(waveform: Waveform, volume: f32) -> Vec<f32> {
+    let mut tone = ToneGenerator::new(waveform, FREQUENCY, volume, SAMPLE_RATE);
+    tone.samples(Duration::from_millis(10), true);
+    tone.samples(Duration::from_secs(1), true)
+}
+
+#[test]
+fn every_waveform_repeats_at_the_frequency_asked_for() {
+    for waveform in Waveform::ALL {
+        let samples = steady(waveform, 1.0);
+        // each waveform goes up through zero once a cycle
+        let starts: Vec<usize> = (1..samples.len()).filter(|n| samples[n - 1] < 0.0 && samples[*n] >= 0.0).collect();
+        assert_eq!(starts.len(), 640, "{}", waveform);
+        for pair in starts.windows(2) {
+            assert!((PERIOD - 1..=PERIOD + 1).contains(&(pair[1] - pair[0])), "{}: a cycle of {} samples", waveform, pair[1] - pair[0]);
+        }
+    }
+}
+
+#[test]
+fn every_waveform_swings_between_plus_and_minus_the_volume() {
+    for waveform in Waveform::ALL {
+        for volume in [1.0, 0.25] {
+            let samples = steady(waveform, volume);
+            let max = samples.iter().copied().fold(f32::MIN, f32::max);
+            let min = samples.iter().copied().fold(f32::MAX, f32::min);
+            let mean = samples.iter().sum::<f32>() / samples.len() as f32;
+            // the peaks fall between samples, so they can come up a little short
+            assert!(max <= volume && max > 0.97 * volume, "{} at {}: max {}", waveform, volume, max);
+            assert!(min >= -volume && min < -0.97 * volume, "{} at {}: min {}", waveform, volume, min);
+            assert!(mean.abs() < 0.02 * volume, "{} at {}: mean {}", waveform, volume, mean);
+        }
+    }
+}
+
+#[test]
+fn the_square_wave_only_ever_sits_at_the_peaks() {
+    let samples = steady(Waveform::Square, 0.5);
+    assert!(samples.iter().all(|sample| *sample == 0.5 || *sample == -0.5));
+    let high = samples.iter().filter(|sample| **sample > 0.0).count();
+    assert!((high as f32 / samples.len() as f32 - 0.5).abs() < 0.01, "{} of {} high", high, samples.len());
+}
+
+#[test]
+fn waveforms_parse_from_their_names() {
+    for waveform in Waveform::ALL {
+        assert_eq!(waveform.name().parse::<Waveform>(), Ok(waveform));
+        assert_eq!(waveform.to_string(), waveform.name());
+    }
+    assert!("noise".parse::<Waveform>().unwrap_err().contains("square, sine, triangle, sawtooth"));
+}
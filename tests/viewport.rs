@@ -0,0 +1,88 @@
+// The screen scaled into windows of sizes that don't divide evenly, or at all.
+
+use rust_8::{blit_scaled, letterbox, Viewport, HEIGHT, WIDTH};
+
+const FILL: u32 = 0xFF00FF;
+
+// every pixel of the screen different, and none the same as the bars
+fn screen() -> Vec<u32> {
+    (0..WIDTH * HEIGHT).map(|n| n as u32 + 1).collect()
+}
+
+// what blit_scaled should draw, worked out one window pixel at a time
+fn expected(src: &[u32], window_width: usize, window_height: usize, viewport: Viewport) -> Vec<u32> {
+    let mut dst = vec![FILL; window_width * window_height];
+    for y in 0..window_height {
+        for x in 0..window_width {
+            let (Some(sx), Some(sy)) = (x.checked_sub(viewport.x), y.checked_sub(viewport.y)) else {
+                continue;
+            };
+            let (sx, sy) = (sx / viewport.scale, sy / viewport.scale);
+            if sx < WIDTH && sy < HEIGHT {
+                dst[y * window_width + x] = src[sy * WIDTH + sx];
+            }
+        }
+    }
+    dst
+}
+
+#[test]
+fn letterbox_picks_the_largest_whole_scale_and_centers_it() {
+    assert_eq!(letterbox(640, 320, WIDTH, HEIGHT), Viewport { scale: 10, x: 0, y: 0 });
+    // one pixel short of scale 10 in each direction
+    assert_eq!(letterbox(639, 320, WIDTH, HEIGHT), Viewport { scale: 9, x: 31, y: 16 });
+    assert_eq!(letterbox(640, 319, WIDTH, HEIGHT), Viewport { scale: 9, x: 32, y: 15 });
+    // odd leftovers put the extra pixel of bar on the right and bottom
+    assert_eq!(letterbox(129, 65, WIDTH, HEIGHT), Viewport { scale: 2, x: 0, y: 0 });
+    assert_eq!(letterbox(131, 67, WIDTH, HEIGHT), Viewport { scale: 2, x: 1, y: 1 });
+    // far wider or taller than the screen's 2:1
+    assert_eq!(letterbox(2000, 33, WIDTH, HEIGHT), Viewport { scale: 1, x: 968, y: 0 });
+    assert_eq!(letterbox(65, 1000, WIDTH, HEIGHT), Viewport { scale: 1, x: 0, y: 484 });
+}
+
+#[test]
+fn letterbox_never_scales_below_one() {
+    for (width, height) in [(0, 0), (1, 1), (63, 32), (64, 31), (10, 500)] {
+        let viewport = letterbox(width, height, WIDTH, HEIGHT);
+        assert_eq!(viewport.scale, 1, "{}x{}", width, height);
+        assert!(viewport.x <= width && viewport.y <= height, "{}x{}: {:?}", width, height, viewport);
+    }
+    // nor divides by an empty image
+    assert_eq!(letterbox(100, 100, 0, 0).scale, 100);
+}
+
+#[test]
+fn blit_matches_a_pixel_by_pixel_scale_in_awkward_windows() {
+    let src = screen();
+    let sizes = [(64, 32), (65, 33), (127, 63), (129, 65), (131, 67), (639, 320), (641, 333), (1000, 7), (7, 1000), (63, 31), (1, 1), (3, 200)];
+    for (width, height) in sizes {
+        let viewport = letterbox(width, height, WIDTH, HEIGHT);
+        let mut dst = vec![0; width * height];
+        blit_scaled(&src, WIDTH, &mut dst, width, viewport, FILL);
+        assert!(dst == expected(&src, width, height, viewport), "{}x{} at {:?}", width, height, viewport);
+    }
+}
+
+#[test]
+fn blit_into_an_empty_window_does_nothing() {
+    let src = screen();
+    let mut dst = Vec::new();
+    blit_scaled(&src, WIDTH, &mut dst, 0, letterbox(0, 0, WIDTH, HEIGHT), FILL);
+    blit_scaled(&src, WIDTH, &mut dst, 100, letterbox(100, 0, WIDTH, HEIGHT), FILL);
+    assert!(dst.is_empty());
+}
+
+#[test]
+fn the_bars_are_all_fill() {
+    let src = screen();
+    let (width, height) = (300, 200);
+    let viewport = letterbox(width, height, WIDTH, HEIGHT);
+    let mut dst = vec![0; width * height];
+    blit_scaled(&src, WIDTH, &mut dst, width, viewport, FILL);
+    let (image_width, image_height) = viewport.size(WIDTH, HEIGHT);
+    for (n, pixel) in dst.iter().enumerate() {
+        let (x, y) = (n % width, n / width);
+        let inside = (viewport.x..viewport.x + image_width).contains(&x) && (viewport.y..viewport.y + image_height).contains(&y);
+        assert_eq!(*pixel == FILL, !inside, "({}, {})", x, y);
+    }
+}
@@ -0,0 +1,72 @@
+// The on-screen keyboard drawn into a strip of pixels, and touches on it mapped to keys.
+
+use rust_8::{Keypad, VirtualKeyboard, KEYBOARD_LAYOUT, WIDTH};
+
+const FG: u32 = 0xFFFFFF;
+const BG: u32 = 0x000000;
+
+fn strip(keyboard: &VirtualKeyboard, keypad: &Keypad) -> Vec<u32> {
+    let mut strip = vec![0x123456; WIDTH * keyboard.height(WIDTH)];
+    keyboard.draw(&mut strip, WIDTH, keypad, FG, BG);
+    strip
+}
+
+// the pixels of the cell for `key`, inside its grid lines
+fn cell(strip: &[u32], key: u8) -> Vec<u32> {
+    let height = strip.len() / WIDTH;
+    let row = KEYBOARD_LAYOUT.iter().position(|keys| keys.contains(&key)).unwrap();
+    let column = KEYBOARD_LAYOUT[row].iter().position(|&other| other == key).unwrap();
+    let (left, right) = (column * WIDTH / 4 + 1, (column + 1) * WIDTH / 4);
+    (row * height / 4 + 1..(row + 1) * height / 4)
+        .flat_map(|y| strip[y * WIDTH + left..y * WIDTH + right].to_vec())
+        .collect()
+}
+
+#[test]
+fn hidden_until_toggled() {
+    let mut keyboard = VirtualKeyboard::default();
+    assert_eq!(keyboard.height(WIDTH), 0);
+    assert_eq!(keyboard.key_at(0, 0, WIDTH), None);
+    keyboard.toggle();
+    assert!(keyboard.show_keyboard);
+    assert_eq!(keyboard.height(WIDTH), 32);
+    assert_eq!(keyboard.height(128), 64);
+    // a turned screen is narrow, but the digits still fit
+    assert_eq!(keyboard.height(32), 28);
+}
+
+#[test]
+fn touches_land_on_the_keypad_layout() {
+    let keyboard = VirtualKeyboard::new(true);
+    assert_eq!(keyboard.key_at(0, 0, WIDTH), Some(0x1));
+    assert_eq!(keyboard.key_at(63, 0, WIDTH), Some(0xC));
+    assert_eq!(keyboard.key_at(16, 8, WIDTH), Some(0x5));
+    assert_eq!(keyboard.key_at(20, 31, WIDTH), Some(0x0));
+    assert_eq!(keyboard.key_at(63, 31, WIDTH), Some(0xF));
+    assert_eq!(keyboard.key_at(64, 0, WIDTH), None);
+    assert_eq!(keyboard.key_at(0, 32, WIDTH), None);
+}
+
+#[test]
+fn every_key_is_drawn_and_held_ones_are_lit() {
+    let keyboard = VirtualKeyboard::new(true);
+    let mut keypad = Keypad::new();
+    let idle = strip(&keyboard, &keypad);
+    // the whole strip is drawn over
+    assert!(!idle.contains(&0x123456));
+    for key in 0..16 {
+        let pixels = cell(&idle, key);
+        let lit = pixels.iter().filter(|&&pixel| pixel == FG).count();
+        // a digit on a dark key, no two the same
+        assert!(lit > 0 && lit < pixels.len() / 2, "key {:X}", key);
+    }
+    assert_ne!(cell(&idle, 0x8), cell(&idle, 0x0));
+
+    keypad.press(0xA);
+    let held = strip(&keyboard, &keypad);
+    let pixels = cell(&held, 0xA);
+    assert!(pixels.iter().filter(|&&pixel| pixel == FG).count() > pixels.len() / 2);
+    for key in (0..16).filter(|&key| key != 0xA) {
+        assert_eq!(cell(&held, key), cell(&idle, key), "key {:X}", key);
+    }
+}
@@ -0,0 +1,93 @@
+// --watch: a ROM rebuilt on disk is picked up by RomWatcher and run from a reset, the
+// way the window loop does it.
+
+use std::{
+    fs,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use rust_8::{Chip8Builder, Command, EmulatorThread, RomWatcher};
+
+// 200: JP 200
+const IDLE: [u8; 2] = [0x12, 0x00];
+// 200: LD F, V0
+// 202: DRW V0, V0, 5
+// 204: JP 204
+const DRAW_ZERO: [u8; 6] = [0xF0, 0x29, 0xD0, 0x05, 0x12, 0x04];
+
+// the watcher hears about a change some time after it is made
+fn reloaded(watcher: &mut RomWatcher, wait: Duration) -> bool {
+    let deadline = Instant::now() + wait;
+    while Instant::now() < deadline {
+        if watcher.reload() {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+    false
+}
+
+#[test]
+fn a_rebuilt_rom_is_reloaded_by_a_reset() {
+    let dir = std::env::temp_dir().join(format!("rust-8-watch-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("game.ch8");
+    fs::write(&path, IDLE).unwrap();
+
+    let rom = Arc::new(Mutex::new(IDLE.to_vec()));
+    let mut watcher = RomWatcher::new(path.to_str().unwrap(), Arc::clone(&rom)).unwrap().unwrap();
+    let boots = Arc::new(AtomicUsize::new(0));
+    let emulator = {
+        let (rom, boots) = (Arc::clone(&rom), Arc::clone(&boots));
+        EmulatorThread::spawn(
+            move || {
+                boots.fetch_add(1, Ordering::SeqCst);
+                let mut chip8 = Chip8Builder::new().build();
+                chip8.load_rom(rom.lock().unwrap().clone())?;
+                Ok(chip8)
+            },
+            10,
+        )
+    };
+    assert!(!watcher.reload(), "nothing has changed yet");
+
+    // caught halfway through a rebuild: an empty file doesn't load, so it waits
+    fs::write(&path, []).unwrap();
+    assert!(!reloaded(&mut watcher, Duration::from_millis(500)));
+    assert_eq!(*rom.lock().unwrap(), IDLE);
+
+    fs::write(&path, DRAW_ZERO).unwrap();
+    assert!(reloaded(&mut watcher, Duration::from_secs(5)));
+    assert_eq!(*rom.lock().unwrap(), DRAW_ZERO);
+    emulator.send(Command::Reset);
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let drawn = loop {
+        let drawn = emulator.latest_frame(0).is_some_and(|frame| frame.framebuffer.pixel(0, 0));
+        if drawn || Instant::now() > deadline {
+            break drawn;
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+    assert!(drawn, "the reset ran the new ROM");
+    assert_eq!(boots.load(Ordering::SeqCst), 2);
+
+    // the same bytes written again are not a change worth a reset
+    fs::write(&path, DRAW_ZERO).unwrap();
+    assert!(!reloaded(&mut watcher, Duration::from_millis(500)));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn only_a_rom_file_is_watched() {
+    let rom = Arc::new(Mutex::new(IDLE.to_vec()));
+    let dir = std::env::temp_dir();
+    for spec in ["-", "https://example.com/game.ch8", dir.to_str().unwrap(), "no-such-rom.ch8"] {
+        assert!(RomWatcher::new(spec, Arc::clone(&rom)).unwrap().is_none(), "{}", spec);
+    }
+}
@@ -0,0 +1,144 @@
+// Watch expressions: parsing them, and Debugger reporting the instructions that change them.
+
+use rust_8::{Break, Chip8Builder, DebugCommand, Debugger, ExprError, WatchExpr, WatchHit};
+
+// 200: LD V4, 03
+// 202: ADD V4, 01
+// 204: LD I, 300
+// 206: LD V0, 11
+// 208: LD V1, 22
+// 20A: LD [I], V1       both bytes in one store
+// 20C: JP 20C
+const ROM: [u8; 14] = [0x64, 0x03, 0x74, 0x01, 0xA3, 0x00, 0x60, 0x11, 0x61, 0x22, 0xF1, 0x55, 0x12, 0x0C];
+
+fn debugger() -> Debugger {
+    let mut chip8 = Chip8Builder::new().with_rng_seed(0).build();
+    chip8.load_rom(ROM.to_vec()).unwrap();
+    Debugger::new(chip8, 9).unwrap()
+}
+
+fn step(debugger: &mut Debugger, n: usize) {
+    for _ in 0..n {
+        debugger.step().unwrap();
+    }
+}
+
+fn error(text: &str) -> (usize, String) {
+    match WatchExpr::parse(text) {
+        Err(ExprError::Parse { column, reason }) => (column, reason),
+        Ok(expr) => panic!("{:?} parsed as {:?}", text, expr),
+    }
+}
+
+fn hit(expr: WatchExpr, pc: u16, old: u16, new: u16) -> WatchHit {
+    WatchHit { expr, pc, old, new }
+}
+
+#[test]
+fn parses_registers_timers_and_memory() {
+    let parsed: Vec<WatchExpr> = ["V4", "vf", "i", "PC", "sp", "DT", "st", "mem[0x300]", " MEM [ 2a ] "]
+        .iter()
+        .map(|text| text.parse().unwrap())
+        .collect();
+    assert_eq!(
+        parsed,
+        [
+            WatchExpr::V(4),
+            WatchExpr::V(0xF),
+            WatchExpr::I,
+            WatchExpr::Pc,
+            WatchExpr::Sp,
+            WatchExpr::Delay,
+            WatchExpr::Sound,
+            WatchExpr::Memory(0x300),
+            WatchExpr::Memory(0x2A),
+        ]
+    );
+    assert_eq!(WatchExpr::Memory(0x300).to_string(), "mem[0x300]");
+    assert_eq!(WatchExpr::V(0xA).to_string(), "VA");
+}
+
+#[test]
+fn malformed_expressions_say_where() {
+    assert_eq!(error("").0, 1);
+    assert_eq!(error("V10"), (1, String::from("unknown register \"V10\"")));
+    assert_eq!(error("Vx").0, 1);
+    assert_eq!(error("mem"), (4, String::from("expected [ after mem")));
+    assert_eq!(error("mem 300").0, 5);
+    assert_eq!(error("mem[").0, 5);
+    assert_eq!(error("mem[]"), (5, String::from("expected an address after mem[, got \"]\"")));
+    assert_eq!(error("mem[0x300"), (10, String::from("expected ] to close mem[")));
+    assert_eq!(error("mem[0x1000]"), (5, String::from("0x1000 is past the end of memory")));
+    assert_eq!(error("mem[zz]"), (5, String::from("\"zz\" is not a hex address")));
+    assert_eq!(error("V4 + 1"), (4, String::from("unexpected '+'")));
+    assert_eq!(error("V4 I"), (4, String::from("unexpected \"I\" after V4")));
+    assert_eq!(error("mem[300]]").0, 9);
+}
+
+#[test]
+fn reports_register_and_memory_changes() {
+    let mut debugger = debugger();
+    debugger.watch(WatchExpr::V(4), false);
+    debugger.watch(WatchExpr::Memory(0x300), false);
+    debugger.watch(WatchExpr::Memory(0x301), false);
+    // V0 is set but not watched, and 302 isn't written
+    debugger.watch(WatchExpr::Memory(0x302), false);
+    step(&mut debugger, 8);
+    let hits = debugger.take_watch_hits();
+    assert_eq!(
+        hits,
+        [
+            hit(WatchExpr::V(4), 0x200, 0x00, 0x03),
+            hit(WatchExpr::V(4), 0x202, 0x03, 0x04),
+            hit(WatchExpr::Memory(0x300), 0x20A, 0x00, 0x11),
+            hit(WatchExpr::Memory(0x301), 0x20A, 0x00, 0x22),
+        ]
+    );
+    assert_eq!(hits[1].to_string(), "V4 changed at 0x202: 03 -> 04");
+    assert_eq!(hits[3].to_string(), "mem[0x301] changed at 0x20A: 00 -> 22");
+    assert!(debugger.take_watch_hits().is_empty());
+}
+
+#[test]
+fn a_breaking_watch_stops_after_the_change() {
+    let mut debugger = debugger();
+    debugger.watch(WatchExpr::Memory(0x301), true);
+    debugger.watch(WatchExpr::I, false);
+    assert_eq!(
+        debugger.run_to_break(100).unwrap(),
+        Some(Break::Watch(hit(WatchExpr::Memory(0x301), 0x20A, 0x00, 0x22)))
+    );
+    assert_eq!(debugger.position(), 6);
+    assert_eq!(debugger.take_watch_hits()[0], hit(WatchExpr::I, 0x204, 0x000, 0x300));
+
+    assert!(debugger.unwatch(WatchExpr::Memory(0x301)));
+    assert!(!debugger.unwatch(WatchExpr::Memory(0x301)));
+    // on to the jump to itself, which halts
+    assert_eq!(debugger.run_to_break(10).unwrap(), None);
+    assert_eq!(debugger.chip8().pc(), 0x20C);
+}
+
+#[test]
+fn going_back_and_forward_again_reports_the_change_again() {
+    let mut debugger = debugger();
+    debugger.watch(WatchExpr::V(4), false);
+    step(&mut debugger, 3);
+    debugger.take_watch_hits();
+    debugger.reverse_step(2).unwrap();
+    assert!(debugger.take_watch_hits().is_empty());
+    step(&mut debugger, 1);
+    assert_eq!(debugger.take_watch_hits(), [hit(WatchExpr::V(4), 0x202, 0x03, 0x04)]);
+}
+
+#[test]
+fn parses_watch_commands() {
+    assert_eq!("w V4".parse(), Ok(DebugCommand::Watch { expr: WatchExpr::V(4), stop: false }));
+    assert_eq!(
+        "watch --break mem[ 0x300 ]".parse(),
+        Ok(DebugCommand::Watch { expr: WatchExpr::Memory(0x300), stop: true })
+    );
+    assert_eq!("unwatch sp".parse(), Ok(DebugCommand::Unwatch(WatchExpr::Sp)));
+    assert!("watch".parse::<DebugCommand>().is_err());
+    assert!("watch --break".parse::<DebugCommand>().is_err());
+    assert_eq!("watch V4 V5".parse::<DebugCommand>(), Err(String::from("column 4: unexpected \"V5\" after V4")));
+}
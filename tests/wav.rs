@@ -0,0 +1,78 @@
+// The buzzer recorded to a WAV file, frame by frame alongside a headless run.
+
+use std::fs;
+
+use rust_8::{Chip8Builder, SoundRecorder, Waveform};
+
+// 44100 Hz over 60 frames a second
+const SAMPLES_PER_FRAME: usize = 735;
+// the 2 ms fade out, at 44100 Hz
+const FADE_SAMPLES: usize = 89;
+
+// runs of samples that are not silent, as start and end sample indices
+fn sounding(samples: &[i16]) -> Vec<(usize, usize)> {
+    let mut runs = Vec::new();
+    let mut start = None;
+    for (n, sample) in samples.iter().enumerate() {
+        match (start, *sample != 0) {
+            (None, true) => start = Some(n),
+            (Some(first), false) => {
+                runs.push((first, n));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    runs.extend(start.map(|first| (first, samples.len())));
+    runs
+}
+
+#[test]
+fn beeps_line_up_with_the_frames_the_sound_timer_ran() {
+    // 200: LD V0, 03
+    // 202: LD ST, V0
+    // 204: LD V1, 06
+    // 206: LD DT, V1
+    // 208: LD V2, DT
+    // 20A: SE V2, 00
+    // 20C: JP 208
+    // 20E: LD V0, 02
+    // 210: LD ST, V0
+    // 212: LD V3, DT
+    // 214: JP 212
+    let rom = [
+        0x60, 0x03, 0xF0, 0x18, 0x61, 0x06, 0xF1, 0x15, 0xF2, 0x07, 0x32, 0x00, 0x12, 0x08, 0x60, 0x02, 0xF0, 0x18, 0xF3, 0x07, 0x12, 0x12,
+    ];
+    let mut chip8 = Chip8Builder::new().with_rng_seed(0).build();
+    chip8.load_rom(rom.to_vec()).unwrap();
+
+    let path = std::env::temp_dir().join(format!("rust-8-wav-{}.wav", std::process::id()));
+    let mut recorder = SoundRecorder::create(&path, Waveform::Square, 440.0, 0.5).unwrap();
+    for _frame in 0..12 {
+        for _i in 0..10 {
+            chip8.step().unwrap();
+        }
+        let sound = chip8.sound_active();
+        chip8.tick_timers();
+        recorder.frame(sound).unwrap();
+    }
+    recorder.finish().unwrap();
+    let wav = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(&wav[..4], b"RIFF");
+    assert_eq!(&wav[36..40], b"data");
+    assert_eq!(u32::from_le_bytes([wav[40], wav[41], wav[42], wav[43]]) as usize, 12 * SAMPLES_PER_FRAME * 2);
+    let samples: Vec<i16> = wav[44..].chunks(2).map(|pair| i16::from_le_bytes([pair[0], pair[1]])).collect();
+    assert_eq!(samples.len(), 12 * SAMPLES_PER_FRAME);
+
+    // ST=3 sounds through frames 0 to 2, and ST=2 once DT has run out after frame 5
+    // sounds through frames 6 and 7, each beep trailing off into the frame after it
+    let runs = sounding(&samples);
+    let expected = [(0, 3), (6, 8)];
+    assert_eq!(runs.len(), expected.len(), "{:?}", runs);
+    for ((start, end), (first_frame, end_frame)) in runs.into_iter().zip(expected) {
+        assert_eq!(start, first_frame * SAMPLES_PER_FRAME);
+        assert!(end > end_frame * SAMPLES_PER_FRAME && end <= end_frame * SAMPLES_PER_FRAME + FADE_SAMPLES, "ends at {}", end);
+    }
+}